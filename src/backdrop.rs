@@ -0,0 +1,119 @@
+//! What is drawn behind transparent images so they stay visible.
+//!
+//! A white logo on a transparent background disappears in the light theme
+//! and a black one in the dark theme. When a thumbnail decodes with
+//! transparency, the average luminance of its opaque pixels is kept in a
+//! per-profile sidecar (luminance.json) and picks a checkerboard that
+//! contrasts with it. Images without transparency get no backdrop.
+
+use crate::settings::PreviewBackdrop;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Pixels at least this opaque count towards the luminance.
+const OPAQUE_ALPHA: u8 = 128;
+const CHECKER_CELL: f32 = 8.0;
+
+/// Average luminance (0.0 to 1.0) of the opaque pixels, or None when the
+/// image has no transparency or nothing opaque.
+pub fn luminance(rgba: &image::RgbaImage) -> Option<f32> {
+    if rgba.pixels().all(|pixel| pixel[3] == u8::MAX) {
+        return None;
+    }
+    let (sum, count) = rgba
+        .pixels()
+        .filter(|pixel| pixel[3] >= OPAQUE_ALPHA)
+        .fold((0.0f64, 0usize), |(sum, count), pixel| {
+            let [r, g, b, _] = pixel.0;
+            // Rec. 709 weights on the encoded values; close enough to pick a side
+            let luma = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+            (sum + luma / 255.0, count + 1)
+        });
+    (count > 0).then(|| (sum / count as f64) as f32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backdrop {
+    Checkerboard { light: bool },
+    Solid(egui::Color32),
+}
+
+impl Backdrop {
+    /// The backdrop for an image, from the setting and its stored luminance.
+    /// `luminance` is None for opaque or not yet decoded images.
+    pub fn choose(mode: PreviewBackdrop, fixed: [u8; 3], luminance: Option<f32>, dark_mode: bool) -> Option<Backdrop> {
+        match mode {
+            PreviewBackdrop::Auto => luminance.map(|luma| Backdrop::Checkerboard { light: luma < 0.5 }),
+            PreviewBackdrop::Checkerboard => Some(Backdrop::Checkerboard { light: !dark_mode }),
+            PreviewBackdrop::Fixed => Some(Backdrop::Solid(egui::Color32::from_rgb(fixed[0], fixed[1], fixed[2]))),
+        }
+    }
+
+    pub fn shape(self, rect: egui::Rect) -> egui::Shape {
+        let (dark, light) = match self {
+            Backdrop::Solid(color) => return egui::Shape::rect_filled(rect, 0.0, color),
+            Backdrop::Checkerboard { light: true } => (egui::Color32::from_gray(204), egui::Color32::from_gray(240)),
+            Backdrop::Checkerboard { light: false } => (egui::Color32::from_gray(40), egui::Color32::from_gray(60)),
+        };
+        let mut shapes = vec![egui::Shape::rect_filled(rect, 0.0, dark)];
+        let columns = (rect.width() / CHECKER_CELL).ceil() as usize;
+        let rows = (rect.height() / CHECKER_CELL).ceil() as usize;
+        for row in 0..rows {
+            for column in (row % 2..columns).step_by(2) {
+                let min = rect.min + egui::vec2(column as f32, row as f32) * CHECKER_CELL;
+                let cell = egui::Rect::from_min_size(min, egui::Vec2::splat(CHECKER_CELL)).intersect(rect);
+                shapes.push(egui::Shape::rect_filled(cell, 0.0, light));
+            }
+        }
+        egui::Shape::Vec(shapes)
+    }
+}
+
+/// Luminance per file, filled in as thumbnails decode.
+pub struct LuminanceIndex {
+    /// None: the file has no transparency.
+    store: HashMap<String, Option<f32>>,
+    store_path: PathBuf,
+    dirty: bool,
+}
+
+impl LuminanceIndex {
+    pub fn load(store_path: PathBuf) -> Self {
+        let store = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            store_path,
+            dirty: false,
+        }
+    }
+
+    /// Luminance of a transparent image; None when opaque or not measured yet.
+    pub fn get(&self, path: &str) -> Option<f32> {
+        self.store.get(path).copied().flatten()
+    }
+
+    pub fn insert(&mut self, path: &str, luminance: Option<f32>) {
+        if self.store.get(path) != Some(&luminance) {
+            self.store.insert(path.to_string(), luminance);
+            self.dirty = true;
+        }
+    }
+
+    /// Writes the sidecar file if anything changed since the last write.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(parent) = self.store_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&self.store) {
+            if std::fs::write(&self.store_path, json).is_ok() {
+                self.dirty = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! `--bench`: times the expensive steps of a session against a given manifest
+//! and prints the results as JSON, without opening a window.
+//!
+//! Every phase calls the same functions the GUI uses, so the numbers track
+//! what users actually wait for.
+//!
+//! ```text
+//! chlorine --bench images.json [--fixtures <dir>]
+//! chlorine --bench --synthetic 100000
+//! ```
+//!
+//! `--synthetic` stands in a generated library for the manifest: one
+//! category with that many images, none of which exist on disk, so the
+//! decode phase only runs against `--fixtures`.
+
+use crate::{decode_thumbnail, grouping, manifest, search, source_hints, Category, ImageData, ImageInfo, ImageSearchApp};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Thumbnails decoded in the decode phase.
+const DECODE_COUNT: usize = 200;
+
+/// Typical searches: empty, short prefixes, words, a path operator and a miss.
+const QUERIES: [&str; 10] = ["", "a", "e", "img", "cat", "screenshot", "2024", "png", "path:/", "zzzz-no-match"];
+
+/// Name of the one category of a synthetic library.
+const SYNTHETIC_CATEGORY: &str = "Synthetic";
+
+pub struct BenchArgs {
+    pub manifest: String,
+    /// Images in a generated library, used instead of the manifest.
+    pub synthetic: Option<usize>,
+    /// Images to decode; defaults to the first files in the manifest.
+    pub fixtures: Option<PathBuf>,
+}
+
+/// Parses `--bench <manifest> [--fixtures <dir>]`; None when `--bench` isn't given.
+pub fn parse_args(args: &[String]) -> Option<Result<BenchArgs, String>> {
+    let position = args.iter().position(|arg| arg == "--bench")?;
+    let synthetic = match args.iter().position(|arg| arg == "--synthetic") {
+        Some(index) => match args.get(index + 1).and_then(|count| count.parse().ok()) {
+            Some(count) => Some(count),
+            None => return Some(Err("--synthetic needs an image count".to_string())),
+        },
+        None => None,
+    };
+    let manifest = match (args.get(position + 1).filter(|arg| !arg.starts_with("--")), synthetic) {
+        (Some(manifest), _) => manifest.clone(),
+        (None, Some(count)) => format!("synthetic:{}", count),
+        (None, None) => {
+            return Some(Err(
+                "usage: chlorine --bench <manifest.json | --synthetic <count>> [--fixtures <dir>]".to_string(),
+            ))
+        }
+    };
+    let fixtures = match args.iter().position(|arg| arg == "--fixtures") {
+        Some(index) => match args.get(index + 1) {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => return Some(Err("--fixtures needs a directory".to_string())),
+        },
+        None => None,
+    };
+    Some(Ok(BenchArgs {
+        manifest,
+        synthetic,
+        fixtures,
+    }))
+}
+
+/// One category of `count` images named the way cameras, phones and
+/// screenshot tools name them, so searches and source hints have something
+/// realistic to match.
+fn synthetic(count: usize) -> ImageData {
+    let images: Vec<ImageInfo> = (0..count)
+        .map(|n| {
+            let filename = match n % 4 {
+                0 => format!("IMG_{:06}.jpg", n),
+                1 => format!("Screenshot 2024-{:02}-{:02} at {:06}.png", n % 12 + 1, n % 28 + 1, n),
+                2 => format!("cat-{}.png", n),
+                _ => format!("export-{:06}.webp", n),
+            };
+            let relative_path = format!("{}/{}", SYNTHETIC_CATEGORY, filename);
+            ImageInfo {
+                extension: filename.rsplit_once('.').map(|(_, ext)| format!(".{}", ext)).unwrap_or_default(),
+                full_path: format!("/synthetic/{}", relative_path),
+                relative_path,
+                filename,
+                size: 100_000 + (n as u64 % 4_000_000),
+                checksum: None,
+                width: None,
+                height: None,
+            }
+        })
+        .collect();
+    let category = Category {
+        directory: SYNTHETIC_CATEGORY.to_string(),
+        count: images.len() as u32,
+        images,
+    };
+    ImageData {
+        categories: HashMap::from([(SYNTHETIC_CATEGORY.to_string(), category)]),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+pub fn run(args: BenchArgs) -> Result<serde_json::Value, String> {
+    let mut phases = Vec::new();
+
+    let start = Instant::now();
+    let data = match args.synthetic {
+        Some(count) => synthetic(count),
+        None => {
+            let loaded = manifest::load(&args.manifest).map_err(|e| e.to_string())?;
+            let mut data = loaded.data;
+            grouping::apply(&mut data, &loaded.grouping);
+            data
+        }
+    };
+    phases.push(serde_json::json!({ "phase": "load_manifest", "ms": elapsed_ms(start) }));
+
+    let start = Instant::now();
+    let index = search::SearchIndex::build(&data, &source_hints::Classifier::new(&source_hints::default_rules()));
+    phases.push(serde_json::json!({ "phase": "build_index", "ms": elapsed_ms(start) }));
+
+    let image_count = index.entries.len();
+    let largest_category = data
+        .categories
+        .iter()
+        .max_by_key(|(_, category)| category.images.len())
+        .map(|(name, _)| name.clone());
+    let manifest_paths: Vec<String> = data
+        .categories
+        .values()
+        .flat_map(|category| category.images.iter())
+        .filter(|image| crate::decoder_supports(&image.extension))
+        .take(DECODE_COUNT)
+        .map(|image| image.full_path.clone())
+        .collect();
+
+    // Filtering runs through the app itself, with the active profile's hidden
+    // list and settings, so it costs what it costs in the GUI
+    let mut app = ImageSearchApp::default();
+    app.search_index = index;
+    app.image_data = Some(data);
+
+    for query in QUERIES {
+        app.search_query = query.to_string();
+        let start = Instant::now();
+        app.update_filtered_images();
+        phases.push(serde_json::json!({
+            "phase": "query",
+            "query": query,
+            "results": app.filtered_images.len(),
+            "ms": elapsed_ms(start),
+        }));
+    }
+
+    app.search_query.clear();
+    if let Some(category) = largest_category {
+        app.selected_category = category.clone();
+        app.show_all_categories = false;
+        let start = Instant::now();
+        app.update_filtered_images();
+        phases.push(serde_json::json!({
+            "phase": "category_switch",
+            "category": category,
+            "results": app.filtered_images.len(),
+            "ms": elapsed_ms(start),
+        }));
+    }
+
+    let files: Vec<String> = match &args.fixtures {
+        Some(dir) => {
+            let mut files: Vec<String> = std::fs::read_dir(dir)
+                .map_err(|e| format!("{}: {}", dir.display(), e))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(crate::decoder_supports)
+                })
+                .map(|path| path.display().to_string())
+                .collect();
+            // Sorted so every run decodes the same files
+            files.sort();
+            files.truncate(DECODE_COUNT);
+            files
+        }
+        None => manifest_paths,
+    };
+    let start = Instant::now();
+    let decoded = files.iter().filter(|path| decode_thumbnail(path, false, 128, None).is_ok()).count();
+    phases.push(serde_json::json!({
+        "phase": "decode_thumbnails",
+        "files": files.len(),
+        "decoded": decoded,
+        "ms": elapsed_ms(start),
+    }));
+
+    Ok(serde_json::json!({
+        "manifest": args.manifest,
+        "images": image_count,
+        "phases": phases,
+    }))
+}
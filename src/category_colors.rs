@@ -0,0 +1,113 @@
+//! Color labels for categories, from a fixed palette, stored per profile.
+//!
+//! Labels are keyed by category name, so they survive manifest reloads and
+//! come back when a category reappears; a renamed category starts unlabelled.
+
+use crate::store::UserData;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SECTION: &str = "category_colors";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
+    Gray,
+}
+
+impl LabelColor {
+    pub const ALL: [LabelColor; 8] = [
+        LabelColor::Red,
+        LabelColor::Orange,
+        LabelColor::Yellow,
+        LabelColor::Green,
+        LabelColor::Teal,
+        LabelColor::Blue,
+        LabelColor::Purple,
+        LabelColor::Gray,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LabelColor::Red => "Red",
+            LabelColor::Orange => "Orange",
+            LabelColor::Yellow => "Yellow",
+            LabelColor::Green => "Green",
+            LabelColor::Teal => "Teal",
+            LabelColor::Blue => "Blue",
+            LabelColor::Purple => "Purple",
+            LabelColor::Gray => "Gray",
+        }
+    }
+
+    /// Mid-tone values that read on both the light and the dark theme.
+    pub fn color(self) -> egui::Color32 {
+        match self {
+            LabelColor::Red => egui::Color32::from_rgb(224, 76, 76),
+            LabelColor::Orange => egui::Color32::from_rgb(232, 140, 48),
+            LabelColor::Yellow => egui::Color32::from_rgb(214, 184, 40),
+            LabelColor::Green => egui::Color32::from_rgb(76, 176, 80),
+            LabelColor::Teal => egui::Color32::from_rgb(38, 166, 154),
+            LabelColor::Blue => egui::Color32::from_rgb(66, 133, 224),
+            LabelColor::Purple => egui::Color32::from_rgb(156, 100, 210),
+            LabelColor::Gray => egui::Color32::from_rgb(140, 140, 140),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CategoryColors {
+    labels: BTreeMap<String, LabelColor>,
+}
+
+impl CategoryColors {
+    pub fn load(store: &mut UserData) -> Self {
+        Self {
+            labels: store.get(SECTION),
+        }
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.labels)
+    }
+
+    pub fn get(&self, category: &str) -> Option<LabelColor> {
+        self.labels.get(category).copied()
+    }
+
+    /// Labels `category`, or removes its label with None.
+    pub fn set(&mut self, category: &str, color: Option<LabelColor>) {
+        match color {
+            Some(color) => {
+                self.labels.insert(category.to_string(), color);
+            }
+            None => {
+                self.labels.remove(category);
+            }
+        }
+    }
+
+    pub fn labels(&self) -> &BTreeMap<String, LabelColor> {
+        &self.labels
+    }
+
+    pub fn replace(&mut self, labels: BTreeMap<String, LabelColor>) {
+        self.labels = labels;
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
@@ -0,0 +1,163 @@
+//! Verifying files against the `checksum` (SHA-256) a manifest may carry.
+//!
+//! Build pipelines can write a hex digest per image, optionally prefixed
+//! with "sha256:". "Verify checksums" hashes every file that has one on a
+//! small worker pool and sorts the results into verified, mismatched
+//! (corrupted or swapped) and missing. Hashes are kept for the session
+//! together with the size and modification time they were taken at, so a
+//! second run only reads files that changed since.
+
+use crate::integrity::{hash_file, HashRecord};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+const MAX_WORKERS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Verified,
+    /// The file's hash, which differs from the manifest's.
+    Mismatch(String),
+    Missing,
+    Unreadable(String),
+}
+
+/// The manifest's checksum reduced to lowercase hex, or None if it isn't SHA-256 hex.
+pub fn expected_hash(checksum: &str) -> Option<String> {
+    let checksum = checksum.trim();
+    let hex = match checksum.split_once(':') {
+        Some((algorithm, hex)) if algorithm.eq_ignore_ascii_case("sha256") => hex,
+        Some(_) => return None,
+        None => checksum,
+    };
+    (hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hex.to_ascii_lowercase())
+}
+
+/// Shared with the UI while a run is going.
+#[derive(Default)]
+pub struct Progress {
+    pub done: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancelled: AtomicBool,
+}
+
+/// What a run found for one file, plus the hash to remember when it could be read.
+pub struct Checked {
+    pub path: String,
+    pub status: Status,
+    pub record: Option<HashRecord>,
+}
+
+/// A hash from earlier in the session, if the file still has the size and
+/// modification time it had then.
+fn cached(path: &str, known: &HashMap<String, HashRecord>) -> Option<HashRecord> {
+    let record = known.get(path)?;
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    (record.size == metadata.len() && record.modified == modified).then(|| record.clone())
+}
+
+fn check(path: &str, expected: &str, known: &HashMap<String, HashRecord>) -> Checked {
+    let record = match cached(path, known).map(Ok).unwrap_or_else(|| hash_file(path)) {
+        Ok(record) => record,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Checked {
+                path: path.to_string(),
+                status: Status::Missing,
+                record: None,
+            };
+        }
+        Err(e) => {
+            return Checked {
+                path: path.to_string(),
+                status: Status::Unreadable(e.to_string()),
+                record: None,
+            };
+        }
+    };
+    let status = if record.hash == expected {
+        Status::Verified
+    } else {
+        Status::Mismatch(record.hash.clone())
+    };
+    Checked {
+        path: path.to_string(),
+        status,
+        record: Some(record),
+    }
+}
+
+/// Checks (path, expected hex) pairs in input order. Meant for a background thread.
+pub fn run(files: Vec<(String, String)>, known: HashMap<String, HashRecord>, progress: Arc<Progress>) -> Vec<Checked> {
+    progress.total.store(files.len(), Ordering::Relaxed);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(files.len()));
+    let workers = std::thread::available_parallelism()
+        .map_or(2, |count| count.get())
+        .clamp(1, MAX_WORKERS);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if progress.cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some((path, expected)) = files.get(index) else {
+                    break;
+                };
+                let checked = check(path, expected, &known);
+                if let Ok(mut results) = results.lock() {
+                    results.push((index, checked));
+                }
+                progress.done.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap_or_default();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, checked)| checked).collect()
+}
+
+/// Verification results for this session, by full path.
+#[derive(Default)]
+pub struct Verification {
+    statuses: HashMap<String, Status>,
+    hashes: HashMap<String, HashRecord>,
+}
+
+impl Verification {
+    pub fn status(&self, path: &str) -> Option<&Status> {
+        self.statuses.get(path)
+    }
+
+    /// Hashes to hand to the next run.
+    pub fn known(&self) -> HashMap<String, HashRecord> {
+        self.hashes.clone()
+    }
+
+    pub fn record(&mut self, results: Vec<Checked>) {
+        for checked in results {
+            match checked.record {
+                Some(record) => {
+                    self.hashes.insert(checked.path.clone(), record);
+                }
+                None => {
+                    self.hashes.remove(&checked.path);
+                }
+            }
+            self.statuses.insert(checked.path, checked.status);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.statuses.clear();
+        self.hashes.clear();
+    }
+}
@@ -0,0 +1,110 @@
+//! Opening the system clipboard, and what to do when that never works.
+//!
+//! Some sessions (Wayland without a data-control portal, bare X servers over
+//! SSH) fail every `arboard::Clipboard::new()`. After a few failures in a row
+//! the clipboard counts as unavailable and Copy switches to a fallback instead
+//! of flashing the same error; it is probed again when the window regains
+//! focus, so a fixed environment recovers without a restart.
+
+use crate::error::ChlorineError;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Consecutive failures to open the clipboard before it counts as unavailable.
+pub const FAILURES_BEFORE_FALLBACK: u32 = 3;
+
+#[derive(Default)]
+pub struct ClipboardAccess {
+    consecutive_failures: u32,
+}
+
+impl ClipboardAccess {
+    pub fn open(&mut self) -> Result<arboard::Clipboard, ChlorineError> {
+        let result = arboard::Clipboard::new();
+        if result.is_ok() {
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        }
+        Ok(result?)
+    }
+
+    pub fn set_text(&mut self, text: String) -> Result<(), ChlorineError> {
+        Ok(self.open()?.set_text(text)?)
+    }
+
+    pub fn set_image(&mut self, image: arboard::ImageData) -> Result<(), ChlorineError> {
+        Ok(self.open()?.set_image(image)?)
+    }
+
+    /// Puts files on the clipboard as a file list, for pasting into file managers and chat apps.
+    pub fn set_files(&mut self, files: &[PathBuf]) -> Result<(), ChlorineError> {
+        Ok(self.open()?.set().file_list(files)?)
+    }
+
+    pub fn unavailable(&self) -> bool {
+        self.consecutive_failures >= FAILURES_BEFORE_FALLBACK
+    }
+
+    /// Tries the clipboard again while it is unavailable. Returns true when it
+    /// works again.
+    pub fn probe(&mut self) -> bool {
+        self.unavailable() && self.open().is_ok()
+    }
+}
+
+/// Command-line clipboard tools to try, most likely first.
+#[cfg(target_os = "windows")]
+const TEXT_TOOLS: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(target_os = "macos")]
+const TEXT_TOOLS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const TEXT_TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Copies text through the first clipboard tool that is installed and
+/// succeeds. Returns the tool's name.
+pub fn copy_text_with_tool(text: &str) -> Result<&'static str, String> {
+    for &(program, args) in TEXT_TOOLS {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if child.wait().is_ok_and(|status| status.success()) && written {
+            return Ok(program);
+        }
+    }
+    let names: Vec<&str> = TEXT_TOOLS.iter().map(|(program, _)| *program).collect();
+    Err(format!("none of {} worked", names.join(", ")))
+}
+
+/// `Chlorine` inside the user's Pictures folder (the home folder if there is none).
+pub fn pictures_dir() -> PathBuf {
+    dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Chlorine")
+}
+
+/// Copies the file into `pictures_dir()` without overwriting anything there.
+pub fn save_to_pictures(source: &Path) -> Result<PathBuf, String> {
+    let dir = pictures_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let filename = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("{} has no file name", source.display()))?;
+    let destination = dir.join(crate::import::free_name(&dir, &filename));
+    std::fs::copy(source, &destination).map_err(|e| format!("{}: {}", destination.display(), e))?;
+    Ok(destination)
+}
@@ -0,0 +1,57 @@
+//! Remembers which library file Chlorine last put on the clipboard, and
+//! notices when something else has replaced it.
+//!
+//! The fingerprint is taken by reading the clipboard back right after the
+//! copy, so it matches whatever form the platform hands out later rather
+//! than the bytes Chlorine wrote.
+
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fingerprint {
+    Image(u64),
+    Text(u64),
+    /// Content arboard can't read back, such as a file list.
+    Unreadable,
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn read_fingerprint() -> Option<Fingerprint> {
+    let mut clipboard = arboard::Clipboard::new().ok()?;
+    if let Ok(image) = clipboard.get_image() {
+        return Some(Fingerprint::Image(hash_of((image.width, image.height, image.bytes.as_ref()))));
+    }
+    Some(match clipboard.get_text() {
+        Ok(text) => Fingerprint::Text(hash_of(text)),
+        Err(_) => Fingerprint::Unreadable,
+    })
+}
+
+pub struct ClipboardMarker {
+    pub path: String,
+    fingerprint: Option<Fingerprint>,
+}
+
+impl ClipboardMarker {
+    /// Call right after a successful copy of `path`.
+    pub fn capture(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            fingerprint: read_fingerprint(),
+        }
+    }
+
+    /// False once the clipboard holds something else. If the clipboard can't
+    /// be opened at all, the marker is trusted.
+    pub fn still_on_clipboard(&self) -> bool {
+        match (&self.fingerprint, read_fingerprint()) {
+            (Some(expected), Some(current)) => *expected == current,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Detection of cloud-sync placeholder files (OneDrive, iCloud Drive, Dropbox "online-only").
+//!
+//! These look like normal files but reading them makes the sync client download
+//! the content first, so thumbnail loading asks before touching them.
+
+use std::path::Path;
+
+/// True if the file's content lives in the cloud and reading it would trigger a download.
+/// Always false on platforms that don't expose placeholder attributes.
+pub fn is_placeholder(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata_is_placeholder(&metadata))
+}
+
+#[cfg(windows)]
+fn metadata_is_placeholder(metadata: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+    metadata.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+#[cfg(target_os = "macos")]
+fn metadata_is_placeholder(metadata: &std::fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    // SF_DATALESS from <sys/stat.h>: the file's data is not materialized locally
+    const SF_DATALESS: u32 = 0x4000_0000;
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn metadata_is_placeholder(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
@@ -0,0 +1,208 @@
+//! Dominant-color signatures for "find by color".
+//!
+//! A signature is the few most common colors of a small thumbnail, after
+//! quantizing so near-identical shades count together. Signatures come from
+//! thumbnail decoding when possible; the rest are backfilled by a slow
+//! background worker and cached in a per-profile sidecar.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// Colors kept per image.
+pub const SIGNATURE_COLORS: usize = 3;
+/// Default maximum RGB distance between a signature color and the target.
+pub const DEFAULT_TOLERANCE: f32 = 60.0;
+/// Pause between two files on the backfill thread.
+const BACKFILL_INTERVAL: Duration = Duration::from_millis(30);
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+/// Bits kept per channel when bucketing pixels.
+const QUANT_BITS: u32 = 3;
+
+pub type Rgb = [u8; 3];
+
+/// Most common colors of an image, most frequent first. Transparent pixels are ignored.
+pub fn signature(image: &image::RgbaImage) -> Vec<Rgb> {
+    // Bucket by quantized color, but keep channel sums so the result is the bucket's average
+    let mut buckets: HashMap<u32, (u32, [u64; 3])> = HashMap::new();
+    let shift = 8 - QUANT_BITS;
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        let key = (((r >> shift) as u32) << (2 * QUANT_BITS)) | (((g >> shift) as u32) << QUANT_BITS) | (b >> shift) as u32;
+        let bucket = buckets.entry(key).or_insert((0, [0; 3]));
+        bucket.0 += 1;
+        bucket.1[0] += r as u64;
+        bucket.1[1] += g as u64;
+        bucket.1[2] += b as u64;
+    }
+
+    let mut buckets: Vec<(u32, [u64; 3])> = buckets.into_values().collect();
+    buckets.sort_by_key(|bucket| std::cmp::Reverse(bucket.0));
+    buckets
+        .into_iter()
+        .take(SIGNATURE_COLORS)
+        .map(|(count, sums)| sums.map(|sum| (sum / count as u64) as u8))
+        .collect()
+}
+
+pub fn distance(a: Rgb, b: Rgb) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| (x as f32 - y as f32).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// True if any dominant color is within `tolerance` of `target`.
+pub fn matches(signature: &[Rgb], target: Rgb, tolerance: f32) -> bool {
+    signature.iter().any(|&color| distance(color, target) <= tolerance)
+}
+
+/// Parses "#ff0066", "ff0066" or the short form "#f06".
+pub fn parse_hex(text: &str) -> Option<Rgb> {
+    let hex = text.trim_start_matches('#');
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        6 => Some([channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?]),
+        3 => {
+            let mut rgb = [0; 3];
+            for (out, c) in rgb.iter_mut().zip(hex.chars()) {
+                *out = channel(&c.to_string())? * 17;
+            }
+            Some(rgb)
+        }
+        _ => None,
+    }
+}
+
+pub fn to_hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+struct SignatureResult {
+    path: String,
+    signature: Vec<Rgb>,
+}
+
+pub struct ColorIndex {
+    /// Path to signature; an empty signature means the file couldn't be decoded.
+    store: HashMap<String, Vec<Rgb>>,
+    store_path: PathBuf,
+    requests: Option<Sender<String>>,
+    results: Option<Receiver<SignatureResult>>,
+    queued: HashSet<String>,
+    in_flight: usize,
+    dirty: bool,
+    last_save: Instant,
+}
+
+impl ColorIndex {
+    pub fn load(store_path: PathBuf) -> Self {
+        let store = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            store_path,
+            requests: None,
+            results: None,
+            queued: HashSet::new(),
+            in_flight: 0,
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[Rgb]> {
+        self.store.get(path).map(Vec::as_slice)
+    }
+
+    /// Records a signature computed elsewhere (during thumbnail decoding).
+    pub fn insert(&mut self, path: &str, signature: Vec<Rgb>) {
+        if self.store.get(path) != Some(&signature) {
+            self.store.insert(path.to_string(), signature);
+            self.dirty = true;
+        }
+    }
+
+    fn ensure_worker(&mut self) {
+        if self.requests.is_some() {
+            return;
+        }
+        let (request_tx, request_rx) = mpsc::channel::<String>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let _ = std::thread::Builder::new()
+            .name("color_signatures".to_string())
+            .spawn(move || {
+                for path in request_rx {
+                    let signature = crate::formats::open(&path)
+                        .map(|img| signature(&img.thumbnail(64, 64).to_rgba8()))
+                        .unwrap_or_default();
+                    if result_tx.send(SignatureResult { path, signature }).is_err() {
+                        break;
+                    }
+                    std::thread::sleep(BACKFILL_INTERVAL);
+                }
+            });
+        self.requests = Some(request_tx);
+        self.results = Some(result_rx);
+    }
+
+    /// Queues a file for backfilling unless it already has a signature or is queued.
+    pub fn queue(&mut self, path: &str) {
+        if self.store.contains_key(path) || !self.queued.insert(path.to_string()) {
+            return;
+        }
+        self.ensure_worker();
+        if let Some(requests) = &self.requests {
+            if requests.send(path.to_string()).is_ok() {
+                self.in_flight += 1;
+            }
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.in_flight > 0
+    }
+
+    /// Collects finished signatures. Returns how many arrived.
+    pub fn poll(&mut self) -> usize {
+        let Some(results) = &self.results else {
+            return 0;
+        };
+        let mut received = Vec::new();
+        while let Ok(result) = results.try_recv() {
+            received.push(result);
+        }
+        self.in_flight = self.in_flight.saturating_sub(received.len());
+        let count = received.len();
+        for SignatureResult { path, signature } in received {
+            self.insert(&path, signature);
+        }
+        if self.dirty && self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+        count
+    }
+
+    /// Writes the sidecar file if anything changed since the last write.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(parent) = self.store_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&self.store) {
+            if std::fs::write(&self.store_path, json).is_ok() {
+                self.dirty = false;
+            }
+        }
+        self.last_save = Instant::now();
+    }
+}
@@ -0,0 +1,156 @@
+//! Pixel copies, one at a time in the order they were asked for.
+//!
+//! Decoding and resizing run on a background thread; the app puts the
+//! finished pixels on the clipboard itself, so whatever completed last is
+//! what a paste gets. Finished items stay listed for a while so the status
+//! bar popover can show what happened to each.
+
+use crate::orientation::Orientation;
+use poll_promise::Promise;
+use std::collections::VecDeque;
+
+/// Finished items kept for the popover.
+const KEEP_FINISHED: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum State {
+    Queued,
+    Encoding,
+    Done,
+    Failed(String),
+}
+
+impl State {
+    pub fn label(&self) -> String {
+        match self {
+            State::Queued => "queued".to_string(),
+            State::Encoding => "encoding…".to_string(),
+            State::Done => "done".to_string(),
+            State::Failed(e) => format!("failed: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    id: u64,
+    pub path: String,
+    pub filename: String,
+    pub max_dimension: Option<u32>,
+    pub orientation: Orientation,
+    pub state: State,
+}
+
+#[derive(Default)]
+pub struct CopyQueue {
+    /// Oldest first: finished items, then the one encoding, then the queued ones.
+    items: VecDeque<Item>,
+    in_flight: Option<(u64, Promise<Result<image::RgbaImage, String>>)>,
+    next_id: u64,
+}
+
+impl CopyQueue {
+    /// Queues a copy; returns how many copies are ahead of it.
+    pub fn push(
+        &mut self,
+        path: String,
+        filename: String,
+        max_dimension: Option<u32>,
+        orientation: Orientation,
+    ) -> usize {
+        let ahead = self.pending();
+        self.next_id += 1;
+        self.items.push_back(Item {
+            id: self.next_id,
+            path,
+            filename,
+            max_dimension,
+            orientation,
+            state: State::Queued,
+        });
+        ahead
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+
+    /// Items queued or encoding.
+    pub fn pending(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.state, State::Queued | State::Encoding))
+            .count()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.pending() > 0
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.path == path && matches!(item.state, State::Queued | State::Encoding))
+    }
+
+    /// The last copy that reached the clipboard.
+    pub fn last_done(&self) -> Option<&Item> {
+        self.items.iter().rev().find(|item| item.state == State::Done)
+    }
+
+    /// Drops the queued items; the one encoding still finishes.
+    pub fn cancel_queued(&mut self) -> usize {
+        let before = self.items.len();
+        self.items.retain(|item| item.state != State::Queued);
+        before - self.items.len()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.items.retain(|item| matches!(item.state, State::Queued | State::Encoding));
+    }
+
+    /// The item whose pixels are ready, with them or the reason they aren't.
+    /// Report how putting them on the clipboard went with [`CopyQueue::finish`].
+    pub fn take_finished(&mut self) -> Option<(Item, Result<image::RgbaImage, String>)> {
+        let (id, promise) = self.in_flight.take()?;
+        let promise = match promise.try_take() {
+            Ok(result) => {
+                let item = self.items.iter().find(|item| item.id == id)?.clone();
+                return Some((item, result));
+            }
+            Err(promise) => promise,
+        };
+        self.in_flight = Some((id, promise));
+        None
+    }
+
+    pub fn finish(&mut self, item: &Item, result: Result<(), String>) {
+        if let Some(entry) = self.items.iter_mut().find(|entry| entry.id == item.id) {
+            entry.state = match result {
+                Ok(()) => State::Done,
+                Err(e) => State::Failed(e),
+            };
+        }
+        let finished = self.items.len() - self.pending();
+        for _ in KEEP_FINISHED..finished {
+            self.items.pop_front();
+        }
+    }
+
+    /// Starts decoding the next queued item when nothing else is.
+    pub fn start_next(&mut self) {
+        if self.in_flight.is_some() {
+            return;
+        }
+        let Some(item) = self.items.iter_mut().find(|item| item.state == State::Queued) else {
+            return;
+        };
+        item.state = State::Encoding;
+        let (path, max_dimension, orientation) = (item.path.clone(), item.max_dimension, item.orientation);
+        let promise = Promise::spawn_thread("copy_pixels", move || {
+            let img = crate::decode_oriented(&path, orientation).map_err(|e| e.to_string())?;
+            Ok(crate::encode::resize_to_fit(img, max_dimension).to_rgba8())
+        });
+        self.in_flight = Some((item.id, promise));
+    }
+}
@@ -0,0 +1,38 @@
+//! User-chosen category covers, stored per profile as category → full path.
+//!
+//! Categories without a chosen cover (or whose cover left the category) use
+//! their first image alphabetically.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub struct CategoryCovers {
+    covers: BTreeMap<String, String>,
+    file: PathBuf,
+}
+
+impl CategoryCovers {
+    pub fn load(file: PathBuf) -> Self {
+        let covers = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { covers, file }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&self.covers).map_err(|e| e.to_string())?;
+        std::fs::write(&self.file, json).map_err(|e| format!("{}: {}", self.file.display(), e))
+    }
+
+    pub fn get(&self, category: &str) -> Option<&str> {
+        self.covers.get(category).map(String::as_str)
+    }
+
+    pub fn set(&mut self, category: &str, path: &str) {
+        self.covers.insert(category.to_string(), path.to_string());
+    }
+}
@@ -0,0 +1,156 @@
+//! `chlorine://` links, so a wiki page or chat message can open the app
+//! already filtered or showing one image:
+//!
+//! - `chlorine://search?q=cat&category=reactions`
+//! - `chlorine://image?path=/full/path/to/file.png`
+//!
+//! A link arrives as a command-line argument. When another instance with
+//! remote control enabled is already running, the new process forwards the
+//! link there and exits.
+
+use crate::remote::{percent_decode, query_param};
+
+pub const SCHEME: &str = "chlorine";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    Search { query: String, category: Option<String> },
+    Image { path: String },
+}
+
+/// The first argument that looks like a link, if any.
+pub fn from_args(args: &[String]) -> Option<String> {
+    let prefix = format!("{}:", SCHEME);
+    args.iter()
+        .skip(1)
+        .find(|arg| arg.to_ascii_lowercase().starts_with(&prefix))
+        .cloned()
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, String> {
+    let rest = link
+        .get(..SCHEME.len() + 3)
+        .filter(|start| start.eq_ignore_ascii_case(&format!("{}://", SCHEME)))
+        .map(|_| &link[SCHEME.len() + 3..])
+        .ok_or_else(|| format!("\"{}\" is not a {}:// link", link, SCHEME))?;
+    // Some launchers add a trailing slash to the host part
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let action = action.trim_end_matches('/');
+    if query.split('&').any(|pair| !pair.is_empty() && percent_decode(pair).is_none()) {
+        return Err("the link contains invalid percent-encoding".to_string());
+    }
+
+    match action {
+        "search" => {
+            let query_text = query_param(query, "q").unwrap_or_default();
+            let category = query_param(query, "category").filter(|category| !category.is_empty());
+            if query_text.is_empty() && category.is_none() {
+                return Err("a search link needs a q or category parameter".to_string());
+            }
+            Ok(DeepLink::Search {
+                query: query_text,
+                category,
+            })
+        }
+        "image" => match query_param(query, "path") {
+            Some(path) if !path.is_empty() => Ok(DeepLink::Image { path }),
+            _ => Err("an image link needs a path parameter".to_string()),
+        },
+        "" => Err("the link doesn't say what to open".to_string()),
+        other => Err(format!("unknown link type \"{}\" (expected search or image)", other)),
+    }
+}
+
+impl DeepLink {
+    pub fn to_url(&self) -> String {
+        match self {
+            DeepLink::Search { query, category } => {
+                let mut url = format!("{}://search?q={}", SCHEME, percent_encode(query));
+                if let Some(category) = category {
+                    url.push_str(&format!("&category={}", percent_encode(category)));
+                }
+                url
+            }
+            DeepLink::Image { path } => format!("{}://image?path={}", SCHEME, percent_encode(path)),
+        }
+    }
+}
+
+/// Keeps unreserved characters and slashes readable; everything else is escaped.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Registers this executable as the handler for `chlorine://` links for the
+/// current user. Returns a description of what was done.
+pub fn register() -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    register_for(&exe)
+}
+
+#[cfg(target_os = "windows")]
+fn register_for(exe: &std::path::Path) -> Result<String, String> {
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let entries: [(String, &str, String); 3] = [
+        (key.clone(), "/ve", "URL:Chlorine link".to_string()),
+        (key.clone(), "URL Protocol", String::new()),
+        (format!(r"{}\shell\open\command", key), "/ve", command),
+    ];
+    for (path, name, value) in entries {
+        let mut reg = std::process::Command::new("reg");
+        reg.args(["add", &path]);
+        if name == "/ve" {
+            reg.arg("/ve");
+        } else {
+            reg.args(["/v", name]);
+        }
+        let status = reg
+            .args(["/d", &value, "/f"])
+            .status()
+            .map_err(|e| format!("Could not run reg: {}", e))?;
+        if !status.success() {
+            return Err(format!("reg add {} failed", path));
+        }
+    }
+    Ok(format!("Registered {}:// for {}", SCHEME, exe.display()))
+}
+
+#[cfg(target_os = "linux")]
+fn register_for(exe: &std::path::Path) -> Result<String, String> {
+    let applications = dirs::data_dir()
+        .ok_or("Could not find the data directory")?
+        .join("applications");
+    std::fs::create_dir_all(&applications).map_err(|e| format!("{}: {}", applications.display(), e))?;
+    let desktop_name = format!("{}-link.desktop", SCHEME);
+    let desktop_file = applications.join(&desktop_name);
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Chlorine\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        exe.display(),
+        SCHEME
+    );
+    std::fs::write(&desktop_file, entry).map_err(|e| format!("{}: {}", desktop_file.display(), e))?;
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", &desktop_name, &format!("x-scheme-handler/{}", SCHEME)])
+        .status()
+        .map_err(|e| format!("Wrote {} but could not run xdg-mime: {}", desktop_file.display(), e))?;
+    if !status.success() {
+        return Err(format!("Wrote {} but xdg-mime failed", desktop_file.display()));
+    }
+    Ok(format!("Registered {}:// via {}", SCHEME, desktop_file.display()))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_for(_exe: &std::path::Path) -> Result<String, String> {
+    Err(format!(
+        "On this platform {}:// is registered by the app bundle (CFBundleURLTypes in Info.plist)",
+        SCHEME
+    ))
+}
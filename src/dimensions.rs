@@ -0,0 +1,296 @@
+//! Lazily collected image dimensions, for filtering by orientation.
+//!
+//! Sizes come from thumbnail decoding when possible. While an orientation
+//! filter is active, the remaining candidates are probed from their file
+//! headers on a dedicated thread, so they don't wait behind thumbnail loads.
+//! Results are cached in a per-profile sidecar.
+//!
+//! Dimension filters (orientation chips, `ar:` and `mp:`) exclude images whose
+//! size isn't known yet and queue them for probing; they join the results as
+//! soon as they're measured.
+//!
+//! "Index dimensions" probes every file up front with [`probe_all`] instead,
+//! and stores the sizes in the manifest when it's writable, so the filters
+//! don't settle over time in later sessions.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+/// How far the aspect ratio may be from 1:1 and still count as square.
+const SQUARE_TOLERANCE: f32 = 0.05;
+/// Relative difference allowed when matching `ar:`, so 16:9 matches 1366×768.
+pub const ASPECT_TOLERANCE: f32 = 0.02;
+/// Named ratios preferred over an exact but unwieldy fraction like 683:384.
+const COMMON_RATIOS: [(u32, u32); 9] = [(1, 1), (5, 4), (4, 3), (3, 2), (16, 10), (16, 9), (2, 1), (21, 9), (32, 9)];
+
+/// Bytes read from files the `image` crate can't parse, looking for a size.
+const HEADER_SCAN_BYTES: usize = 64 * 1024;
+/// Threads for [`probe_all`]; header reads are mostly waiting on the disk.
+const MAX_WORKERS: usize = 4;
+
+/// Width and height from the file header, without decoding pixels. Besides
+/// every format the `image` crate reads, knows PSD and HEIC/AVIF (their
+/// `ispe` box), which only get an info tile.
+pub fn read_header(path: &str) -> Option<(u32, u32)> {
+    if let Ok(size) = image::image_dimensions(path) {
+        return Some(size);
+    }
+    use std::io::Read;
+    let mut header = Vec::with_capacity(HEADER_SCAN_BYTES);
+    std::fs::File::open(path)
+        .ok()?
+        .take(HEADER_SCAN_BYTES as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    let be_u32 = |at: usize| header.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let size = if header.starts_with(b"8BPS") {
+        // Height comes before width
+        Some((be_u32(18)?, be_u32(14)?))
+    } else if header.get(4..8) == Some(b"ftyp") {
+        // ispe: 4-byte box tag, 4 bytes of version and flags, then width and height
+        let at = header.windows(4).position(|window| window == b"ispe")?;
+        Some((be_u32(at + 8)?, be_u32(at + 12)?))
+    } else {
+        None
+    };
+    size.filter(|&(width, height)| width > 0 && height > 0)
+}
+
+/// Shared with the UI while an indexing run is going.
+#[derive(Default)]
+pub struct Progress {
+    pub done: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancelled: AtomicBool,
+}
+
+/// Reads the header of every path on a small worker pool. Unreadable files
+/// come back as (0, 0), so they're remembered and not probed again. A
+/// cancelled run returns what it measured so far. Meant for a background thread.
+pub fn probe_all(paths: Vec<String>, progress: Arc<Progress>) -> Vec<(String, (u32, u32))> {
+    progress.total.store(paths.len(), Ordering::Relaxed);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(paths.len()));
+    let workers = std::thread::available_parallelism()
+        .map_or(2, |count| count.get())
+        .clamp(1, MAX_WORKERS);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if progress.cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                let size = read_header(path).unwrap_or((0, 0));
+                if let Ok(mut results) = results.lock() {
+                    results.push((path.clone(), size));
+                }
+                progress.done.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    });
+    results.into_inner().unwrap_or_default()
+}
+
+pub fn aspect_ratio(width: u32, height: u32) -> f32 {
+    width as f32 / height.max(1) as f32
+}
+
+pub fn megapixels(width: u32, height: u32) -> f32 {
+    width as f32 * height as f32 / 1_000_000.0
+}
+
+pub fn matches_aspect(width: u32, height: u32, target: f32) -> bool {
+    target > 0.0 && (aspect_ratio(width, height) / target - 1.0).abs() <= ASPECT_TOLERANCE
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// "16:9 (1.78)": the reduced fraction, or the nearest common ratio when the
+/// exact fraction is unwieldy, followed by the decimal value.
+pub fn aspect_label(width: u32, height: u32) -> String {
+    let ratio = aspect_ratio(width, height);
+    let divisor = gcd(width, height).max(1);
+    let (w, h) = (width / divisor, height / divisor);
+    let fraction = if w <= 32 && h <= 32 {
+        Some(format!("{}:{}", w, h))
+    } else {
+        COMMON_RATIOS
+            .iter()
+            .flat_map(|&(a, b)| [(a, b), (b, a)])
+            .find(|&(a, b)| matches_aspect(width, height, a as f32 / b as f32))
+            .map(|(a, b)| format!("≈{}:{}", a, b))
+    };
+    match fraction {
+        Some(fraction) => format!("{} ({:.2})", fraction, ratio),
+        None => format!("{:.2}", ratio),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 3] = [Orientation::Landscape, Orientation::Portrait, Orientation::Square];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "▭ Landscape",
+            Orientation::Portrait => "▯ Portrait",
+            Orientation::Square => "◻ Square",
+        }
+    }
+
+    /// Lowercase name without the icon, for filter chips.
+    pub fn name(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+            Orientation::Square => "square",
+        }
+    }
+
+    pub fn of(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let ratio = width as f32 / height as f32;
+        Some(if (ratio - 1.0).abs() <= SQUARE_TOLERANCE {
+            Orientation::Square
+        } else if ratio > 1.0 {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        })
+    }
+}
+
+pub struct DimensionIndex {
+    /// Path to (width, height); (0, 0) means the header couldn't be read.
+    store: HashMap<String, (u32, u32)>,
+    store_path: PathBuf,
+    requests: Option<Sender<String>>,
+    results: Option<Receiver<(String, (u32, u32))>>,
+    queued: HashSet<String>,
+    dirty: bool,
+    last_save: Instant,
+}
+
+impl DimensionIndex {
+    pub fn load(store_path: PathBuf) -> Self {
+        let store = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            store_path,
+            requests: None,
+            results: None,
+            queued: HashSet::new(),
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Known size, or None if not measured yet. Unreadable files report (0, 0).
+    pub fn get(&self, path: &str) -> Option<(u32, u32)> {
+        self.store.get(path).copied()
+    }
+
+    /// Records a size measured elsewhere (during thumbnail decoding).
+    pub fn insert(&mut self, path: &str, size: (u32, u32)) {
+        if self.store.get(path) != Some(&size) {
+            self.store.insert(path.to_string(), size);
+            self.dirty = true;
+        }
+    }
+
+    fn ensure_worker(&mut self) {
+        if self.requests.is_some() {
+            return;
+        }
+        let (request_tx, request_rx) = mpsc::channel::<String>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let _ = std::thread::Builder::new()
+            .name("dimension_probe".to_string())
+            .spawn(move || {
+                // Header reads are cheap, so unlike the color backfill this doesn't pause between files
+                for path in request_rx {
+                    let size = read_header(&path).unwrap_or((0, 0));
+                    if result_tx.send((path, size)).is_err() {
+                        break;
+                    }
+                }
+            });
+        self.requests = Some(request_tx);
+        self.results = Some(result_rx);
+    }
+
+    /// Queues a header probe unless the size is known or already queued.
+    pub fn queue(&mut self, path: &str) {
+        if self.store.contains_key(path) || !self.queued.insert(path.to_string()) {
+            return;
+        }
+        self.ensure_worker();
+        if let Some(requests) = &self.requests {
+            if requests.send(path.to_string()).is_err() {
+                self.queued.remove(path);
+            }
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.queued.is_empty()
+    }
+
+    /// Collects finished probes. Returns how many arrived.
+    pub fn poll(&mut self) -> usize {
+        let Some(results) = &self.results else {
+            return 0;
+        };
+        let received: Vec<(String, (u32, u32))> = results.try_iter().collect();
+        let count = received.len();
+        for (path, size) in received {
+            self.queued.remove(&path);
+            self.insert(&path, size);
+        }
+        if self.dirty && self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+        count
+    }
+
+    /// Writes the sidecar file if anything changed since the last write.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(parent) = self.store_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&self.store) {
+            if std::fs::write(&self.store_path, json).is_ok() {
+                self.dirty = false;
+            }
+        }
+        self.last_save = Instant::now();
+    }
+}
@@ -0,0 +1,43 @@
+//! Images whose `full_path` is listed under more than one category, which
+//! happens when manifests from several tools are merged.
+
+use crate::ImageData;
+use std::collections::HashMap;
+
+/// Duplicated full paths, each with the sorted names of the categories listing it.
+pub fn find(data: &ImageData) -> HashMap<String, Vec<String>> {
+    let mut seen: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, category) in &data.categories {
+        for image in &category.images {
+            let categories = seen.entry(image.full_path.as_str()).or_default();
+            if !categories.contains(&name.as_str()) {
+                categories.push(name);
+            }
+        }
+    }
+    seen.into_iter()
+        .filter(|(_, categories)| categories.len() > 1)
+        .map(|(path, categories)| {
+            let mut categories: Vec<String> = categories.into_iter().map(str::to_string).collect();
+            categories.sort();
+            (path.to_string(), categories)
+        })
+        .collect()
+}
+
+/// Drops each duplicate from every category but the alphabetically first,
+/// in memory only. Returns how many entries were removed.
+pub fn keep_first(data: &mut ImageData, duplicates: &HashMap<String, Vec<String>>) -> usize {
+    let mut removed = 0;
+    for (name, category) in data.categories.iter_mut() {
+        let before = category.images.len();
+        category.images.retain(|image| {
+            duplicates
+                .get(&image.full_path)
+                .map_or(true, |categories| categories.first() == Some(name))
+        });
+        removed += before - category.images.len();
+        category.count = category.images.len() as u32;
+    }
+    removed
+}
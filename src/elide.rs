@@ -0,0 +1,49 @@
+//! Fitting long names into a fixed width by cutting out the middle, so both
+//! the start and the extension stay readable: "very_long_na…_final2.png".
+
+use eframe::egui;
+
+const ELLIPSIS: char = '…';
+
+/// Share of the kept characters taken from the start of the text.
+const HEAD_SHARE: f32 = 0.6;
+
+/// Width of `text` laid out on one line in `font`.
+pub fn width(ctx: &egui::Context, text: &str, font: &egui::FontId) -> f32 {
+    ctx.fonts(|fonts| {
+        fonts
+            .layout_no_wrap(text.to_string(), font.clone(), egui::Color32::WHITE)
+            .size()
+            .x
+    })
+}
+
+/// `text` unchanged if it fits in `max_width`, otherwise the longest
+/// middle-truncated version that does.
+pub fn middle(ctx: &egui::Context, text: &str, font: &egui::FontId, max_width: f32) -> String {
+    if width(ctx, text, font) <= max_width {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let shorten = |keep: usize| -> String {
+        let head = ((keep as f32) * HEAD_SHARE).ceil() as usize;
+        let tail = keep - head.min(keep);
+        chars[..head.min(keep)]
+            .iter()
+            .chain(std::iter::once(&ELLIPSIS))
+            .chain(chars[chars.len() - tail..].iter())
+            .collect()
+    };
+
+    // Binary search for the most characters that still fit
+    let (mut low, mut high) = (0, chars.len().saturating_sub(1));
+    while low < high {
+        let mid = (low + high + 1) / 2;
+        if width(ctx, &shorten(mid), font) <= max_width {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    shorten(low)
+}
@@ -0,0 +1,382 @@
+//! Re-encoding helpers shared by the copy and export paths.
+//!
+//! The encoders used here only ever write pixel data, so anything they produce
+//! is free of EXIF/GPS blocks.
+
+use image::{DynamicImage, ImageFormat};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const SANITIZED_JPEG_QUALITY: u8 = 95;
+
+/// Writes `img` as a JPEG (flattened to RGB) or PNG without any metadata.
+pub fn write_clean(img: &DynamicImage, dest: &Path, format: ImageFormat) -> Result<(), String> {
+    let describe = |e: &dyn std::fmt::Display| format!("{}: {}", dest.display(), e);
+    match format {
+        ImageFormat::Jpeg => {
+            let rgb = flatten(img, [255, 255, 255]);
+            let file = std::fs::File::create(dest).map_err(|e| describe(&e))?;
+            let mut writer = std::io::BufWriter::new(file);
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, SANITIZED_JPEG_QUALITY)
+                .encode(rgb.as_raw(), rgb.width(), rgb.height(), image::ColorType::Rgb8)
+                .map_err(|e| describe(&e))
+        }
+        _ => img
+            .save_with_format(dest, ImageFormat::Png)
+            .map_err(|e| describe(&e)),
+    }
+}
+
+/// Re-encodes `source` into `dest_dir`, keeping JPEGs as JPEG and writing everything else as PNG.
+/// Each source gets its own subfolder named after a hash of its full path, so
+/// same-named files from different folders don't overwrite each other while the
+/// copy keeps the original file name.
+pub fn sanitized_copy(source: &Path, dest_dir: &Path) -> Result<PathBuf, String> {
+    let img = crate::formats::open(&source.to_string_lossy()).map_err(|e| format!("{}: {}", source.display(), e))?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    let dest_dir = dest_dir.join(format!("{:016x}", hasher.finish()));
+    std::fs::create_dir_all(&dest_dir).map_err(|e| format!("{}: {}", dest_dir.display(), e))?;
+
+    let format = match ImageFormat::from_path(source) {
+        Ok(ImageFormat::Jpeg) => ImageFormat::Jpeg,
+        _ => ImageFormat::Png,
+    };
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "image".to_string());
+    let extension = if format == ImageFormat::Jpeg { "jpg" } else { "png" };
+    let dest = dest_dir.join(format!("{}.{}", stem, extension));
+
+    write_clean(&img, &dest, format)?;
+    Ok(dest)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Png, ExportFormat::Jpeg, ExportFormat::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG",
+            ExportFormat::Jpeg => "JPEG",
+            ExportFormat::WebP => "WebP (lossless)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::WebP => "webp",
+        }
+    }
+}
+
+/// How an image is turned into output bytes; shared by export and resized copies.
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    pub format: ExportFormat,
+    pub jpeg_quality: u8,
+    /// Longest side in pixels; larger images are scaled down, smaller ones untouched.
+    pub max_dimension: Option<u32>,
+    /// Color transparent pixels are composited over when the format has no alpha.
+    pub background: [u8; 3],
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Png,
+            jpeg_quality: 90,
+            max_dimension: None,
+            background: [255, 255, 255],
+        }
+    }
+}
+
+/// Scales `img` down so its longest side fits `max_dimension`.
+pub fn resize_to_fit(img: DynamicImage, max_dimension: Option<u32>) -> DynamicImage {
+    match max_dimension {
+        Some(max) if max > 0 && (img.width() > max || img.height() > max) => {
+            img.resize(max, max, image::imageops::FilterType::Lanczos3)
+        }
+        _ => img,
+    }
+}
+
+/// Blends every pixel over an opaque background, so alpha doesn't turn black in JPEG.
+pub fn flatten(img: &DynamicImage, background: [u8; 3]) -> image::RgbImage {
+    let rgba = img.to_rgba8();
+    let mut out = image::RgbImage::new(rgba.width(), rgba.height());
+    for (src, dst) in rgba.pixels().zip(out.pixels_mut()) {
+        let alpha = src[3] as u32;
+        for ((out_channel, &color), &back) in dst.0.iter_mut().zip(src.0.iter()).zip(background.iter()) {
+            *out_channel = ((color as u32 * alpha + back as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+    out
+}
+
+/// Full pipeline: decode, resize, flatten when needed, encode to `dest`.
+pub fn export(source: &Path, dest: &Path, options: &EncodeOptions) -> Result<(), String> {
+    let img = crate::formats::open(&source.to_string_lossy()).map_err(|e| format!("{}: {}", source.display(), e))?;
+    let img = resize_to_fit(img, options.max_dimension);
+    let describe = |e: &dyn std::fmt::Display| format!("{}: {}", dest.display(), e);
+
+    match options.format {
+        ExportFormat::Png => img.save_with_format(dest, ImageFormat::Png).map_err(|e| describe(&e)),
+        ExportFormat::WebP => img.save_with_format(dest, ImageFormat::WebP).map_err(|e| describe(&e)),
+        ExportFormat::Jpeg => {
+            let rgb = flatten(&img, options.background);
+            let file = std::fs::File::create(dest).map_err(|e| describe(&e))?;
+            let mut writer = std::io::BufWriter::new(file);
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, options.jpeg_quality)
+                .encode(rgb.as_raw(), rgb.width(), rgb.height(), image::ColorType::Rgb8)
+                .map_err(|e| describe(&e))
+        }
+    }
+}
+
+/// Most images a combined sheet accepts.
+pub const MAX_SHEET_IMAGES: usize = 12;
+
+/// Where each image goes on a sheet, plus the sheet's size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetLayout {
+    pub width: u32,
+    pub height: u32,
+    /// Top-left corner of each image, in input order.
+    pub positions: Vec<(u32, u32)>,
+}
+
+/// One row for up to three images, otherwise two rows. Each image is centered
+/// in a cell as wide as its column's widest image and as tall as its row's tallest.
+pub fn sheet_layout(sizes: &[(u32, u32)], padding: u32) -> SheetLayout {
+    let rows = if sizes.len() <= 3 { 1 } else { 2 };
+    let columns = sizes.len().div_ceil(rows).max(1);
+
+    let mut column_widths = vec![0; columns];
+    let mut row_heights = vec![0; rows];
+    for (index, &(w, h)) in sizes.iter().enumerate() {
+        let (row, column) = (index / columns, index % columns);
+        column_widths[column] = column_widths[column].max(w);
+        row_heights[row] = row_heights[row].max(h);
+    }
+
+    let offsets = |lengths: &[u32]| -> Vec<u32> {
+        lengths
+            .iter()
+            .scan(padding, |next, &length| {
+                let start = *next;
+                *next += length + padding;
+                Some(start)
+            })
+            .collect()
+    };
+    let column_x = offsets(&column_widths);
+    let row_y = offsets(&row_heights);
+
+    let positions = sizes
+        .iter()
+        .enumerate()
+        .map(|(index, &(w, h))| {
+            let (row, column) = (index / columns, index % columns);
+            (
+                column_x[column] + (column_widths[column] - w) / 2,
+                row_y[row] + (row_heights[row] - h) / 2,
+            )
+        })
+        .collect();
+
+    SheetLayout {
+        width: column_widths.iter().sum::<u32>() + padding * (columns as u32 + 1),
+        height: row_heights.iter().sum::<u32>() + padding * (rows as u32 + 1),
+        positions,
+    }
+}
+
+/// Composites images onto one sheet over `background`, each scaled to fit `max_dimension`.
+pub fn compose_sheet(
+    images: Vec<DynamicImage>,
+    padding: u32,
+    background: [u8; 4],
+    max_dimension: Option<u32>,
+) -> Result<image::RgbaImage, String> {
+    if images.is_empty() {
+        return Err("Nothing to combine".to_string());
+    }
+    if images.len() > MAX_SHEET_IMAGES {
+        return Err(format!("Select at most {} images to combine", MAX_SHEET_IMAGES));
+    }
+
+    let tiles: Vec<image::RgbaImage> = images
+        .into_iter()
+        .map(|img| resize_to_fit(img, max_dimension).to_rgba8())
+        .collect();
+    let sizes: Vec<(u32, u32)> = tiles.iter().map(|t| t.dimensions()).collect();
+    let layout = sheet_layout(&sizes, padding);
+
+    let mut sheet = image::RgbaImage::from_pixel(layout.width, layout.height, image::Rgba(background));
+    for (tile, &(x, y)) in tiles.iter().zip(&layout.positions) {
+        image::imageops::overlay(&mut sheet, tile, x as i64, y as i64);
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// A minimal EXIF block: little-endian TIFF header and one IFD entry,
+    /// Make (0x010f) as 5 ASCII bytes at offset 26.
+    fn exif_block() -> Vec<u8> {
+        let mut tiff = b"II*\0\x08\0\0\0\x01\0\x0f\x01\x02\0\x05\0\0\0\x1a\0\0\0\0\0\0\0".to_vec();
+        tiff.extend_from_slice(b"Test\0");
+        tiff
+    }
+
+    /// A JPEG with an APP1 EXIF segment right after SOI.
+    fn jpeg_with_exif(path: &Path) {
+        let mut encoded = Vec::new();
+        let img = image::RgbImage::from_pixel(8, 8, image::Rgb([200, 30, 30]));
+        image::codecs::jpeg::JpegEncoder::new(&mut encoded)
+            .encode(img.as_raw(), 8, 8, image::ColorType::Rgb8)
+            .unwrap();
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&exif_block());
+        let length = (app1.len() + 2) as u16;
+
+        let mut bytes = encoded[..2].to_vec();
+        bytes.extend_from_slice(&[0xff, 0xe1]);
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(&app1);
+        bytes.extend_from_slice(&encoded[2..]);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    fn has_exif(path: &Path) -> bool {
+        let file = std::fs::File::open(path).unwrap();
+        exif::Reader::new()
+            .read_from_container(&mut std::io::BufReader::new(file))
+            .is_ok()
+    }
+
+    #[test]
+    fn sanitized_jpeg_has_no_exif() {
+        let dir = TestDir::new("encode-jpeg");
+        let source = dir.join("photo.jpg");
+        jpeg_with_exif(&source);
+        assert!(has_exif(&source));
+
+        let copy = sanitized_copy(&source, &dir.join("out")).unwrap();
+        assert_eq!(copy.file_name().unwrap(), "photo.jpg");
+        assert_eq!(ImageFormat::from_path(&copy).unwrap(), ImageFormat::Jpeg);
+        assert!(!has_exif(&copy));
+    }
+
+    #[test]
+    fn sanitized_png_has_no_exif() {
+        let dir = TestDir::new("encode-png");
+        let source = dir.join("scan.png");
+        image::RgbImage::from_pixel(8, 8, image::Rgb([30, 200, 30])).save(&source).unwrap();
+        // An eXIf chunk; the CRC isn't checked by either reader
+        let mut png = std::fs::read(&source).unwrap();
+        let exif = exif_block();
+        let mut chunk = (exif.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"eXIf");
+        chunk.extend_from_slice(&exif);
+        chunk.extend_from_slice(&[0; 4]);
+        // Right after the 8-byte signature and the 25-byte IHDR chunk
+        png.splice(33..33, chunk);
+        std::fs::write(&source, png).unwrap();
+        assert!(has_exif(&source));
+
+        let copy = sanitized_copy(&source, &dir.join("out")).unwrap();
+        assert_eq!(copy.file_name().unwrap(), "scan.png");
+        assert!(image::open(&copy).is_ok());
+        assert!(!has_exif(&copy));
+    }
+
+    #[test]
+    fn same_stem_from_different_folders_doesnt_collide() {
+        let dir = TestDir::new("encode-collide");
+        let out = dir.join("out");
+        let mut copies = Vec::new();
+        for (folder, color) in [("a", [255, 0, 0]), ("b", [0, 0, 255])] {
+            std::fs::create_dir_all(dir.join(folder)).unwrap();
+            let source = dir.join(folder).join("icon.png");
+            image::RgbImage::from_pixel(4, 4, image::Rgb(color)).save(&source).unwrap();
+            copies.push((sanitized_copy(&source, &out).unwrap(), color));
+        }
+
+        assert_ne!(copies[0].0, copies[1].0);
+        for (copy, color) in copies {
+            assert_eq!(copy.file_name().unwrap(), "icon.png");
+            assert_eq!(image::open(&copy).unwrap().to_rgb8().get_pixel(0, 0).0, color);
+        }
+    }
+
+    #[test]
+    fn up_to_three_images_share_one_row() {
+        let layout = sheet_layout(&[(100, 50), (40, 80), (60, 60)], 10);
+        assert_eq!((layout.width, layout.height), (100 + 40 + 60 + 4 * 10, 80 + 2 * 10));
+        // Vertically centered in the 80 px row
+        assert_eq!(layout.positions, vec![(10, 25), (120, 10), (170, 20)]);
+    }
+
+    #[test]
+    fn more_than_three_images_use_two_rows() {
+        let sizes = [(10, 10), (30, 20), (20, 10), (10, 40), (20, 20)];
+        let layout = sheet_layout(&sizes, 5);
+        // Three columns: widest per column is 10, 30, 20; rows are 20 and 40 tall
+        assert_eq!((layout.width, layout.height), (10 + 30 + 20 + 4 * 5, 20 + 40 + 3 * 5));
+        assert_eq!(layout.positions, vec![(5, 10), (20, 5), (55, 10), (5, 30), (25, 40)]);
+    }
+
+    #[test]
+    fn layout_without_padding_is_tight() {
+        let layout = sheet_layout(&[(8, 8), (8, 8), (8, 8), (8, 8)], 0);
+        assert_eq!((layout.width, layout.height), (16, 16));
+        assert_eq!(layout.positions, vec![(0, 0), (8, 0), (0, 8), (8, 8)]);
+    }
+
+    #[test]
+    fn sheet_is_filled_with_tiles_and_background() {
+        let red = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255])));
+        let blue = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(2, 4, image::Rgba([0, 0, 255, 255])));
+        let background = [0, 255, 0, 255];
+        let sheet = compose_sheet(vec![red, blue], 1, background, None).unwrap();
+
+        assert_eq!(sheet.dimensions(), (4 + 2 + 3, 4 + 2));
+        assert_eq!(sheet.get_pixel(0, 0).0, background);
+        assert_eq!(sheet.get_pixel(1, 2).0, [255, 0, 0, 255]);
+        assert_eq!(sheet.get_pixel(6, 1).0, [0, 0, 255, 255]);
+        // Above and below the shorter red tile
+        assert_eq!(sheet.get_pixel(1, 1).0, background);
+        assert_eq!(sheet.get_pixel(1, 4).0, background);
+    }
+
+    #[test]
+    fn sheet_tiles_are_scaled_to_max_dimension() {
+        let big = DynamicImage::ImageRgba8(image::RgbaImage::new(200, 100));
+        let small = DynamicImage::ImageRgba8(image::RgbaImage::new(20, 20));
+        let sheet = compose_sheet(vec![big, small], 0, [0; 4], Some(50)).unwrap();
+        assert_eq!(sheet.dimensions(), (50 + 20, 25));
+    }
+
+    #[test]
+    fn sheet_rejects_empty_and_oversized_selections() {
+        assert!(compose_sheet(Vec::new(), 0, [0; 4], None).is_err());
+        let images = vec![DynamicImage::ImageRgba8(image::RgbaImage::new(1, 1)); MAX_SHEET_IMAGES + 1];
+        assert!(compose_sheet(images, 0, [0; 4], None).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Typed errors for the manifest, thumbnail loading, the clipboard and the
+//! settings file.
+//!
+//! Code below the UI returns these so callers can tell causes apart: a parse
+//! error has a line to show, and a missing file isn't worth retrying. They
+//! become text only where the UI reports them, and every message names the
+//! file involved.
+
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum ChlorineError {
+    Manifest(ManifestError),
+    Loader(LoaderError),
+    Clipboard(String),
+    Settings { path: PathBuf, message: String },
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io { path: String, source: std::io::Error },
+    /// Not valid JSON; `line` and `column` are 1-based, the column in bytes.
+    Parse { path: String, line: usize, column: usize, message: String },
+    /// Valid JSON that isn't a manifest, or an edit that doesn't apply to it.
+    Validation { path: String, message: String },
+}
+
+/// Why a thumbnail couldn't be loaded.
+#[derive(Debug, Clone)]
+pub enum LoaderError {
+    NotFound { path: String },
+    Decode { path: String, message: String },
+    /// IO errors and empty reads, typically a file still being synced. The
+    /// only kind that is retried.
+    Unreadable { path: String, message: String },
+}
+
+impl ManifestError {
+    pub fn io(path: &str, source: std::io::Error) -> Self {
+        ManifestError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    /// Splits serde_json errors into syntax errors (with a line to show) and
+    /// everything else.
+    pub fn json(path: &str, error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Syntax | serde_json::error::Category::Eof => ManifestError::Parse {
+                path: path.to_string(),
+                line: error.line(),
+                column: error.column(),
+                message: error.to_string(),
+            },
+            serde_json::error::Category::Io => ManifestError::Io {
+                path: path.to_string(),
+                source: error.into(),
+            },
+            serde_json::error::Category::Data => ManifestError::Validation {
+                path: path.to_string(),
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl LoaderError {
+    /// A failed read: missing files are final, anything else may be retried.
+    pub fn io(path: &str, error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            LoaderError::NotFound { path: path.to_string() }
+        } else {
+            LoaderError::Unreadable {
+                path: path.to_string(),
+                message: error.to_string(),
+            }
+        }
+    }
+
+    /// The cause without the path, for lists that already show it.
+    pub fn reason(&self) -> String {
+        match self {
+            LoaderError::NotFound { .. } => "File not found".to_string(),
+            LoaderError::Decode { message, .. } => format!("Could not decode: {}", message),
+            LoaderError::Unreadable { message, .. } => format!("Could not read: {}", message),
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(self, LoaderError::Unreadable { .. })
+    }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io { path, source } => write!(f, "{}: {}", path, source),
+            ManifestError::Parse { path, message, .. } => write!(f, "{} is not valid JSON: {}", path, message),
+            ManifestError::Validation { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self {
+            LoaderError::NotFound { path } | LoaderError::Decode { path, .. } | LoaderError::Unreadable { path, .. } => path,
+        };
+        write!(f, "{}: {}", path, self.reason())
+    }
+}
+
+impl fmt::Display for ChlorineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChlorineError::Manifest(e) => e.fmt(f),
+            ChlorineError::Loader(e) => e.fmt(f),
+            ChlorineError::Clipboard(message) => write!(f, "clipboard: {}", message),
+            ChlorineError::Settings { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ManifestError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {}
+
+impl std::error::Error for ChlorineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChlorineError::Manifest(e) => Some(e),
+            ChlorineError::Loader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ManifestError> for ChlorineError {
+    fn from(error: ManifestError) -> Self {
+        ChlorineError::Manifest(error)
+    }
+}
+
+impl From<LoaderError> for ChlorineError {
+    fn from(error: LoaderError) -> Self {
+        ChlorineError::Loader(error)
+    }
+}
+
+impl From<arboard::Error> for ChlorineError {
+    fn from(error: arboard::Error) -> Self {
+        ChlorineError::Clipboard(error.to_string())
+    }
+}
@@ -0,0 +1,58 @@
+//! Starred images, stored per profile as an ordered list of full paths.
+
+use crate::store::UserData;
+
+const SECTION: &str = "favorites";
+
+pub struct Favorites {
+    paths: Vec<String>,
+}
+
+impl Favorites {
+    pub fn load(store: &mut UserData) -> Self {
+        Self {
+            paths: store.get(SECTION),
+        }
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.paths)
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.iter().any(|p| p == path)
+    }
+
+    /// Adds or removes a path. Returns true if it is a favorite afterwards.
+    pub fn toggle(&mut self, path: &str) -> bool {
+        if let Some(index) = self.paths.iter().position(|p| p == path) {
+            self.paths.remove(index);
+            false
+        } else {
+            self.paths.push(path.to_string());
+            true
+        }
+    }
+
+    /// Moves the entry at `from` so it ends up at position `to`.
+    pub fn move_item(&mut self, from: usize, to: usize) {
+        if from >= self.paths.len() || from == to {
+            return;
+        }
+        let item = self.paths.remove(from);
+        let to = to.min(self.paths.len());
+        self.paths.insert(to, item);
+    }
+
+    pub fn replace(&mut self, paths: Vec<String>) {
+        self.paths = paths;
+    }
+}
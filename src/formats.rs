@@ -0,0 +1,155 @@
+//! Decoding for every format Chlorine shows, including the optional ones.
+//!
+//! HEIC/HEIF needs libheif (`--features heif`) and AVIF needs dav1d
+//! (`--features avif`); both are system libraries, so they are off by
+//! default. Every decode goes through here so thumbnails, previews, copies
+//! and exports agree on what can be read.
+
+use image::DynamicImage;
+
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+const AVIF_EXTENSIONS: &[&str] = &["avif"];
+
+fn normalized(extension: &str) -> String {
+    extension.trim_start_matches('.').to_ascii_lowercase()
+}
+
+fn is_heif(extension: &str) -> bool {
+    HEIF_EXTENSIONS.contains(&normalized(extension).as_str())
+}
+
+fn is_avif(extension: &str) -> bool {
+    AVIF_EXTENSIONS.contains(&normalized(extension).as_str())
+}
+
+/// Optional formats compiled into this build.
+pub fn supports(extension: &str) -> bool {
+    (cfg!(feature = "heif") && is_heif(extension)) || (cfg!(feature = "avif") && is_avif(extension))
+}
+
+/// Why an optional format can't be read in this build, e.g. for the info tile's tooltip.
+pub fn missing_support(extension: &str) -> Option<&'static str> {
+    if is_heif(extension) && !cfg!(feature = "heif") {
+        Some("Built without HEIC support")
+    } else if is_avif(extension) && !cfg!(feature = "avif") {
+        Some("Built without AVIF support")
+    } else {
+        None
+    }
+}
+
+fn extension_of(path: &str) -> String {
+    std::path::Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Decodes a file already read into memory. `path` only picks the decoder.
+pub fn decode(path: &str, bytes: &[u8]) -> Result<DynamicImage, String> {
+    if is_heif(&extension_of(path)) {
+        return decode_heif(bytes);
+    }
+    // AVIF goes through the image crate, whose decoder the `avif` feature enables
+    image::load_from_memory(bytes).map_err(|e| e.to_string())
+}
+
+pub fn open(path: &str) -> Result<DynamicImage, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    decode(path, &bytes)
+}
+
+#[cfg(feature = "heif")]
+fn decode_heif(bytes: &[u8]) -> Result<DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib = LibHeif::new();
+    let context = HeifContext::read_from_bytes(bytes).map_err(|e| e.to_string())?;
+    let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = lib
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| e.to_string())?;
+    let plane = decoded.planes().interleaved.ok_or("HEIC image has no RGBA plane")?;
+    let row_bytes = plane.width as usize * 4;
+    // Rows are padded to `stride`
+    let mut pixels = Vec::with_capacity(row_bytes * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    image::RgbaImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "HEIC image has fewer pixels than its size".to_string())
+}
+
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_bytes: &[u8]) -> Result<DynamicImage, String> {
+    Err("built without HEIC support".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fixtures are 8×6, red on the left half and blue on the right.
+    fn assert_red_and_blue(img: &DynamicImage) {
+        assert_eq!((img.width(), img.height()), (8, 6));
+        let rgb = img.to_rgb8();
+        let [r, _, b] = rgb.get_pixel(0, 3).0;
+        assert!(r > 200 && b < 60, "left edge is {:?}", rgb.get_pixel(0, 3));
+        let [r, _, b] = rgb.get_pixel(7, 3).0;
+        assert!(b > 200 && r < 60, "right edge is {:?}", rgb.get_pixel(7, 3));
+    }
+
+    #[test]
+    fn optional_formats_follow_the_build() {
+        assert_eq!(supports("HEIC"), cfg!(feature = "heif"));
+        assert_eq!(supports(".heif"), cfg!(feature = "heif"));
+        assert_eq!(supports("avif"), cfg!(feature = "avif"));
+        assert_eq!(missing_support(".HEIC").is_some(), !cfg!(feature = "heif"));
+        assert_eq!(missing_support("avif").is_some(), !cfg!(feature = "avif"));
+        // Always-on formats are the image crate's business
+        assert!(!supports("png"));
+        assert!(missing_support("png").is_none());
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[test]
+    fn heic_without_the_feature_says_so() {
+        let error = decode("photo.heic", b"\0\0\0\x18ftypheic").unwrap_err();
+        assert!(error.contains("HEIC"), "{}", error);
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn decodes_avif() {
+        let bytes = include_bytes!("../testdata/tiny.avif");
+        assert_red_and_blue(&decode("tiny.avif", bytes).unwrap());
+    }
+
+    #[cfg(feature = "heif")]
+    #[test]
+    fn decodes_heic() {
+        use libheif_rs::{Channel, ColorSpace, CompressionFormat, EncoderQuality, HeifContext, Image, LibHeif, RgbChroma};
+
+        // Written with libheif's own HEVC encoder, since no HEIC fixture small
+        // enough to check in is freely licensed
+        let mut image = Image::new(8, 6, ColorSpace::Rgb(RgbChroma::Rgb)).unwrap();
+        image.create_plane(Channel::Interleaved, 8, 6, 24).unwrap();
+        let plane = image.planes_mut().interleaved.unwrap();
+        for y in 0..6 {
+            for x in 0..8 {
+                let at = plane.stride * y + x * 3;
+                let color = if x < 4 { [255, 0, 0] } else { [0, 0, 255] };
+                plane.data[at..at + 3].copy_from_slice(&color);
+            }
+        }
+        let lib = LibHeif::new();
+        let mut encoder = lib.encoder_for_format(CompressionFormat::Hevc).unwrap();
+        encoder.set_quality(EncoderQuality::LossLess).unwrap();
+        let mut context = HeifContext::new().unwrap();
+        context.encode_image(&image, &mut encoder, None).unwrap();
+        let bytes = context.write_to_bytes().unwrap();
+
+        assert_red_and_blue(&decode("tiny.heic", &bytes).unwrap());
+    }
+}
@@ -0,0 +1,104 @@
+//! Keeps Chlorine nearly idle while its window is in the background.
+//!
+//! egui only runs a frame when something asks for one, so the cost of an
+//! unfocused window is whatever keeps requesting repaints: spinners, copy
+//! flashes and the polling of background work. While the window is
+//! unfocused or minimized those requests are stretched to
+//! [`BACKGROUND_POLL`]; regaining focus delivers an input event, so the next
+//! frame is immediate. The guard also times frames for the debug overlay.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How often background work is checked on while the window isn't focused.
+pub const BACKGROUND_POLL: Duration = Duration::from_secs(1);
+/// In-flight thumbnails are collected a little sooner, so a visible but
+/// unfocused window still fills in.
+pub const BACKGROUND_THUMBNAIL_POLL: Duration = Duration::from_millis(250);
+/// Frames counted towards the overlay's frame rate.
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct FrameGuard {
+    focused: bool,
+    minimized: bool,
+    /// When the window last went to the background.
+    since: Option<Instant>,
+    frame_start: Option<Instant>,
+    recent_frames: VecDeque<Instant>,
+    /// Smoothed time spent in `update`, in milliseconds.
+    update_ms: f32,
+}
+
+impl FrameGuard {
+    /// Reads the window state at the start of a frame.
+    pub fn begin(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let (focused, minimized) = ctx.input(|i| (i.focused, i.viewport().minimized.unwrap_or(false)));
+        self.focused = focused;
+        self.minimized = minimized;
+        match (self.is_background(), self.since) {
+            (true, None) => self.since = Some(now),
+            (false, Some(_)) => self.since = None,
+            _ => {}
+        }
+        self.frame_start = Some(now);
+        self.recent_frames.push_back(now);
+        while self.recent_frames.front().is_some_and(|&at| now - at > RATE_WINDOW) {
+            self.recent_frames.pop_front();
+        }
+    }
+
+    /// Records how long the frame took.
+    pub fn end(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            let ms = start.elapsed().as_secs_f32() * 1000.0;
+            self.update_ms = if self.update_ms == 0.0 { ms } else { self.update_ms * 0.9 + ms * 0.1 };
+        }
+    }
+
+    /// Unfocused or minimized: animations pause and polling slows down.
+    pub fn is_background(&self) -> bool {
+        !self.focused || self.minimized
+    }
+
+    /// Nothing is drawn, so nothing new needs loading.
+    pub fn is_hidden(&self) -> bool {
+        self.minimized
+    }
+
+    /// `request_repaint_after`, no sooner than [`BACKGROUND_POLL`] in the background.
+    pub fn repaint_after(&self, ctx: &egui::Context, delay: Duration) {
+        let delay = if self.is_background() { delay.max(BACKGROUND_POLL) } else { delay };
+        ctx.request_repaint_after(delay);
+    }
+
+    /// `request_repaint` in the foreground; a slow poll in the background.
+    pub fn repaint(&self, ctx: &egui::Context, background: Duration) {
+        if self.is_background() {
+            ctx.request_repaint_after(background);
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    /// "foreground · 60.0 frames/s · update 1.2 ms", for the debug overlay.
+    pub fn describe(&self) -> String {
+        let state = match self.since {
+            Some(since) if self.minimized => format!("minimized {}s", since.elapsed().as_secs()),
+            Some(since) => format!("unfocused {}s", since.elapsed().as_secs()),
+            None => "foreground".to_string(),
+        };
+        let span = self
+            .recent_frames
+            .front()
+            .map_or(0.0, |first| first.elapsed().as_secs_f32())
+            .max(1.0);
+        format!(
+            "{} · {:.1} frames/s · update {:.1} ms",
+            state,
+            self.recent_frames.len() as f32 / span,
+            self.update_ms
+        )
+    }
+}
@@ -0,0 +1,165 @@
+//! Virtual sub-categories for flat folders, inferred from filenames.
+//!
+//! Rules are stored in the manifest (see [`crate::manifest`]) keyed by the
+//! category they split, and re-applied every time the manifest loads, so a
+//! regenerated manifest keeps the same grouping.
+
+use crate::{Category, ImageData, ImageInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Group name for images a rule can't place.
+pub const UNMATCHED_GROUP: &str = "Other";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GroupingRule {
+    /// Text before the first of these delimiter characters, e.g. "slack" from "slack_1234.png".
+    Prefix { delimiters: String },
+    /// Regex with a named `category` capture.
+    Pattern { regex: String },
+    /// "2024-05" from a date in the filename, falling back to the file's modification time.
+    YearMonth,
+}
+
+impl GroupingRule {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupingRule::Prefix { .. } => "Filename prefix",
+            GroupingRule::Pattern { .. } => "Custom regex",
+            GroupingRule::YearMonth => "Year-month",
+        }
+    }
+
+    /// Checks the rule can run; only regexes can be invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            GroupingRule::Pattern { regex } => compile(regex).map(|_| ()),
+            GroupingRule::Prefix { delimiters } if delimiters.is_empty() => {
+                Err("Enter at least one delimiter character".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn compile(pattern: &str) -> Result<regex::Regex, String> {
+    let regex = regex::Regex::new(pattern).map_err(|e| e.to_string())?;
+    if !regex.capture_names().any(|name| name == Some("category")) {
+        return Err("The regex needs a named capture: (?P<category>...)".to_string());
+    }
+    Ok(regex)
+}
+
+/// Splits `images` into groups, keyed by group name.
+pub fn group(images: &[ImageInfo], rule: &GroupingRule) -> Result<BTreeMap<String, Vec<ImageInfo>>, String> {
+    let regex = match rule {
+        GroupingRule::Pattern { regex } => Some(compile(regex)?),
+        _ => None,
+    };
+
+    let mut groups: BTreeMap<String, Vec<ImageInfo>> = BTreeMap::new();
+    for image in images {
+        let stem = image
+            .filename
+            .rsplit_once('.')
+            .map_or(image.filename.as_str(), |(stem, _)| stem);
+        let name = match rule {
+            GroupingRule::Prefix { delimiters } => stem
+                .split_once(|c: char| delimiters.contains(c))
+                .map(|(prefix, _)| prefix.trim().to_lowercase())
+                .filter(|prefix| !prefix.is_empty()),
+            GroupingRule::Pattern { .. } => regex
+                .as_ref()
+                .and_then(|regex| regex.captures(&image.filename))
+                .and_then(|captures| captures.name("category"))
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|name| !name.is_empty()),
+            GroupingRule::YearMonth => year_month_from_name(stem).or_else(|| year_month_from_mtime(&image.full_path)),
+        };
+        groups
+            .entry(name.unwrap_or_else(|| UNMATCHED_GROUP.to_string()))
+            .or_default()
+            .push(image.clone());
+    }
+    Ok(groups)
+}
+
+/// Replaces each category that has a rule with "<category>/<group>" categories.
+/// Categories whose rule fails (e.g. a regex edited by hand) are left as they are.
+pub fn apply(data: &mut ImageData, rules: &BTreeMap<String, GroupingRule>) {
+    for (source, rule) in rules {
+        let Some(category) = data.categories.get(source) else {
+            continue;
+        };
+        let Ok(groups) = group(&category.images, rule) else {
+            continue;
+        };
+        let directory = category.directory.clone();
+        data.categories.remove(source);
+        for (name, images) in groups {
+            data.categories.insert(
+                format!("{}/{}", source, name),
+                Category {
+                    directory: directory.clone(),
+                    count: images.len() as u32,
+                    images,
+                },
+            );
+        }
+    }
+}
+
+/// Finds a `YYYY-MM`, `YYYY_MM` or `YYYYMMDD` date in a filename stem.
+fn year_month_from_name(stem: &str) -> Option<String> {
+    let bytes = stem.as_bytes();
+    let digits = |range: std::ops::Range<usize>| -> Option<u32> {
+        let slice = bytes.get(range)?;
+        if slice.iter().all(u8::is_ascii_digit) {
+            std::str::from_utf8(slice).ok()?.parse().ok()
+        } else {
+            None
+        }
+    };
+    let plausible = |year: u32, month: u32| (1970..=2100).contains(&year) && (1..=12).contains(&month);
+
+    for start in 0..bytes.len() {
+        // Don't start in the middle of a longer number
+        if start > 0 && bytes[start - 1].is_ascii_digit() {
+            continue;
+        }
+        let Some(year) = digits(start..start + 4) else {
+            continue;
+        };
+        let separated = matches!(bytes.get(start + 4), Some(b'-' | b'_' | b'.'));
+        let month = if separated {
+            digits(start + 5..start + 7)
+        } else {
+            digits(start + 4..start + 6).filter(|_| digits(start + 6..start + 8).is_some())
+        };
+        if let Some(month) = month.filter(|&month| plausible(year, month)) {
+            return Some(format!("{:04}-{:02}", year, month));
+        }
+    }
+    None
+}
+
+fn year_month_from_mtime(path: &str) -> Option<String> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let seconds = modified.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs();
+    let (year, month) = civil_from_days((seconds / 86_400) as i64);
+    Some(format!("{:04}-{:02}", year, month))
+}
+
+/// Days since 1970-01-01 to (year, month), per Howard Hinnant's civil calendar algorithm.
+fn civil_from_days(days: i64) -> (i64, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month)
+}
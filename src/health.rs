@@ -0,0 +1,138 @@
+//! Quick startup checks that turn silent failure modes into actionable messages.
+
+use std::path::{Path, PathBuf};
+
+/// How many manifest entries are probed for existence.
+pub const SAMPLE_COUNT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fix {
+    PickManifest,
+    CheckPermissions,
+    CheckClipboard,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthIssue {
+    pub check: &'static str,
+    pub problem: String,
+    pub suggestion: String,
+    pub fix: Fix,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub checks_run: usize,
+    pub issues: Vec<HealthIssue>,
+    /// Sampled images that exist only as cloud-sync placeholders.
+    pub cloud_placeholders: usize,
+    pub sampled: usize,
+}
+
+impl HealthReport {
+    pub fn summary(&self) -> String {
+        match self.issues.len() {
+            0 => "✅ Library OK".to_string(),
+            1 => "⚠ 1 issue found (click to view)".to_string(),
+            n => format!("⚠ {} issues found (click to view)", n),
+        }
+    }
+}
+
+pub struct HealthOptions {
+    pub manifest_path: String,
+    /// A spread-out sample of library paths to probe (see [`sample_paths`]).
+    pub sample_paths: Vec<String>,
+    pub cache_dir: PathBuf,
+    pub check_manifest: bool,
+    pub check_sample_files: bool,
+    pub check_cache_dir: bool,
+    pub check_clipboard: bool,
+}
+
+pub fn run(options: HealthOptions) -> HealthReport {
+    let mut report = HealthReport::default();
+
+    if options.check_manifest {
+        report.checks_run += 1;
+        if let Err(e) = std::fs::File::open(&options.manifest_path) {
+            let cwd = std::env::current_dir()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            report.issues.push(HealthIssue {
+                check: "Manifest",
+                problem: format!("Cannot read {} ({}), working directory is {}", options.manifest_path, e, cwd),
+                suggestion: "Pick the manifest file for this profile, or start Chlorine from the library folder."
+                    .to_string(),
+                fix: Fix::PickManifest,
+            });
+        }
+    }
+
+    if options.check_sample_files && !options.sample_paths.is_empty() {
+        report.checks_run += 1;
+        let sample = &options.sample_paths;
+        let missing: Vec<&String> = sample.iter().filter(|p| !Path::new(p).exists()).collect();
+        report.sampled = sample.len();
+        report.cloud_placeholders = sample
+            .iter()
+            .filter(|p| crate::cloud::is_placeholder(Path::new(p)))
+            .count();
+        if !missing.is_empty() {
+            report.issues.push(HealthIssue {
+                check: "Image files",
+                problem: format!(
+                    "{} of {} sampled images are missing (e.g. {})",
+                    missing.len(),
+                    sample.len(),
+                    missing[0]
+                ),
+                suggestion: "Paths in the manifest may be relative to another folder or a drive that isn't mounted. \
+                    Regenerate the manifest or start Chlorine from the folder it was created in."
+                    .to_string(),
+                fix: Fix::PickManifest,
+            });
+        }
+    }
+
+    if options.check_cache_dir {
+        report.checks_run += 1;
+        if let Err(e) = probe_writable(&options.cache_dir) {
+            report.issues.push(HealthIssue {
+                check: "Cache directory",
+                problem: format!("{} is not writable ({})", options.cache_dir.display(), e),
+                suggestion: "Fix the folder's permissions or free up disk space; thumbnails will not be cached."
+                    .to_string(),
+                fix: Fix::CheckPermissions,
+            });
+        }
+    }
+
+    if options.check_clipboard {
+        report.checks_run += 1;
+        if let Err(e) = arboard::Clipboard::new() {
+            report.issues.push(HealthIssue {
+                check: "Clipboard",
+                problem: format!("Clipboard is not accessible ({})", e),
+                suggestion: "On Wayland, make sure a clipboard portal or wl-clipboard is available.".to_string(),
+                fix: Fix::CheckClipboard,
+            });
+        }
+    }
+
+    report
+}
+
+/// Picks up to [`SAMPLE_COUNT`] paths spread evenly over the library.
+pub fn sample_paths<'a>(paths: impl Iterator<Item = &'a str>, total: usize) -> Vec<String> {
+    let step = (total / SAMPLE_COUNT).max(1);
+    paths.step_by(step).take(SAMPLE_COUNT).map(str::to_string).collect()
+}
+
+/// Creates and removes a scratch file to prove the directory accepts writes.
+fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(".chlorine_write_test");
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(probe)
+}
@@ -0,0 +1,59 @@
+//! Per-profile list of images the user never wants in results.
+//!
+//! Paths are stored normalized (see [`normalize_path`]) so the same file
+//! matches whether the manifest was written on Windows or Unix.
+
+use crate::search::normalize_path;
+use crate::store::UserData;
+use std::collections::BTreeSet;
+
+const SECTION: &str = "hidden";
+
+pub struct HiddenList {
+    paths: BTreeSet<String>,
+}
+
+impl HiddenList {
+    pub fn load(store: &mut UserData) -> Self {
+        Self {
+            paths: store.get(SECTION),
+        }
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.paths)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(&normalize_path(path))
+    }
+
+    /// Hides or unhides a path. Returns true if it is hidden afterwards.
+    pub fn toggle(&mut self, path: &str) -> bool {
+        let path = normalize_path(path);
+        if self.paths.remove(&path) {
+            false
+        } else {
+            self.paths.insert(path);
+            true
+        }
+    }
+
+    /// Replaces the whole list.
+    pub fn replace(&mut self, paths: impl IntoIterator<Item = String>) {
+        self.paths = paths.into_iter().map(|path| normalize_path(&path)).collect();
+    }
+
+    /// Normalized paths, sorted.
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.paths.iter()
+    }
+}
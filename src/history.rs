@@ -0,0 +1,61 @@
+//! Recently committed search queries, newest first, stored per profile.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+/// Queries kept in the ring.
+pub const MAX_ENTRIES: usize = 20;
+/// Shorter queries are too trivial to be worth recalling.
+const MIN_QUERY_CHARS: usize = 2;
+
+pub struct SearchHistory {
+    queries: VecDeque<String>,
+    file: PathBuf,
+}
+
+impl SearchHistory {
+    pub fn load(file: PathBuf) -> Self {
+        let queries = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { queries, file }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&self.queries).map_err(|e| e.to_string())?;
+        std::fs::write(&self.file, json).map_err(|e| format!("{}: {}", self.file.display(), e))
+    }
+
+    /// Moves `query` to the top, dropping the oldest entry when full.
+    /// Returns false if the query was too short or already on top.
+    pub fn record(&mut self, query: &str) -> bool {
+        let query = query.trim();
+        if query.chars().count() < MIN_QUERY_CHARS || self.queries.front().is_some_and(|q| q == query) {
+            return false;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.push_front(query.to_string());
+        self.queries.truncate(MAX_ENTRIES);
+        true
+    }
+
+    pub fn queries(&self) -> impl Iterator<Item = &String> {
+        self.queries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.queries.clear();
+    }
+}
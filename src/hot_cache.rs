@@ -0,0 +1,106 @@
+//! A small per-profile snapshot of the last session's favorites and top
+//! results, with their thumbnails, so there is something to copy while a
+//! large manifest is still loading.
+//!
+//! Written on exit; thumbnails are kept as PNG files next to the list and
+//! only re-encoded for entries that don't have one yet.
+
+use crate::ImageInfo;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+pub const MAX_ENTRIES: usize = 50;
+
+/// Same size as the thumbnails in the result list.
+const THUMBNAIL_SIZE: u32 = 128;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HotEntry {
+    pub category: String,
+    pub image: ImageInfo,
+    /// The file was gone when the cache was loaded.
+    #[serde(skip)]
+    pub missing: bool,
+}
+
+#[derive(Default)]
+pub struct HotCache {
+    entries: Vec<HotEntry>,
+    dir: PathBuf,
+}
+
+fn list_path(dir: &Path) -> PathBuf {
+    dir.join("hot_cache.json")
+}
+
+fn thumbnail_dir(dir: &Path) -> PathBuf {
+    dir.join("hot_thumbnails")
+}
+
+fn thumbnail_name(full_path: &str) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    full_path.hash(&mut hasher);
+    format!("{:016x}.png", hasher.finish())
+}
+
+impl HotCache {
+    /// `dir` is the profile's sidecar folder.
+    pub fn load(dir: PathBuf) -> Self {
+        let mut entries: Vec<HotEntry> = std::fs::read_to_string(list_path(&dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        entries.truncate(MAX_ENTRIES);
+        for entry in &mut entries {
+            entry.missing = !Path::new(&entry.image.full_path).exists();
+        }
+        Self { entries, dir }
+    }
+
+    pub fn entries(&self) -> &[HotEntry] {
+        &self.entries
+    }
+
+    /// The stored thumbnail for an entry, if one was written.
+    pub fn thumbnail(&self, full_path: &str) -> Option<egui::ColorImage> {
+        let path = thumbnail_dir(&self.dir).join(thumbnail_name(full_path));
+        let rgba = image::open(path).ok()?.to_rgba8();
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        Some(egui::ColorImage::from_rgba_unmultiplied(size, &rgba.into_raw()))
+    }
+
+    /// Replaces the snapshot with `entries` (first `MAX_ENTRIES` kept), writing
+    /// thumbnails that don't exist yet and removing ones no longer listed.
+    pub fn save(&mut self, mut entries: Vec<HotEntry>) -> Result<(), String> {
+        entries.truncate(MAX_ENTRIES);
+        let thumbnails = thumbnail_dir(&self.dir);
+        std::fs::create_dir_all(&thumbnails).map_err(|e| format!("{}: {}", thumbnails.display(), e))?;
+
+        let keep: Vec<String> = entries.iter().map(|entry| thumbnail_name(&entry.image.full_path)).collect();
+        for (entry, name) in entries.iter().zip(&keep) {
+            let file = thumbnails.join(name);
+            if file.exists() {
+                continue;
+            }
+            // Unreadable images just have no cached thumbnail
+            if let Ok(image) = crate::formats::open(&entry.image.full_path) {
+                let _ = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8().save(&file);
+            }
+        }
+        if let Ok(existing) = std::fs::read_dir(&thumbnails) {
+            for file in existing.filter_map(|entry| entry.ok()) {
+                if !keep.iter().any(|name| file.file_name().to_string_lossy() == name.as_str()) {
+                    let _ = std::fs::remove_file(file.path());
+                }
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+        let list = list_path(&self.dir);
+        std::fs::write(&list, json).map_err(|e| format!("{}: {}", list.display(), e))?;
+        self.entries = entries;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! The global show/hide shortcut. It is registered with the OS on first use
+//! and re-registered whenever the setting changes, so edits apply without a
+//! restart.
+//!
+//! Presses are delivered on the hotkey thread, which wakes the UI, so a
+//! window in the background doesn't have to poll for them.
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::sync::mpsc::{self, Receiver};
+
+#[derive(Default)]
+pub struct GlobalHotkey {
+    manager: Option<GlobalHotKeyManager>,
+    /// The setting text and the hotkey registered for it.
+    registered: Option<(String, HotKey)>,
+    /// The setting text the last attempt was for, so a bad value isn't retried every frame.
+    attempted: Option<String>,
+    /// Events forwarded by the handler installed in [`GlobalHotkey::wake`].
+    events: Option<Receiver<GlobalHotKeyEvent>>,
+    /// Why the current setting isn't registered.
+    pub error: Option<String>,
+}
+
+impl GlobalHotkey {
+    /// Registers `spec` (e.g. "Ctrl+Shift+C") if it isn't already, replacing
+    /// the previous hotkey. An empty spec unregisters.
+    pub fn sync(&mut self, spec: &str) {
+        let spec = spec.trim();
+        if self.attempted.as_deref() == Some(spec) {
+            return;
+        }
+        self.attempted = Some(spec.to_string());
+        self.error = None;
+
+        if self.manager.is_none() {
+            match GlobalHotKeyManager::new() {
+                Ok(manager) => self.manager = Some(manager),
+                Err(e) => {
+                    self.error = Some(format!("Global hotkeys aren't available: {}", e));
+                    return;
+                }
+            }
+        }
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        if let Some((_, hotkey)) = self.registered.take() {
+            let _ = manager.unregister(hotkey);
+        }
+        if spec.is_empty() {
+            return;
+        }
+        let hotkey: HotKey = match spec.parse() {
+            Ok(hotkey) => hotkey,
+            Err(e) => {
+                self.error = Some(format!("\"{}\" isn't a valid shortcut: {}", spec, e));
+                return;
+            }
+        };
+        match manager.register(hotkey) {
+            Ok(()) => self.registered = Some((spec.to_string(), hotkey)),
+            Err(e) => self.error = Some(format!("Could not register {}: {}", spec, e)),
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.registered.is_some()
+    }
+
+    /// Repaints `ctx` whenever a hotkey event arrives. Installed once; events
+    /// then go to this struct instead of the crate's global receiver.
+    pub fn wake(&mut self, ctx: &egui::Context) {
+        if self.events.is_some() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        }));
+        self.events = Some(receiver);
+    }
+
+    /// True if the hotkey was pressed since the last call.
+    pub fn pressed(&self) -> bool {
+        let Some((_, hotkey)) = &self.registered else {
+            return false;
+        };
+        let pressed = |event: &GlobalHotKeyEvent| event.id == hotkey.id() && event.state == HotKeyState::Pressed;
+        match &self.events {
+            Some(events) => events.try_iter().filter(pressed).count() > 0,
+            None => GlobalHotKeyEvent::receiver().try_iter().filter(pressed).count() > 0,
+        }
+    }
+}
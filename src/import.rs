@@ -0,0 +1,213 @@
+//! "Import folder into category": adds a folder of loose images to an
+//! existing category, copying, moving or referencing them in place. The new
+//! entries are handed back so the caller can queue the manifest update.
+
+use crate::ImageInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    Move,
+    /// Leave files where they are; the manifest points at them.
+    Reference,
+}
+
+impl ImportMode {
+    pub const ALL: [ImportMode; 3] = [ImportMode::Copy, ImportMode::Move, ImportMode::Reference];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportMode::Copy => "Copy into the category folder",
+            ImportMode::Move => "Move into the category folder",
+            ImportMode::Reference => "Reference in place",
+        }
+    }
+}
+
+pub struct ImportPlan {
+    pub source: PathBuf,
+    pub category: String,
+    /// Library root as it appears in `full_path`, e.g. "downloads".
+    pub root: String,
+    /// The category's folder relative to `root`.
+    pub directory: String,
+    pub mode: ImportMode,
+}
+
+/// Shared with the UI while an import runs.
+#[derive(Default)]
+pub struct ImportProgress {
+    pub done: AtomicUsize,
+    pub total: AtomicUsize,
+    /// Set by the UI; the import stops before the next file and keeps what it already did.
+    pub cancelled: AtomicBool,
+}
+
+pub struct ImportSummary {
+    /// Entries to add to the category, already on disk where they point.
+    pub imported: Vec<ImageInfo>,
+    /// Where each entry in `imported` came from, in the same order.
+    pub sources: Vec<PathBuf>,
+    /// Files skipped because they aren't decodable images.
+    pub skipped: usize,
+    /// Files that couldn't be copied or moved, with the reason.
+    pub failed: Vec<String>,
+}
+
+/// Derives the library root from any entry: `full_path` minus `relative_path`.
+pub fn library_root(image: &ImageInfo) -> Option<String> {
+    let full = image.full_path.replace('\\', "/");
+    let relative = image.relative_path.replace('\\', "/");
+    let root = full.strip_suffix(&relative)?;
+    Some(root.trim_end_matches('/').to_string())
+}
+
+fn join(root: &str, relative: &str) -> String {
+    if root.is_empty() {
+        relative.to_string()
+    } else {
+        format!("{}/{}", root, relative)
+    }
+}
+
+/// "name.png", then "name (1).png", "name (2).png", … until the name is free in `dir`.
+pub fn free_name(dir: &Path, filename: &str) -> String {
+    if !dir.join(filename).exists() {
+        return filename.to_string();
+    }
+    let (stem, extension) = match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (filename, String::new()),
+    };
+    (1..)
+        .map(|n| format!("{} ({}){}", stem, n, extension))
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap_or_else(|| filename.to_string())
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // rename fails across drives; fall back to copy + delete
+    std::fs::rename(from, to).or_else(|_| {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
+    })
+}
+
+/// Copies or moves the files. Meant for a background thread.
+pub fn run(plan: ImportPlan, progress: Arc<ImportProgress>) -> Result<ImportSummary, String> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&plan.source)
+        .map_err(|e| format!("{}: {}", plan.source.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let total = files.len();
+    files.retain(|path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(crate::decoder_supports)
+    });
+    let skipped = total - files.len();
+    progress.total.store(files.len(), Ordering::Relaxed);
+
+    let summary = transfer(files, &plan.root, &plan.directory, plan.mode, &progress)?;
+    Ok(ImportSummary { skipped, ..summary })
+}
+
+/// Copies or moves specific files into `directory` under `root`, e.g. the
+/// current search results into a new category. Meant for a background thread.
+pub fn run_files(
+    files: Vec<PathBuf>,
+    root: String,
+    directory: String,
+    mode: ImportMode,
+    progress: Arc<ImportProgress>,
+) -> Result<ImportSummary, String> {
+    progress.total.store(files.len(), Ordering::Relaxed);
+    transfer(files, &root, &directory, mode, &progress)
+}
+
+/// The per-file work shared by both entry points; nothing is counted as skipped.
+fn transfer(
+    files: Vec<PathBuf>,
+    root: &str,
+    directory: &str,
+    mode: ImportMode,
+    progress: &ImportProgress,
+) -> Result<ImportSummary, String> {
+    let target_dir = PathBuf::from(join(root, directory));
+    if mode != ImportMode::Reference {
+        std::fs::create_dir_all(&target_dir).map_err(|e| format!("{}: {}", target_dir.display(), e))?;
+    }
+
+    let mut imported = Vec::new();
+    let mut sources = Vec::new();
+    let mut failed = Vec::new();
+    for file in files {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let original = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = file
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy().to_ascii_lowercase()))
+            .unwrap_or_default();
+
+        let entry = match mode {
+            ImportMode::Reference => {
+                let full_path = crate::portable::stored_path(&file);
+                std::fs::metadata(&file).map(|metadata| ImageInfo {
+                    filename: original.clone(),
+                    relative_path: full_path.clone(),
+                    full_path,
+                    extension,
+                    size: metadata.len(),
+                    checksum: None,
+                    width: None,
+                    height: None,
+                })
+            }
+            ImportMode::Copy | ImportMode::Move => {
+                let filename = free_name(&target_dir, &original);
+                let destination = target_dir.join(&filename);
+                let result = if mode == ImportMode::Copy {
+                    std::fs::copy(&file, &destination).map(|_| ())
+                } else {
+                    move_file(&file, &destination)
+                };
+                result.and_then(|()| std::fs::metadata(&destination)).map(|metadata| {
+                    let relative_path = join(directory, &filename);
+                    ImageInfo {
+                        full_path: join(root, &relative_path),
+                        relative_path,
+                        filename,
+                        extension,
+                        size: metadata.len(),
+                        checksum: None,
+                        width: None,
+                        height: None,
+                    }
+                })
+            }
+        };
+        match entry {
+            Ok(entry) => {
+                imported.push(entry);
+                sources.push(file);
+            }
+            Err(e) => failed.push(format!("{}: {}", original, e)),
+        }
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    Ok(ImportSummary {
+        imported,
+        sources,
+        skipped: 0,
+        failed,
+    })
+}
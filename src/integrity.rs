@@ -0,0 +1,290 @@
+//! Content hashes for library files, used to spot silent changes and corruption.
+//!
+//! Files are hashed one at a time on a background worker that sleeps between
+//! files, so verification never competes with thumbnail loading for disk time.
+//! Every queued file is read again, since corruption rarely touches size or
+//! modification time; files where either moved are only hashed first.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Pause between two files on the worker thread.
+const HASH_INTERVAL: Duration = Duration::from_millis(50);
+/// Minimum time between two writes of the sidecar file.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashRecord {
+    pub size: u64,
+    pub modified: u64,
+    pub hash: String,
+    /// Set when the content differed from the previously stored hash; cleared on acknowledge.
+    #[serde(default)]
+    pub changed: bool,
+}
+
+struct HashResult {
+    path: String,
+    record: Option<HashRecord>,
+}
+
+pub struct IntegrityChecker {
+    store: HashMap<String, HashRecord>,
+    store_path: PathBuf,
+    /// Paths to hash, with their stored record if there is one.
+    requests: Option<Sender<(String, Option<HashRecord>)>>,
+    results: Option<Receiver<HashResult>>,
+    queued: HashSet<String>,
+    in_flight: usize,
+    dirty: bool,
+    last_save: Instant,
+}
+
+/// SHA-256 of a file's content, hex encoded, plus the metadata it was taken with.
+pub fn hash_file(path: &str) -> std::io::Result<HashRecord> {
+    let mut file = std::fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let hash: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok(HashRecord {
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        hash,
+        changed: false,
+    })
+}
+
+/// Whether the file still has the size and modification time `record` was taken at.
+/// Only decides what is hashed first.
+fn unchanged(path: &str, record: &HashRecord) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    record.size == metadata.len() && record.modified == modified
+}
+
+impl IntegrityChecker {
+    pub fn load(store_path: PathBuf) -> Self {
+        let store = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            store_path,
+            requests: None,
+            results: None,
+            queued: HashSet::new(),
+            in_flight: 0,
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    fn ensure_worker(&mut self) {
+        if self.requests.is_some() {
+            return;
+        }
+        let (request_tx, request_rx) = mpsc::channel::<(String, Option<HashRecord>)>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let _ = std::thread::Builder::new()
+            .name("integrity_hash".to_string())
+            .spawn(move || {
+                let mut pending: VecDeque<String> = VecDeque::new();
+                loop {
+                    if pending.is_empty() {
+                        match request_rx.recv() {
+                            Ok((path, _)) => pending.push_back(path),
+                            Err(_) => break,
+                        }
+                    }
+                    // Files that visibly changed (or were never hashed) jump the queue
+                    for (path, previous) in request_rx.try_iter() {
+                        if previous.is_some_and(|previous| unchanged(&path, &previous)) {
+                            pending.push_back(path);
+                        } else {
+                            pending.push_front(path);
+                        }
+                    }
+                    let Some(path) = pending.pop_front() else {
+                        continue;
+                    };
+                    let record = hash_file(&path).ok();
+                    if result_tx.send(HashResult { path, record }).is_err() {
+                        break;
+                    }
+                    std::thread::sleep(HASH_INTERVAL);
+                }
+            });
+        self.requests = Some(request_tx);
+        self.results = Some(result_rx);
+    }
+
+    /// Queues a file for verification; each path is hashed at most once per session.
+    pub fn queue(&mut self, path: &str) {
+        if !self.queued.insert(path.to_string()) {
+            return;
+        }
+        self.ensure_worker();
+        let previous = self.store.get(path).cloned();
+        if let Some(requests) = &self.requests {
+            if requests.send((path.to_string(), previous)).is_ok() {
+                self.in_flight += 1;
+            }
+        }
+    }
+
+    /// Stops the worker; anything still queued is dropped and can be re-queued later.
+    pub fn stop(&mut self) {
+        self.requests = None;
+        self.results = None;
+        self.queued.clear();
+        self.in_flight = 0;
+    }
+
+    /// True while hashes are still expected back from the worker.
+    pub fn is_busy(&self) -> bool {
+        self.in_flight > 0
+    }
+
+    /// Collects finished hashes. Returns how many files were newly flagged as changed.
+    pub fn poll(&mut self) -> usize {
+        let Some(results) = &self.results else {
+            return 0;
+        };
+        let mut newly_changed = 0;
+        while let Ok(HashResult { path, record }) = results.try_recv() {
+            self.in_flight = self.in_flight.saturating_sub(1);
+            let Some(mut record) = record else {
+                continue;
+            };
+            if let Some(previous) = self.store.get(&path) {
+                if previous.hash != record.hash {
+                    record.changed = true;
+                    if !previous.changed {
+                        newly_changed += 1;
+                    }
+                } else {
+                    record.changed = previous.changed;
+                }
+            }
+            if self.store.get(&path) != Some(&record) {
+                self.store.insert(path, record);
+                self.dirty = true;
+            }
+        }
+        self.save_if_due();
+        newly_changed
+    }
+
+    pub fn is_changed(&self, path: &str) -> bool {
+        self.store.get(path).is_some_and(|r| r.changed)
+    }
+
+    pub fn changed_count(&self) -> usize {
+        self.store.values().filter(|r| r.changed).count()
+    }
+
+    pub fn hashed_count(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Stored hash for a path, for features (like duplicate detection) that need content identity.
+    pub fn hash(&self, path: &str) -> Option<&str> {
+        self.store.get(path).map(|r| r.hash.as_str())
+    }
+
+    pub fn acknowledge(&mut self, path: &str) {
+        if let Some(record) = self.store.get_mut(path) {
+            if record.changed {
+                record.changed = false;
+                self.dirty = true;
+            }
+        }
+    }
+
+    fn save_if_due(&mut self) {
+        if self.dirty && self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Writes the sidecar file if anything changed since the last write.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(parent) = self.store_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&self.store) {
+            if std::fs::write(&self.store_path, json).is_ok() {
+                self.dirty = false;
+            }
+        }
+        self.last_save = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// Polls until every queued file has been hashed, returning the newly changed count.
+    fn settle(checker: &mut IntegrityChecker) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut changed = 0;
+        while checker.is_busy() {
+            assert!(Instant::now() < deadline, "hashing didn't finish");
+            changed += checker.poll();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        changed
+    }
+
+    #[test]
+    fn flipped_bytes_are_flagged_even_with_the_same_size_and_time() {
+        let dir = TestDir::new("integrity-flip");
+        let file = dir.join("photo.png");
+        let path = file.to_string_lossy().into_owned();
+        std::fs::write(&file, b"original content").unwrap();
+        let modified = std::fs::metadata(&file).unwrap().modified().unwrap();
+
+        let mut first = IntegrityChecker::load(dir.join("hashes.json"));
+        first.queue(&path);
+        assert_eq!(settle(&mut first), 0);
+        first.save();
+
+        // Same length, and the modification time put back, as silent corruption would leave it
+        std::fs::write(&file, b"originaL content").unwrap();
+        std::fs::File::options().write(true).open(&file).unwrap().set_modified(modified).unwrap();
+        assert!(unchanged(&path, first.store.get(&path).unwrap()));
+
+        let mut second = IntegrityChecker::load(dir.join("hashes.json"));
+        second.queue(&path);
+        assert_eq!(settle(&mut second), 1);
+        assert!(second.is_changed(&path));
+    }
+}
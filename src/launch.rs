@@ -0,0 +1,108 @@
+//! Opening files in other applications: the OS default, or a per-extension
+//! command configured in settings.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Placeholder for the file path inside a configured command.
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Splits a command line on whitespace, keeping "double quoted" parts together.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_part = false;
+    for c in command.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_part = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_part {
+                    parts.push(std::mem::take(&mut current));
+                    has_part = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_part = true;
+            }
+        }
+    }
+    if has_part {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Opens `path` with `command` if given, otherwise with the OS default application.
+/// The file is appended as the last argument unless the command contains `{file}`.
+/// Errors name the command that was attempted.
+pub fn open(path: &Path, command: Option<&str>) -> Result<(), String> {
+    let file = path.display().to_string();
+    let mut parts = match command {
+        Some(command) => split_command(command),
+        None => default_opener(),
+    };
+    if parts.is_empty() {
+        return Err("No command configured".to_string());
+    }
+    if parts.iter().any(|part| part.contains(FILE_PLACEHOLDER)) {
+        for part in &mut parts {
+            *part = part.replace(FILE_PLACEHOLDER, &file);
+        }
+    } else {
+        parts.push(file);
+    }
+
+    let attempted = parts.join(" ");
+    Command::new(&parts[0])
+        .args(&parts[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not run `{}`: {}", attempted, e))
+}
+
+/// Opens a web page in the default browser.
+pub fn open_url(url: &str) -> Result<(), String> {
+    let parts = default_opener();
+    Command::new(&parts[0])
+        .args(&parts[1..])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not open {}: {}", url, e))
+}
+
+fn default_opener() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    let parts = ["explorer"];
+    #[cfg(target_os = "macos")]
+    let parts = ["open"];
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let parts = ["xdg-open"];
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+/// Opens a text file at `line` in the editor named by `$VISUAL` or `$EDITOR`,
+/// passing `+line` before the path as vi, nano, emacs and most others
+/// understand. Without either it opens with the default application, at the
+/// top. Returns whether the editor was asked to go to the line.
+pub fn open_at_line(path: &Path, line: usize) -> Result<bool, String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty());
+    match editor {
+        Some(editor) => open(path, Some(&format!("{} +{} {}", editor, line, FILE_PLACEHOLDER))).map(|()| true),
+        None => open(path, None).map(|()| false),
+    }
+}
@@ -0,0 +1,149 @@
+//! Diagnosis for a manifest that couldn't be opened, shown in place of the
+//! library: the absolute path that was tried, whether anything is there and
+//! readable, and nearby JSON files that might be the right one.
+
+use std::path::{Path, PathBuf};
+
+/// JSON files suggested at most.
+pub const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileState {
+    Missing,
+    /// Something is there, but it's a folder.
+    NotAFile,
+    /// The file exists but opening it failed, usually for permissions.
+    Unreadable(String),
+    /// The file opens fine, so the problem is what's in it.
+    Readable,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnosis {
+    pub tried: PathBuf,
+    pub state: FileState,
+    pub suggestions: Vec<PathBuf>,
+}
+
+/// `path` made absolute against `cwd`, without touching the filesystem.
+pub fn absolute(path: &Path, cwd: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    }
+}
+
+fn file_state(path: &Path) -> FileState {
+    match std::fs::metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => FileState::Missing,
+        Err(e) => FileState::Unreadable(e.to_string()),
+        Ok(metadata) if metadata.is_dir() => FileState::NotAFile,
+        Ok(_) => match std::fs::File::open(path) {
+            Ok(_) => FileState::Readable,
+            Err(e) => FileState::Unreadable(e.to_string()),
+        },
+    }
+}
+
+/// `*.json` files directly inside `dirs`, in order and without repeats,
+/// leaving out `tried` itself.
+fn json_files(dirs: &[PathBuf], tried: &Path) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+            .collect();
+        files.sort();
+        for file in files {
+            if file != tried && !found.contains(&file) {
+                found.push(file);
+            }
+        }
+    }
+    found.truncate(MAX_SUGGESTIONS);
+    found
+}
+
+/// Looks at `manifest_path` as resolved from `cwd` and for JSON files in
+/// `search_dirs`; reads the filesystem but changes nothing.
+pub fn diagnose(manifest_path: &str, cwd: &Path, search_dirs: &[PathBuf]) -> Diagnosis {
+    let tried = absolute(Path::new(manifest_path), cwd);
+    Diagnosis {
+        state: file_state(&tried),
+        suggestions: json_files(search_dirs, &tried),
+        tried,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn missing_file_is_resolved_against_cwd() {
+        let dir = TestDir::new("load-failure-missing");
+        let diagnosis = diagnose("image_list.json", &dir, &[]);
+        assert_eq!(diagnosis.tried, dir.join("image_list.json"));
+        assert_eq!(diagnosis.state, FileState::Missing);
+        assert!(diagnosis.suggestions.is_empty());
+    }
+
+    #[test]
+    fn directory_is_not_a_file() {
+        let dir = TestDir::new("load-failure-directory");
+        std::fs::create_dir(dir.join("library.json")).unwrap();
+        let absolute = dir.join("library.json");
+        let diagnosis = diagnose(&absolute.to_string_lossy(), Path::new("/elsewhere"), &[]);
+        assert_eq!(diagnosis.tried, absolute);
+        assert_eq!(diagnosis.state, FileState::NotAFile);
+    }
+
+    #[test]
+    fn unreadable_path_reports_the_error() {
+        let dir = TestDir::new("load-failure-unreadable");
+        std::fs::write(dir.join("library.json"), "{}").unwrap();
+        // Permissions don't stop root, but a path through a file fails for everyone
+        let diagnosis = diagnose("library.json/image_list.json", &dir, &[]);
+        match diagnosis.state {
+            FileState::Unreadable(error) => assert!(!error.is_empty()),
+            state => panic!("expected Unreadable, got {:?}", state),
+        }
+    }
+
+    #[test]
+    fn bad_json_is_still_readable() {
+        let dir = TestDir::new("load-failure-bad-json");
+        std::fs::write(dir.join("image_list.json"), "{ \"categories\": ").unwrap();
+        // The file opens; the parse error itself is reported elsewhere
+        assert_eq!(diagnose("image_list.json", &dir, &[]).state, FileState::Readable);
+    }
+
+    #[test]
+    fn candidates_are_json_files_in_order_without_the_tried_one() {
+        let dir = TestDir::new("load-failure-candidates");
+        let nested = dir.join("nested");
+        std::fs::create_dir_all(nested.join("folder.json")).unwrap();
+        for name in ["b.json", "a.JSON", "notes.txt", "image_list.json"] {
+            std::fs::write(dir.join(name), "{}").unwrap();
+        }
+        for name in ["c.json", "d.json", "e.json", "f.json"] {
+            std::fs::write(nested.join(name), "{}").unwrap();
+        }
+
+        let diagnosis = diagnose("image_list.json", &dir, &[dir.to_path_buf(), nested.clone(), dir.to_path_buf()]);
+        assert_eq!(diagnosis.state, FileState::Readable);
+        assert_eq!(
+            diagnosis.suggestions,
+            vec![dir.join("a.JSON"), dir.join("b.json"), nested.join("c.json"), nested.join("d.json"), nested.join("e.json")]
+        );
+        assert_eq!(diagnosis.suggestions.len(), MAX_SUGGESTIONS);
+    }
+}
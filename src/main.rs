@@ -1,22 +1,153 @@
+mod backdrop;
+mod bench;
+mod category_colors;
+mod checksums;
+mod clipboard_access;
+mod clipboard_marker;
+mod cloud;
+mod colors;
+mod copy_queue;
+mod covers;
+mod deep_link;
+mod dimensions;
+mod duplicates;
+mod elide;
+mod encode;
+mod error;
+mod favorites;
+mod formats;
+mod frame_guard;
+mod grouping;
+mod health;
+mod hidden;
+mod history;
+mod hot_cache;
+mod hotkey;
+mod import;
+mod integrity;
+mod launch;
+mod load_failure;
+mod manifest;
+mod metadata;
+mod offline;
+mod operations;
+mod optimize;
+mod new_images;
+mod orientation;
+mod palette;
+mod pins;
+mod placeholder;
+mod portable;
+mod preview;
+mod remote;
+mod removal_review;
+mod safe_mode;
+mod scroll;
+mod search;
+mod sensitive;
+mod sequences;
+mod settings;
+mod shortcuts;
+mod snippet;
+mod source_hints;
+mod status;
+mod store;
+#[cfg(test)]
+mod test_dir;
+mod thumbnail_cache;
+mod undo;
+mod update_check;
+mod usage;
+mod user_data;
+mod watcher;
+mod zip_import;
+
 use eframe::egui;
+use error::{ChlorineError, LoaderError, ManifestError};
 use serde::{Deserialize, Serialize};
+use settings::{AppSettings, Animations, DuplicatePolicy, PrimaryAction};
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, Instant};
 use poll_promise::Promise;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AppSettings {
-    dark_mode: bool,
-    hotkey: String,
+/// How long a copied row pulses after a successful copy.
+const COPY_FLASH_DURATION: Duration = Duration::from_millis(600);
+/// How long copy buttons read "✓ Copied" before reverting.
+const COPY_LABEL_DURATION: Duration = Duration::from_secs(2);
+
+/// Categories listed under the search box while typing a "category/" prefix.
+const MAX_SCOPE_SUGGESTIONS: usize = 8;
+
+/// Longest side offered by the large-copy confirmation.
+const LARGE_COPY_RESIZE: u32 = 2048;
+
+/// Size of the cover thumbnails next to category names.
+const COVER_SIZE: f32 = 20.0;
+/// Thumbnails in the strip shown when hovering a category, and their size.
+const PEEK_IMAGES: usize = 5;
+const PEEK_SIZE: f32 = 64.0;
+/// Covers only start a decode while fewer thumbnail loads than this are running,
+/// so they never hold up the result list.
+const COVER_LOAD_LIMIT: usize = 3;
+/// Categories with at least this many images are marked "(large)" in the dropdown.
+const LARGE_CATEGORY: usize = 10_000;
+/// Lines shown either side of a manifest parse error.
+const MANIFEST_ERROR_CONTEXT: usize = 5;
+
+/// Dragging the preview pane narrower than this collapses it.
+const PREVIEW_PANE_COLLAPSE_WIDTH: f32 = 140.0;
+
+/// Room kept next to a result's name for badges such as "⚠ changed" or "×2".
+const ROW_BADGE_WIDTH: f32 = 110.0;
+
+/// Thumbnail textures kept in memory; the oldest are dropped beyond this.
+const TEXTURE_CACHE_LIMIT: usize = 2000;
+const LOW_MEMORY_TEXTURE_CACHE_LIMIT: usize = 100;
+
+/// Thumbnail decode sizes in pixels. Textures are cached per tier, so small
+/// changes in display size or scale don't cause repeated decodes.
+const THUMBNAIL_TIERS: [u32; 4] = [64, 128, 256, 512];
+/// How far a thumbnail may be stretched before a sharper tier is decoded.
+const THUMBNAIL_UPSCALE_TOLERANCE: f32 = 1.25;
+/// Tier recorded for text previews, which are rendered at a fixed size.
+const FIXED_TIER: u32 = u32::MAX;
+/// Thumbnail uploads per frame; the rest wait for the next one.
+const MAX_UPLOADS_PER_FRAME: usize = 3;
+/// Uploads stop for the frame once this much time went into them.
+const UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+
+/// Delays before retrying a thumbnail that failed for a transient reason; then it's given up on.
+const RETRY_BACKOFF: [Duration; 3] = [Duration::from_secs(5), Duration::from_secs(30), Duration::from_secs(120)];
+
+/// Extensions the bundled `image` decoders can turn into thumbnails.
+const DECODABLE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "tif", "tiff", "tga",
+    "pnm", "pbm", "pgm", "ppm", "pam", "hdr", "dds", "ff", "exr", "qoi",
+];
+
+fn decoder_supports(extension: &str) -> bool {
+    let ext = extension.trim_start_matches('.').to_ascii_lowercase();
+    DECODABLE_EXTENSIONS.contains(&ext.as_str()) || formats::supports(&ext)
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            dark_mode: true,
-            hotkey: "Ctrl+Shift+C".to_string(),
-        }
-    }
+/// Why a file shows an info tile instead of a thumbnail.
+fn unsupported_hint(extension: &str) -> String {
+    formats::missing_support(extension)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("No decoder for {} files", placeholder::extension_label(extension)))
+}
+
+fn search_box_id() -> egui::Id {
+    egui::Id::new("search_box")
+}
+
+fn search_history_popup_id() -> egui::Id {
+    egui::Id::new("search_history_popup")
+}
+
+fn palette_input_id() -> egui::Id {
+    egui::Id::new("palette_input")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +157,14 @@ struct ImageInfo {
     full_path: String,
     extension: String,
     size: u64,
+    /// SHA-256 from the build pipeline, hex with an optional "sha256:" prefix.
+    #[serde(default, alias = "sha256", skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    /// Pixel size, written by "Index dimensions" so later sessions know it up front.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,218 +179,8377 @@ struct ImageData {
     categories: HashMap<String, Category>,
 }
 
+/// What a row can show for an entry right now.
+enum ThumbnailState {
+    Ready(egui::TextureHandle),
+    /// A decode is running on a load slot.
+    Loading,
+    /// Not requested yet, e.g. because all load slots are busy.
+    Waiting,
+    /// No decoder for this format; shown as an extension tile.
+    Unsupported,
+    /// A transient failure; the load is tried again after a backoff.
+    Retrying,
+    /// A cloud-sync placeholder, left alone until the user asks to download it.
+    InCloud,
+    /// Marked sensitive; never drawn as a thumbnail.
+    Sensitive,
+    /// On a drive or mount that wasn't there at load time.
+    Offline,
+    /// Text-only mode is on; shown as an extension tile.
+    TextOnly,
+    Failed,
+}
+
+/// A transient failure waiting for its next attempt.
+struct PendingRetry {
+    /// Failed attempts so far.
+    attempts: usize,
+    retry_at: Instant,
+    failure: LoaderError,
+}
+
+/// Result of a background thumbnail decode.
+struct Thumbnail {
+    image: egui::ColorImage,
+    /// Dominant colors, when the thumbnail came from real pixels.
+    colors: Option<Vec<colors::Rgb>>,
+    /// Full-size width and height, when the thumbnail came from real pixels.
+    dimensions: Option<(u32, u32)>,
+    /// Luminance of the opaque pixels when the image has transparency.
+    luminance: Option<f32>,
+    /// The tier it was decoded for.
+    tier: u32,
+}
+
+/// Totals for the current filter, accumulated while filtering.
+#[derive(Default)]
+struct FilterStats {
+    total_bytes: u64,
+    largest_bytes: u64,
+    /// Image count per lowercase extension, most common first.
+    by_extension: Vec<(String, usize)>,
+    /// Image count per guessed source, most common first.
+    by_source: Vec<(String, usize)>,
+    /// Candidates left out of a color filter because their signature isn't computed yet.
+    color_pending: usize,
+    /// Candidates left out of an orientation filter because their size isn't known yet.
+    dimension_pending: usize,
+    /// Matches folded into a collapsed sequence stack.
+    stacked: usize,
+}
+
+impl FilterStats {
+    /// "812 png · 214 gif · 77 jpg", then "640 screenshot · 463 unknown" on the next line.
+    fn extension_breakdown(&self) -> String {
+        let line = |counts: &[(String, usize)]| {
+            counts
+                .iter()
+                .map(|(name, count)| format!("{} {}", count, name))
+                .collect::<Vec<_>>()
+                .join(" · ")
+        };
+        format!("{}\n{}", line(&self.by_extension), line(&self.by_source))
+    }
+
+    /// "58 results, mostly PNG, largest 4.2 MB", for screen readers.
+    fn summary(&self, count: usize) -> String {
+        let mut parts = vec![format!("{} results", count)];
+        if let Some((extension, top)) = self.by_extension.first() {
+            if self.by_extension.len() == 1 {
+                parts.push(format!("all {}", extension.to_uppercase()));
+            } else if top * 2 > count {
+                parts.push(format!("mostly {}", extension.to_uppercase()));
+            }
+        }
+        if count > 0 {
+            parts.push(format!("largest {}", format_bytes(self.largest_bytes)));
+        }
+        parts.join(", ")
+    }
+}
+
+/// One part of the active filter, shown as a removable chip under the search box.
+#[derive(Debug, Clone, PartialEq)]
+enum FilterChip {
+    /// The free text of the search.
+    Text(String),
+    /// An operator word of the search, by position among its words.
+    Operator { index: usize, word: String },
+    Category(String),
+    /// Folder scope from "Show files from this folder", with `/` separators.
+    Folder(String),
+    Color(egui::Color32),
+    Orientation(dimensions::Orientation),
+    FavoritesOnly,
+    ShowHidden,
+}
+
+impl FilterChip {
+    fn label(&self) -> String {
+        match self {
+            FilterChip::Text(text) => format!("text: {}", text),
+            FilterChip::Operator { word, .. } => match word.split_once(':') {
+                Some((operator, value)) => format!("{}: {}", operator.to_lowercase(), value),
+                None => word.clone(),
+            },
+            FilterChip::Category(category) => format!("category: {}", category),
+            FilterChip::Folder(folder) if folder.is_empty() => "folder: (top level)".to_string(),
+            FilterChip::Folder(folder) => format!("folder: {}", folder),
+            FilterChip::Color(_) => "color".to_string(),
+            FilterChip::Orientation(orientation) => format!("orientation: {}", orientation.name()),
+            FilterChip::FavoritesOnly => "favorites only".to_string(),
+            FilterChip::ShowHidden => "including hidden".to_string(),
+        }
+    }
+}
+
+/// Human-readable byte count using binary units ("1.4 GB").
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    // 1 048 575 B would otherwise round up to "1024.0 KB"
+    while (value >= 1024.0 || (unit > 0 && value >= 1023.95)) && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Paints a texture scaled to fit inside `rect`, keeping its aspect ratio.
+fn paint_fitted(painter: &egui::Painter, texture: &egui::TextureHandle, rect: egui::Rect) {
+    let size = texture.size_vec2();
+    let scale = (rect.width() / size.x).min(rect.height() / size.y);
+    let fitted = egui::Rect::from_center_size(rect.center(), size * scale);
+    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    painter.image(texture.id(), fitted, uv, egui::Color32::WHITE);
+}
+
+/// State of the "Save as…" window for one image.
+struct ExportDialog {
+    image_info: ImageInfo,
+    options: encode::EncodeOptions,
+    resize: bool,
+    max_dimension: u32,
+    background: egui::Color32,
+}
+
+impl ExportDialog {
+    fn new(image_info: ImageInfo) -> Self {
+        Self {
+            image_info,
+            options: encode::EncodeOptions::default(),
+            resize: false,
+            max_dimension: 1024,
+            background: egui::Color32::WHITE,
+        }
+    }
+}
+
+/// State of the "Group into sub-categories" window for one category.
+struct GroupingDialog {
+    category: String,
+    rule: grouping::GroupingRule,
+    delimiters: String,
+    regex: String,
+    /// Proposed group names with image counts, or why the rule can't run.
+    preview: Result<Vec<(String, usize)>, String>,
+}
+
+impl GroupingDialog {
+    fn new(category: String) -> Self {
+        Self {
+            category,
+            rule: grouping::GroupingRule::Prefix { delimiters: "_-".to_string() },
+            delimiters: "_-".to_string(),
+            regex: "^(?P<category>[a-z]+)".to_string(),
+            preview: Ok(Vec::new()),
+        }
+    }
+
+    fn refresh_preview(&mut self, images: &[ImageInfo]) {
+        self.preview = self.rule.validate().and_then(|_| grouping::group(images, &self.rule)).map(|groups| {
+            groups
+                .into_iter()
+                .map(|(name, images)| (name, images.len()))
+                .collect()
+        });
+    }
+}
+
+/// State of the "Import folder into category" window.
+struct LargeCopyConfirm {
+    image: ImageInfo,
+    /// Full size, from the dimension cache or a header probe.
+    size: (u32, u32),
+    always_resize: bool,
+}
+
+/// "Import user data…" once the file is read: each section resolved against
+/// the library, with how many entries it doesn't have, and merge or replace.
+struct UserDataImport {
+    source: std::path::PathBuf,
+    sections: user_data::Resolved,
+    /// Favorites, hidden, pins, category colors.
+    modes: [user_data::ImportMode; 4],
+    /// Sections that didn't parse.
+    errors: Vec<String>,
+}
+
+struct ImportDialog {
+    source: Option<std::path::PathBuf>,
+    category: String,
+    mode: import::ImportMode,
+    running: Option<(Promise<Result<import::ImportSummary, String>>, std::sync::Arc<import::ImportProgress>)>,
+}
+
+/// "Import ZIP…": the archive's folders, each mapped to a category.
+struct ZipImportDialog {
+    archive: std::path::PathBuf,
+    /// Per archive folder: the preview, the category name, and whether it's
+    /// added to an existing category of that name instead.
+    rows: Vec<(zip_import::FolderPreview, String, bool)>,
+    skipped: usize,
+    mode: zip_import::ZipMode,
+    destination: Option<std::path::PathBuf>,
+    running: Option<(Promise<Result<zip_import::ZipSummary, String>>, std::sync::Arc<import::ImportProgress>)>,
+}
+
+/// "Create category from results": the matches are captured when the dialog opens.
+struct ResultsCategoryDialog {
+    name: String,
+    mode: import::ImportMode,
+    /// Reference mode only: drop the entries from the categories they came from.
+    remove_from_original: bool,
+    /// (manifest category, image) for every result.
+    images: Vec<(String, ImageInfo)>,
+    running: Option<(Promise<Result<import::ImportSummary, String>>, std::sync::Arc<import::ImportProgress>)>,
+}
+
+/// "Optimize…" on the selection or a category: a dry run first, then apply.
+struct OptimizeDialog {
+    images: Vec<ImageInfo>,
+    /// "12 selected images" or the category name, for the title line.
+    scope: String,
+    conversion: optimize::Conversion,
+    jpeg_quality: u8,
+    /// The last finished dry run; applying is offered once there is one.
+    reports: Option<Vec<optimize::FileReport>>,
+    /// The promise, its progress, and whether it writes.
+    running: Option<(Promise<Vec<optimize::FileReport>>, std::sync::Arc<optimize::Progress>, bool)>,
+}
+
+impl ImportDialog {
+    fn new(category: String) -> Self {
+        Self {
+            source: None,
+            category,
+            mode: import::ImportMode::Copy,
+            running: None,
+        }
+    }
+}
+
+/// Opens a folder in the platform's file manager.
+fn open_in_file_manager(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+    std::process::Command::new(program).arg(path).spawn().map(|_| ())
+}
+
 struct ImageSearchApp {
     image_data: Option<ImageData>,
+    search_index: search::SearchIndex,
     search_query: String,
+    search_history: history::SearchHistory,
     selected_category: String,
-    filtered_images: Vec<(String, ImageInfo)>,
-    selected_image: Option<(String, ImageInfo)>,
+    /// Current results as indices into `search_index.entries`; rows are resolved when drawn.
+    filtered_images: Vec<usize>,
+    filter_stats: FilterStats,
+    /// Sequence stacks in the current results, keyed by the entry that stands for each.
+    stacks: HashMap<usize, sequences::Stack>,
+    /// Keys of stacks the user opened; kept for the session.
+    expanded_stacks: std::collections::HashSet<String>,
+    /// Open detail windows, oldest first; the last one is active and follows the arrow keys.
+    detail_windows: Vec<DetailView>,
+    next_detail_id: u64,
+    /// Window to bring to the front on the next frame.
+    raise_detail: Option<u64>,
+    /// Multi-selection (Ctrl+click), as full paths in the order they were picked.
+    selection: Vec<String>,
     show_all_categories: bool,
-    loaded_textures: HashMap<String, egui::TextureHandle>,
-    loading_promises: HashMap<String, Promise<Option<egui::ColorImage>>>,
-    failed_images: std::collections::HashSet<String>,
-    status_message: String,
+    /// Thumbnail textures with the tier each was decoded at.
+    loaded_textures: HashMap<String, (u32, egui::TextureHandle)>,
+    /// Largest thumbnail drawn this frame, in points.
+    frame_thumbnail_points: f32,
+    /// Pixels needed for the largest thumbnail on screen, from the last frame that drew one.
+    thumbnail_pixels: f32,
+    /// Paths in `loaded_textures`, oldest first, for eviction.
+    texture_order: std::collections::VecDeque<String>,
+    loading_promises: HashMap<String, Promise<Result<Thumbnail, LoaderError>>>,
+    /// Finished decodes waiting for a frame with upload budget left.
+    decoded: std::collections::VecDeque<(String, Result<Thumbnail, LoaderError>)>,
+    /// Paths rows asked for this frame while their thumbnail was pending; uploaded first next frame.
+    thumbnail_requests: std::collections::HashSet<String>,
+    /// Permanent failures; cleared only by a manual retry or a profile switch.
+    failed_images: HashMap<String, LoaderError>,
+    retries: HashMap<String, PendingRetry>,
+    /// Cloud placeholders found when a load was about to start.
+    cloud_files: std::collections::HashSet<String>,
+    /// Placeholders the user chose to download.
+    hydrate_requested: std::collections::HashSet<String>,
+    placeholder_textures: HashMap<String, egui::TextureHandle>,
+    integrity: integrity::IntegrityChecker,
+    colors: colors::ColorIndex,
+    /// Color picked in the filter popover; a `color:` operator in the query takes precedence.
+    color_filter: Option<egui::Color32>,
+    color_tolerance: f32,
+    dimensions: dimensions::DimensionIndex,
+    luminance: backdrop::LuminanceIndex,
+    /// Backdrops picked by hand in a detail window, for this session.
+    backdrop_overrides: HashMap<String, egui::Color32>,
+    /// Only images directly in this folder (relative, `/` separators), in any category.
+    folder_scope: Option<String>,
+    /// Orientation chips that are on; an image passes if it matches any of them.
+    orientations: std::collections::HashSet<dimensions::Orientation>,
+    favorites: favorites::Favorites,
+    /// Favorites resolved against the loaded library: (index in favorites, category, image).
+    favorite_entries: Vec<(usize, String, ImageInfo)>,
+    favorites_only: bool,
+    hidden: hidden::HiddenList,
+    sensitive: sensitive::SensitiveList,
+    category_colors: category_colors::CategoryColors,
+    new_images: new_images::NewImages,
+    /// Sensitive images the user chose to show in the detail window this session.
+    revealed: std::collections::HashSet<String>,
+    /// Temporarily include hidden images in results, drawn greyed out.
+    show_hidden: bool,
+    favorite_drag: Option<usize>,
+    pins: pins::PinnedSlots,
+    /// Pinned paths resolved against the loaded library, one per slot.
+    pin_entries: Vec<Option<(String, ImageInfo)>>,
+    /// Slots whose pinned file no longer exists on disk.
+    pin_missing: Vec<bool>,
+    /// A pin that would replace an occupied slot, waiting for confirmation.
+    pin_confirm: Option<(usize, ImageInfo)>,
+    metadata_cache: HashMap<String, metadata::ImageMetadata>,
+    metadata_promises: HashMap<String, Promise<metadata::ImageMetadata>>,
+    /// Contents of text entries shown in the detail window, keyed by path.
+    text_cache: HashMap<String, Result<String, String>>,
+    export_dialog: Option<ExportDialog>,
+    export_promise: Option<Promise<Result<std::path::PathBuf, String>>>,
+    export_folder: Option<std::path::PathBuf>,
+    grouping_rules: std::collections::BTreeMap<String, grouping::GroupingRule>,
+    grouping_dialog: Option<GroupingDialog>,
+    import_dialog: Option<ImportDialog>,
+    results_category: Option<ResultsCategoryDialog>,
+    zip_import: Option<ZipImportDialog>,
+    optimize_dialog: Option<OptimizeDialog>,
+    removal_review: Option<removal_review::RemovalReview>,
+    user_data_import: Option<UserDataImport>,
+    /// Writes manifest edits off the UI thread.
+    manifest_saver: manifest::ManifestSaver,
+    /// Full paths listed under several categories, with those categories.
+    duplicates: HashMap<String, Vec<String>>,
+    /// Set at load when the duplicate policy is "Ask".
+    show_duplicate_prompt: bool,
+    /// Full-size textures for the detail window and its neighbors.
+    previews: preview::PreviewCache,
+    /// Refresh was asked for while some manifest edits failed to save.
+    refresh_confirm: bool,
+    /// `chlorine://` link from the command line, followed once the library has loaded.
+    pending_link: Option<String>,
+    /// Result shown in the preview pane, moved by clicking a name or Up/Down.
+    highlighted: Option<(String, ImageInfo)>,
+    results_scroll: scroll::ResultsScroll,
+    /// "matched path: …" for results found by something other than their filename, by index entry.
+    matched_fields: HashMap<usize, String>,
+    /// The preview pane's own full-size texture, separate from the detail window's.
+    pane_preview: preview::PreviewCache,
+    covers: covers::CategoryCovers,
+    /// Why the manifest can't be rewritten; None when the library is writable.
+    read_only: Option<String>,
+    undo: undo::UndoStack,
+    health_promise: Option<Promise<health::HealthReport>>,
+    /// A running update check, and whether it was asked for from About.
+    update_check: Option<(Promise<Result<Option<update_check::Release>, String>>, bool)>,
+    /// Whether this session's automatic update check has been considered.
+    update_check_started: bool,
+    /// A newer release found by the last check.
+    available_update: Option<update_check::Release>,
+    /// The outcome of a check from About, shown there.
+    update_check_note: Option<String>,
+    health_report: Option<health::HealthReport>,
+    checksum_verification: checksums::Verification,
+    checksum_run: Option<(Promise<Vec<checksums::Checked>>, std::sync::Arc<checksums::Progress>)>,
+    dimension_run: Option<(Promise<Vec<(String, (u32, u32))>>, std::sync::Arc<dimensions::Progress>)>,
+    show_health: bool,
+    show_usage: bool,
+    usage: usage::UsageLog,
+    /// Favorites, hidden and sensitive lists, pins, the seen snapshot and usage, in one file per profile.
+    user_store: store::UserData,
+    status_message: status::StatusMessage,
     settings: AppSettings,
     show_settings: bool,
+    confirm_reset_settings: bool,
+    /// Started with `--safe-mode`.
+    safe_mode: bool,
+    /// Settings as of the last `apply_settings`; any difference is applied on the next frame.
+    applied_settings: AppSettings,
+    hotkey: hotkey::GlobalHotkey,
+    /// Minimized by the global hotkey.
+    hidden_by_hotkey: bool,
+    /// Manifest being parsed and indexed in the background.
+    library_load: Option<Promise<Result<LoadedLibrary, ChlorineError>>>,
+    /// Status to show once the running load finishes, instead of the load summary.
+    status_after_load: Option<status::StatusMessage>,
+    /// Lines around a manifest parse error, opened from the status bar.
+    manifest_error: Option<ManifestErrorView>,
+    /// Why the last load failed, shown in place of the library until one loads.
+    load_failure: Option<(String, load_failure::Diagnosis)>,
+    thumbnail_cache_prune: Option<Promise<thumbnail_cache::Usage>>,
+    /// Size of the disk thumbnail cache after the last prune.
+    thumbnail_cache_usage: Option<thumbnail_cache::Usage>,
+    /// Write failures of the disk thumbnail cache, counted by the loader threads.
+    thumbnail_cache_health: std::sync::Arc<thumbnail_cache::Health>,
+    /// Why the disk thumbnail cache is off for the rest of the session, if it is.
+    thumbnail_cache_off: Option<String>,
+    watcher: Option<watcher::Watcher>,
+    copy_queue: copy_queue::CopyQueue,
+    /// Compiled `settings.source_rules`, for the search index and the detail window.
+    source_classifier: source_hints::Classifier,
+    /// Folder changes held back while an import, optimize, load or save runs.
+    watch_pending: Vec<watcher::Change>,
+    /// New files already submitted to the manifest, until the library reloads.
+    watch_added: std::collections::HashSet<String>,
+    hot_cache: hot_cache::HotCache,
+    /// Textures for hot cache entries; None when no thumbnail was stored.
+    hot_textures: HashMap<String, Option<egui::TextureHandle>>,
+    /// Set while a "category/" prefix in the search box chose the category.
+    scoped_by_prefix: bool,
+    /// Categories matching the first word of the search, with image counts.
+    scope_suggestions: Vec<(String, usize)>,
+    scope_selected: usize,
+    /// A pixel copy over the size threshold, waiting for the user's choice.
+    large_copy_confirm: Option<LargeCopyConfirm>,
+    /// Background work running this frame, for busy-state checks.
+    operations: operations::Operations,
+    /// Roots of the loaded library that weren't there at load time.
+    offline: offline::OfflineRoots,
+    /// A rotated or flipped detail image waiting for confirmation to replace its file.
+    overwrite_confirm: Option<(u64, ImageInfo, orientation::Orientation)>,
+    /// Banner reset action waiting for confirmation.
+    safe_mode_confirm: Option<safe_mode::Reset>,
+    confirm_delete_profile: Option<String>,
+    /// Manifest path being typed for a profile (id, text); it becomes the
+    /// profile's path, and reloads the library, only once the field loses focus.
+    manifest_path_draft: Option<(String, String)>,
+    new_profile_name: String,
+    /// Extension and command being typed into the "Open with" settings row.
+    new_open_with: (String, String),
+    show_shortcuts: bool,
+    show_debug_overlay: bool,
+    frame_guard: frame_guard::FrameGuard,
+    palette: Option<palette::CommandPalette>,
+    remote: Option<remote::RemoteServer>,
+    last_copied: Option<(String, Instant)>,
+    /// The library file Chlorine last put on the clipboard, until something replaces it.
+    on_clipboard: Option<clipboard_marker::ClipboardMarker>,
+    /// Window focus last frame; the clipboard is re-checked when focus returns.
+    window_focused: bool,
+    clipboard: clipboard_access::ClipboardAccess,
+    /// The "clipboard unavailable" banner was dismissed this session.
+    clipboard_banner_dismissed: bool,
+    /// Scroll the settings window to the Copying section on its next frame.
+    focus_copy_settings: bool,
+    /// OS reduced-motion preference, read once at startup for `Animations::Auto`.
+    os_reduced_motion: bool,
 }
 
 impl Default for ImageSearchApp {
     fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl ImageSearchApp {
+    /// In safe mode the settings file is not read and profile sidecars live in
+    /// a scratch folder, so nothing on disk can affect startup.
+    fn new(safe_mode: bool) -> Self {
+        let settings = if safe_mode {
+            AppSettings::default()
+        } else {
+            settings::load()
+        };
         let mut app = Self {
             image_data: None,
+            search_index: search::SearchIndex::default(),
             search_query: String::new(),
+            search_history: history::SearchHistory::load(std::path::PathBuf::new()),
             selected_category: "All Categories".to_string(),
             filtered_images: Vec::new(),
-            selected_image: None,
+            stacks: HashMap::new(),
+            expanded_stacks: std::collections::HashSet::new(),
+            filter_stats: FilterStats::default(),
+            detail_windows: Vec::new(),
+            next_detail_id: 0,
+            raise_detail: None,
+            selection: Vec::new(),
             show_all_categories: true,
             loaded_textures: HashMap::new(),
+            frame_thumbnail_points: 0.0,
+            thumbnail_pixels: 128.0,
+            texture_order: std::collections::VecDeque::new(),
             loading_promises: HashMap::new(),
-            failed_images: std::collections::HashSet::new(),
-            status_message: "Loading image list...".to_string(),
-            settings: AppSettings::default(),
+            decoded: std::collections::VecDeque::new(),
+            thumbnail_requests: std::collections::HashSet::new(),
+            failed_images: HashMap::new(),
+            retries: HashMap::new(),
+            cloud_files: std::collections::HashSet::new(),
+            hydrate_requested: std::collections::HashSet::new(),
+            placeholder_textures: HashMap::new(),
+            integrity: integrity::IntegrityChecker::load(std::path::PathBuf::new()),
+            colors: colors::ColorIndex::load(std::path::PathBuf::new()),
+            color_filter: None,
+            folder_scope: None,
+            color_tolerance: colors::DEFAULT_TOLERANCE,
+            dimensions: dimensions::DimensionIndex::load(std::path::PathBuf::new()),
+            luminance: backdrop::LuminanceIndex::load(std::path::PathBuf::new()),
+            backdrop_overrides: HashMap::new(),
+            orientations: std::collections::HashSet::new(),
+            favorites: favorites::Favorites::load(&mut store::UserData::default()),
+            favorite_entries: Vec::new(),
+            favorites_only: false,
+            hidden: hidden::HiddenList::load(&mut store::UserData::default()),
+            sensitive: sensitive::SensitiveList::load(&mut store::UserData::default()),
+            category_colors: category_colors::CategoryColors::default(),
+            new_images: new_images::NewImages::load(&mut store::UserData::default()),
+            revealed: std::collections::HashSet::new(),
+            show_hidden: false,
+            favorite_drag: None,
+            pins: pins::PinnedSlots::load(&mut store::UserData::default(), 0),
+            pin_entries: Vec::new(),
+            pin_missing: Vec::new(),
+            pin_confirm: None,
+            metadata_cache: HashMap::new(),
+            metadata_promises: HashMap::new(),
+            text_cache: HashMap::new(),
+            export_dialog: None,
+            export_promise: None,
+            export_folder: None,
+            grouping_rules: std::collections::BTreeMap::new(),
+            grouping_dialog: None,
+            import_dialog: None,
+            results_category: None,
+            zip_import: None,
+            optimize_dialog: None,
+            removal_review: None,
+            user_data_import: None,
+            manifest_saver: manifest::ManifestSaver::new(),
+            duplicates: HashMap::new(),
+            show_duplicate_prompt: false,
+            previews: preview::PreviewCache::new(),
+            refresh_confirm: false,
+            pending_link: None,
+            highlighted: None,
+            results_scroll: scroll::ResultsScroll::default(),
+            matched_fields: HashMap::new(),
+            pane_preview: preview::PreviewCache::new(),
+            covers: covers::CategoryCovers::load(std::path::PathBuf::new()),
+            read_only: None,
+            undo: undo::UndoStack::default(),
+            health_promise: None,
+            update_check: None,
+            update_check_started: false,
+            available_update: None,
+            update_check_note: None,
+            health_report: None,
+            checksum_verification: checksums::Verification::default(),
+            checksum_run: None,
+            dimension_run: None,
+            show_health: false,
+            show_usage: false,
+            usage: usage::UsageLog::load(&mut store::UserData::default()),
+            user_store: store::UserData::default(),
+            status_message: status::StatusMessage::new("Loading image list..."),
+            source_classifier: source_hints::Classifier::new(&settings.source_rules),
+            settings: settings.clone(),
+            safe_mode,
+            safe_mode_confirm: None,
+            large_copy_confirm: None,
+            overwrite_confirm: None,
+            offline: offline::OfflineRoots::default(),
+            operations: operations::Operations::default(),
+            applied_settings: settings,
+            hotkey: hotkey::GlobalHotkey::default(),
+            hidden_by_hotkey: false,
+            library_load: None,
+            status_after_load: None,
+            manifest_error: None,
+            load_failure: None,
+            thumbnail_cache_prune: None,
+            thumbnail_cache_usage: None,
+            thumbnail_cache_health: Default::default(),
+            thumbnail_cache_off: None,
+            watcher: None,
+            copy_queue: copy_queue::CopyQueue::default(),
+            watch_pending: Vec::new(),
+            watch_added: std::collections::HashSet::new(),
+            hot_cache: hot_cache::HotCache::default(),
+            hot_textures: HashMap::new(),
+            scoped_by_prefix: false,
+            scope_suggestions: Vec::new(),
+            scope_selected: 0,
             show_settings: false,
+            confirm_reset_settings: false,
+            confirm_delete_profile: None,
+            manifest_path_draft: None,
+            new_profile_name: String::new(),
+            new_open_with: (String::new(), String::new()),
+            show_shortcuts: false,
+            show_debug_overlay: false,
+            frame_guard: frame_guard::FrameGuard::default(),
+            palette: None,
+            remote: None,
+            last_copied: None,
+            on_clipboard: None,
+            window_focused: true,
+            clipboard: clipboard_access::ClipboardAccess::default(),
+            clipboard_banner_dismissed: false,
+            focus_copy_settings: false,
+            os_reduced_motion: settings::os_prefers_reduced_motion(),
         };
-        app.load_image_data();
+        app.apply_active_profile();
+        app.prune_thumbnail_cache();
         app
     }
-}
 
-impl ImageSearchApp {
-    fn load_image_data(&mut self) {
-        if let Ok(content) = std::fs::read_to_string("image_list.json") {
-            match serde_json::from_str::<ImageData>(&content) {
-                Ok(data) => {
-                    self.image_data = Some(data);
-                    self.update_filtered_images();
-                    self.status_message = format!("Loaded {} categories", 
-                        self.image_data.as_ref().unwrap().categories.len());
-                }
-                Err(e) => {
-                    self.status_message = format!("Error parsing JSON: {}", e);
-                }
-            }
+    /// A profile's sidecar folder, or the throwaway one in safe mode.
+    fn profile_dir(&self, profile: &settings::LibraryProfile) -> std::path::PathBuf {
+        if self.safe_mode {
+            safe_mode::scratch_dir().join(&profile.id)
         } else {
-            let cwd = std::env::current_dir()
-                .map(|p| p.display().to_string())
-                .unwrap_or_else(|_| "unknown".to_string());
-            self.status_message = format!("Error: Could not read image_list.json from: {}", cwd);
+            profile.data_dir()
         }
     }
 
-    fn update_filtered_images(&mut self) {
-        if let Some(data) = &self.image_data {
-            self.filtered_images.clear();
-            
-            for (category_name, category) in &data.categories {
-                if self.show_all_categories || self.selected_category == *category_name {
-                    for image in &category.images {
-                        let search_lower = self.search_query.to_lowercase();
-                        let filename_lower = image.filename.to_lowercase();
-                        let category_lower = category_name.to_lowercase();
-                        
-                        let matches_search = self.search_query.is_empty() ||
-                            filename_lower.starts_with(&search_lower) ||  // First letter match
-                            filename_lower.contains(&search_lower) ||     // Contains match
-                            category_lower.contains(&search_lower);       // Category match
-                        
-                        if matches_search {
-                            self.filtered_images.push((category_name.clone(), image.clone()));
+    fn safe_mode_banner(&mut self, ctx: &egui::Context) {
+        if !self.safe_mode {
+            return;
+        }
+        egui::TopBottomPanel::top("safe_mode_banner").show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(egui::RichText::new("🛟 Safe mode").strong().color(ui.visuals().warn_fg_color));
+                ui.label(
+                    "Settings, favorites and other profile files were not loaded, remote control is off, \
+                     and changes made now are discarded on exit.",
+                );
+            });
+            ui.horizontal(|ui| {
+                if let Some(reset) = self.safe_mode_confirm {
+                    ui.label(format!("Delete {}?", reset.path().display()));
+                    if ui.button("Delete").clicked() {
+                        self.set_status(match reset.run() {
+                            Ok(message) => message,
+                            Err(e) => format!("{} failed: {}", reset.label(), e),
+                        });
+                        self.safe_mode_confirm = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.safe_mode_confirm = None;
+                    }
+                } else {
+                    for reset in [safe_mode::Reset::SettingsFile, safe_mode::Reset::ThumbnailCache] {
+                        if ui.button(reset.label()).clicked() {
+                            self.safe_mode_confirm = Some(reset);
+                        }
+                    }
+                    if ui.button("Exit safe mode and restart normally").clicked() {
+                        match safe_mode::restart_normally() {
+                            Ok(()) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                            Err(e) => self.set_status(e),
+                        }
+                    }
+                }
+            });
+            ui.add_space(5.0);
+        });
+    }
+
+    /// Loads the active profile's manifest on a background thread; the current
+    /// library (or the hot cache) stays on screen until it's ready.
+    fn load_image_data(&mut self) {
+        let manifest_path = self.settings.active_profile().manifest_path;
+        let policy = self.settings.duplicate_policy;
+        let sources = self.source_classifier.clone();
+        self.library_load = Some(Promise::spawn_thread("load_library", move || {
+            load_library(&manifest_path, policy, &sources)
+        }));
+    }
+
+    fn poll_library_load(&mut self, ctx: &egui::Context) {
+        let Some(promise) = &self.library_load else {
+            return;
+        };
+        if promise.ready().is_none() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+            return;
+        }
+        let result = self
+            .library_load
+            .take()
+            .and_then(|promise| promise.try_take().ok())
+            .unwrap_or_else(|| {
+                let manifest_path = self.settings.active_profile().manifest_path;
+                let stopped = std::io::Error::new(std::io::ErrorKind::Other, "the loading thread stopped unexpectedly");
+                Err(ManifestError::io(&manifest_path, stopped).into())
+            });
+        match result {
+            Ok(library) => {
+                let duplicate_count = library.duplicates.len();
+                self.show_duplicate_prompt = self.settings.duplicate_policy == DuplicatePolicy::Ask && duplicate_count > 0;
+                self.grouping_rules = library.grouping;
+                self.read_only = library.read_only;
+                self.duplicates = library.duplicates;
+                self.search_index = library.index;
+                // Files the watcher saw disappear are still gone after a reload
+                let missing: Vec<String> = self
+                    .offline
+                    .missing_files()
+                    .filter(|path| !Path::new(path.as_str()).exists())
+                    .cloned()
+                    .collect();
+                self.offline = library.offline;
+                for path in &missing {
+                    self.offline.mark_missing(path);
+                }
+                self.watch_added.clear();
+                // Sizes indexed into the manifest fill in what this profile hasn't measured
+                for image in library.data.categories.values().flat_map(|category| &category.images) {
+                    if let (Some(width), Some(height)) = (image.width, image.height) {
+                        if self.dimensions.get(&image.full_path).is_none() {
+                            self.dimensions.insert(&image.full_path, (width, height));
                         }
                     }
                 }
+                self.new_images.compare(&library.data);
+                self.image_data = Some(library.data);
+                self.load_failure = None;
+                self.prune_thumbnail_cache();
+                // Keep watching unless the folders changed, so nothing between listings is missed
+                let dirs = self.watched_dirs();
+                if self.watcher.as_ref().is_some_and(|watcher| watcher.dirs() != dirs.as_slice()) {
+                    self.watcher = None;
+                }
+                self.update_filtered_images();
+                self.refresh_favorite_entries();
+                self.refresh_pin_entries();
+                self.set_status(format!(
+                    "Loaded {} categories",
+                    self.image_data.as_ref().map_or(0, |data| data.categories.len())
+                ));
+                if duplicate_count > 0 {
+                    self.status_message
+                        .text
+                        .push_str(&format!(", {} image(s) listed in several categories", duplicate_count));
+                    self.status_message.action = Some(status::StatusAction::ShowDuplicates);
+                }
+                if let Some(message) = self.status_after_load.take() {
+                    self.status_message = message;
+                }
+                if self.settings.index_dimensions && !self.safe_mode {
+                    self.start_dimension_index(true);
+                }
+            }
+            Err(e) => {
+                let cwd = std::env::current_dir()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|_| "unknown".to_string());
+                if self.image_data.is_none() {
+                    let manifest_path = self.settings.active_profile().manifest_path;
+                    let cwd = std::path::PathBuf::from(&cwd);
+                    let search_dirs: Vec<std::path::PathBuf> =
+                        [Some(cwd.clone()), portable::exe_dir()].into_iter().flatten().collect();
+                    let diagnosis = load_failure::diagnose(&manifest_path, &cwd, &search_dirs);
+                    self.load_failure = Some((e.to_string(), diagnosis));
+                }
+                let message = status::StatusMessage::new(format!("Error: {} (working directory: {})", e, cwd));
+                self.status_message = match e {
+                    ChlorineError::Manifest(ManifestError::Parse { path, line, column, message: error }) => {
+                        let action = status::StatusAction::ShowManifestError {
+                            path,
+                            line,
+                            column,
+                            message: error,
+                        };
+                        // Opened right away; the status message reopens it
+                        self.run_status_action(action.clone());
+                        message.with_action(action)
+                    }
+                    _ => message,
+                };
+                self.status_after_load = None;
             }
-            
-            // Sort once after filtering
-            self.filtered_images.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.filename.cmp(&b.1.filename)));
         }
+        self.start_health_check();
     }
 
-    fn load_image_texture(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> Option<egui::TextureHandle> {
-        let path = image_info.full_path.clone();
-        
-        // Check if already loaded
-        if let Some(texture) = self.loaded_textures.get(&path) {
-            return Some(texture.clone());
+    /// Favorites first, then the current top results, for next session's hot cache.
+    fn save_hot_cache(&mut self) {
+        if self.image_data.is_none() {
+            return;
         }
-
-        // Check if failed before
-        if self.failed_images.contains(&path) {
-            return None;
+        let mut entries: Vec<hot_cache::HotEntry> = Vec::new();
+        let favorites = self
+            .favorite_entries
+            .iter()
+            .map(|(_, category, image)| (category.clone(), image.clone()));
+        let top = (0..self.filtered_images.len().min(hot_cache::MAX_ENTRIES)).filter_map(|row| self.filtered_entry(row));
+        for (category, image) in favorites.chain(top) {
+            if entries.len() >= hot_cache::MAX_ENTRIES {
+                break;
+            }
+            // Sensitive thumbnails are never written to disk
+            if self.sensitive.contains(&image.full_path) {
+                continue;
+            }
+            if !entries.iter().any(|entry| entry.image.full_path == image.full_path) {
+                entries.push(hot_cache::HotEntry {
+                    category,
+                    image,
+                    missing: false,
+                });
+            }
         }
+        if let Err(e) = self.hot_cache.save(entries) {
+            self.set_status(format!("Failed to save the hot cache: {}", e));
+        }
+    }
 
-        // Check if currently loading
-        if let Some(promise) = self.loading_promises.get(&path) {
-            if let Some(result) = promise.ready() {
-                // Loading complete, create texture
-                if let Some(color_image) = result {
-                    let texture = ctx.load_texture(
-                        &path,
-                        color_image.clone(),
-                        egui::TextureOptions::default(),
-                    );
-                    self.loaded_textures.insert(path.clone(), texture.clone());
-                    self.loading_promises.remove(&path);
-                    return Some(texture);
+    /// Last session's favorites and top results, usable while the manifest loads.
+    fn hot_cache_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            self.loading_indicator(ui);
+            ui.heading("Loading library…");
+        });
+        let entries = self.hot_cache.entries().to_vec();
+        if entries.is_empty() {
+            return;
+        }
+        ui.label(egui::RichText::new("Recent images from last time are ready to copy meanwhile.").weak());
+        ui.add_space(5.0);
+        egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+            for entry in entries {
+                let path = entry.image.full_path.clone();
+                let texture = if self.sensitive.contains(&path) {
+                    Some(self.sensitive_texture(ctx))
                 } else {
-                    // Loading failed
-                    self.loading_promises.remove(&path);
-                    self.failed_images.insert(path);
-                    return None;
+                    self.hot_textures
+                        .entry(path.clone())
+                        .or_insert_with(|| {
+                            self.hot_cache
+                                .thumbnail(&path)
+                                .map(|image| ctx.load_texture(format!("hot:{}", path), image, egui::TextureOptions::default()))
+                        })
+                        .clone()
+                };
+                let texture = texture.unwrap_or_else(|| self.placeholder_texture(ctx, &entry.image.extension));
+                ui.horizontal(|ui| {
+                    let size = texture.size_vec2();
+                    let scale = (48.0 / size.x).min(48.0 / size.y);
+                    ui.image((texture.id(), size * scale));
+                    ui.vertical(|ui| {
+                        let name = egui::RichText::new(&entry.image.filename).strong();
+                        ui.label(if entry.missing { name.weak().strikethrough() } else { name });
+                        ui.label(egui::RichText::new(format!("📁 {}", entry.category)).small().weak());
+                    });
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let copy = ui
+                            .add_enabled(!entry.missing, egui::Button::new("📋 Copy Image"))
+                            .on_disabled_hover_text("This file no longer exists");
+                        if copy.clicked() {
+                            self.copy_image(&entry.category, &entry.image);
+                        }
+                    });
+                });
+                ui.add_space(4.0);
+            }
+        });
+    }
+
+    /// Runs the enabled startup checks on a background thread.
+    fn start_health_check(&mut self) {
+        let checks = &self.settings.health_checks;
+        let sample_paths = match &self.image_data {
+            Some(data) if checks.sample_files => {
+                let total = data.categories.values().map(|c| c.images.len()).sum();
+                let paths = data
+                    .categories
+                    .values()
+                    .flat_map(|c| c.images.iter().map(|i| i.full_path.as_str()));
+                health::sample_paths(paths, total)
+            }
+            _ => Vec::new(),
+        };
+        let options = health::HealthOptions {
+            manifest_path: self.settings.active_profile().manifest_path,
+            sample_paths,
+            cache_dir: settings::cache_dir(),
+            check_manifest: checks.manifest,
+            check_sample_files: checks.sample_files,
+            check_cache_dir: checks.cache_dir && !self.safe_mode,
+            check_clipboard: checks.clipboard,
+        };
+        self.health_report = None;
+        self.health_promise = Some(Promise::spawn_thread("health_check", move || health::run(options)));
+    }
+
+    /// Asks GitHub for the latest release on a background thread. `manual`
+    /// checks come from About and report their outcome there; automatic ones
+    /// only speak up when there is a newer version.
+    fn start_update_check(&mut self, manual: bool) {
+        if self.update_check.is_some() {
+            return;
+        }
+        self.update_check_note = None;
+        let promise = Promise::spawn_thread("update_check", || update_check::check(env!("CARGO_PKG_VERSION")));
+        self.update_check = Some((promise, manual));
+        self.settings.last_update_check = update_check::now();
+        self.persist_settings();
+    }
+
+    /// The About section's update controls.
+    fn update_check_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.settings.check_for_updates, "Check for updates at startup")
+            .on_hover_text("Asks GitHub for the latest release at most once a day. Nothing is downloaded.");
+        ui.horizontal(|ui| {
+            let checking = self.update_check.is_some();
+            if ui.add_enabled(!checking, egui::Button::new("Check now")).clicked() {
+                self.start_update_check(true);
+            }
+            if checking {
+                ui.spinner();
+            } else if let Some(release) = &self.available_update {
+                let url = release.url.clone();
+                if ui.link(format!("Chlorine {} is available", release.version)).on_hover_text(&url).clicked() {
+                    if let Err(e) = launch::open_url(&url) {
+                        self.set_status(e);
+                    }
                 }
-            } else {
-                // Still loading, request repaint
-                ctx.request_repaint();
-                return None;
+            } else if let Some(note) = &self.update_check_note {
+                ui.label(egui::RichText::new(note).small().weak());
             }
+        });
+        if self.settings.last_update_check > 0 {
+            let ago = update_check::now().saturating_sub(self.settings.last_update_check);
+            let ago = match ago {
+                0..=59 => "just now".to_string(),
+                60..=3599 => format!("{} min ago", ago / 60),
+                3600..=86_399 => format!("{} h ago", ago / 3600),
+                _ => format!("{} days ago", ago / 86_400),
+            };
+            ui.label(egui::RichText::new(format!("Last checked {}", ago)).small().weak());
         }
+    }
 
-        // Limit concurrent loads to prevent thread explosion
-        const MAX_CONCURRENT_LOADS: usize = 10;
-        if self.loading_promises.len() >= MAX_CONCURRENT_LOADS {
-            return None;
+    fn poll_update_check(&mut self, ctx: &egui::Context) {
+        if !self.update_check_started {
+            self.update_check_started = true;
+            if self.settings.check_for_updates
+                && !self.safe_mode
+                && update_check::is_due(self.settings.last_update_check)
+            {
+                self.start_update_check(false);
+            }
+        }
+        let Some((promise, manual)) = self.update_check.take() else {
+            return;
+        };
+        let result = match promise.try_take() {
+            Ok(result) => result,
+            Err(promise) => {
+                self.update_check = Some((promise, manual));
+                self.frame_guard.repaint_after(ctx, Duration::from_millis(500));
+                return;
+            }
+        };
+        match result {
+            Ok(Some(release)) => {
+                self.set_status_with(
+                    format!("Chlorine {} is available", release.version),
+                    status::StatusAction::OpenReleasePage(release.url.clone()),
+                );
+                self.available_update = Some(release);
+            }
+            Ok(None) => {
+                self.available_update = None;
+                if manual {
+                    self.update_check_note = Some(format!("Chlorine {} is the latest version", env!("CARGO_PKG_VERSION")));
+                }
+            }
+            Err(e) => {
+                self.set_status(format!("Couldn't check for updates: {}", e));
+                if manual {
+                    self.update_check_note = Some(format!("Couldn't check for updates: {}", e));
+                }
+            }
         }
+    }
 
-        // Start loading in background thread
-        let path_clone = path.clone();
-        let promise = Promise::spawn_thread("load_image", move || {
-            if !Path::new(&path_clone).exists() {
-                return None;
+    fn poll_health_check(&mut self) {
+        let finished = self.health_promise.as_ref().is_some_and(|p| p.ready().is_some());
+        if finished {
+            if let Some(promise) = self.health_promise.take() {
+                self.health_report = promise.try_take().ok();
             }
-            
-            let image_data = std::fs::read(&path_clone).ok()?;
-            let img = image::load_from_memory(&image_data).ok()?;
-            
-            // Resize to thumbnail (max 128x128) for better performance
-            let thumbnail = img.thumbnail(128, 128);
-            let rgba = thumbnail.to_rgba8();
-            let size = [rgba.width() as usize, rgba.height() as usize];
-            let pixels = rgba.into_raw();
-            
-            Some(egui::ColorImage::from_rgba_unmultiplied(
-                size,
-                &pixels,
-            ))
-        });
-        
-        self.loading_promises.insert(path, promise);
-        ctx.request_repaint();
-        None
+        }
     }
 
-    fn copy_image_to_clipboard(&mut self, image_info: &ImageInfo) {
-        if Path::new(&image_info.full_path).exists() {
-            if let Ok(image_data) = std::fs::read(&image_info.full_path) {
-                if let Ok(img) = image::load_from_memory(&image_data) {
-                    if let Some(rgba) = img.as_rgba8() {
-                        match arboard::Clipboard::new() {
-                            Ok(mut clipboard) => {
-                                match clipboard.set_image(arboard::ImageData {
-                                    width: rgba.width() as usize,
-                                    height: rgba.height() as usize,
-                                    bytes: std::borrow::Cow::Borrowed(rgba.as_raw()),
-                                }) {
-                                    Ok(_) => {
-                                        self.status_message = format!("Copied {} to clipboard", image_info.filename);
-                                    }
-                                    Err(e) => {
-                                        self.status_message = format!("Failed to copy to clipboard: {}", e);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                self.status_message = format!("Failed to access clipboard: {}", e);
-                            }
+    fn health_window(&mut self, ctx: &egui::Context) {
+        let Some(report) = self.health_report.clone() else {
+            return;
+        };
+        let mut open = self.show_health;
+        let mut pick_manifest = false;
+        let mut rerun = false;
+
+        egui::Window::new("🩺 Library Health")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{} checks run, {} issue(s) found.", report.checks_run, report.issues.len()));
+                ui.add_space(8.0);
+
+                for issue in &report.issues {
+                    ui.group(|ui| {
+                        ui.strong(issue.check);
+                        ui.label(&issue.problem);
+                        ui.label(egui::RichText::new(&issue.suggestion).weak());
+                        if issue.fix == health::Fix::PickManifest && ui.button("📂 Pick manifest…").clicked() {
+                            pick_manifest = true;
                         }
-                    }
+                    });
                 }
-            } else {
-                self.status_message = format!("Image file not found: {}", image_info.full_path);
-            }
+
+                if report.cloud_placeholders > 0 {
+                    ui.add_space(8.0);
+                    ui.label(format!(
+                        "☁ {} of {} sampled images are cloud-only placeholders and haven't been downloaded.",
+                        report.cloud_placeholders, report.sampled
+                    ));
+                }
+
+                if !self.failed_images.is_empty() || !self.retries.is_empty() {
+                    ui.add_space(8.0);
+                    self.failed_thumbnails_ui(ui);
+                }
+
+                ui.add_space(8.0);
+                self.checksums_ui(ui);
+
+                ui.add_space(8.0);
+                ui.label(
+                    egui::RichText::new("Individual checks can be turned off in Settings → Startup checks.")
+                        .small()
+                        .weak(),
+                );
+                let blocker = self.operations.blocker(operations::OperationKind::HealthCheck);
+                let button = ui.add_enabled(blocker.is_none(), egui::Button::new("🔄 Run checks again"));
+                if button.on_disabled_hover_text(blocker.unwrap_or_default()).clicked() {
+                    rerun = true;
+                }
+            });
+        self.show_health = open;
+
+        if pick_manifest {
+            self.pick_manifest();
+        }
+        if rerun {
+            self.start_health_check();
         }
     }
-}
 
-impl eframe::App for ImageSearchApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Apply theme
-        if self.settings.dark_mode {
-            ctx.set_visuals(egui::Visuals::dark());
-        } else {
-            ctx.set_visuals(egui::Visuals::light());
+    fn pick_manifest(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+            self.use_manifest(&path);
         }
-        
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.add_space(10.0);
-            
+    }
+
+    /// Points the active profile at another manifest and loads it.
+    fn use_manifest(&mut self, path: &Path) {
+        let active = self.settings.active_profile.clone();
+        if let Some(profile) = self.settings.profiles.iter_mut().find(|p| p.id == active) {
+            profile.manifest_path = portable::stored_path(path);
+        }
+        self.persist_settings();
+        self.apply_active_profile();
+    }
+
+    /// Stands in for the results when the manifest couldn't be loaded.
+    fn load_failure_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((error, diagnosis)) = self.load_failure.clone() else {
+            return;
+        };
+        let mut load = None;
+        let mut pick = false;
+        let mut retry = false;
+        egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+            ui.heading("⚠ The library couldn't be opened");
+            ui.label(egui::RichText::new(&error).color(ui.visuals().warn_fg_color));
+            ui.add_space(8.0);
             ui.horizontal(|ui| {
-                ui.heading("Chlorine");
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.button("⚙️ Settings").clicked() {
-                        self.show_settings = !self.show_settings;
-                    }
-                    ui.add_space(10.0);
-                    ui.label(&self.status_message);
-                });
+                ui.label("Tried:");
+                ui.label(egui::RichText::new(diagnosis.tried.display().to_string()).monospace());
+            });
+            let explanation = match &diagnosis.state {
+                load_failure::FileState::Missing => {
+                    "Nothing exists at that path. Relative paths are resolved from the working directory.".to_string()
+                }
+                load_failure::FileState::NotAFile => "That path is a folder; point the library at the JSON file inside it.".to_string(),
+                load_failure::FileState::Unreadable(e) => {
+                    format!("The file exists but can't be read ({}). Check its permissions.", e)
+                }
+                load_failure::FileState::Readable => {
+                    "The file exists and can be read, so the problem is its contents (see the error above).".to_string()
+                }
+            };
+            ui.label(explanation);
+            ui.add_space(8.0);
+            if !diagnosis.suggestions.is_empty() {
+                ui.strong("JSON files nearby");
+                for suggestion in &diagnosis.suggestions {
+                    ui.horizontal(|ui| {
+                        if ui.button("Load this").clicked() {
+                            load = Some(suggestion.clone());
+                        }
+                        ui.label(suggestion.display().to_string());
+                    });
+                }
+                ui.add_space(8.0);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("📂 Choose manifest…").clicked() {
+                    pick = true;
+                }
+                if ui.button("🔄 Try again").clicked() {
+                    retry = true;
+                }
+            });
+        });
+        if let Some(path) = load {
+            self.use_manifest(&path);
+        } else if pick {
+            self.pick_manifest();
+        } else if retry {
+            self.load_image_data();
+        }
+    }
+
+    /// Every library file with a usable checksum, once each: (full path, expected hex).
+    fn checksum_targets(&self) -> Vec<(String, String)> {
+        let Some(data) = &self.image_data else {
+            return Vec::new();
+        };
+        let mut seen = std::collections::HashSet::new();
+        data.categories
+            .values()
+            .flat_map(|category| &category.images)
+            .filter(|image| seen.insert(image.full_path.as_str()))
+            .filter_map(|image| {
+                let expected = checksums::expected_hash(image.checksum.as_deref()?)?;
+                Some((image.full_path.clone(), expected))
+            })
+            .collect()
+    }
+
+    /// Hashes every file with a checksum on a background pool, reusing this session's hashes.
+    fn start_checksum_verification(&mut self) {
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::Verify) {
+            self.set_status(reason);
+            return;
+        }
+        let targets = self.checksum_targets();
+        if targets.is_empty() {
+            self.set_status("This library has no checksums to verify".to_string());
+            return;
+        }
+        let known = self.checksum_verification.known();
+        let progress = std::sync::Arc::new(checksums::Progress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("verify_checksums", move || checksums::run(targets, known, worker_progress));
+        self.checksum_run = Some((promise, progress));
+    }
+
+    fn poll_checksums(&mut self) {
+        let finished = self
+            .checksum_run
+            .as_ref()
+            .is_some_and(|(promise, _)| promise.ready().is_some());
+        if !finished {
+            return;
+        }
+        let Some((promise, progress)) = self.checksum_run.take() else {
+            return;
+        };
+        let results = promise.try_take().unwrap_or_default();
+        let checked = results.len();
+        let mismatched = results
+            .iter()
+            .filter(|result| matches!(result.status, checksums::Status::Mismatch(_)))
+            .count();
+        let missing = results
+            .iter()
+            .filter(|result| result.status == checksums::Status::Missing)
+            .count();
+        self.checksum_verification.record(results);
+        let cancelled = if progress.cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+            "Cancelled. "
+        } else {
+            ""
+        };
+        self.set_status(format!(
+            "{}Checked {} checksum(s): {} mismatched, {} missing",
+            cancelled, checked, mismatched, missing
+        ));
+    }
+
+    /// Probes the size of every image not measured yet on a background pool.
+    /// Automatic runs after a load stay quiet when there's nothing to do.
+    fn start_dimension_index(&mut self, automatic: bool) {
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::IndexDimensions) {
+            if !automatic {
+                self.set_status(reason);
+            }
+            return;
+        }
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        let mut seen = std::collections::HashSet::new();
+        let paths: Vec<String> = data
+            .categories
+            .values()
+            .flat_map(|category| &category.images)
+            .filter(|image| seen.insert(image.full_path.as_str()))
+            .filter(|image| self.dimensions.get(&image.full_path).is_none())
+            .map(|image| image.full_path.clone())
+            .collect();
+        if paths.is_empty() {
+            let written = self.write_dimensions_to_manifest();
+            if !automatic {
+                self.set_status(if written > 0 {
+                    format!("Saving the sizes of {} image(s) to the manifest", written)
+                } else {
+                    "Every image's size is already indexed".to_string()
+                });
+            }
+            return;
+        }
+        let progress = std::sync::Arc::new(dimensions::Progress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("index_dimensions", move || dimensions::probe_all(paths, worker_progress));
+        self.dimension_run = Some((promise, progress));
+    }
+
+    fn poll_dimension_index(&mut self) {
+        let finished = self
+            .dimension_run
+            .as_ref()
+            .is_some_and(|(promise, _)| promise.ready().is_some());
+        if !finished {
+            return;
+        }
+        let Some((promise, progress)) = self.dimension_run.take() else {
+            return;
+        };
+        let results = promise.try_take().unwrap_or_default();
+        let unreadable = results.iter().filter(|(_, size)| *size == (0, 0)).count();
+        let measured = results.len() - unreadable;
+        for (path, size) in &results {
+            self.dimensions.insert(path, *size);
+        }
+        self.dimensions.save();
+        let written = self.write_dimensions_to_manifest();
+        let cancelled = if progress.cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+            "Cancelled. "
+        } else {
+            ""
+        };
+        let stored = if written > 0 {
+            "saving them to the manifest".to_string()
+        } else if let Some(reason) = &self.read_only {
+            format!("kept outside the manifest ({})", reason)
+        } else {
+            "the manifest is up to date".to_string()
+        };
+        self.set_status(format!(
+            "{}Indexed the size of {} image(s), {} unreadable; {}",
+            cancelled, measured, unreadable, stored
+        ));
+        self.update_filtered_images();
+    }
+
+    /// Queues a manifest edit recording every known size the manifest lacks
+    /// or has wrong. Returns how many images it covers; none when read-only.
+    fn write_dimensions_to_manifest(&mut self) -> usize {
+        if self.read_only.is_some() {
+            return 0;
+        }
+        let Some(data) = &self.image_data else {
+            return 0;
+        };
+        let sizes: HashMap<String, (u32, u32)> = data
+            .categories
+            .values()
+            .flat_map(|category| &category.images)
+            .filter_map(|image| {
+                let (width, height) = self.dimensions.get(&image.full_path)?;
+                let known = width > 0 && height > 0;
+                (known && (image.width, image.height) != (Some(width), Some(height)))
+                    .then(|| (image.full_path.clone(), (width, height)))
+            })
+            .collect();
+        let count = sizes.len();
+        if count > 0 {
+            self.manifest_saver
+                .submit(&self.settings.active_profile().manifest_path, manifest::set_dimensions(sizes));
+        }
+        count
+    }
+
+    /// Settings → Performance: the automatic run toggle, and a manual run with its progress.
+    fn dimension_index_settings(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.settings.index_dimensions, "Index image sizes after loading")
+            .on_hover_text(
+                "Reads every image's size from its file header in the background, so orientation, \
+                 ar: and mp: filters are complete right away. Sizes are saved into the manifest when it's writable.",
+            );
+        ui.horizontal(|ui| {
+            if let Some((_, progress)) = &self.dimension_run {
+                let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+                let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+                let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                ui.add(egui::ProgressBar::new(fraction).desired_width(200.0).text(format!("{} / {}", done, total)));
+                let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+                if ui.add_enabled(!cancelled, egui::Button::new("Cancel").small()).clicked() {
+                    progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+                return;
+            }
+            let blocker = self.operations.blocker(operations::OperationKind::IndexDimensions);
+            if ui
+                .add_enabled(blocker.is_none(), egui::Button::new("📐 Index now").small())
+                .on_disabled_hover_text(blocker.unwrap_or_default())
+                .on_hover_text("Measure every image whose size isn't known yet")
+                .clicked()
+            {
+                self.start_dimension_index(false);
+            }
+        });
+    }
+
+    /// The "Verify checksums" part of the health window: progress while it
+    /// runs, then mismatches and missing files listed separately.
+    fn checksums_ui(&mut self, ui: &mut egui::Ui) {
+        ui.strong("Checksums");
+        if let Some((_, progress)) = &self.checksum_run {
+            let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+            let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+            let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+            ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {}", done, total)));
+            let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+            if ui.add_enabled(!cancelled, egui::Button::new("Cancel")).clicked() {
+                progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+            return;
+        }
+        let targets = self.checksum_targets();
+        if targets.is_empty() {
+            ui.label(egui::RichText::new("The manifest has no checksums for this library.").weak());
+            return;
+        }
+
+        let mut verified = 0;
+        let mut unchecked = 0;
+        let mut mismatched = Vec::new();
+        let mut missing = Vec::new();
+        let mut unreadable = Vec::new();
+        for (path, expected) in &targets {
+            match self.checksum_verification.status(path) {
+                Some(checksums::Status::Verified) => verified += 1,
+                Some(checksums::Status::Mismatch(actual)) => mismatched.push((path, expected, actual)),
+                Some(checksums::Status::Missing) => missing.push(path),
+                Some(checksums::Status::Unreadable(e)) => unreadable.push((path, e)),
+                None => unchecked += 1,
+            }
+        }
+        ui.label(format!(
+            "{} with a checksum: {} verified, {} mismatched, {} missing, {} unreadable, {} not checked",
+            targets.len(),
+            verified,
+            mismatched.len(),
+            missing.len(),
+            unreadable.len(),
+            unchecked
+        ));
+        const LISTED: usize = 10;
+        if !mismatched.is_empty() {
+            ui.label(
+                egui::RichText::new("Content differs from the manifest (corrupted or swapped):")
+                    .color(ui.visuals().error_fg_color),
+            );
+            for (path, expected, actual) in mismatched.iter().take(LISTED) {
+                ui.label(format!("  {}", path)).on_hover_text(format!("expected {}
+found    {}", expected, actual));
+            }
+        }
+        let mut remove_missing = false;
+        if !missing.is_empty() {
+            ui.label(egui::RichText::new("Missing files:").color(ui.visuals().warn_fg_color));
+            for path in missing.iter().take(LISTED) {
+                ui.label(format!("  {}", path));
+            }
+            remove_missing = self
+                .read_only_action(ui, egui::Button::new("🗑 Remove missing entries…").small())
+                .clicked();
+        }
+        if !unreadable.is_empty() {
+            ui.label(egui::RichText::new("Couldn't be read:").color(ui.visuals().warn_fg_color));
+            for (path, e) in unreadable.iter().take(LISTED) {
+                ui.label(format!("  {}", path)).on_hover_text(e.as_str());
+            }
+        }
+
+        let blocker = self.operations.blocker(operations::OperationKind::Verify);
+        if ui
+            .add_enabled(blocker.is_none(), egui::Button::new("🔐 Verify checksums"))
+            .on_disabled_hover_text(blocker.unwrap_or_default())
+            .on_hover_text("Hashes files that changed since they were last verified this session")
+            .clicked()
+        {
+            self.start_checksum_verification();
+        }
+        if remove_missing {
+            let missing: Vec<String> = missing.into_iter().cloned().collect();
+            let removals = missing
+                .iter()
+                .flat_map(|path| self.removals_for(path, "file not found when verifying checksums"))
+                .collect();
+            self.open_removal_review("Remove missing entries", removals);
+        }
+    }
+
+    /// Badge text, hover and color for a file's checksum result, once it's known.
+    fn checksum_badge(&self, ui: &egui::Ui, path: &str) -> Option<(&'static str, String, egui::Color32)> {
+        let weak = ui.visuals().weak_text_color();
+        match self.checksum_verification.status(path)? {
+            checksums::Status::Verified => Some(("🔐 ✔", "Checksum verified".to_string(), weak)),
+            checksums::Status::Mismatch(actual) => Some((
+                "🔐 mismatch",
+                format!("The file's SHA-256 is {}, not the manifest's", actual),
+                ui.visuals().error_fg_color,
+            )),
+            checksums::Status::Missing => Some(("🔐 missing", "The file wasn't found".to_string(), ui.visuals().warn_fg_color)),
+            checksums::Status::Unreadable(e) => Some(("🔐 unreadable", e.clone(), ui.visuals().warn_fg_color)),
+        }
+    }
+
+    /// The results as thumbnails in rows. Like the list, only the rows in view
+    /// (and the margin `show_rows` keeps) are laid out; cells take their id
+    /// from the image's path, so hover state follows the image as rows recycle.
+    fn results_grid(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let cell = self.settings.grid_cell_size;
+        let spacing = ui.spacing().item_spacing;
+        let columns = scroll::grid_columns(ui.available_width(), cell, spacing.x);
+        let row_height = scroll::grid_row_height(cell);
+        let count = self.filtered_images.len();
+        self.results_scroll.relayout(columns, row_height + spacing.y);
+        let mut results = egui::ScrollArea::vertical().auto_shrink([false; 2]);
+        if let Some(offset) = self.results_scroll.frame_offset() {
+            results = results.vertical_scroll_offset(offset);
+        }
+        let output = results.show_rows(ui, row_height, count.div_ceil(columns), |ui, row_range| {
+            for row in row_range {
+                ui.horizontal(|ui| {
+                    for i in row * columns..((row + 1) * columns).min(count) {
+                        if let Some((category, image_info)) = self.filtered_entry(i) {
+                            ui.push_id(&image_info.full_path, |ui| {
+                                self.grid_cell(ctx, ui, &category, &image_info, cell);
+                            });
+                        }
+                    }
+                });
+            }
+        });
+        self.results_scroll.record(output.state.offset.y, output.inner_rect.height(), row_height + spacing.y);
+        if self.results_scroll.is_animating() {
+            ctx.request_repaint();
+        }
+    }
+
+    /// One grid cell: the thumbnail and an elided name, with the list row's
+    /// clicks (highlight, Ctrl+click select, double-click copy) and context menu.
+    fn grid_cell(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, category: &str, image_info: &ImageInfo, cell: f32) {
+        let size = egui::vec2(cell + 2.0 * scroll::GRID_CELL_PADDING, scroll::grid_row_height(cell));
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        if !ui.is_rect_visible(rect) {
+            return;
+        }
+        let flash = self.copy_flash_strength(&image_info.full_path);
+        let highlighted = self.highlighted.as_ref().is_some_and(|(_, image)| image.full_path == image_info.full_path);
+        let visuals = ui.visuals().clone();
+        let fill = if flash > 0.0 {
+            visuals.selection.bg_fill.gamma_multiply(flash * 0.6)
+        } else if highlighted {
+            visuals.selection.bg_fill.gamma_multiply(0.25)
+        } else if response.hovered() {
+            visuals.widgets.hovered.weak_bg_fill
+        } else {
+            egui::Color32::TRANSPARENT
+        };
+        ui.painter().rect_filled(rect, 4.0, fill);
+        if self.selection.contains(&image_info.full_path) {
+            ui.painter().rect_stroke(rect, 4.0, visuals.selection.stroke);
+        }
+        self.category_stripe(ui, rect, category);
+
+        let mut inner = ui.child_ui(rect.shrink(scroll::GRID_CELL_PADDING), egui::Layout::top_down(egui::Align::Center));
+        let dimmed = (self.show_hidden && self.hidden.contains(&image_info.full_path)) || self.offline.contains(&image_info.full_path);
+        if dimmed {
+            inner.set_opacity(0.4);
+        }
+        self.thumbnail_ui(ctx, &mut inner, image_info, cell);
+        let font = egui::TextStyle::Small.resolve(inner.style());
+        let shown = elide::middle(ctx, &image_info.filename, &font, cell);
+        inner.add(egui::Label::new(egui::RichText::new(shown).small()).wrap(false));
+
+        let response = response.on_hover_text(&image_info.filename);
+        if response.double_clicked() {
+            self.copy_image(category, image_info);
+        } else if response.clicked() && ui.input(|i| i.modifiers.command) {
+            self.toggle_selection(&image_info.full_path);
+        } else if response.clicked() {
+            self.highlighted = Some((category.to_string(), image_info.clone()));
+        }
+        response.context_menu(|ui| self.image_context_menu(ui, category, image_info));
+    }
+
+    /// Thumbnails that failed to load, split into those still being retried and those given up on.
+    fn failed_thumbnails_ui(&mut self, ui: &mut egui::Ui) {
+        let now = Instant::now();
+        let mut retrying: Vec<(String, String)> = self
+            .retries
+            .iter()
+            .map(|(path, retry)| {
+                let seconds = retry.retry_at.saturating_duration_since(now).as_secs();
+                (path.clone(), format!("{} · retrying in {} s", retry.failure.reason(), seconds))
+            })
+            .collect();
+        let mut failed: Vec<(String, String)> = self
+            .failed_images
+            .iter()
+            .map(|(path, failure)| (path.clone(), failure.reason()))
+            .collect();
+        retrying.sort();
+        failed.sort();
+
+        let mut retry_now = Vec::new();
+        egui::CollapsingHeader::new(format!(
+            "Thumbnails: {} retrying, {} failed",
+            retrying.len(),
+            failed.len()
+        ))
+        .id_source("failed_thumbnails")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for (heading, entries) in [("Retrying automatically", &retrying), ("Failed", &failed)] {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    ui.strong(heading);
+                    for (path, reason) in entries {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("🔄").on_hover_text("Retry now").clicked() {
+                                retry_now.push(path.clone());
+                            }
+                            ui.label(path).on_hover_text(reason);
+                        });
+                    }
+                }
+            });
+            if !failed.is_empty() && ui.button("🔄 Retry all failed").clicked() {
+                retry_now.extend(failed.iter().map(|(path, _)| path.clone()));
+            }
+        });
+        for path in retry_now {
+            self.retry_thumbnail(&path);
+        }
+    }
+
+    fn update_filtered_images(&mut self) {
+        self.apply_category_scope();
+        // Viewing a category clears its "+N new" badge
+        if !self.show_all_categories {
+            self.new_images.mark_seen(&self.selected_category);
+        }
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        let viewing_new = !self.show_all_categories && self.selected_category == new_images::CATEGORY;
+        let query = search::ParsedQuery::parse(self.effective_query());
+        let color_target = query.color.or(self.color_filter.map(|c| [c.r(), c.g(), c.b()]));
+        let folder = self.folder_scope.as_deref().map(search::normalize_path);
+
+        let mut matches = Vec::new();
+        let mut color_pending = 0;
+        let mut dimension_pending = 0;
+        let mut total_bytes = 0;
+        let mut largest_bytes = 0;
+        let mut extension_counts: HashMap<String, usize> = HashMap::new();
+        let mut source_counts: HashMap<&str, usize> = HashMap::new();
+        // A single category is scanned on its own, however large the library
+        let range = if self.show_all_categories || viewing_new {
+            0..self.search_index.entries.len()
+        } else {
+            self.search_index.category_range(&self.selected_category)
+        };
+        for (entry_index, entry) in self.search_index.entries.iter().enumerate().take(range.end).skip(range.start) {
+            if folder.as_deref().is_some_and(|folder| search::parent_dir(&entry.path) != folder) {
+                continue;
+            }
+            let Some(image) = data
+                .categories
+                .get(&entry.category)
+                .and_then(|c| c.images.get(entry.image_index))
+            else {
+                continue;
+            };
+            if viewing_new && !self.new_images.contains(&image.full_path) {
+                continue;
+            }
+            if !self.show_hidden && self.hidden.contains(&image.full_path) {
+                continue;
+            }
+            if self.settings.offline_images == settings::OfflineImages::Hide && self.offline.contains(&image.full_path) {
+                continue;
+            }
+            if self.favorites_only && !self.favorites.contains(&image.full_path) {
+                continue;
+            }
+            let Some(rank) = query.rank(entry) else {
+                continue;
+            };
+            if let Some(target) = color_target {
+                match self.colors.get(&image.full_path) {
+                    Some(signature) if colors::matches(signature, target, self.color_tolerance) => {}
+                    Some(_) => continue,
+                    None => {
+                        // Backfill in the background; the entry shows up once its signature exists
+                        if decoder_supports(&image.extension) {
+                            color_pending += 1;
+                            self.colors.queue(&image.full_path);
+                        }
+                        continue;
+                    }
+                }
+            }
+            if !self.orientations.is_empty() || query.needs_dimensions() {
+                match self.dimensions.get(&image.full_path) {
+                    Some((width, height)) => {
+                        let orientation = dimensions::Orientation::of(width, height);
+                        if !self.orientations.is_empty() && !orientation.is_some_and(|o| self.orientations.contains(&o)) {
+                            continue;
+                        }
+                        // Unreadable files are stored as 0 × 0 and never match a size filter
+                        if orientation.is_none() && query.needs_dimensions() {
+                            continue;
+                        }
+                        if query.aspect.is_some_and(|target| !dimensions::matches_aspect(width, height, target)) {
+                            continue;
+                        }
+                        if query
+                            .megapixels
+                            .is_some_and(|filter| !filter.matches(dimensions::megapixels(width, height)))
+                        {
+                            continue;
+                        }
+                    }
+                    None => {
+                        if decoder_supports(&image.extension) {
+                            dimension_pending += 1;
+                            self.dimensions.queue(&image.full_path);
+                        }
+                        continue;
+                    }
+                }
+            }
+            total_bytes += image.size;
+            largest_bytes = largest_bytes.max(image.size);
+            let extension = image.extension.trim_start_matches('.').to_ascii_lowercase();
+            *extension_counts.entry(extension).or_default() += 1;
+            *source_counts.entry(entry.source.as_str()).or_default() += 1;
+            matches.push((rank, entry_index));
+        }
+
+        let mut by_extension: Vec<(String, usize)> = extension_counts.into_iter().collect();
+        by_extension.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let mut by_source: Vec<(String, usize)> =
+            source_counts.into_iter().map(|(source, count)| (source.to_string(), count)).collect();
+        by_source.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        self.filter_stats = FilterStats {
+            total_bytes,
+            largest_bytes,
+            by_extension,
+            by_source,
+            color_pending,
+            dimension_pending,
+            stacked: 0,
+        };
+
+        search::sort_matches(&mut matches);
+        if viewing_new {
+            // Most recently modified first; the sort is stable, so ties keep the order above
+            let modified = |entry_index: usize| {
+                let entry = &self.search_index.entries[entry_index];
+                data.categories
+                    .get(&entry.category)
+                    .and_then(|c| c.images.get(entry.image_index))
+                    .and_then(|image| self.new_images.modified(&image.full_path))
+            };
+            matches.sort_by_key(|&(_, entry_index)| std::cmp::Reverse(modified(entry_index)));
+        }
+        self.matched_fields = matches
+            .iter()
+            .filter_map(|&(rank, entry_index)| {
+                let field = rank.field()?;
+                Some((entry_index, format!("matched {}: {}", field, query.text)))
+            })
+            .collect();
+        self.filtered_images = matches.into_iter().map(|(_, entry_index)| entry_index).collect();
+        self.stack_sequences();
+    }
+
+    /// Folds numbered frames in the results into one row each, unless the stack is expanded.
+    fn stack_sequences(&mut self) {
+        self.stacks.clear();
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        if !self.settings.stack_sequences {
+            return;
+        }
+        let files = self.filtered_images.iter().filter_map(|&entry_index| {
+            let entry = &self.search_index.entries[entry_index];
+            let image = data.categories.get(&entry.category)?.images.get(entry.image_index)?;
+            Some((entry_index, entry.category.as_str(), image.filename.as_str()))
+        });
+        // Folded frame -> the first frame, which takes the place of the stack's best-ranked match
+        let mut folded: HashMap<usize, usize> = HashMap::new();
+        for stack in sequences::detect(files) {
+            if !self.expanded_stacks.contains(&stack.key) {
+                folded.extend(stack.frames.iter().map(|&frame| (frame, stack.frames[0])));
+            }
+            self.stacks.insert(stack.frames[0], stack);
+        }
+        let mut placed = std::collections::HashSet::new();
+        let rows: Vec<usize> = self
+            .filtered_images
+            .iter()
+            .map(|entry_index| folded.get(entry_index).copied().unwrap_or(*entry_index))
+            .filter(|row| !self.stacks.contains_key(row) || placed.insert(*row))
+            .collect();
+        self.filter_stats.stacked = self.filtered_images.len() - rows.len();
+        self.filtered_images = rows;
+    }
+
+    /// Narrows the results to the folder `image_info` sits in, across all
+    /// categories; the search text still applies on top.
+    fn show_folder(&mut self, image_info: &ImageInfo) {
+        let relative = image_info.relative_path.replace('\\', "/");
+        self.folder_scope = Some(search::parent_dir(&relative).to_string());
+        if self.scoped_by_prefix {
+            self.search_query = self.effective_query().to_string();
+            self.scoped_by_prefix = false;
+        }
+        self.selected_category = "All Categories".to_string();
+        self.show_all_categories = true;
+        self.update_filtered_images();
+    }
+
+    fn toggle_stack(&mut self, key: &str) {
+        if !self.expanded_stacks.remove(key) {
+            self.expanded_stacks.insert(key.to_string());
+        }
+        self.update_filtered_images();
+    }
+
+    /// Follows a "category/" prefix in the search box: selects the category it
+    /// names, and goes back to All Categories once the prefix is erased.
+    fn apply_category_scope(&mut self) {
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        let scope = search::split_scope(&self.search_query)
+            .and_then(|(scope, _)| search::resolve_scope(data.categories.keys(), scope));
+        match scope {
+            Some(category) => {
+                self.selected_category = category;
+                self.show_all_categories = false;
+                self.scoped_by_prefix = true;
+            }
+            None if self.scoped_by_prefix => {
+                self.scoped_by_prefix = false;
+                self.selected_category = "All Categories".to_string();
+                self.show_all_categories = true;
+            }
+            None => {}
+        }
+    }
+
+    /// The search text without a category prefix that is scoping the results.
+    fn effective_query(&self) -> &str {
+        match search::split_scope(&self.search_query) {
+            Some((_, rest)) if self.scoped_by_prefix => rest,
+            _ => &self.search_query,
+        }
+    }
+
+    /// Replaces the search text after a "category/" prefix that is scoping the results.
+    fn set_effective_query(&mut self, text: String) {
+        self.search_query = match search::split_scope(&self.search_query) {
+            Some((scope, _)) if self.scoped_by_prefix => format!("{}/{}", scope, text),
+            _ => text,
+        };
+    }
+
+    /// The active filter broken into chips, in the order the controls appear.
+    fn filter_chips(&self) -> Vec<FilterChip> {
+        let mut chips = Vec::new();
+        let mut text = Vec::new();
+        for (index, word) in self.effective_query().split_whitespace().enumerate() {
+            if search::is_operator(word) {
+                chips.push(FilterChip::Operator {
+                    index,
+                    word: word.to_string(),
+                });
+            } else {
+                text.push(word);
+            }
+        }
+        if !text.is_empty() {
+            chips.insert(0, FilterChip::Text(text.join(" ")));
+        }
+        if !self.show_all_categories {
+            chips.push(FilterChip::Category(self.selected_category.clone()));
+        }
+        if let Some(folder) = &self.folder_scope {
+            chips.push(FilterChip::Folder(folder.clone()));
+        }
+        if self.favorites_only {
+            chips.push(FilterChip::FavoritesOnly);
+        }
+        if let Some(color) = self.color_filter {
+            chips.push(FilterChip::Color(color));
+        }
+        let mut orientations: Vec<_> = self.orientations.iter().copied().collect();
+        orientations.sort_by_key(|orientation| dimensions::Orientation::ALL.iter().position(|o| o == orientation));
+        chips.extend(orientations.into_iter().map(FilterChip::Orientation));
+        if self.show_hidden && !self.hidden.is_empty() {
+            chips.push(FilterChip::ShowHidden);
+        }
+        chips
+    }
+
+    fn remove_filter_chip(&mut self, chip: &FilterChip) {
+        match chip {
+            FilterChip::Text(_) => {
+                let operators: Vec<&str> = self
+                    .effective_query()
+                    .split_whitespace()
+                    .filter(|word| search::is_operator(word))
+                    .collect();
+                self.set_effective_query(operators.join(" "));
+            }
+            FilterChip::Operator { index, .. } => {
+                let words: Vec<&str> = self
+                    .effective_query()
+                    .split_whitespace()
+                    .enumerate()
+                    .filter(|(i, _)| i != index)
+                    .map(|(_, word)| word)
+                    .collect();
+                self.set_effective_query(words.join(" "));
+            }
+            FilterChip::Category(_) => {
+                if self.scoped_by_prefix {
+                    self.search_query = self.effective_query().to_string();
+                    self.scoped_by_prefix = false;
+                }
+                self.selected_category = "All Categories".to_string();
+                self.show_all_categories = true;
+            }
+            FilterChip::Folder(_) => self.folder_scope = None,
+            FilterChip::Color(_) => self.color_filter = None,
+            FilterChip::Orientation(orientation) => {
+                self.orientations.remove(orientation);
+            }
+            FilterChip::FavoritesOnly => self.favorites_only = false,
+            FilterChip::ShowHidden => self.show_hidden = false,
+        }
+    }
+
+    /// Chips for every active filter under the search box, so it's clear why
+    /// the results are what they are. Clicking one removes that filter.
+    fn filter_chips_ui(&mut self, ui: &mut egui::Ui) {
+        let chips = self.filter_chips();
+        if chips.is_empty() {
+            return;
+        }
+        let mut remove = Vec::new();
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new("Filters:").small().weak());
+            for chip in &chips {
+                let mut text = egui::RichText::new(format!("{} ×", chip.label())).small();
+                if let FilterChip::Color(color) = chip {
+                    text = text.color(*color);
+                }
+                if ui
+                    .add(egui::Button::new(text).rounding(8.0))
+                    .on_hover_text("Remove this filter")
+                    .clicked()
+                {
+                    remove.push(chip.clone());
+                }
+            }
+            if chips.len() > 1
+                && ui
+                    .add(egui::Button::new(egui::RichText::new("clear all").small()).rounding(8.0))
+                    .clicked()
+            {
+                remove = chips.clone();
+            }
+        });
+        if remove.is_empty() {
+            return;
+        }
+        // Search words go from last to first so positions stay valid
+        for chip in remove.iter().rev() {
+            self.remove_filter_chip(chip);
+        }
+        self.update_filtered_images();
+        self.refresh_scope_suggestions();
+    }
+
+    /// Categories offered while the first word of the search could still be a "category/" prefix.
+    fn refresh_scope_suggestions(&mut self) {
+        self.scope_suggestions.clear();
+        self.scope_selected = 0;
+        let query = self.search_query.trim_start();
+        if query.is_empty() || query.contains(|c: char| c.is_whitespace() || c == '/' || c == ':') {
+            return;
+        }
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        self.scope_suggestions = search::categories_starting_with(data.categories.keys(), query)
+            .into_iter()
+            .take(MAX_SCOPE_SUGGESTIONS)
+            .map(|name| (name.clone(), data.categories[name].images.len()))
+            .collect();
+    }
+
+    fn accept_scope_suggestion(&mut self, index: usize) {
+        let Some((name, _)) = self.scope_suggestions.get(index) else {
+            return;
+        };
+        self.search_query = format!("{}/", name);
+        self.scope_suggestions.clear();
+        self.update_filtered_images();
+    }
+
+    fn scope_suggestions_popup(&mut self, ui: &mut egui::Ui, search_box: &egui::Response) {
+        let popup_id = egui::Id::new("category_scope_popup");
+        let open = search_box.has_focus() && !self.scope_suggestions.is_empty();
+        if open != ui.memory(|m| m.is_popup_open(popup_id)) {
+            ui.memory_mut(|m| if open { m.open_popup(popup_id) } else { m.close_popup() });
+        }
+        let mut chosen = None;
+        egui::popup_below_widget(ui, popup_id, search_box, |ui| {
+            ui.set_min_width(search_box.rect.width());
+            ui.label(egui::RichText::new("Scope to category (type / or press Enter)").small().weak());
+            for (index, (name, count)) in self.scope_suggestions.iter().enumerate() {
+                if ui
+                    .selectable_label(index == self.scope_selected, format!("📁 {}/  ({})", name, count))
+                    .clicked()
+                {
+                    chosen = Some(index);
+                }
+            }
+        });
+        if let Some(index) = chosen {
+            self.accept_scope_suggestion(index);
+            ui.memory_mut(|m| m.request_focus(search_box_id()));
+        }
+    }
+
+    /// Category and image for a result row.
+    fn filtered_entry(&self, row: usize) -> Option<(String, ImageInfo)> {
+        let entry = self.search_index.entries.get(*self.filtered_images.get(row)?)?;
+        let image = self
+            .image_data
+            .as_ref()?
+            .categories
+            .get(&entry.category)?
+            .images
+            .get(entry.image_index)?;
+        Some((entry.category.clone(), image.clone()))
+    }
+
+    fn load_image_texture(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> Option<egui::TextureHandle> {
+        let path = image_info.full_path.clone();
+        
+        // Check if already loaded, sharp enough for the current display size
+        let wanted = thumbnail_tier(self.thumbnail_pixels);
+        let cached = self.loaded_textures.get(&path).cloned();
+        if let Some((tier, texture)) = &cached {
+            if *tier >= wanted || (*tier as f32) * THUMBNAIL_UPSCALE_TOLERANCE >= self.thumbnail_pixels {
+                return Some(texture.clone());
+            }
+        }
+        // A lower tier stays on screen while the sharper one decodes
+        let fallback = cached.map(|(_, texture)| texture);
+
+        // Files on a missing drive aren't tried one by one
+        if self.offline.contains(&path) {
+            return fallback;
+        }
+
+        // Check if failed before, or if there is no decoder to try
+        let is_text = self.is_text_entry(image_info);
+        if !(is_text || decoder_supports(&image_info.extension)) {
+            return self.info_tile(ctx, image_info);
+        }
+        if self.failed_images.contains_key(&path) {
+            return fallback;
+        }
+        if let Some(retry) = self.retries.get(&path) {
+            let now = Instant::now();
+            if now < retry.retry_at {
+                // Checked again while the row stays visible
+                ctx.request_repaint_after(retry.retry_at - now);
+                return fallback;
+            }
+        }
+
+        // Decoding, or decoded and waiting for its turn to upload in `upload_thumbnails`
+        if self.loading_promises.contains_key(&path) || self.decoded.iter().any(|(decoded, _)| *decoded == path) {
+            self.thumbnail_requests.insert(path);
+            return fallback;
+        }
+
+        // Nothing is on screen while minimized; rows ask again once it's restored
+        if self.frame_guard.is_hidden() {
+            return fallback;
+        }
+
+        // Limit concurrent loads to prevent thread explosion
+        const MAX_CONCURRENT_LOADS: usize = 10;
+        if self.loading_promises.len() + self.decoded.len() >= MAX_CONCURRENT_LOADS {
+            return fallback;
+        }
+
+        // Reading a cloud placeholder downloads it, so only do that when asked
+        if self.cloud_files.contains(&path) {
+            return None;
+        }
+        if !self.settings.auto_download_cloud_files
+            && !self.hydrate_requested.contains(&path)
+            && cloud::is_placeholder(Path::new(&path))
+        {
+            self.cloud_files.insert(path);
+            return None;
+        }
+
+        if self.settings.integrity_hashing && fallback.is_none() {
+            self.integrity.queue(&path);
+        }
+
+        // Start loading in background thread
+        let path_clone = path.clone();
+        let cache = self.thumbnail_cache();
+        let promise = Promise::spawn_thread("load_image", move || {
+            decode_thumbnail(&path_clone, is_text, wanted, cache.as_ref())
+        });
+        
+        self.loading_promises.insert(path, promise);
+        ctx.request_repaint();
+        fallback
+    }
+
+    /// The disk cache thumbnails are read from and written to; none in safe
+    /// mode or once writing to it kept failing.
+    fn thumbnail_cache(&self) -> Option<thumbnail_cache::ThumbnailCache> {
+        (!self.safe_mode && self.thumbnail_cache_off.is_none()).then(|| {
+            thumbnail_cache::ThumbnailCache::new(
+                thumbnail_cache::dir(&self.settings.thumbnail_cache_dir),
+                self.settings.thumbnail_encoding,
+                self.settings.thumbnail_quality,
+                self.thumbnail_cache_health.clone(),
+            )
+        })
+    }
+
+    /// Turns the disk cache off, once, when its writes keep failing; decodes
+    /// carry on uncached until another folder is chosen.
+    fn check_thumbnail_cache_health(&mut self) {
+        if self.thumbnail_cache_off.is_some() {
+            return;
+        }
+        let Some(error) = self.thumbnail_cache_health.failing() else {
+            return;
+        };
+        self.set_status_with(
+            format!(
+                "⚠ Thumbnails can't be cached ({}), so caching is off for this session. Thumbnails still load, just slower.",
+                error
+            ),
+            status::StatusAction::ChooseThumbnailCacheDir,
+        );
+        self.thumbnail_cache_off = Some(error);
+    }
+
+    /// Picks a folder for the thumbnail cache, checking it can be written first.
+    fn choose_thumbnail_cache_dir(&mut self) {
+        let current = thumbnail_cache::dir(&self.settings.thumbnail_cache_dir);
+        let mut dialog = rfd::FileDialog::new();
+        if current.is_dir() {
+            dialog = dialog.set_directory(&current);
+        }
+        let Some(dir) = dialog.pick_folder() else {
+            return;
+        };
+        match thumbnail_cache::probe(&dir) {
+            Ok(()) => {
+                self.settings.thumbnail_cache_dir = portable::stored_path(&dir);
+                // Also when it's the same folder, back after its drive returned
+                self.thumbnail_cache_health.reset();
+                self.thumbnail_cache_off = None;
+                self.set_status(format!("Thumbnails are cached in {}", dir.display()));
+            }
+            Err(e) => self.set_status(format!("Can't use that folder for thumbnails: {}", e)),
+        }
+    }
+
+    fn thumbnail_cache_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label("Thumbnail cache on disk:");
+        ui.horizontal(|ui| {
+            ui.label("Folder:");
+            let dir = thumbnail_cache::dir(&self.settings.thumbnail_cache_dir);
+            ui.label(egui::RichText::new(dir.display().to_string()).monospace().small());
+            if ui.small_button("Choose…").clicked() {
+                self.choose_thumbnail_cache_dir();
+            }
+            if !self.settings.thumbnail_cache_dir.is_empty() && ui.small_button("Use default").clicked() {
+                self.settings.thumbnail_cache_dir.clear();
+            }
+        });
+        if let Some(error) = &self.thumbnail_cache_off {
+            ui.label(
+                egui::RichText::new(format!("⚠ Off for this session, writing failed: {}. Choose another folder to turn it back on.", error))
+                    .small()
+                    .color(ui.visuals().warn_fg_color),
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.label("Format:");
+            egui::ComboBox::from_id_source("thumbnail_encoding")
+                .selected_text(self.settings.thumbnail_encoding.label())
+                .show_ui(ui, |ui| {
+                    for encoding in settings::ThumbnailEncoding::ALL {
+                        ui.selectable_value(&mut self.settings.thumbnail_encoding, encoding, encoding.label());
+                    }
+                });
+        })
+        .response
+        .on_hover_text("Thumbnails cached in another format are regenerated as they're shown");
+        if self.settings.thumbnail_encoding == settings::ThumbnailEncoding::Jpeg {
+            ui.add(egui::Slider::new(&mut self.settings.thumbnail_quality, 40..=95).text("JPEG quality"));
+        }
+        ui.horizontal(|ui| {
+            ui.label("Size limit:");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.thumbnail_cache_mb)
+                    .clamp_range(16..=16384)
+                    .suffix(" MB"),
+            );
+        });
+        ui.horizontal(|ui| {
+            let usage = match (&self.thumbnail_cache_prune, self.thumbnail_cache_usage) {
+                (Some(_), _) => "Measuring…".to_string(),
+                (None, Some(usage)) => format!("{} thumbnails, {}", usage.entries, format_bytes(usage.bytes)),
+                (None, None) => "Not measured yet".to_string(),
+            };
+            ui.label(egui::RichText::new(usage).weak());
+            let blocker = if self.safe_mode {
+                Some("The thumbnail cache isn't used in safe mode")
+            } else if self.thumbnail_cache_prune.is_some() {
+                Some("Already pruning")
+            } else {
+                None
+            };
+            if ui
+                .add_enabled(blocker.is_none(), egui::Button::new("Prune now").small())
+                .on_hover_text("Delete the least recently used thumbnails until the cache is under its limit")
+                .on_disabled_hover_text(blocker.unwrap_or_default())
+                .clicked()
+            {
+                self.prune_thumbnail_cache();
+            }
+        });
+    }
+
+    /// Trims the disk thumbnail cache to its cap on a background thread.
+    fn prune_thumbnail_cache(&mut self) {
+        if self.safe_mode || self.thumbnail_cache_prune.is_some() {
+            return;
+        }
+        let limit = u64::from(self.settings.thumbnail_cache_mb) * 1024 * 1024;
+        let dir = thumbnail_cache::dir(&self.settings.thumbnail_cache_dir);
+        self.thumbnail_cache_prune = Some(Promise::spawn_thread("prune_thumbnails", move || {
+            thumbnail_cache::prune(&dir, limit)
+        }));
+    }
+
+    fn poll_thumbnail_cache_prune(&mut self, ctx: &egui::Context) {
+        let Some(promise) = &self.thumbnail_cache_prune else {
+            return;
+        };
+        match promise.ready() {
+            Some(usage) => {
+                self.thumbnail_cache_usage = Some(*usage);
+                self.thumbnail_cache_prune = None;
+            }
+            None => self.frame_guard.repaint_after(ctx, Duration::from_millis(200)),
+        }
+    }
+
+    /// Moves finished decodes out of their promises, then uploads a few of
+    /// them: the ones rows asked for last frame first, oldest first after
+    /// that. Runs before any panel, so a burst of finished thumbnails is
+    /// spread over several frames instead of stalling the list mid-layout.
+    fn upload_thumbnails(&mut self, ctx: &egui::Context) {
+        let finished: Vec<String> = self
+            .loading_promises
+            .iter()
+            .filter(|(_, promise)| promise.ready().is_some())
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in finished {
+            // Take the pixels out so the buffer is freed once uploaded
+            let result = self
+                .loading_promises
+                .remove(&path)
+                .and_then(|p| p.try_take().ok())
+                .unwrap_or_else(|| {
+                    Err(LoaderError::Unreadable {
+                        path: path.clone(),
+                        message: "load was interrupted".to_string(),
+                    })
+                });
+            self.decoded.push_back((path, result));
+        }
+
+        let visible = std::mem::take(&mut self.thumbnail_requests);
+        let started = Instant::now();
+        let mut uploaded = 0;
+        while uploaded < MAX_UPLOADS_PER_FRAME && started.elapsed() < UPLOAD_BUDGET {
+            let next = self
+                .decoded
+                .iter()
+                .position(|(path, _)| visible.contains(path))
+                .or((!self.decoded.is_empty()).then_some(0));
+            let Some((path, result)) = next.and_then(|index| self.decoded.remove(index)) else {
+                break;
+            };
+            self.upload_thumbnail(ctx, path, result);
+            uploaded += 1;
+        }
+
+        if !self.decoded.is_empty() {
+            ctx.request_repaint();
+        } else if !self.loading_promises.is_empty() {
+            // Collected at a slower pace in the background
+            self.frame_guard.repaint(ctx, frame_guard::BACKGROUND_THUMBNAIL_POLL);
+        }
+    }
+
+    fn upload_thumbnail(&mut self, ctx: &egui::Context, path: String, result: Result<Thumbnail, LoaderError>) {
+        match result {
+            Ok(thumbnail) => {
+                let texture = ctx.load_texture(
+                    format!("{}@{}", path, thumbnail.tier),
+                    thumbnail.image,
+                    egui::TextureOptions::default(),
+                );
+                if let Some(signature) = thumbnail.colors {
+                    self.colors.insert(&path, signature);
+                }
+                if let Some(size) = thumbnail.dimensions {
+                    self.dimensions.insert(&path, size);
+                    self.luminance.insert(&path, thumbnail.luminance);
+                }
+                self.retries.remove(&path);
+                self.cache_texture(path, thumbnail.tier, texture);
+            }
+            Err(failure) => {
+                // Keep the lower tier rather than failing a file that loaded before
+                if let Some((_, texture)) = self.loaded_textures.get(&path).cloned() {
+                    self.loaded_textures.insert(path, (FIXED_TIER, texture));
+                    return;
+                }
+                self.record_load_failure(path, failure);
+            }
+        }
+    }
+
+    /// Schedules the next attempt for a transient failure, or gives up on the file.
+    fn record_load_failure(&mut self, path: String, failure: LoaderError) {
+        if !failure.is_transient() {
+            self.retries.remove(&path);
+            self.failed_images.insert(path, failure);
+            return;
+        }
+        let attempts = self.retries.get(&path).map_or(0, |retry| retry.attempts) + 1;
+        match RETRY_BACKOFF.get(attempts - 1) {
+            Some(delay) => {
+                self.retries.insert(
+                    path,
+                    PendingRetry {
+                        attempts,
+                        retry_at: Instant::now() + *delay,
+                        failure,
+                    },
+                );
+            }
+            None => {
+                self.retries.remove(&path);
+                self.failed_images.insert(path, failure);
+            }
+        }
+    }
+
+    /// Lets a cloud placeholder load (and so download) on the next frame.
+    fn download_cloud_file(&mut self, path: &str) {
+        self.cloud_files.remove(path);
+        self.hydrate_requested.insert(path.to_string());
+    }
+
+    /// Forgets a failure so the next visible row loads the file again.
+    fn retry_thumbnail(&mut self, path: &str) {
+        self.failed_images.remove(path);
+        self.retries.remove(path);
+    }
+
+    /// Stores a thumbnail texture, evicting the oldest ones past the cache limit.
+    fn texture_cache_limit(&self) -> usize {
+        if self.settings.low_memory {
+            LOW_MEMORY_TEXTURE_CACHE_LIMIT
+        } else {
+            TEXTURE_CACHE_LIMIT
+        }
+    }
+
+    fn cache_texture(&mut self, path: String, tier: u32, texture: egui::TextureHandle) {
+        let limit = self.texture_cache_limit();
+        // A sharper tier replaces the old texture in place
+        if self.loaded_textures.insert(path.clone(), (tier, texture)).is_none() {
+            self.texture_order.push_back(path);
+        }
+        while self.loaded_textures.len() > limit {
+            let Some(oldest) = self.texture_order.pop_front() else {
+                break;
+            };
+            self.loaded_textures.remove(&oldest);
+        }
+    }
+
+    /// Rough size of what Chlorine keeps in memory, for the debug overlay.
+    fn memory_estimate(&self) -> Vec<(&'static str, usize)> {
+        let texture_bytes: usize = self
+            .loaded_textures
+            .values()
+            .map(|(_, texture)| texture)
+            .chain(self.placeholder_textures.values())
+            .map(|t| t.byte_size())
+            .sum();
+        // Strings dominate the manifest: count their bytes plus struct overhead per image
+        let manifest_bytes: usize = self.image_data.as_ref().map_or(0, |data| {
+            data.categories
+                .values()
+                .flat_map(|c| &c.images)
+                .map(|i| {
+                    std::mem::size_of::<ImageInfo>()
+                        + i.filename.len()
+                        + i.relative_path.len()
+                        + i.full_path.len()
+                        + i.extension.len()
+                })
+                .sum()
+        });
+        let index_bytes: usize = self
+            .search_index
+            .entries
+            .iter()
+            .map(|e| std::mem::size_of::<search::IndexEntry>() + e.category.len() + e.filename.len() + e.path.len() + e.category_lower.len())
+            .sum();
+        vec![
+            ("Textures", texture_bytes),
+            ("Manifest", manifest_bytes),
+            ("Search index", index_bytes),
+            ("Results", self.filtered_images.len() * std::mem::size_of::<usize>()),
+        ]
+    }
+
+    fn debug_overlay(&self, ctx: &egui::Context) {
+        let estimate = self.memory_estimate();
+        let total: usize = estimate.iter().map(|(_, bytes)| bytes).sum();
+        egui::Area::new(egui::Id::new("debug_overlay"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(egui::RichText::new("Debug (F12)").strong());
+                    ui.monospace(format!(
+                        "textures {} · loading {} · to upload {} · tier {} px · low memory {}",
+                        self.loaded_textures.len(),
+                        self.loading_promises.len(),
+                        self.decoded.len(),
+                        thumbnail_tier(self.thumbnail_pixels),
+                        if self.settings.low_memory { "on" } else { "off" }
+                    ));
+                    ui.monospace(self.frame_guard.describe());
+                    for (label, bytes) in &estimate {
+                        ui.monospace(format!("{:<13}{:>10}", label, format_bytes(*bytes as u64)));
+                    }
+                    ui.monospace(format!("{:<13}{:>10}", "≈ Total", format_bytes(total as u64)));
+                });
+            });
+    }
+
+    /// Requests the thumbnail if possible and reports which of the row states applies.
+    fn thumbnail_state(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> ThumbnailState {
+        if self.sensitive.contains(&image_info.full_path) {
+            ThumbnailState::Sensitive
+        } else if self.offline.contains(&image_info.full_path) {
+            ThumbnailState::Offline
+        } else if self.settings.text_only {
+            ThumbnailState::TextOnly
+        } else if let Some(texture) = self.load_image_texture(ctx, image_info) {
+            ThumbnailState::Ready(texture)
+        } else if self.loading_promises.contains_key(&image_info.full_path)
+            || self.decoded.iter().any(|(path, _)| *path == image_info.full_path)
+        {
+            ThumbnailState::Loading
+        } else if self.failed_images.contains_key(&image_info.full_path) {
+            ThumbnailState::Failed
+        } else if self.retries.contains_key(&image_info.full_path) {
+            ThumbnailState::Retrying
+        } else if self.cloud_files.contains(&image_info.full_path) {
+            ThumbnailState::InCloud
+        } else if !(self.is_text_entry(image_info) || decoder_supports(&image_info.extension)) {
+            ThumbnailState::Unsupported
+        } else {
+            ThumbnailState::Waiting
+        }
+    }
+
+    /// Tile with the extension, file size and header dimensions for a file no
+    /// decoder reads. Rendered once the size probe is back and then cached like
+    /// a thumbnail; until then the row shows the plain extension tile.
+    fn info_tile(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> Option<egui::TextureHandle> {
+        let Some(size) = self.dimensions.get(&image_info.full_path) else {
+            self.dimensions.queue(&image_info.full_path);
+            return None;
+        };
+        let dimensions = (size != (0, 0)).then_some(size);
+        let label = placeholder::extension_label(&image_info.extension);
+        let texture = ctx.load_texture(
+            format!("{}@info", image_info.full_path),
+            placeholder::render_info_tile(&label, image_info.size, dimensions),
+            egui::TextureOptions::NEAREST,
+        );
+        self.cache_texture(image_info.full_path.clone(), FIXED_TIER, texture.clone());
+        Some(texture)
+    }
+
+    fn error_texture(&mut self, ctx: &egui::Context) -> egui::TextureHandle {
+        self.placeholder_textures
+            .entry("!error".to_string())
+            .or_insert_with(|| {
+                ctx.load_texture("placeholder:!error", placeholder::render_error_tile(), egui::TextureOptions::NEAREST)
+            })
+            .clone()
+    }
+
+    fn sensitive_texture(&mut self, ctx: &egui::Context) -> egui::TextureHandle {
+        self.placeholder_textures
+            .entry("!sensitive".to_string())
+            .or_insert_with(|| {
+                ctx.load_texture(
+                    "placeholder:!sensitive",
+                    placeholder::render_tile("PRIV"),
+                    egui::TextureOptions::NEAREST,
+                )
+            })
+            .clone()
+    }
+
+    /// Draws a square thumbnail slot: the image, a spinner while decoding, a dimmed
+    /// extension tile while waiting for a slot, or an error tile.
+    fn thumbnail_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, image_info: &ImageInfo, size: f32) {
+        self.frame_thumbnail_points = self.frame_thumbnail_points.max(size);
+        let size = egui::Vec2::splat(size);
+        match self.thumbnail_state(ctx, image_info) {
+            ThumbnailState::Ready(texture) => {
+                // Painted behind the image once its rect is known
+                let slot = ui.painter().add(egui::Shape::Noop);
+                let response = ui.image((texture.id(), size));
+                let has_pixels = !self.is_text_entry(image_info) && decoder_supports(&image_info.extension);
+                if let Some(backdrop) = self.backdrop_for(&image_info.full_path).filter(|_| has_pixels) {
+                    ui.painter().set(slot, backdrop.shape(response.rect));
+                }
+                if !(self.is_text_entry(image_info) || decoder_supports(&image_info.extension)) {
+                    response.on_hover_text(unsupported_hint(&image_info.extension));
+                }
+            }
+            ThumbnailState::Loading => {
+                ui.allocate_ui(size, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        self.loading_indicator(ui);
+                    });
+                });
+            }
+            ThumbnailState::Waiting => {
+                let texture = self.placeholder_texture(ctx, &image_info.extension);
+                ui.add(egui::Image::new((texture.id(), size)).tint(egui::Color32::from_white_alpha(90)));
+            }
+            ThumbnailState::Unsupported => {
+                let texture = self.placeholder_texture(ctx, &image_info.extension);
+                ui.image((texture.id(), size)).on_hover_text(unsupported_hint(&image_info.extension));
+            }
+            ThumbnailState::TextOnly => {
+                let texture = self.placeholder_texture(ctx, &image_info.extension);
+                ui.image((texture.id(), size));
+            }
+            ThumbnailState::Sensitive => {
+                let texture = self.sensitive_texture(ctx);
+                ui.image((texture.id(), size)).on_hover_text("Marked sensitive");
+            }
+            ThumbnailState::Offline => {
+                let texture = self.placeholder_texture(ctx, &image_info.extension);
+                let root = offline::root_of(&image_info.full_path).unwrap_or_default();
+                ui.add(egui::Image::new((texture.id(), size)).tint(egui::Color32::from_white_alpha(60)))
+                    .on_hover_text(format!("{} is not mounted", root));
+            }
+            ThumbnailState::InCloud => {
+                let button = egui::Button::new("☁\nDownload");
+                if ui
+                    .add_sized(size, button)
+                    .on_hover_text("Stored in the cloud only. Click to download & load it.")
+                    .clicked()
+                {
+                    self.download_cloud_file(&image_info.full_path);
+                }
+            }
+            ThumbnailState::Retrying => {
+                let texture = self.error_texture(ctx);
+                let hover = self.retries.get(&image_info.full_path).map(|retry| {
+                    format!(
+                        "{}\nRetrying in {} s (attempt {} of {})",
+                        retry.failure.reason(),
+                        retry.retry_at.saturating_duration_since(Instant::now()).as_secs() + 1,
+                        retry.attempts + 1,
+                        RETRY_BACKOFF.len() + 1
+                    )
+                });
+                let response = ui.add(egui::Image::new((texture.id(), size)).tint(egui::Color32::from_white_alpha(90)));
+                if let Some(hover) = hover {
+                    response.on_hover_text(hover);
+                }
+            }
+            ThumbnailState::Failed => {
+                let texture = self.error_texture(ctx);
+                let hover = self
+                    .failed_images
+                    .get(&image_info.full_path)
+                    .map_or_else(|| "This file could not be loaded".to_string(), LoaderError::reason);
+                ui.image((texture.id(), size)).on_hover_text(hover);
+            }
+        }
+    }
+
+    fn is_text_entry(&self, image_info: &ImageInfo) -> bool {
+        self.settings.is_text_extension(&image_info.extension)
+    }
+
+    /// Text of a snippet entry, read once and cached; errors are cached too.
+    fn text_content(&mut self, path: &str) -> Result<String, String> {
+        self.text_cache
+            .entry(path.to_string())
+            .or_insert_with(|| snippet::read_text(path))
+            .clone()
+    }
+
+    fn copy_snippet_text(&mut self, image_info: &ImageInfo) {
+        match self.text_content(&image_info.full_path) {
+            Ok(text) => {
+                if self.copy_text_to_clipboard(text, &image_info.filename) {
+                    self.mark_copied(&image_info.full_path);
+                }
+            }
+            Err(e) => self.set_status(format!("Failed to copy: {}", e)),
+        }
+    }
+
+    /// Extension tile texture, rendered once per extension and reused.
+    fn placeholder_texture(&mut self, ctx: &egui::Context, extension: &str) -> egui::TextureHandle {
+        let label = placeholder::extension_label(extension);
+        self.placeholder_textures
+            .entry(label.clone())
+            .or_insert_with(|| {
+                ctx.load_texture(
+                    format!("placeholder:{}", label),
+                    placeholder::render_tile(&label),
+                    egui::TextureOptions::NEAREST,
+                )
+            })
+            .clone()
+    }
+
+    fn persist_settings(&mut self) {
+        // Safe mode never overwrites the real settings file
+        if self.safe_mode {
+            return;
+        }
+        if let Err(e) = settings::save(&self.settings) {
+            self.set_status(format!("Failed to save settings: {}", e));
+        }
+    }
+
+    fn export_settings(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("chlorine-settings.json")
+            .save_file()
+        else {
+            return;
+        };
+
+        let bundle = settings::SettingsBundle {
+            version: settings::BUNDLE_VERSION,
+            settings: Some(self.settings.clone()),
+            favorites: Some(self.favorites.paths().to_vec()),
+        };
+        self.set_status(match settings::export_bundle(&path, &bundle) {
+            Ok(()) => format!("Exported settings to {}", path.display()),
+            Err(e) => format!("Failed to export settings: {}", e),
+        });
+    }
+
+    fn import_settings(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+
+        match settings::import_bundle(&path) {
+            Ok((bundle, errors)) => {
+                let mut applied = Vec::new();
+                if let Some(imported) = bundle.settings {
+                    self.settings = imported;
+                    applied.push("settings");
+                }
+                self.persist_settings();
+                self.apply_active_profile();
+                if let Some(favorites) = bundle.favorites {
+                    self.favorites.replace(favorites);
+                    self.save_favorites();
+                    self.refresh_favorite_entries();
+                    applied.push("favorites");
+                }
+
+                let mut message = if applied.is_empty() {
+                    "Nothing to import".to_string()
+                } else {
+                    format!("Imported {}", applied.join(", "))
+                };
+                if !errors.is_empty() {
+                    message.push_str(&format!(" (skipped {})", errors.join("; ")));
+                }
+                self.set_status(message);
+            }
+            Err(e) => {
+                self.set_status(format!("Failed to import settings: {}", e));
+            }
+        }
+    }
+
+    /// Favorites, hidden images and pins by relative path, and category color
+    /// labels, for a teammate on the same library.
+    fn export_user_data(&mut self) {
+        let Some(data) = &self.image_data else {
+            self.set_status("Load a library before exporting user data");
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("chlorine-user-data.json")
+            .save_file()
+        else {
+            return;
+        };
+
+        let (bundle, left_out) = user_data::bundle(
+            data,
+            self.favorites.paths(),
+            self.hidden.paths(),
+            self.pins.slots(),
+            self.category_colors.labels(),
+        );
+        self.set_status(match user_data::export(&path, &bundle) {
+            Ok(()) if left_out > 0 => format!(
+                "Exported user data to {} ({} entries not in this library left out)",
+                path.display(),
+                left_out
+            ),
+            Ok(()) => format!("Exported user data to {}", path.display()),
+            Err(e) => format!("Failed to export user data: {}", e),
+        });
+    }
+
+    /// Reads a user data file and opens the dialog that previews and applies it.
+    fn import_user_data(&mut self) {
+        let Some(data) = &self.image_data else {
+            self.set_status("Load a library before importing user data");
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+        let (bundle, errors) = match user_data::read(&path) {
+            Ok(read) => read,
+            Err(e) => {
+                self.set_status(format!("Failed to import user data: {}", e));
+                return;
+            }
+        };
+
+        self.user_data_import = Some(UserDataImport {
+            source: path,
+            sections: user_data::resolve(bundle, data),
+            modes: [user_data::ImportMode::Merge; 4],
+            errors,
+        });
+    }
+
+    fn user_data_import_window(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.user_data_import else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("📥 Import user data")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(dialog.source.display().to_string()).small().weak());
+                ui.add_space(5.0);
+                let found = &dialog.sections;
+                let pin_count = found.pins.as_ref().map(|(slots, skipped)| (slots.iter().flatten().count(), *skipped));
+                let sections = [
+                    ("★ Favorites", found.favorites.as_ref().map(|(paths, skipped)| (paths.len(), *skipped))),
+                    ("👁 Hidden images", found.hidden.as_ref().map(|(paths, skipped)| (paths.len(), *skipped))),
+                    ("📌 Pinned slots", pin_count),
+                    (
+                        "🎨 Category colors",
+                        found.category_colors.as_ref().map(|(labels, skipped)| (labels.len(), *skipped)),
+                    ),
+                ];
+                egui::Grid::new("user_data_sections").num_columns(3).spacing([12.0, 6.0]).show(ui, |ui| {
+                    for ((label, counts), mode) in sections.into_iter().zip(dialog.modes.iter_mut()) {
+                        ui.label(label);
+                        match counts {
+                            Some((matched, skipped)) => {
+                                ui.label(format!("{} matched, {} not in this library", matched, skipped));
+                                ui.horizontal(|ui| {
+                                    for choice in [user_data::ImportMode::Merge, user_data::ImportMode::Replace] {
+                                        ui.radio_value(mode, choice, choice.label());
+                                    }
+                                });
+                            }
+                            None => {
+                                ui.label(egui::RichText::new("not in the file").weak());
+                                ui.label("");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                for error in &dialog.errors {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("Skipped {}", error));
+                }
+                ui.label(
+                    egui::RichText::new("Merge adds to what you have (pins only fill empty slots); replace overwrites the section.")
+                        .small()
+                        .weak(),
+                );
+                ui.add_space(5.0);
+                if ui.button("📥 Import").clicked() {
+                    apply = true;
+                }
+            });
+
+        if apply {
+            if let Some(dialog) = self.user_data_import.take() {
+                self.apply_user_data(dialog);
+            }
+        } else if !open {
+            self.user_data_import = None;
+        }
+    }
+
+    fn apply_user_data(&mut self, dialog: UserDataImport) {
+        let [favorites_mode, hidden_mode, pins_mode, colors_mode] = dialog.modes;
+        let sections = dialog.sections;
+        let mut applied = Vec::new();
+        let mut skipped = 0;
+
+        if let Some((paths, missing)) = sections.favorites {
+            skipped += missing;
+            let current = self.favorites.paths().to_vec();
+            self.favorites.replace(user_data::merge_paths(current, paths, favorites_mode));
+            self.save_favorites();
+            self.refresh_favorite_entries();
+            applied.push("favorites");
+        }
+
+        if let Some((paths, missing)) = sections.hidden {
+            skipped += missing;
+            let current = self.hidden.paths().cloned().collect();
+            self.hidden.replace(user_data::merge_paths(current, paths, hidden_mode));
+            if let Err(e) = self.hidden.save(&mut self.user_store) {
+                self.set_status(format!("Failed to save hidden list: {}", e));
+                return;
+            }
+            applied.push("hidden images");
+        }
+
+        if let Some((slots, missing)) = sections.pins {
+            skipped += missing;
+            let merged = user_data::merge_pins(self.pins.slots(), slots, pins_mode);
+            for (slot, path) in merged.into_iter().enumerate() {
+                self.pins.set(slot, path);
+            }
+            self.save_pins();
+            applied.push("pinned slots");
+        }
+
+        if let Some((labels, missing)) = sections.category_colors {
+            skipped += missing;
+            let merged = user_data::merge_labels(self.category_colors.labels(), labels, colors_mode);
+            self.category_colors.replace(merged);
+            if let Err(e) = self.category_colors.save(&mut self.user_store) {
+                self.set_status(format!("Failed to save category colors: {}", e));
+                return;
+            }
+            applied.push("category colors");
+        }
+
+        self.update_filtered_images();
+        let mut message = if applied.is_empty() {
+            "Nothing to import".to_string()
+        } else {
+            format!("Imported {}", applied.join(", "))
+        };
+        if skipped > 0 {
+            message.push_str(&format!(" ({} entries not in this library skipped)", skipped));
+        }
+        self.set_status(message);
+    }
+
+    fn reset_settings(&mut self) {
+        self.settings = AppSettings::default();
+        self.persist_settings();
+        self.apply_active_profile();
+        self.set_status("Settings reset to defaults".to_string());
+    }
+
+    /// Drops everything tied to the current library and loads the active profile's.
+    fn apply_active_profile(&mut self) {
+        self.image_data = None;
+        self.load_failure = None;
+        self.watcher = None;
+        self.watch_pending.clear();
+        self.watch_added.clear();
+        self.search_index = search::SearchIndex::default();
+        self.filtered_images.clear();
+        self.filter_stats = FilterStats::default();
+        self.detail_windows.clear();
+        self.selection.clear();
+        self.search_query.clear();
+        self.scoped_by_prefix = false;
+        self.scope_suggestions.clear();
+        self.loaded_textures.clear();
+        self.texture_order.clear();
+        self.loading_promises.clear();
+        self.decoded.clear();
+        self.failed_images.clear();
+        self.retries.clear();
+        self.cloud_files.clear();
+        self.hydrate_requested.clear();
+        self.offline = offline::OfflineRoots::default();
+        self.undo.clear();
+        self.metadata_cache.clear();
+        self.metadata_promises.clear();
+        self.text_cache.clear();
+        self.last_copied = None;
+        self.checksum_verification.clear();
+        if let Some((_, progress)) = self.dimension_run.take() {
+            // Its sizes belong to the profile being left
+            progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        let profile = self.settings.active_profile();
+        let data_dir = self.profile_dir(&profile);
+        self.integrity.save();
+        self.colors.save();
+        self.dimensions.save();
+        self.luminance.save();
+        self.new_images.save(&mut self.user_store);
+        // Shown once the library has loaded, instead of "Loaded N categories"
+        let mut problems = Vec::new();
+        if let Err(e) = self.user_store.save() {
+            problems.push(format!("Failed to save the previous profile's user data: {}", e));
+        }
+        self.user_store = store::UserData::open(&data_dir);
+        self.integrity = integrity::IntegrityChecker::load(data_dir.join("hashes.json"));
+        self.colors = colors::ColorIndex::load(data_dir.join("colors.json"));
+        self.dimensions = dimensions::DimensionIndex::load(data_dir.join("dimensions.json"));
+        self.luminance = backdrop::LuminanceIndex::load(data_dir.join("luminance.json"));
+        self.backdrop_overrides.clear();
+        self.favorites = favorites::Favorites::load(&mut self.user_store);
+        self.pins = pins::PinnedSlots::load(&mut self.user_store, self.settings.pinned_slots);
+        self.pin_confirm = None;
+        self.hidden = hidden::HiddenList::load(&mut self.user_store);
+        self.sensitive = sensitive::SensitiveList::load(&mut self.user_store);
+        self.category_colors = category_colors::CategoryColors::load(&mut self.user_store);
+        self.new_images = new_images::NewImages::load(&mut self.user_store);
+        self.revealed.clear();
+        self.covers = covers::CategoryCovers::load(data_dir.join("covers.json"));
+        self.hot_cache = hot_cache::HotCache::load(data_dir.clone());
+        self.hot_textures.clear();
+        self.search_history = history::SearchHistory::load(data_dir.join("history.json"));
+        self.usage = usage::UsageLog::load(&mut self.user_store);
+        problems.extend(self.user_store.take_warnings());
+        // Migration writes the new layout (and retires the old files) right away;
+        // a failure also stays on the status bar's save indicator
+        if let Err(e) = self.user_store.save() {
+            problems.push(format!("Failed to save user data: {}", e));
+        }
+        self.status_after_load = (!problems.is_empty()).then(|| status::StatusMessage::new(problems.join("; ")));
+        self.show_hidden = false;
+        self.favorite_entries.clear();
+        self.favorite_drag = None;
+        match profile.default_category {
+            Some(category) if !category.is_empty() => {
+                self.selected_category = category;
+                self.show_all_categories = false;
+            }
+            _ => {
+                self.selected_category = "All Categories".to_string();
+                self.show_all_categories = true;
+            }
+        }
+        // Everything above was built from the current settings
+        self.applied_settings = self.settings.clone();
+        self.load_image_data();
+    }
+
+    /// Makes settings changes take effect immediately, whichever way they were
+    /// made (settings window, palette, import or reset). Settings read every
+    /// frame, such as the theme, animations and copy behavior, need nothing here;
+    /// the hotkey and remote control are synced every frame.
+    fn apply_settings(&mut self, before: &AppSettings) {
+        let changes = settings::changes(before, &self.settings);
+        if changes.reload_profile {
+            self.apply_active_profile();
+            return;
+        }
+        if changes.resize_pins {
+            self.pins.resize(self.settings.pinned_slots);
+            self.save_pins();
+            self.refresh_pin_entries();
+        }
+        if changes.trim_textures {
+            while self.loaded_textures.len() > LOW_MEMORY_TEXTURE_CACHE_LIMIT {
+                let Some(oldest) = self.texture_order.pop_front() else {
+                    break;
+                };
+                self.loaded_textures.remove(&oldest);
+            }
+        }
+        if changes.move_thumbnail_cache {
+            // A new folder gets a fresh chance, and its size measured
+            self.thumbnail_cache_health.reset();
+            self.thumbnail_cache_off = None;
+            self.thumbnail_cache_usage = None;
+            self.prune_thumbnail_cache();
+        } else if changes.prune_thumbnail_cache {
+            self.prune_thumbnail_cache();
+        }
+        if changes.stop_hashing {
+            self.integrity.stop();
+        }
+        if changes.drop_textures {
+            self.loaded_textures.clear();
+            self.texture_order.clear();
+        }
+        if changes.reclassify_sources {
+            self.source_classifier = source_hints::Classifier::new(&self.settings.source_rules);
+            self.search_index.classify(&self.source_classifier);
+            self.update_filtered_images();
+        }
+        if changes.reset_previews {
+            self.loaded_textures.clear();
+            self.texture_order.clear();
+            self.failed_images.clear();
+            self.text_cache.clear();
+        }
+        if changes.reset_cloud_files {
+            self.cloud_files.clear();
+        }
+        if changes.reload_library {
+            self.load_image_data();
+        } else if changes.refilter {
+            self.update_filtered_images();
+        } else if changes.restart_health_check {
+            self.start_health_check();
+        }
+    }
+
+    /// The toolbar's ⋮ menu: quick toggles for common settings, then refresh,
+    /// checks and library switching. Toggles write to the settings like the
+    /// settings window does, so `apply_settings` picks them up next frame.
+    fn overflow_menu(&mut self, ui: &mut egui::Ui) {
+        let mut persist = false;
+        persist |= ui.checkbox(&mut self.settings.dark_mode, "🌙 Dark theme").changed();
+        ui.horizontal(|ui| {
+            ui.label("View:");
+            for view in settings::ResultView::ALL {
+                persist |= ui.radio_value(&mut self.settings.result_view, view, view.label()).changed();
+            }
+        });
+        persist |= ui.checkbox(&mut self.settings.stack_sequences, "Stack numbered frames").changed();
+        let mut hide_missing = self.settings.offline_images == settings::OfflineImages::Hide;
+        if ui
+            .checkbox(&mut hide_missing, "Hide missing images")
+            .on_hover_text("Otherwise images that aren't on disk are greyed out")
+            .changed()
+        {
+            self.settings.offline_images = if hide_missing { settings::OfflineImages::Hide } else { settings::OfflineImages::Grey };
+            persist = true;
+        }
+        persist |= ui.checkbox(&mut self.settings.show_preview_pane, "◨ Preview pane").changed();
+        persist |= ui
+            .checkbox(&mut self.settings.text_only, "🔤 Text-only mode")
+            .on_hover_text("Extension tiles instead of thumbnails")
+            .changed();
+        let mut privacy = self.settings.privacy_mode;
+        if ui.checkbox(&mut privacy, "🕶 Privacy mode (F9)").changed() {
+            // Also clears revealed images and reports the change
+            self.toggle_privacy_mode();
+        }
+        if persist {
+            self.persist_settings();
+        }
+
+        ui.separator();
+        let blocker = self.operations.blocker(operations::OperationKind::LibraryLoad);
+        if ui
+            .add_enabled(blocker.is_none(), egui::Button::new("🔄 Refresh library"))
+            .on_disabled_hover_text(blocker.unwrap_or_default())
+            .clicked()
+        {
+            self.refresh_library();
+            ui.close_menu();
+        }
+        let blocker = self.operations.blocker(operations::OperationKind::HealthCheck);
+        if ui
+            .add_enabled(blocker.is_none(), egui::Button::new("🩺 Check library health"))
+            .on_disabled_hover_text(blocker.unwrap_or_default())
+            .clicked()
+        {
+            self.start_health_check();
+            self.show_health = true;
+            ui.close_menu();
+        }
+        if self.read_only_action(ui, egui::Button::new("🗜 Import ZIP…")).clicked() {
+            ui.close_menu();
+            self.open_zip_import();
+        }
+        let active_id = self.settings.active_profile().id;
+        let others: Vec<(String, String)> = self
+            .settings
+            .profiles
+            .iter()
+            .filter(|profile| profile.id != active_id)
+            .map(|profile| (profile.id.clone(), profile.name.clone()))
+            .collect();
+        ui.add_enabled_ui(!others.is_empty(), |ui| {
+            ui.menu_button("📚 Open library", |ui| {
+                for (id, name) in &others {
+                    if ui.button(name).clicked() {
+                        self.switch_profile(id);
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_disabled_hover_text("Add libraries under Settings → Profiles");
+        });
+    }
+
+    /// Registers the hotkey setting and minimizes or restores the window when it's pressed.
+    fn sync_hotkey(&mut self, ctx: &egui::Context) {
+        self.hotkey.sync(&self.settings.hotkey);
+        if !self.hotkey.is_registered() {
+            return;
+        }
+        self.hotkey.wake(ctx);
+        if self.hotkey.pressed() {
+            self.hidden_by_hotkey = !self.hidden_by_hotkey;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(self.hidden_by_hotkey));
+            if !self.hidden_by_hotkey {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+        }
+    }
+
+    fn switch_profile(&mut self, profile_id: &str) {
+        if self.settings.active_profile == profile_id {
+            return;
+        }
+        self.settings.active_profile = profile_id.to_string();
+        self.persist_settings();
+        self.apply_active_profile();
+    }
+
+    fn delete_profile(&mut self, profile_id: &str) {
+        if self.settings.profiles.len() <= 1 {
+            return;
+        }
+        let Some(index) = self.settings.profiles.iter().position(|p| p.id == profile_id) else {
+            return;
+        };
+        let profile = self.settings.profiles.remove(index);
+        let dir = self.profile_dir(&profile);
+        let root = if self.safe_mode {
+            safe_mode::scratch_dir()
+        } else {
+            settings::config_dir().join("profiles")
+        };
+        // Never delete anything an id could point outside the profiles folder
+        match (dir.canonicalize(), root.canonicalize()) {
+            (Ok(dir), Ok(root)) if dir.starts_with(&root) && dir != root => {
+                if let Err(e) = std::fs::remove_dir_all(&dir) {
+                    self.set_status(format!("Failed to remove profile data: {}", e));
+                }
+            }
+            (Err(e), _) if e.kind() == std::io::ErrorKind::NotFound => {}
+            _ => self.set_status(format!(
+                "Left profile data at {} in place: it isn't inside the profiles folder",
+                dir.display()
+            )),
+        }
+        if self.settings.active_profile == profile.id {
+            self.settings.normalize();
+            self.apply_active_profile();
+        }
+        self.persist_settings();
+    }
+
+    fn profiles_ui(&mut self, ui: &mut egui::Ui) {
+        let mut delete_requested = None;
+        let mut switch_to = None;
+        let can_delete = self.settings.profiles.len() > 1;
+
+        for profile in &mut self.settings.profiles {
+            ui.group(|ui| {
+                egui::Grid::new(("profile_grid", profile.id.as_str()))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut profile.name);
+                        ui.end_row();
+
+                        ui.label("Manifest:");
+                        ui.horizontal(|ui| {
+                            let mut text = match &self.manifest_path_draft {
+                                Some((id, draft)) if *id == profile.id => draft.clone(),
+                                _ => profile.manifest_path.clone(),
+                            };
+                            let response = ui.text_edit_singleline(&mut text);
+                            if response.changed() {
+                                self.manifest_path_draft = Some((profile.id.clone(), text));
+                            }
+                            // Enter also ends editing; Escape throws the draft away
+                            if !response.has_focus()
+                                && self.manifest_path_draft.as_ref().is_some_and(|(id, _)| *id == profile.id)
+                            {
+                                let escaped = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                                if let Some((_, draft)) = self.manifest_path_draft.take().filter(|_| !escaped) {
+                                    profile.manifest_path = draft.trim().to_string();
+                                }
+                            }
+                            if ui.button("📂").on_hover_text("Browse…").clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("JSON", &["json"])
+                                    .pick_file()
+                                {
+                                    profile.manifest_path = portable::stored_path(&path);
+                                    self.manifest_path_draft = None;
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Default category:");
+                        let mut category = profile.default_category.clone().unwrap_or_default();
+                        if ui
+                            .add(egui::TextEdit::singleline(&mut category).hint_text("All Categories"))
+                            .changed()
+                        {
+                            profile.default_category =
+                                (!category.trim().is_empty()).then(|| category.trim().to_string());
+                        }
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    if profile.id == self.settings.active_profile {
+                        ui.label(egui::RichText::new("● Active").strong());
+                    } else if ui.button("Switch to").clicked() {
+                        switch_to = Some(profile.id.clone());
+                    }
+
+                    if can_delete {
+                        if self.confirm_delete_profile.as_deref() == Some(profile.id.as_str()) {
+                            ui.label("Delete profile and its data?");
+                            if ui.button("Delete").clicked() {
+                                delete_requested = Some(profile.id.clone());
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.confirm_delete_profile = None;
+                            }
+                        } else if ui.button("🗑 Delete").clicked() {
+                            self.confirm_delete_profile = Some(profile.id.clone());
+                        }
+                    }
+                });
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_profile_name).hint_text("New profile name"));
+            if ui.button("➕ Add profile").clicked() && !self.new_profile_name.trim().is_empty() {
+                let profile = settings::LibraryProfile::new(self.new_profile_name.trim());
+                self.settings.profiles.push(profile);
+                self.new_profile_name.clear();
+            }
+        });
+
+        if let Some(id) = delete_requested {
+            self.confirm_delete_profile = None;
+            self.delete_profile(&id);
+        }
+        if let Some(id) = switch_to {
+            self.switch_profile(&id);
+        }
+    }
+
+    fn save_favorites(&mut self) {
+        if let Err(e) = self.favorites.save(&mut self.user_store) {
+            self.set_status(format!("Failed to save favorites: {}", e));
+        }
+    }
+
+    fn set_category_color(&mut self, category: &str, color: Option<category_colors::LabelColor>) {
+        self.category_colors.set(category, color);
+        if let Err(e) = self.category_colors.save(&mut self.user_store) {
+            self.set_status(format!("Failed to save category colors: {}", e));
+        }
+    }
+
+    /// The palette as swatches, plus "No color"; for a category's menus.
+    fn category_color_menu(&mut self, ui: &mut egui::Ui, category: &str) {
+        let current = self.category_colors.get(category);
+        ui.horizontal(|ui| {
+            for color in category_colors::LabelColor::ALL {
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(18.0, 18.0), egui::Sense::click());
+                ui.painter().circle_filled(rect.center(), 7.0, color.color());
+                if current == Some(color) {
+                    ui.painter().circle_stroke(rect.center(), 8.5, ui.visuals().strong_text_color());
+                }
+                if response.on_hover_text(color.name()).clicked() {
+                    self.set_category_color(category, Some(color));
+                    ui.close_menu();
+                }
+            }
+        });
+        if ui.add_enabled(current.is_some(), egui::Button::new("No color")).clicked() {
+            self.set_category_color(category, None);
+            ui.close_menu();
+        }
+    }
+
+    /// With "All Categories" showing, a thin bar down the left edge of a
+    /// result in its category's color label.
+    fn category_stripe(&self, ui: &egui::Ui, rect: egui::Rect, category: &str) {
+        if !self.show_all_categories {
+            return;
+        }
+        if let Some(color) = self.category_colors.get(category) {
+            let stripe = egui::Rect::from_min_size(rect.min, egui::vec2(3.0, rect.height()));
+            ui.painter().rect_filled(stripe, 2.0, color.color());
+        }
+    }
+
+    /// A category's color dot, or the same space when it has none so names stay aligned.
+    fn category_color_dot(&self, ui: &mut egui::Ui, category: &str) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(8.0, 8.0), egui::Sense::hover());
+        if let Some(color) = self.category_colors.get(category) {
+            ui.painter().circle_filled(rect.center(), 4.0, color.color());
+        }
+    }
+
+    /// Re-resolves favorite paths against the loaded library, in favorites order.
+    fn refresh_favorite_entries(&mut self) {
+        self.favorite_entries.clear();
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        let wanted: std::collections::HashSet<&str> =
+            self.favorites.paths().iter().map(String::as_str).collect();
+        let mut found: HashMap<&str, (String, ImageInfo)> = HashMap::new();
+        for (category_name, category) in &data.categories {
+            for image in &category.images {
+                if wanted.contains(image.full_path.as_str()) {
+                    found
+                        .entry(image.full_path.as_str())
+                        .or_insert_with(|| (category_name.clone(), image.clone()));
+                }
+            }
+        }
+        for (index, path) in self.favorites.paths().iter().enumerate() {
+            if let Some((category, image)) = found.remove(path.as_str()) {
+                self.favorite_entries.push((index, category, image));
+            }
+        }
+    }
+
+    fn refresh_pin_entries(&mut self) {
+        let slots = self.pins.slots().to_vec();
+        self.pin_entries = slots
+            .iter()
+            .map(|path| path.as_deref().and_then(|path| self.find_image(path)))
+            .collect();
+        self.pin_missing = slots
+            .iter()
+            .map(|path| path.as_deref().is_some_and(|path| !Path::new(path).exists()))
+            .collect();
+    }
+
+    /// Pins an image; replacing a different image in an occupied slot asks first.
+    fn pin_image(&mut self, slot: usize, image_info: &ImageInfo, confirmed: bool) {
+        let occupied = self.pins.get(slot).is_some_and(|path| path != image_info.full_path);
+        if occupied && !confirmed {
+            self.pin_confirm = Some((slot, image_info.clone()));
+            return;
+        }
+        self.pins.set(slot, Some(image_info.full_path.clone()));
+        self.save_pins();
+        self.set_status(format!("Pinned {} to slot {}", image_info.filename, slot + 1));
+    }
+
+    fn unpin(&mut self, slot: usize) {
+        self.pins.set(slot, None);
+        self.save_pins();
+    }
+
+    fn save_pins(&mut self) {
+        if let Err(e) = self.pins.save(&mut self.user_store) {
+            self.set_status(format!("Failed to save pinned slots: {}", e));
+        }
+        self.refresh_pin_entries();
+    }
+
+    fn copy_pinned(&mut self, slot: usize) {
+        let Some(path) = self.pins.get(slot).map(str::to_string) else {
+            self.set_status(format!("Slot {} is empty", slot + 1));
+            return;
+        };
+        // The file may have disappeared since the last refresh
+        self.refresh_pin_entries();
+        match self.pin_entries.get(slot).cloned().flatten() {
+            Some(_) if self.pin_missing.get(slot) == Some(&true) => {
+                self.set_status(format!("Pinned file is missing: {}", path));
+            }
+            Some((category, image_info)) => self.copy_image(&category, &image_info),
+            None => self.set_status(format!("Pinned file is not in this library: {}", path)),
+        }
+    }
+
+    /// Small wells in the top bar, one per pinned slot; click copies.
+    fn pinned_slots_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        const WELL_SIZE: f32 = 28.0;
+        for slot in 0..self.pins.slots().len() {
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(WELL_SIZE, WELL_SIZE), egui::Sense::click());
+            let visuals = ui.visuals().clone();
+            ui.painter().rect_filled(rect, 4.0, visuals.extreme_bg_color);
+            let missing = self.pin_missing.get(slot) == Some(&true);
+            let entry = self.pin_entries.get(slot).cloned().flatten();
+
+            let hover = match (&entry, self.pins.get(slot)) {
+                (Some((_, image_info)), _) if missing => {
+                    format!("Slot {}: {} (file missing)", slot + 1, image_info.filename)
+                }
+                (Some((_, image_info)), _) => {
+                    if let Some(texture) = self.preview_texture(ctx, image_info) {
+                        let size = texture.size_vec2();
+                        let scale = (WELL_SIZE / size.x).min(WELL_SIZE / size.y);
+                        let fitted = egui::Rect::from_center_size(rect.center(), size * scale);
+                        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                        ui.painter().image(texture.id(), fitted, uv, egui::Color32::WHITE);
+                    }
+                    format!("Slot {}: {}\nClick or Ctrl+Shift+{} to copy", slot + 1, image_info.filename, slot + 1)
+                }
+                (None, Some(path)) => format!("Slot {}: {} (not in this library)", slot + 1, path),
+                (None, None) => format!("Slot {} is empty\nRight-click an image → Pin to slot", slot + 1),
+            };
+
+            let marker = match (&entry, self.pins.get(slot)) {
+                (Some(_), _) if missing => Some(("⚠", visuals.warn_fg_color)),
+                (None, Some(_)) => Some(("⚠", visuals.warn_fg_color)),
+                (None, None) => Some(("", visuals.weak_text_color())),
+                _ => None,
+            };
+            if let Some((text, color)) = marker {
+                let label = if text.is_empty() { (slot + 1).to_string() } else { text.to_string() };
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    label,
+                    egui::FontId::proportional(12.0),
+                    color,
+                );
+            }
+            let stroke = if response.hovered() {
+                visuals.widgets.hovered.fg_stroke
+            } else {
+                visuals.widgets.noninteractive.bg_stroke
+            };
+            ui.painter().rect_stroke(rect, 4.0, stroke);
+
+            let response = response.on_hover_text(hover);
+            if response.clicked() {
+                self.copy_pinned(slot);
+            }
+            if self.pins.get(slot).is_some() {
+                response.context_menu(|ui| {
+                    if ui.button("✖ Unpin").clicked() {
+                        self.unpin(slot);
+                        ui.close_menu();
+                    }
+                });
+            }
+        }
+    }
+
+    fn pin_confirm_window(&mut self, ctx: &egui::Context) {
+        let Some((slot, image_info)) = self.pin_confirm.clone() else {
+            return;
+        };
+        let current = self
+            .pin_entries
+            .get(slot)
+            .cloned()
+            .flatten()
+            .map_or_else(|| self.pins.get(slot).unwrap_or_default().to_string(), |(_, info)| info.filename);
+        let mut decided = None;
+        egui::Window::new("Replace pin?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Slot {} holds {}. Replace it with {}?", slot + 1, current, image_info.filename));
+                ui.horizontal(|ui| {
+                    if ui.button("Replace").clicked() {
+                        decided = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        decided = Some(false);
+                    }
+                });
+            });
+        match decided {
+            Some(true) => {
+                self.pin_confirm = None;
+                self.pin_image(slot, &image_info, true);
+            }
+            Some(false) => self.pin_confirm = None,
+            None => {}
+        }
+    }
+
+    fn toggle_favorite(&mut self, image_info: &ImageInfo) {
+        let added = !self.favorites.contains(&image_info.full_path);
+        self.set_favorite(&image_info.full_path, added);
+        self.undo.record(undo::Edit::Favorite {
+            path: image_info.full_path.clone(),
+            filename: image_info.filename.clone(),
+            added,
+        });
+        self.set_status(if added {
+            format!("Added {} to favorites", image_info.filename)
+        } else {
+            format!("Removed {} from favorites", image_info.filename)
+        });
+    }
+
+    fn set_favorite(&mut self, path: &str, favorite: bool) {
+        self.set_favorites(&[path.to_string()], favorite);
+    }
+
+    /// Changes several favorites with a single save and refresh.
+    fn set_favorites(&mut self, paths: &[String], favorite: bool) {
+        let mut changed = false;
+        for path in paths {
+            if self.favorites.contains(path) != favorite {
+                self.favorites.toggle(path);
+                changed = true;
+            }
+        }
+        if !changed {
+            return;
+        }
+        self.save_favorites();
+        self.refresh_favorite_entries();
+        if self.favorites_only {
+            self.update_filtered_images();
+        }
+    }
+
+    /// Favorites every selected image, or unfavorites them all if they already are.
+    fn toggle_selection_favorite(&mut self) {
+        let added = self.selection.iter().any(|path| !self.favorites.contains(path));
+        let paths: Vec<String> = self
+            .selection
+            .iter()
+            .filter(|path| self.favorites.contains(path) != added)
+            .cloned()
+            .collect();
+        self.set_favorites(&paths, added);
+        self.set_status(if added {
+            format!("Added {} images to favorites", paths.len())
+        } else {
+            format!("Removed {} images from favorites", paths.len())
+        });
+        self.undo.record(undo::Edit::Favorites { paths, added });
+    }
+
+    fn toggle_hidden(&mut self, path: &str, filename: &str) {
+        let hidden = !self.hidden.contains(path);
+        match self.set_hidden(path, hidden) {
+            Err(e) => self.set_status(e),
+            Ok(()) => {
+                self.undo.record(undo::Edit::Hidden {
+                    path: path.to_string(),
+                    filename: filename.to_string(),
+                    hidden,
+                });
+                self.set_status(if hidden {
+                    format!("Hid {} from results", filename)
+                } else {
+                    format!("{} is visible again", filename)
+                });
+            }
+        }
+    }
+
+    fn set_hidden(&mut self, path: &str, hidden: bool) -> Result<(), String> {
+        if self.hidden.contains(path) == hidden {
+            return Ok(());
+        }
+        self.hidden.toggle(path);
+        let saved = self.hidden.save(&mut self.user_store).map_err(|e| format!("Failed to save hidden list: {}", e));
+        self.update_filtered_images();
+        saved
+    }
+
+    /// Ctrl+Z. An edit that can't be reverted stays on the stack.
+    fn undo_last_edit(&mut self) {
+        let Some(edit) = self.undo.take_undo() else {
+            self.set_status("Nothing to undo".to_string());
+            return;
+        };
+        match self.apply_edit(&edit, false) {
+            Ok(()) => {
+                self.set_status(format!("Undid: {}", edit.describe()));
+                self.undo.push_undone(edit);
+            }
+            Err(e) => {
+                self.set_status(format!("Couldn't undo {}: {}", edit.describe(), e));
+                self.undo.push_done(edit);
+            }
+        }
+    }
+
+    /// Ctrl+Shift+Z.
+    fn redo_last_edit(&mut self) {
+        let Some(edit) = self.undo.take_redo() else {
+            self.set_status("Nothing to redo".to_string());
+            return;
+        };
+        match self.apply_edit(&edit, true) {
+            Ok(()) => {
+                self.set_status(format!("Redid: {}", edit.describe()));
+                self.undo.push_done(edit);
+            }
+            Err(e) => {
+                self.set_status(format!("Couldn't redo {}: {}", edit.describe(), e));
+                self.undo.push_undone(edit);
+            }
+        }
+    }
+
+    /// Puts the library into the state after (`forward`) or before an edit.
+    fn apply_edit(&mut self, edit: &undo::Edit, forward: bool) -> Result<(), String> {
+        match edit {
+            undo::Edit::Favorite { path, added, .. } => {
+                self.set_favorite(path, *added == forward);
+                Ok(())
+            }
+            undo::Edit::Favorites { paths, added } => {
+                self.set_favorites(paths, *added == forward);
+                Ok(())
+            }
+            undo::Edit::Hidden { path, hidden, .. } => self.set_hidden(path, *hidden == forward),
+            undo::Edit::Grouping { category, before, after } => {
+                let (expected, target) = if forward { (before, after) } else { (after, before) };
+                if self.grouping_rules.get(category) != expected.as_ref() {
+                    return Err(format!("the grouping of \"{}\" was changed since", category));
+                }
+                self.write_category_grouping(category, target.as_ref())
+            }
+        }
+    }
+
+    /// Hidden paths resolved against the loaded library; entries no longer in it come back as None.
+    fn hidden_entries(&self) -> Vec<(String, Option<ImageInfo>)> {
+        let mut found: HashMap<String, ImageInfo> = HashMap::new();
+        if let Some(data) = &self.image_data {
+            for image in data.categories.values().flat_map(|c| &c.images) {
+                if self.hidden.contains(&image.full_path) {
+                    found.insert(search::normalize_path(&image.full_path), image.clone());
+                }
+            }
+        }
+        self.hidden
+            .paths()
+            .map(|path| (path.clone(), found.remove(path)))
+            .collect()
+    }
+
+    fn category_colors_settings_ui(&mut self, ui: &mut egui::Ui) {
+        if self.category_colors.is_empty() {
+            ui.label(
+                egui::RichText::new("No category has a color. Right-click the category dropdown, or use its ⚙ menu, to pick one.")
+                    .weak(),
+            );
+            return;
+        }
+        let mut remove = None;
+        for (category, color) in self.category_colors.labels() {
+            ui.horizontal(|ui| {
+                self.category_color_dot(ui, category);
+                ui.label(category).on_hover_text(color.name());
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(category.clone());
+                }
+            });
+        }
+        if let Some(category) = remove {
+            self.set_category_color(&category, None);
+        }
+        if ui.button(format!("Clear all labels ({})", self.category_colors.len())).clicked() {
+            self.category_colors.replace(Default::default());
+            match self.category_colors.save(&mut self.user_store) {
+                Ok(()) => self.set_status("Category colors cleared"),
+                Err(e) => self.set_status(format!("Failed to save category colors: {}", e)),
+            }
+        }
+    }
+
+    fn hidden_settings_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if self.hidden.is_empty() {
+            ui.label(egui::RichText::new("Nothing hidden. Use \"Hide from results\" in an image's right-click menu.").weak());
+            return;
+        }
+        let mut unhide = None;
+        egui::ScrollArea::vertical()
+            .id_source("hidden_list")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for (path, image_info) in self.hidden_entries() {
+                    ui.horizontal(|ui| {
+                        match &image_info {
+                            Some(image_info) => {
+                                if let Some(texture) = self.preview_texture(ctx, image_info) {
+                                    ui.image((texture.id(), egui::Vec2::new(32.0, 32.0)));
+                                } else {
+                                    ui.allocate_space(egui::Vec2::new(32.0, 32.0));
+                                }
+                                ui.label(&image_info.filename).on_hover_text(&image_info.relative_path);
+                            }
+                            None => {
+                                ui.allocate_space(egui::Vec2::new(32.0, 32.0));
+                                ui.label(egui::RichText::new(&path).weak())
+                                    .on_hover_text("Not in the current library");
+                            }
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("Unhide").clicked() {
+                                let filename = image_info.as_ref().map_or(path.clone(), |i| i.filename.clone());
+                                unhide = Some((path.clone(), filename));
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some((path, filename)) = unhide {
+            self.toggle_hidden(&path, &filename);
+        }
+    }
+
+    /// Right-click menu shared by result rows and the favorites bar.
+    fn image_context_menu(&mut self, ui: &mut egui::Ui, category: &str, image_info: &ImageInfo) {
+        if self.is_text_entry(image_info) {
+            if ui.button("📋 Copy text").clicked() {
+                self.copy_snippet_text(image_info);
+                ui.close_menu();
+            }
+        } else if ui.button("📋 Copy image").clicked() {
+            self.copy_image_to_clipboard(image_info, None);
+            ui.close_menu();
+        }
+        if ui.button("📄 Copy as file").clicked() {
+            self.copy_file_to_clipboard(image_info);
+            ui.close_menu();
+        }
+        if ui.button("👁️ View details").clicked() {
+            self.open_details(category.to_string(), image_info.clone());
+            ui.close_menu();
+        }
+        if ui.button("↗ Open externally").clicked() {
+            self.open_externally(image_info);
+            ui.close_menu();
+        }
+        if ui.button("📍 Copy path").clicked() {
+            self.run_row_action(settings::RowAction::CopyPath, category, image_info);
+            ui.close_menu();
+        }
+        if ui.button("📂 Reveal in folder").clicked() {
+            self.reveal_in_folder(image_info);
+            ui.close_menu();
+        }
+        if ui.button("🗂 Show files from this folder").clicked() {
+            self.show_folder(image_info);
+            ui.close_menu();
+        }
+        if !self.is_text_entry(image_info) && ui.button("💾 Save as…").clicked() {
+            self.export_dialog = Some(ExportDialog::new(image_info.clone()));
+            ui.close_menu();
+        }
+        let favorite_label = if self.favorites.contains(&image_info.full_path) {
+            "☆ Remove from favorites"
+        } else {
+            "★ Add to favorites"
+        };
+        if ui.button(favorite_label).clicked() {
+            self.toggle_favorite(image_info);
+            ui.close_menu();
+        }
+        if ui.button("🔗 Copy deep link").clicked() {
+            let link = deep_link::DeepLink::Image {
+                path: image_info.full_path.clone(),
+            };
+            self.copy_text_to_clipboard(link.to_url(), "link");
+            ui.close_menu();
+        }
+        if ui.button("🖼 Set as category cover").clicked() {
+            self.set_category_cover(category, image_info);
+            ui.close_menu();
+        }
+        ui.menu_button("📌 Pin to slot", |ui| {
+            for slot in 0..self.pins.slots().len() {
+                let label = match self.pin_entries.get(slot).cloned().flatten() {
+                    Some((_, pinned)) => format!("Slot {}: {}", slot + 1, pinned.filename),
+                    None if self.pins.get(slot).is_some() => format!("Slot {}: (missing)", slot + 1),
+                    None => format!("Slot {} (empty)", slot + 1),
+                };
+                if ui.button(label).clicked() {
+                    self.pin_image(slot, image_info, false);
+                    ui.close_menu();
+                }
+            }
+        });
+        let select_label = if self.selection.contains(&image_info.full_path) {
+            "☐ Deselect"
+        } else {
+            "☑ Select (Ctrl+click)"
+        };
+        if ui.button(select_label).clicked() {
+            self.toggle_selection(&image_info.full_path);
+            ui.close_menu();
+        }
+        let hide_label = if self.hidden.contains(&image_info.full_path) {
+            "👁 Unhide"
+        } else {
+            "🚫 Hide from results"
+        };
+        if ui.button(hide_label).clicked() {
+            self.toggle_hidden(&image_info.full_path, &image_info.filename);
+            ui.close_menu();
+        }
+        let sensitive = self.sensitive.contains(&image_info.full_path);
+        let sensitive_label = if sensitive { "🔓 Unmark sensitive" } else { "🔒 Mark as sensitive" };
+        if ui.button(sensitive_label).clicked() {
+            self.set_sensitive(&[image_info.full_path.clone()], !sensitive);
+            ui.close_menu();
+        }
+    }
+
+    /// Marks or unmarks images as sensitive with a single save.
+    fn set_sensitive(&mut self, paths: &[String], sensitive: bool) {
+        let mut changed = 0;
+        for path in paths {
+            if self.sensitive.set(path, sensitive) {
+                changed += 1;
+            }
+        }
+        if changed == 0 {
+            return;
+        }
+        if let Err(e) = self.sensitive.save(&mut self.user_store) {
+            self.set_status(format!("Failed to save the sensitive list: {}", e));
+            return;
+        }
+        self.set_status(if sensitive {
+            format!("Marked {} image(s) as sensitive", changed)
+        } else {
+            format!("Unmarked {} image(s)", changed)
+        });
+    }
+
+    /// True when a sensitive image must not be shown in a large view.
+    fn is_concealed(&self, path: &str) -> bool {
+        self.sensitive.contains(path) && (self.settings.privacy_mode || !self.revealed.contains(path))
+    }
+
+    /// Stand-in for a concealed image in the detail window and preview pane.
+    fn concealed_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, path: &str) {
+        let texture = self.sensitive_texture(ctx);
+        ui.vertical_centered(|ui| {
+            ui.image((texture.id(), egui::Vec2::splat(128.0)));
+            ui.label(egui::RichText::new("🔒 Marked sensitive").weak());
+            let reveal = ui
+                .add_enabled(!self.settings.privacy_mode, egui::Button::new("Reveal"))
+                .on_disabled_hover_text("Privacy mode is on (F9)");
+            if reveal.clicked() {
+                self.revealed.insert(path.to_string());
+            }
+        });
+    }
+
+    fn toggle_privacy_mode(&mut self) {
+        self.settings.privacy_mode = !self.settings.privacy_mode;
+        self.persist_settings();
+        self.set_status(if self.settings.privacy_mode {
+            "Privacy mode on: sensitive images stay hidden"
+        } else {
+            "Privacy mode off"
+        });
+    }
+
+    /// Thumbnail for small previews: the real texture, a placeholder tile, or None while decoding.
+    fn preview_texture(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> Option<egui::TextureHandle> {
+        match self.thumbnail_state(ctx, image_info) {
+            ThumbnailState::Ready(texture) => Some(texture),
+            ThumbnailState::Loading => None,
+            ThumbnailState::Waiting
+            | ThumbnailState::Unsupported
+            | ThumbnailState::InCloud
+            | ThumbnailState::Offline
+            | ThumbnailState::TextOnly => Some(self.placeholder_texture(ctx, &image_info.extension)),
+            ThumbnailState::Sensitive => Some(self.sensitive_texture(ctx)),
+            ThumbnailState::Retrying | ThumbnailState::Failed => Some(self.error_texture(ctx)),
+        }
+    }
+
+    /// The image representing a category: the chosen cover, or the first by filename.
+    fn category_cover(&self, category: &str) -> Option<ImageInfo> {
+        let images = &self.image_data.as_ref()?.categories.get(category)?.images;
+        self.covers
+            .get(category)
+            .and_then(|path| images.iter().find(|image| image.full_path == path))
+            .or_else(|| images.iter().min_by_key(|image| image.filename.to_lowercase()))
+            .cloned()
+    }
+
+    /// Small cover thumbnail; only loads while its spot is on screen.
+    fn cover_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, category: &str) {
+        let (rect, _) = ui.allocate_exact_size(egui::Vec2::splat(COVER_SIZE), egui::Sense::hover());
+        if !ui.is_rect_visible(rect) {
+            return;
+        }
+        let Some(cover) = self.category_cover(category) else {
+            return;
+        };
+        let started = self.loaded_textures.contains_key(&cover.full_path)
+            || self.loading_promises.contains_key(&cover.full_path)
+            || self.decoded.iter().any(|(path, _)| *path == cover.full_path);
+        let texture = if started || self.loading_promises.len() < COVER_LOAD_LIMIT {
+            self.preview_texture(ctx, &cover)
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(200));
+            None
+        }
+        .unwrap_or_else(|| self.placeholder_texture(ctx, &cover.extension));
+        paint_fitted(ui.painter(), &texture, rect);
+    }
+
+    /// Tooltip strip with a category's first images by filename, so it can be
+    /// peeked at from the dropdown without switching to it.
+    fn category_peek_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, category: &str) {
+        let Some(images) = self.image_data.as_ref().and_then(|data| data.categories.get(category)) else {
+            return;
+        };
+        let count = images.images.len();
+        let mut first: Vec<(String, &ImageInfo)> = Vec::with_capacity(PEEK_IMAGES + 1);
+        for image in &images.images {
+            let key = image.filename.to_lowercase();
+            let at = first.partition_point(|(other, _)| *other <= key);
+            if at < PEEK_IMAGES {
+                first.insert(at, (key, image));
+                first.truncate(PEEK_IMAGES);
+            }
+        }
+        let first: Vec<ImageInfo> = first.into_iter().map(|(_, image)| image.clone()).collect();
+        ui.horizontal(|ui| {
+            for image in &first {
+                let (rect, _) = ui.allocate_exact_size(egui::Vec2::splat(PEEK_SIZE), egui::Sense::hover());
+                let texture = self.peek_texture(ctx, image);
+                paint_fitted(ui.painter(), &texture, rect);
+            }
+        });
+        ui.label(egui::RichText::new(format!("{} image(s)", count)).weak());
+    }
+
+    /// A thumbnail for the category peek. New decodes only start while load
+    /// slots and room in the texture cache are free, so peeking never delays
+    /// or evicts the thumbnails of visible rows.
+    fn peek_texture(&mut self, ctx: &egui::Context, image_info: &ImageInfo) -> egui::TextureHandle {
+        let path = &image_info.full_path;
+        let started = self.loaded_textures.contains_key(path)
+            || self.loading_promises.contains_key(path)
+            || self.decoded.iter().any(|(decoded, _)| decoded == path);
+        let in_flight = self.loading_promises.len() + self.decoded.len();
+        let room = self.loaded_textures.len() + in_flight < self.texture_cache_limit();
+        let texture = if started || (room && in_flight < COVER_LOAD_LIMIT) {
+            self.preview_texture(ctx, image_info)
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(200));
+            None
+        };
+        texture.unwrap_or_else(|| self.placeholder_texture(ctx, &image_info.extension))
+    }
+
+    fn set_category_cover(&mut self, category: &str, image_info: &ImageInfo) {
+        self.covers.set(category, &image_info.full_path);
+        self.set_status(match self.covers.save() {
+            Ok(()) => format!("{} is now the cover of {}", image_info.filename, category),
+            Err(e) => format!("Failed to save category covers: {}", e),
+        });
+    }
+
+    /// Horizontal strip of starred images; click copies, drag reorders.
+    fn favorites_bar_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        const THUMB_SIZE: f32 = 40.0;
+        let entries = self.favorite_entries.clone();
+        let mut rects = Vec::with_capacity(entries.len());
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("★");
+                for (index, (_, category, image_info)) in entries.iter().enumerate() {
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(THUMB_SIZE, THUMB_SIZE),
+                        egui::Sense::click_and_drag(),
+                    );
+                    rects.push(rect);
+
+                    if let Some(texture) = self.preview_texture(ctx, image_info) {
+                        let size = texture.size_vec2();
+                        let scale = (THUMB_SIZE / size.x).min(THUMB_SIZE / size.y);
+                        let fitted = egui::Rect::from_center_size(rect.center(), size * scale);
+                        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                        ui.painter().image(texture.id(), fitted, uv, egui::Color32::WHITE);
+                    } else {
+                        if self.animations_enabled() {
+                            ui.put(rect, egui::Spinner::new());
+                        } else {
+                            ui.put(rect, egui::Label::new(egui::RichText::new("…").weak()));
+                        }
+                    }
+                    if response.hovered() {
+                        ui.painter().rect_stroke(rect, 4.0, ui.visuals().widgets.hovered.fg_stroke);
+                    }
+
+                    let shortcut = if index < 9 {
+                        format!("\nCtrl+{} to copy", index + 1)
+                    } else {
+                        String::new()
+                    };
+                    let response = response.on_hover_text(format!("{}{}", image_info.filename, shortcut));
+                    if response.drag_started() {
+                        self.favorite_drag = Some(index);
+                    }
+                    if response.clicked() {
+                        self.copy_image(category, image_info);
+                    }
+                    response.context_menu(|ui| self.image_context_menu(ui, category, image_info));
+                }
+            });
+        });
+
+        let Some(from) = self.favorite_drag else {
+            return;
+        };
+        let Some(pointer) = ui.input(|i| i.pointer.interact_pos()) else {
+            return;
+        };
+        let target = rects
+            .iter()
+            .position(|rect| pointer.x < rect.center().x)
+            .unwrap_or(rects.len());
+
+        // Insertion marker while dragging
+        let marker_x = rects
+            .get(target)
+            .map(|rect| rect.left() - 2.0)
+            .or_else(|| rects.last().map(|rect| rect.right() + 2.0));
+        if let (Some(x), Some(first)) = (marker_x, rects.first()) {
+            ui.painter().vline(
+                x,
+                first.y_range(),
+                egui::Stroke::new(2.0, ui.visuals().selection.bg_fill),
+            );
+        }
+
+        if ui.input(|i| i.pointer.any_released()) {
+            self.favorite_drag = None;
+            let Some(from_index) = entries.get(from).map(|(index, _, _)| *index) else {
+                return;
+            };
+            let to_index = entries
+                .get(target)
+                .map(|(index, _, _)| *index)
+                .unwrap_or(self.favorites.paths().len());
+            let to_index = if to_index > from_index { to_index - 1 } else { to_index };
+            if to_index != from_index {
+                self.favorites.move_item(from_index, to_index);
+                self.save_favorites();
+                self.refresh_favorite_entries();
+            }
+        }
+    }
+
+    /// Every command the palette offers; categories and profiles come from the loaded data.
+    fn palette_commands(&self) -> Vec<palette::Command> {
+        use palette::{Action, Command};
+        let mut commands = vec![
+            Command::new("Refresh library", Action::RefreshLibrary),
+            Command::new("Open settings", Action::OpenSettings),
+            Command::new("Show keyboard shortcuts", Action::ShowShortcuts).with_shortcut("F1"),
+            Command::new("Show library health", Action::ShowHealth),
+            Command::new("Verify checksums", Action::VerifyChecksums),
+            Command::new("Index image dimensions", Action::IndexDimensions),
+            Command::new("Show usage insights", Action::ShowUsage),
+            Command::new("Toggle favorites only", Action::ToggleFavoritesOnly),
+            Command::new("Toggle favorites bar", Action::ToggleFavoritesBar),
+            Command::new("Toggle preview pane", Action::TogglePreviewPane),
+            Command::new("Toggle privacy mode", Action::TogglePrivacyMode).with_shortcut("F9"),
+            Command::new("Toggle text-only mode", Action::ToggleTextOnly),
+            Command::new("Toggle dark mode", Action::ToggleDarkMode),
+            Command::new("Import folder into category…", Action::ImportFolder),
+            Command::new("Import ZIP…", Action::ImportZip),
+            Command::new("Create category from results…", Action::CategoryFromResults),
+            Command::new("Optimize images…", Action::Optimize),
+        ];
+
+        if let Some(data) = &self.image_data {
+            let mut categories: Vec<&String> = data.categories.keys().collect();
+            categories.sort();
+            commands.push(Command::new("Category: All Categories", Action::ShowCategory("All Categories".to_string())));
+            if self.new_images.total() > 0 {
+                commands.push(Command::new(
+                    format!("Category: {}", new_images::CATEGORY),
+                    Action::ShowCategory(new_images::CATEGORY.to_string()),
+                ));
+            }
+            commands.extend(
+                categories
+                    .into_iter()
+                    .map(|name| Command::new(format!("Category: {}", name), Action::ShowCategory(name.clone()))),
+            );
+        }
+
+        let active_id = self.settings.active_profile().id;
+        commands.extend(
+            self.settings
+                .profiles
+                .iter()
+                .filter(|profile| profile.id != active_id)
+                .map(|profile| Command::new(format!("Profile: {}", profile.name), Action::SwitchProfile(profile.id.clone()))),
+        );
+        commands
+    }
+
+    fn open_palette(&mut self, ctx: &egui::Context) {
+        self.palette = Some(palette::CommandPalette::new(self.palette_commands()));
+        ctx.memory_mut(|m| m.request_focus(palette_input_id()));
+    }
+
+    fn run_palette_action(&mut self, action: palette::Action) {
+        use palette::Action;
+        match action {
+            Action::RefreshLibrary => self.refresh_library(),
+            Action::OpenSettings => self.show_settings = true,
+            Action::ShowShortcuts => self.show_shortcuts = true,
+            Action::ShowHealth => self.show_health = true,
+            Action::VerifyChecksums => {
+                self.show_health = true;
+                self.start_checksum_verification();
+            }
+            Action::IndexDimensions => self.start_dimension_index(false),
+            Action::ShowUsage => self.show_usage = true,
+            Action::ToggleFavoritesOnly => {
+                self.favorites_only = !self.favorites_only;
+                self.update_filtered_images();
+            }
+            Action::ToggleFavoritesBar => {
+                self.settings.show_favorites_bar = !self.settings.show_favorites_bar;
+                self.persist_settings();
+            }
+            Action::TogglePrivacyMode => self.toggle_privacy_mode(),
+            Action::ToggleTextOnly => {
+                self.settings.text_only = !self.settings.text_only;
+                self.persist_settings();
+                self.set_status(if self.settings.text_only {
+                    "Text-only mode on: thumbnails aren't loaded"
+                } else {
+                    "Text-only mode off"
+                });
+            }
+            Action::TogglePreviewPane => {
+                self.settings.show_preview_pane = !self.settings.show_preview_pane;
+                self.persist_settings();
+            }
+            Action::ImportFolder => self.open_import_dialog(),
+            Action::ImportZip => self.open_zip_import(),
+            Action::CategoryFromResults => self.open_results_category_dialog(),
+            Action::Optimize => self.open_optimize_dialog(),
+            Action::ToggleDarkMode => {
+                self.settings.dark_mode = !self.settings.dark_mode;
+                self.persist_settings();
+            }
+            Action::ShowCategory(category) => {
+                self.show_all_categories = category == "All Categories";
+                self.selected_category = category;
+                self.update_filtered_images();
+            }
+            Action::SwitchProfile(profile_id) => self.switch_profile(&profile_id),
+        }
+    }
+
+    /// Centered popup with a filter box over the command list.
+    fn palette_window(&mut self, ctx: &egui::Context) {
+        let Some(palette) = &mut self.palette else {
+            return;
+        };
+        let mut run = None;
+
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .fixed_size([420.0, 0.0])
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 80.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut palette.query)
+                        .id(palette_input_id())
+                        .hint_text("Type a command…")
+                        .desired_width(f32::INFINITY),
+                );
+                if response.changed() {
+                    palette.refilter();
+                }
+
+                ui.separator();
+                if palette.matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for (row, &index) in palette.matches.iter().enumerate() {
+                        let command = &palette.commands[index];
+                        let selected = row == palette.selected;
+                        ui.horizontal(|ui| {
+                            let label = ui.selectable_label(selected, &command.name);
+                            if selected {
+                                label.scroll_to_me(None);
+                            }
+                            if label.clicked() {
+                                run = Some(command.action.clone());
+                            }
+                            if let Some(shortcut) = command.shortcut {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.weak(shortcut);
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some(action) = run {
+            self.palette = None;
+            self.run_palette_action(action);
+        }
+    }
+
+    /// Starts, restarts or stops the local endpoint to match the settings.
+    fn sync_remote_server(&mut self, ctx: &egui::Context) {
+        if self.safe_mode || !self.settings.remote_control.enabled {
+            self.remote = None;
+            return;
+        }
+        if self.settings.remote_control.token.is_empty() {
+            self.settings.remote_control.token = remote::generate_token();
+            self.persist_settings();
+        }
+        let config = &self.settings.remote_control;
+        if self
+            .remote
+            .as_ref()
+            .is_some_and(|server| server.port == config.port && server.token == config.token)
+        {
+            return;
+        }
+
+        self.remote = None;
+        match remote::RemoteServer::start(config.port, config.token.clone(), ctx.clone()) {
+            Ok(server) => self.remote = Some(server),
+            Err(e) => {
+                // Turn it off so a taken port isn't retried every frame
+                self.set_status(format!("Remote control disabled: {}", e));
+                self.settings.remote_control.enabled = false;
+                self.persist_settings();
+            }
+        }
+    }
+
+    /// Answers queued endpoint requests; runs on the UI thread so state changes are ordinary.
+    fn poll_remote(&mut self, ctx: &egui::Context) {
+        let Some(server) = &self.remote else {
+            return;
+        };
+        let requests: Vec<remote::RemoteRequest> = server.requests.try_iter().collect();
+        for request in requests {
+            let reply = self.handle_remote_command(ctx, request.command);
+            let _ = request.reply.send(reply);
+        }
+    }
+
+    fn handle_remote_command(&mut self, ctx: &egui::Context, command: remote::RemoteCommand) -> remote::RemoteReply {
+        use remote::{RemoteCommand, RemoteReply};
+        const SEARCH_LIMIT: usize = 50;
+
+        let target = match command {
+            RemoteCommand::Search { query } => {
+                let results: Vec<serde_json::Value> = self
+                    .search_library(&query, SEARCH_LIMIT)
+                    .into_iter()
+                    .map(|(category, image)| {
+                        serde_json::json!({
+                            "category": category,
+                            "filename": image.filename,
+                            "relative_path": image.relative_path,
+                            "path": image.full_path,
+                        })
+                    })
+                    .collect();
+                return RemoteReply::ok(serde_json::Value::Array(results));
+            }
+            RemoteCommand::Show { query } => {
+                self.search_query = query;
+                self.update_filtered_images();
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                return RemoteReply::ok(serde_json::json!({ "shown": true }));
+            }
+            RemoteCommand::Open { link } => {
+                return match self.follow_deep_link(ctx, link) {
+                    Ok(()) => RemoteReply::ok(serde_json::json!({ "opened": true })),
+                    Err(e) => RemoteReply::error(404, e),
+                };
+            }
+            RemoteCommand::CopyPath { path } => self.find_image(&path),
+            RemoteCommand::CopyQuery { query } => self.search_library(&query, 1).into_iter().next(),
+        };
+
+        // Only library entries can be copied, never arbitrary files
+        let Some((category, image_info)) = target else {
+            return RemoteReply::error(404, "no matching image in the library");
+        };
+        self.last_copied = None;
+        self.copy_image(&category, &image_info);
+        if self.recently_copied(&image_info.full_path) {
+            RemoteReply::ok(serde_json::json!({ "copied": image_info.full_path }))
+        } else if self.copy_queue.contains(&image_info.full_path) {
+            // Pixel copies finish in the background
+            RemoteReply::ok(serde_json::json!({ "queued": image_info.full_path }))
+        } else {
+            RemoteReply::error(500, self.status_message.text.clone())
+        }
+    }
+
+    /// Best matches for a query across the whole library, ignoring the UI filters.
+    fn search_library(&self, query: &str, limit: usize) -> Vec<(String, ImageInfo)> {
+        let Some(data) = &self.image_data else {
+            return Vec::new();
+        };
+        let query = search::ParsedQuery::parse(query);
+        let mut matches: Vec<(search::MatchRank, &String, &ImageInfo)> = self
+            .search_index
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let rank = query.rank(entry)?;
+                let image = data.categories.get(&entry.category)?.images.get(entry.image_index)?;
+                if self.hidden.contains(&image.full_path) {
+                    return None;
+                }
+                Some((rank, &entry.category, image))
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)).then(a.2.filename.cmp(&b.2.filename)));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, category, image)| (category.clone(), image.clone()))
+            .collect()
+    }
+
+    /// Applies a link's search or opens its image, and raises the window.
+    fn follow_deep_link(&mut self, ctx: &egui::Context, link: deep_link::DeepLink) -> Result<(), String> {
+        let data = self.image_data.as_ref().ok_or("the library isn't loaded")?;
+        match link {
+            deep_link::DeepLink::Search { query, category } => {
+                if let Some(category) = &category {
+                    if !data.categories.contains_key(category) {
+                        return Err(format!("there is no category \"{}\"", category));
+                    }
+                }
+                self.scoped_by_prefix = false;
+                self.show_all_categories = category.is_none();
+                self.selected_category = category.unwrap_or_else(|| "All Categories".to_string());
+                self.search_query = query;
+                self.update_filtered_images();
+            }
+            deep_link::DeepLink::Image { path } => {
+                let entry = self
+                    .find_image(&path)
+                    .ok_or_else(|| format!("{} isn't in the library", path))?;
+                self.open_details(entry.0, entry.1);
+            }
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        Ok(())
+    }
+
+    /// Follows the command-line link once loading has finished either way.
+    fn follow_pending_link(&mut self, ctx: &egui::Context) {
+        if self.library_load.is_some() {
+            return;
+        }
+        let Some(link) = self.pending_link.take() else {
+            return;
+        };
+        if let Err(e) = deep_link::parse(&link).and_then(|link| self.follow_deep_link(ctx, link)) {
+            self.set_status(format!("Couldn't open link: {}", e));
+        }
+    }
+
+    fn find_image(&self, full_path: &str) -> Option<(String, ImageInfo)> {
+        let data = self.image_data.as_ref()?;
+        data.categories.iter().find_map(|(name, category)| {
+            category
+                .images
+                .iter()
+                .find(|image| image.full_path == full_path)
+                .map(|image| (name.clone(), image.clone()))
+        })
+    }
+
+    /// Opens the file in the application configured for its extension, or the OS default.
+    fn open_externally(&mut self, image_info: &ImageInfo) {
+        let command = self.settings.open_command(&image_info.extension).map(str::to_string);
+        self.set_status(match launch::open(Path::new(&image_info.full_path), command.as_deref()) {
+            Ok(()) => format!("Opened {}", image_info.filename),
+            Err(e) => format!("Failed to open {}: {}", image_info.filename, e),
+        });
+    }
+
+    fn open_with_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+        for (extension, command) in &self.settings.open_with {
+            ui.horizontal(|ui| {
+                ui.monospace(format!(".{}", extension));
+                ui.label("→");
+                ui.label(command);
+                if ui.small_button("Remove").on_hover_text("Use the system default again").clicked() {
+                    remove = Some(extension.clone());
+                }
+            });
+        }
+        if let Some(extension) = remove {
+            self.settings.open_with.remove(&extension);
+        }
+
+        ui.horizontal(|ui| {
+            let (extension, command) = &mut self.new_open_with;
+            ui.add(egui::TextEdit::singleline(extension).hint_text("png").desired_width(50.0));
+            ui.add(egui::TextEdit::singleline(command).hint_text("Command or application path"));
+            if ui.button("Browse…").clicked() {
+                if let Some(path) = rfd::FileDialog::new().pick_file() {
+                    *command = format!("\"{}\"", portable::stored_path(&path));
+                }
+            }
+            let extension = extension.trim().trim_start_matches('.').to_ascii_lowercase();
+            let ready = !extension.is_empty() && !command.trim().is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Add")).clicked() {
+                self.settings.open_with.insert(extension, command.trim().to_string());
+                self.new_open_with = (String::new(), String::new());
+            }
+        });
+        ui.label(
+            egui::RichText::new(format!(
+                "The file path is added as the last argument, or wherever {} appears.",
+                launch::FILE_PLACEHOLDER
+            ))
+            .small()
+            .weak(),
+        );
+    }
+
+    fn source_rules_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+        for (index, rule) in self.settings.source_rules.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut rule.pattern).hint_text("Regex").font(egui::TextStyle::Monospace));
+                ui.label("→");
+                ui.add(egui::TextEdit::singleline(&mut rule.label).hint_text("Label").desired_width(100.0));
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+            if let Some(problem) = source_hints::validate(&rule.pattern) {
+                ui.label(egui::RichText::new(format!("⚠ Ignored: {}", problem)).small().color(ui.visuals().warn_fg_color));
+            }
+        }
+        if let Some(index) = remove {
+            self.settings.source_rules.remove(index);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add rule").clicked() {
+                self.settings.source_rules.push(source_hints::SourceRule {
+                    pattern: String::new(),
+                    label: String::new(),
+                });
+            }
+            let defaults = source_hints::default_rules();
+            if ui
+                .add_enabled(self.settings.source_rules != defaults, egui::Button::new("Restore defaults"))
+                .clicked()
+            {
+                self.settings.source_rules = defaults;
+            }
+        });
+        ui.label(
+            egui::RichText::new(format!(
+                "Matched against filenames, ignoring case; the first matching rule wins and the rest are \"{}\". \
+                 Search source:label to filter, with spaces in the label written as dashes.",
+                source_hints::UNKNOWN
+            ))
+            .small()
+            .weak(),
+        );
+    }
+
+    /// Runs the configured primary action (copy or open) on an image.
+    fn activate_image(&mut self, category: String, image_info: ImageInfo) {
+        match self.settings.primary_action {
+            PrimaryAction::Copy => self.copy_image(&category, &image_info),
+            PrimaryAction::OpenDetails => self.open_details(category, image_info),
+        }
+    }
+
+    /// Single keyboard pass run before any panel is drawn, so every key is
+    /// handled by exactly one owner in a fixed priority order.
+    fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        // The palette owns the keyboard while it is open
+        if let Some(palette) = &mut self.palette {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.palette = None;
+                return;
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown)) {
+                palette.move_selection(1);
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp)) {
+                palette.move_selection(-1);
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+                let action = palette.selected_action();
+                self.palette = None;
+                if let Some(action) = action {
+                    self.run_palette_action(action);
+                }
+            }
+            return;
+        }
+
+        if ctx.input_mut(|i| {
+            i.consume_key(egui::Modifiers::COMMAND, egui::Key::P) || i.consume_key(egui::Modifiers::COMMAND, egui::Key::K)
+        }) {
+            self.open_palette(ctx);
+            return;
+        }
+
+        let search_focused = ctx.memory(|m| m.has_focus(search_box_id()));
+        let nothing_focused = ctx.memory(|m| m.focused().is_none());
+
+        // Esc closes the topmost thing only: shortcuts, settings, details, then search
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            if ctx.memory(|m| m.is_popup_open(search_history_popup_id())) {
+                ctx.memory_mut(|m| m.close_popup());
+            } else if !self.scope_suggestions.is_empty() {
+                self.scope_suggestions.clear();
+            } else if self.pin_confirm.is_some() {
+                self.pin_confirm = None;
+            } else if self.large_copy_confirm.is_some() {
+                self.large_copy_confirm = None;
+            } else if self.overwrite_confirm.is_some() {
+                self.overwrite_confirm = None;
+            } else if self.refresh_confirm {
+                self.refresh_confirm = false;
+            } else if self.manifest_error.is_some() {
+                self.manifest_error = None;
+            } else if self.show_shortcuts {
+                self.show_shortcuts = false;
+            } else if self.export_dialog.is_some() {
+                self.export_dialog = None;
+            } else if self.grouping_dialog.is_some() {
+                self.grouping_dialog = None;
+            } else if self.import_dialog.as_ref().is_some_and(|dialog| dialog.running.is_none()) {
+                self.import_dialog = None;
+            } else if self.results_category.as_ref().is_some_and(|dialog| dialog.running.is_none()) {
+                self.results_category = None;
+            } else if self.zip_import.as_ref().is_some_and(|dialog| dialog.running.is_none()) {
+                self.zip_import = None;
+            } else if self.optimize_dialog.as_ref().is_some_and(|dialog| dialog.running.is_none()) {
+                self.optimize_dialog = None;
+            } else if self.removal_review.is_some() {
+                self.removal_review = None;
+            } else if self.user_data_import.is_some() {
+                self.user_data_import = None;
+            } else if self.show_settings {
+                self.show_settings = false;
+            } else if !self.detail_windows.is_empty() {
+                self.detail_windows.pop();
+            } else if !self.selection.is_empty() {
+                self.selection.clear();
+            } else if !self.search_query.is_empty() {
+                self.search_query.clear();
+                self.update_filtered_images();
+            } else if search_focused {
+                ctx.memory_mut(|m| m.surrender_focus(search_box_id()));
+            }
+        }
+
+        // Category suggestions take the arrows and Enter while they're showing
+        if search_focused && !self.scope_suggestions.is_empty() {
+            let count = self.scope_suggestions.len();
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown)) {
+                self.scope_selected = (self.scope_selected + 1) % count;
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp)) {
+                self.scope_selected = (self.scope_selected + count - 1) % count;
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+                self.accept_scope_suggestion(self.scope_selected);
+            }
+        }
+
+        // Enter in the search box acts on the top result
+        if search_focused && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+            let query = self.search_query.clone();
+            self.record_search(&query);
+            if let Some((category, image_info)) = self.filtered_entry(0) {
+                self.activate_image(category, image_info);
+            }
+        }
+
+        // Ctrl+F anywhere, or "/" when no text field has focus, jumps to search
+        let mut focus_search = ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F));
+        if nothing_focused {
+            ctx.input_mut(|i| {
+                let before = i.events.len();
+                i.events.retain(|e| !matches!(e, egui::Event::Text(t) if t == "/"));
+                if i.events.len() != before {
+                    focus_search = true;
+                }
+            });
+        }
+        if focus_search {
+            ctx.memory_mut(|m| m.request_focus(search_box_id()));
+        }
+
+        // Space opens the highlighted result, unless a button or field has focus
+        if nothing_focused && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Space)) {
+            if let Some((category, image_info)) = self.highlighted.clone() {
+                self.open_details(category, image_info);
+            }
+        }
+
+        // Ctrl+1..9 copy the first nine favorites, Ctrl+Shift+1..9 the pinned slots
+        const FAVORITE_KEYS: [egui::Key; 9] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+            egui::Key::Num6,
+            egui::Key::Num7,
+            egui::Key::Num8,
+            egui::Key::Num9,
+        ];
+        // Pinned slots first, so the Ctrl-only check below doesn't also match Ctrl+Shift
+        for (slot, key) in FAVORITE_KEYS.into_iter().enumerate().take(self.pins.slots().len()) {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, key)) {
+                self.copy_pinned(slot);
+            }
+        }
+        for (index, key) in FAVORITE_KEYS.into_iter().enumerate() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, key)) {
+                if let Some((_, category, image_info)) = self.favorite_entries.get(index).cloned() {
+                    self.copy_image(&category, &image_info);
+                }
+            }
+        }
+
+        // Left/Right flip the active detail window through the results
+        if let Some(active) = self.detail_windows.last().map(|view| view.id) {
+            if !ctx.wants_keyboard_input() {
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowRight)) {
+                    self.step_detail(active, 1);
+                }
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowLeft)) {
+                    self.step_detail(active, -1);
+                }
+            }
+        }
+
+        // Up/Down and Page Up/Page Down move the preview pane's highlight through the results;
+        // in the grid, Up/Down move a row and Left/Right a cell
+        if self.settings.show_preview_pane && self.detail_windows.is_empty() && !ctx.wants_keyboard_input() {
+            let page = self.results_scroll.page_items() as isize;
+            let row = self.results_scroll.columns() as isize;
+            let grid = self.settings.result_view == settings::ResultView::Grid;
+            for (key, delta) in [
+                (egui::Key::ArrowDown, row),
+                (egui::Key::ArrowUp, -row),
+                (egui::Key::ArrowRight, if grid { 1 } else { 0 }),
+                (egui::Key::ArrowLeft, if grid { -1 } else { 0 }),
+                (egui::Key::PageDown, page),
+                (egui::Key::PageUp, -page),
+            ] {
+                if delta == 0 {
+                    continue;
+                }
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key)) {
+                    self.step_highlight(delta);
+                }
+            }
+        }
+
+        // Text fields keep their own undo
+        if !ctx.wants_keyboard_input() {
+            let redo = ctx.input_mut(|i| {
+                i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                    || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+            });
+            if redo {
+                self.redo_last_edit();
+            } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                self.undo_last_edit();
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F1)) {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F9)) {
+            self.toggle_privacy_mode();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F12)) {
+            self.show_debug_overlay = !self.show_debug_overlay;
+        }
+    }
+
+    /// One-time bubble under the search box pointing out the main shortcuts.
+    fn search_tips(&mut self, ctx: &egui::Context, search_box: &egui::Response) {
+        use shortcuts::{keys, Binding};
+        let enter = match self.settings.primary_action {
+            PrimaryAction::Copy => "copies",
+            PrimaryAction::OpenDetails => "opens",
+        };
+        let mut dismiss = false;
+        egui::Area::new(egui::Id::new("search_tips"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(search_box.rect.left_bottom() + egui::vec2(0.0, 6.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(320.0);
+                    ui.strong("💡 Quick tips");
+                    ui.label(format!("{} jumps to this box from anywhere", keys(Binding::FocusSearch)));
+                    ui.label(format!("{} here {} the top result", keys(Binding::ActivateTop), enter));
+                    ui.label(format!(
+                        "{} step through results in a detail window, {} with the preview pane",
+                        keys(Binding::StepDetail),
+                        keys(Binding::StepHighlight)
+                    ));
+                    ui.label(format!("{} lists every shortcut", keys(Binding::CheatSheet)));
+                    if ui.button("Got it").clicked() {
+                        dismiss = true;
+                    }
+                });
+            });
+        if dismiss {
+            self.settings.search_tips_seen = true;
+            self.persist_settings();
+        }
+    }
+
+    /// Width the configured buttons at the end of a result row need.
+    fn row_actions_width(&self, ui: &egui::Ui) -> f32 {
+        let font = egui::TextStyle::Button.resolve(ui.style());
+        let spacing = ui.spacing();
+        self.settings
+            .row_actions
+            .iter()
+            .map(|action| {
+                elide::width(ui.ctx(), action.button_label(), &font) + spacing.button_padding.x * 2.0 + spacing.item_spacing.x
+            })
+            .sum::<f32>()
+            + spacing.item_spacing.x
+    }
+
+    fn row_action_label(&self, action: settings::RowAction, full_path: &str) -> &'static str {
+        match action {
+            settings::RowAction::CopyImage => self.copy_label(full_path, action.button_label()),
+            settings::RowAction::Favorite if self.favorites.contains(full_path) => "★",
+            _ => action.button_label(),
+        }
+    }
+
+    fn row_action_hint(&self, action: settings::RowAction, category: &str, image_info: &ImageInfo) -> String {
+        let (copy_keys, details_keys) = match self.settings.primary_action {
+            PrimaryAction::Copy => (
+                vec![shortcuts::Binding::ActivateTop, shortcuts::Binding::CopyRow],
+                vec![shortcuts::Binding::OpenHighlighted],
+            ),
+            PrimaryAction::OpenDetails => (
+                vec![shortcuts::Binding::CopyRow],
+                vec![shortcuts::Binding::ActivateTop, shortcuts::Binding::OpenHighlighted],
+            ),
+        };
+        match action {
+            settings::RowAction::CopyImage => format!(
+                "{}\nCopies {}",
+                shortcuts::hint("Copy", &copy_keys),
+                self.settings.copy_behavior(category).describe()
+            ),
+            settings::RowAction::CopyPath => "Copy the full path as text".to_string(),
+            settings::RowAction::CopyAsFile => "Copy the file itself, to paste into a file manager or chat".to_string(),
+            settings::RowAction::OpenExternally => match self.settings.open_command(&image_info.extension) {
+                Some(command) => format!("Opens with {}", command),
+                None => "Opens with the system default application".to_string(),
+            },
+            settings::RowAction::RevealInFolder => "Open the folder containing this file".to_string(),
+            settings::RowAction::Favorite if self.favorites.contains(&image_info.full_path) => {
+                "Remove from favorites".to_string()
+            }
+            settings::RowAction::Favorite => "Add to favorites".to_string(),
+            settings::RowAction::Details => shortcuts::hint("Details", &details_keys),
+        }
+    }
+
+    fn run_row_action(&mut self, action: settings::RowAction, category: &str, image_info: &ImageInfo) {
+        match action {
+            settings::RowAction::CopyImage => self.copy_image(category, image_info),
+            settings::RowAction::CopyPath => {
+                if self.copy_text_to_clipboard(image_info.full_path.clone(), "path") {
+                    self.mark_copied(&image_info.full_path);
+                }
+            }
+            settings::RowAction::CopyAsFile => self.copy_file_to_clipboard(image_info),
+            settings::RowAction::OpenExternally => self.open_externally(image_info),
+            settings::RowAction::RevealInFolder => self.reveal_in_folder(image_info),
+            settings::RowAction::Favorite => self.toggle_favorite(image_info),
+            settings::RowAction::Details => self.open_details(category.to_string(), image_info.clone()),
+        }
+    }
+
+    /// Up to [`settings::RowAction::MAX`] pickers; the first must be set.
+    fn row_action_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Row buttons:");
+            let mut slots: Vec<Option<settings::RowAction>> =
+                (0..settings::RowAction::MAX).map(|i| self.settings.row_actions.get(i).copied()).collect();
+            for (i, slot) in slots.iter_mut().enumerate() {
+                egui::ComboBox::from_id_source(("row_action", i))
+                    .selected_text(slot.map_or("—", |action| action.label()))
+                    .show_ui(ui, |ui| {
+                        if i > 0 {
+                            ui.selectable_value(slot, None, "—");
+                        }
+                        for action in settings::RowAction::ALL {
+                            ui.selectable_value(slot, Some(action), action.label());
+                        }
+                    });
+            }
+            let mut actions: Vec<settings::RowAction> = Vec::new();
+            for action in slots.into_iter().flatten() {
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+            if actions != self.settings.row_actions {
+                self.settings.row_actions = actions;
+            }
+        })
+        .response
+        .on_hover_text(
+            "Shown on each result row, the first rightmost, and in detail windows. \
+             Everything else is in the right-click menu.",
+        );
+    }
+
+    fn reveal_in_folder(&mut self, image_info: &ImageInfo) {
+        let folder = Path::new(&image_info.full_path).parent().unwrap_or(Path::new("."));
+        if let Err(e) = open_in_file_manager(folder) {
+            self.set_status(format!("Failed to open {}: {}", folder.display(), e));
+        }
+    }
+
+    /// Opens a detail window, or brings forward the one already showing the
+    /// image. The oldest window closes once there are too many.
+    fn open_details(&mut self, category: String, image: ImageInfo) {
+        if let Some(index) = self.detail_windows.iter().position(|view| view.image.full_path == image.full_path) {
+            let view = self.detail_windows.remove(index);
+            self.raise_detail = Some(view.id);
+            self.detail_windows.push(view);
+            return;
+        }
+        self.next_detail_id += 1;
+        self.detail_windows.push(DetailView {
+            id: self.next_detail_id,
+            category,
+            image,
+            orientation: orientation::Orientation::default(),
+        });
+        let limit = self.settings.max_detail_windows.max(1);
+        if self.detail_windows.len() > limit {
+            self.detail_windows.drain(..self.detail_windows.len() - limit);
+        }
+    }
+
+    /// Makes a window the one the arrow keys move.
+    fn activate_detail(&mut self, id: u64) {
+        if let Some(index) = self.detail_windows.iter().position(|view| view.id == id) {
+            let view = self.detail_windows.remove(index);
+            self.detail_windows.push(view);
+        }
+    }
+
+    /// Row of a detail window's image in the current results.
+    fn detail_row(&self, id: u64) -> Option<usize> {
+        let image_info = &self.detail_windows.iter().find(|view| view.id == id)?.image;
+        let data = self.image_data.as_ref()?;
+        self.filtered_images.iter().position(|&index| {
+            self.search_index
+                .entries
+                .get(index)
+                .and_then(|entry| data.categories.get(&entry.category)?.images.get(entry.image_index))
+                .is_some_and(|image| image.full_path == image_info.full_path)
+        })
+    }
+
+    /// Moves a detail window to the previous (-1) or next (1) result, stopping at either end.
+    fn step_detail(&mut self, id: u64, delta: isize) {
+        let Some(row) = self.detail_row(id) else {
+            return;
+        };
+        let Some(target) = row.checked_add_signed(delta) else {
+            return;
+        };
+        if let Some((category, image)) = self.filtered_entry(target) {
+            if let Some(view) = self.detail_windows.iter_mut().find(|view| view.id == id) {
+                view.category = category;
+                view.image = image;
+                view.orientation = orientation::Orientation::default();
+            }
+        }
+    }
+
+    /// Keeps full-size textures for every open detail image and the active
+    /// one's neighbors, and drops them once the windows close.
+    fn update_previews(&mut self, ctx: &egui::Context) {
+        let Some(active) = self.detail_windows.last().map(|view| view.id) else {
+            self.previews.clear();
+            return;
+        };
+        let shown = |image: &ImageInfo| {
+            decoder_supports(&image.extension) && !self.is_text_entry(image) && !self.is_concealed(&image.full_path)
+        };
+        // Active window first, then its neighbors, then the other windows
+        let mut wanted: Vec<String> = self
+            .detail_windows
+            .iter()
+            .rev()
+            .take(1)
+            .filter(|view| shown(&view.image))
+            .map(|view| view.image.full_path.clone())
+            .collect();
+        let row = self.detail_row(active);
+        for neighbor in [row.map(|row| row + 1), row.and_then(|row| row.checked_sub(1))] {
+            if let Some((_, entry)) = neighbor.and_then(|row| self.filtered_entry(row)) {
+                if shown(&entry) {
+                    wanted.push(entry.full_path);
+                }
+            }
+        }
+        wanted.extend(
+            self.detail_windows
+                .iter()
+                .rev()
+                .skip(1)
+                .filter(|view| shown(&view.image))
+                .map(|view| view.image.full_path.clone()),
+        );
+        let wanted: Vec<&str> = wanted.iter().map(String::as_str).collect();
+        self.previews.show(&wanted);
+        self.previews.poll(ctx);
+        if self.previews.is_loading() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(30));
+        }
+    }
+
+    /// Row of the preview pane's image in the current results.
+    fn highlighted_row(&self) -> Option<usize> {
+        let (_, image_info) = self.highlighted.as_ref()?;
+        (0..self.filtered_images.len())
+            .find(|&row| self.filtered_entry(row).is_some_and(|(_, image)| image.full_path == image_info.full_path))
+    }
+
+    /// Moves the highlight by `delta` rows, stopping at either end and starting
+    /// from the top result when nothing (still listed) is highlighted. The list
+    /// scrolls to keep it in view.
+    fn step_highlight(&mut self, delta: isize) {
+        let Some(last) = self.filtered_images.len().checked_sub(1) else {
+            return;
+        };
+        let target = match self.highlighted_row() {
+            Some(row) => row.saturating_add_signed(delta).min(last),
+            None => 0,
+        };
+        if let Some(entry) = self.filtered_entry(target) {
+            self.highlighted = Some(entry);
+            self.results_scroll.reveal(target, self.animations_enabled());
+        }
+    }
+
+    /// Explorer-style pane to the right of the results. Dragging its edge
+    /// resizes it (the width is remembered); dragging it narrow enough closes it.
+    fn preview_pane(&mut self, ctx: &egui::Context) {
+        let highlighted = self
+            .highlighted
+            .clone()
+            .filter(|_| self.settings.show_preview_pane && self.image_data.is_some());
+        match &highlighted {
+            Some((_, image_info)) if !self.is_text_entry(image_info) && !self.is_concealed(&image_info.full_path) => {
+                self.pane_preview.show(&[&image_info.full_path]);
+                self.pane_preview.poll(ctx);
+                if self.pane_preview.is_loading() {
+                    self.frame_guard.repaint_after(ctx, Duration::from_millis(30));
+                }
+            }
+            _ => self.pane_preview.clear(),
+        }
+        if !self.settings.show_preview_pane || self.image_data.is_none() {
+            return;
+        }
+
+        let panel = egui::SidePanel::right("preview_pane")
+            .resizable(true)
+            .default_width(self.settings.preview_pane_width)
+            .width_range(80.0..=900.0)
+            .show(ctx, |ui| {
+                let Some((category, image_info)) = highlighted else {
+                    ui.add_space(20.0);
+                    ui.vertical_centered(|ui| {
+                        ui.label(egui::RichText::new("Click a result or press ↓ to preview it here").weak());
+                    });
+                    return;
+                };
+                egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                    ui.add_space(8.0);
+                    let is_text = self.is_text_entry(&image_info);
+                    if is_text {
+                        match self.text_content(&image_info.full_path) {
+                            Ok(text) => {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut text.as_str())
+                                        .font(egui::TextStyle::Monospace)
+                                        .desired_width(f32::INFINITY),
+                                );
+                            }
+                            Err(e) => {
+                                ui.colored_label(ui.visuals().error_fg_color, e);
+                            }
+                        }
+                    } else if self.is_concealed(&image_info.full_path) {
+                        self.concealed_ui(ctx, ui, &image_info.full_path);
+                    } else if let Some(texture) = self
+                        .pane_preview
+                        .get(&image_info.full_path)
+                        .cloned()
+                        .or_else(|| match self.thumbnail_state(ctx, &image_info) {
+                            ThumbnailState::Ready(texture) => Some(texture),
+                            _ => None,
+                        })
+                    {
+                        let size = texture.size_vec2();
+                        let scale = (ui.available_width() / size.x).min(1.0);
+                        ui.vertical_centered(|ui| ui.image((texture.id(), size * scale)));
+                    } else {
+                        self.thumbnail_ui(ctx, ui, &image_info, 128.0);
+                    }
+
+                    ui.add_space(8.0);
+                    ui.separator();
+                    ui.add(egui::Label::new(egui::RichText::new(&image_info.filename).strong()).wrap(true));
+                    ui.label(format!("📁 {}", category));
+                    ui.label(format!("📊 {}", format_bytes(image_info.size)));
+                    ui.add(egui::Label::new(format!("📍 {}", image_info.relative_path)).wrap(true));
+                    ui.add_space(5.0);
+                    if !is_text {
+                        self.metadata_section(ctx, ui, &image_info);
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        let copy_label = self.copy_label(&image_info.full_path, "📋 Copy");
+                        if ui.button(copy_label).clicked() {
+                            self.copy_image(&category, &image_info);
+                        }
+                        if ui.button("👁️ Details").on_hover_text("Open the detail window").clicked() {
+                            self.open_details(category.clone(), image_info.clone());
+                        }
+                    });
+                });
+            });
+
+        let width = panel.response.rect.width();
+        if width < PREVIEW_PANE_COLLAPSE_WIDTH {
+            // Forget the dragged width so reopening uses the saved one
+            ctx.data_mut(|d| d.remove::<egui::containers::panel::PanelState>(egui::Id::new("preview_pane")));
+            self.settings.show_preview_pane = false;
+            self.persist_settings();
+        } else if (width - self.settings.preview_pane_width).abs() >= 1.0 && !ctx.input(|i| i.pointer.any_down()) {
+            self.settings.preview_pane_width = width;
+            self.persist_settings();
+        }
+    }
+
+    /// Whether the animations setting (or the OS hint it follows) allows motion.
+    /// Also off while the window is in the background, so nothing animates unseen.
+    fn animations_enabled(&self) -> bool {
+        if self.frame_guard.is_background() {
+            return false;
+        }
+        match self.settings.animations {
+            Animations::Auto => !self.os_reduced_motion,
+            Animations::On => true,
+            Animations::Off => false,
+        }
+    }
+
+    /// A spinner, or a static ellipsis when animations are off (egui spinners repaint every frame).
+    fn loading_indicator(&self, ui: &mut egui::Ui) {
+        if self.animations_enabled() {
+            ui.spinner();
+        } else {
+            ui.weak("…");
+        }
+    }
+
+    /// Remaining flash intensity (1.0 right after the copy, fading to 0.0) for a path.
+    fn copy_flash_strength(&self, path: &str) -> f32 {
+        if !self.animations_enabled() {
+            return 0.0;
+        }
+        match &self.last_copied {
+            Some((copied, at)) if copied == path && at.elapsed() < COPY_FLASH_DURATION => {
+                1.0 - at.elapsed().as_secs_f32() / COPY_FLASH_DURATION.as_secs_f32()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn recently_copied(&self, path: &str) -> bool {
+        matches!(&self.last_copied, Some((copied, at)) if copied == path && at.elapsed() < COPY_LABEL_DURATION)
+    }
+
+    /// Keeps frames coming while copy feedback is animating or waiting to revert.
+    fn schedule_copy_feedback_repaint(&mut self, ctx: &egui::Context) {
+        if let Some((_, at)) = &self.last_copied {
+            let elapsed = at.elapsed();
+            if elapsed < COPY_FLASH_DURATION && self.animations_enabled() {
+                ctx.request_repaint();
+            } else if elapsed < COPY_LABEL_DURATION {
+                ctx.request_repaint_after(COPY_LABEL_DURATION - elapsed);
+            } else {
+                self.last_copied = None;
+            }
+        }
+    }
+
+    /// Cached metadata for a path, reading it on a background thread on first request.
+    fn image_metadata(&mut self, ctx: &egui::Context, path: &str) -> Option<metadata::ImageMetadata> {
+        if let Some(metadata) = self.metadata_cache.get(path) {
+            return Some(metadata.clone());
+        }
+        if let Some(promise) = self.metadata_promises.get(path) {
+            if promise.ready().is_none() {
+                self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+                return None;
+            }
+            let promise = self.metadata_promises.remove(path)?;
+            let metadata = promise.try_take().ok()?;
+            self.metadata_cache.insert(path.to_string(), metadata.clone());
+            return Some(metadata);
+        }
+
+        let owned_path = path.to_string();
+        self.metadata_promises.insert(
+            path.to_string(),
+            Promise::spawn_thread("read_metadata", move || metadata::read(&owned_path)),
+        );
+        self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+        None
+    }
+
+    fn metadata_section(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, image_info: &ImageInfo) {
+        egui::CollapsingHeader::new("Metadata")
+            .default_open(false)
+            .show(ui, |ui| {
+                let Some(metadata) = self.image_metadata(ctx, &image_info.full_path) else {
+                    self.loading_indicator(ui);
+                    return;
+                };
+
+                egui::Grid::new("metadata_grid").num_columns(2).striped(true).show(ui, |ui| {
+                    ui.label("File size");
+                    ui.label(format_bytes(image_info.size));
+                    ui.end_row();
+                    for (label, value) in &metadata.fields {
+                        ui.label(label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+
+                if metadata.has_exif {
+                    let action = if self.settings.strip_metadata { "Stripped" } else { "Kept" };
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{} on copy: {} EXIF field(s){}",
+                            action,
+                            metadata.exif_field_count,
+                            if metadata.has_gps { " including GPS" } else { "" }
+                        ))
+                        .small()
+                        .weak(),
+                    );
+                }
+                if metadata.has_gps {
+                    ui.label(
+                        egui::RichText::new("⚠ This file contains GPS location data. Sharing the original file reveals where it was taken.")
+                            .small()
+                            .color(ui.visuals().warn_fg_color),
+                    );
+                } else if !metadata.has_exif {
+                    ui.label(egui::RichText::new("No EXIF data").small().weak());
+                }
+
+                if ui.button("📋 Copy metadata").clicked() {
+                    self.copy_text_to_clipboard(metadata.to_text(), "metadata");
+                }
+            });
+    }
+
+    /// Puts a file reference on the clipboard. With metadata stripping on, files carrying
+    /// EXIF are swapped for a sanitized temp copy; otherwise GPS data only triggers a warning.
+    fn copy_file_to_clipboard(&mut self, image_info: &ImageInfo) {
+        let source = std::path::PathBuf::from(&image_info.full_path);
+        if !source.exists() {
+            self.set_status(format!("Image file not found: {}", image_info.full_path));
+            return;
+        }
+
+        let (has_exif, has_gps) = metadata::exif_flags(&image_info.full_path);
+        let mut note = "";
+        let file = if has_exif && self.settings.strip_metadata {
+            let temp_dir = std::env::temp_dir().join("chlorine-sanitized");
+            match encode::sanitized_copy(&source, &temp_dir) {
+                Ok(path) => {
+                    note = " (metadata stripped)";
+                    path
+                }
+                Err(e) => {
+                    self.set_status(format!("Failed to create sanitized copy: {}", e));
+                    return;
+                }
+            }
+        } else {
+            if has_gps {
+                note = " ⚠ original includes GPS location";
+            }
+            source
+        };
+
+        let result = self.clipboard.set_files(&[file]);
+        self.set_status(match result {
+            Ok(()) => {
+                self.mark_copied(&image_info.full_path);
+                format!("Copied {} as file{}", image_info.filename, note)
+            }
+            Err(e) => format!("Failed to copy file to clipboard: {}", e),
+        });
+    }
+
+    fn export_dialog_window(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.export_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut save_clicked = false;
+
+        egui::Window::new(format!("💾 Save {} as…", dialog.image_info.filename))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("export_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Format:");
+                    egui::ComboBox::from_id_source("export_format")
+                        .selected_text(dialog.options.format.label())
+                        .show_ui(ui, |ui| {
+                            for format in encode::ExportFormat::ALL {
+                                ui.selectable_value(&mut dialog.options.format, format, format.label());
+                            }
+                        });
+                    ui.end_row();
+
+                    if dialog.options.format == encode::ExportFormat::Jpeg {
+                        ui.label("Quality:");
+                        ui.add(egui::Slider::new(&mut dialog.options.jpeg_quality, 1..=100));
+                        ui.end_row();
+
+                        ui.label("Background:");
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_srgba(&mut dialog.background);
+                            ui.label(egui::RichText::new("used behind transparent pixels").small().weak());
+                        });
+                        ui.end_row();
+                    }
+
+                    ui.label("Resize:");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut dialog.resize, "Max dimension");
+                        ui.add_enabled(
+                            dialog.resize,
+                            egui::DragValue::new(&mut dialog.max_dimension).clamp_range(16..=16384).suffix(" px"),
+                        );
+                    });
+                    ui.end_row();
+                });
+
+                ui.add_space(10.0);
+                if ui.button("💾 Save…").clicked() {
+                    save_clicked = true;
+                }
+            });
+
+        if save_clicked {
+            let stem = Path::new(&dialog.image_info.filename)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "image".to_string());
+            let extension = dialog.options.format.extension();
+            let target = rfd::FileDialog::new()
+                .add_filter(dialog.options.format.label(), &[extension])
+                .set_file_name(format!("{}.{}", stem, extension))
+                .save_file();
+
+            if let Some(dest) = target {
+                let mut options = dialog.options.clone();
+                options.max_dimension = dialog.resize.then_some(dialog.max_dimension);
+                options.background = [dialog.background.r(), dialog.background.g(), dialog.background.b()];
+                let source = std::path::PathBuf::from(&dialog.image_info.full_path);
+                self.set_status(format!("Saving {}…", dest.display()));
+                self.export_promise = Some(Promise::spawn_thread("export_image", move || {
+                    encode::export(&source, &dest, &options).map(|()| dest)
+                }));
+                open = false;
+            }
+        }
+
+        if !open {
+            self.export_dialog = None;
+        }
+    }
+
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some(promise) = &self.export_promise else {
+            return;
+        };
+        if promise.ready().is_none() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+            return;
+        }
+        let Some(result) = self.export_promise.take().and_then(|p| p.try_take().ok()) else {
+            return;
+        };
+        match result {
+            Ok(dest) => {
+                self.set_status(format!("Saved {}", dest.display()));
+                self.export_folder = dest.parent().map(Path::to_path_buf);
+            }
+            Err(e) => {
+                self.set_status(format!("Failed to save image: {}", e));
+            }
+        }
+    }
+
+    /// Per-category copy override editor, used from the category menu.
+    fn category_copy_menu(&mut self, ui: &mut egui::Ui, category: &str) {
+        ui.label(egui::RichText::new(format!("Copy behavior for \"{}\"", category)).strong());
+        let current = self.settings.category_copy.get(category).copied();
+
+        if ui.radio(current.is_none(), "Use global default").clicked() {
+            self.settings.category_copy.remove(category);
+            self.persist_settings();
+        }
+        let mut behavior = current.unwrap_or(self.settings.default_copy);
+        let mut changed = false;
+        ui.separator();
+        for mode in settings::CopyMode::ALL {
+            if ui.radio(current.is_some() && behavior.mode == mode, mode.label()).clicked() {
+                behavior.mode = mode;
+                changed = true;
+            }
+        }
+        if behavior.mode == settings::CopyMode::Pixels {
+            ui.separator();
+            for preset in settings::COPY_RESIZE_PRESETS {
+                let selected = current.is_some() && behavior.max_dimension == preset;
+                if ui.radio(selected, settings::resize_label(preset)).clicked() {
+                    behavior.max_dimension = preset;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.settings.category_copy.insert(category.to_string(), behavior);
+            self.persist_settings();
+        }
+    }
+
+    /// Swatches, a custom color picker and the match tolerance.
+    fn color_filter_menu(&mut self, ui: &mut egui::Ui) {
+        const SWATCHES: [(u8, u8, u8); 10] = [
+            (220, 40, 40),
+            (240, 140, 30),
+            (240, 220, 50),
+            (60, 170, 60),
+            (40, 180, 200),
+            (40, 80, 220),
+            (150, 60, 200),
+            (240, 120, 180),
+            (20, 20, 20),
+            (245, 245, 245),
+        ];
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            for (r, g, b) in SWATCHES {
+                let color = egui::Color32::from_rgb(r, g, b);
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(20.0, 20.0), egui::Sense::click());
+                ui.painter().rect_filled(rect, 3.0, color);
+                if self.color_filter == Some(color) {
+                    ui.painter().rect_stroke(rect, 3.0, ui.visuals().selection.stroke);
+                }
+                if response.on_hover_text(colors::to_hex([r, g, b])).clicked() {
+                    self.color_filter = Some(color);
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Custom:");
+            let mut color = self.color_filter.unwrap_or(egui::Color32::GRAY);
+            if ui.color_edit_button_srgba(&mut color).changed() {
+                self.color_filter = Some(color);
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Tolerance:");
+            if ui.add(egui::Slider::new(&mut self.color_tolerance, 10.0..=150.0)).changed() {
+                changed = true;
+            }
+        });
+        if ui.add_enabled(self.color_filter.is_some(), egui::Button::new("✖ Clear color filter")).clicked() {
+            self.color_filter = None;
+            changed = true;
+            ui.close_menu();
+        }
+        if changed {
+            self.update_filtered_images();
+        }
+    }
+
+    /// Offers to split a category by filename, or to undo the grouping it came from.
+    fn category_grouping_menu(&mut self, ui: &mut egui::Ui, category: &str) {
+        let source = self
+            .grouping_rules
+            .keys()
+            .find(|source| category.starts_with(&format!("{}/", source)))
+            .cloned();
+        match source {
+            Some(source) => {
+                let button = egui::Button::new(format!("↩ Undo grouping of \"{}\"", source));
+                if self.read_only_action(ui, button).clicked() {
+                    self.set_category_grouping(&source, None);
+                    ui.close_menu();
+                }
+            }
+            None => {
+                if self.read_only_action(ui, egui::Button::new("🗂 Group into sub-categories…")).clicked() {
+                    let mut dialog = GroupingDialog::new(category.to_string());
+                    if let Some(images) = self.image_data.as_ref().and_then(|d| d.categories.get(category)) {
+                        dialog.refresh_preview(&images.images);
+                    }
+                    self.grouping_dialog = Some(dialog);
+                    ui.close_menu();
+                }
+            }
+        }
+    }
+
+    /// Adds a button for an action that rewrites the manifest, disabled while the library is read-only.
+    fn read_only_action(&self, ui: &mut egui::Ui, button: egui::Button) -> egui::Response {
+        let response = ui.add_enabled(self.read_only.is_none(), button);
+        match &self.read_only {
+            Some(reason) => response.on_disabled_hover_text(format!("Library is read-only: {}", reason)),
+            None => response,
+        }
+    }
+
+    /// Stores (or removes) a grouping rule in the manifest; the library reloads once it's saved.
+    fn set_category_grouping(&mut self, category: &str, rule: Option<&grouping::GroupingRule>) {
+        let before = self.grouping_rules.get(category).cloned();
+        match self.write_category_grouping(category, rule) {
+            Ok(()) => self.undo.record(undo::Edit::Grouping {
+                category: category.to_string(),
+                before,
+                after: rule.cloned(),
+            }),
+            Err(e) => self.set_status(e),
+        }
+    }
+
+    /// Reloads the library once every queued manifest edit is on disk, keeping
+    /// the status message of whatever triggered the edit.
+    fn poll_manifest_saver(&mut self, ctx: &egui::Context) {
+        let mut saved = false;
+        for result in self.manifest_saver.poll() {
+            match result {
+                Ok(()) => saved = true,
+                Err(e) => self.set_status(format!("Failed to update manifest: {}", e)),
+            }
+        }
+        if saved && !self.manifest_saver.is_saving() {
+            if !self.status_message.text.is_empty() {
+                self.status_after_load = Some(self.status_message.clone());
+            }
+            self.load_image_data();
+        }
+        if self.manifest_saver.is_saving() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+        }
+    }
+
+    /// Writes queued user data once the edits settle, or as soon as the
+    /// window loses focus. Runs before the focus state is updated.
+    fn poll_user_store(&mut self, ctx: &egui::Context) {
+        if self.window_focused && !ctx.input(|i| i.focused) {
+            self.user_store.flush();
+        }
+        if let Some(e) = self.user_store.poll() {
+            self.set_status_with(
+                format!("Failed to save favorites, pins and other user data: {}", e),
+                status::StatusAction::RetryUserDataSave,
+            );
+        }
+        if self.user_store.is_busy() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(250));
+        }
+    }
+
+    /// Reloads the manifest without losing edits that aren't on disk yet:
+    /// edits still saving reload on their own once written, and failed ones
+    /// ask first.
+    fn refresh_library(&mut self) {
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::LibraryLoad) {
+            self.set_status(reason);
+        } else if self.manifest_saver.unsaved() > 0 {
+            self.refresh_confirm = true;
+        } else if self.manifest_saver.is_saving() {
+            self.set_status("The library reloads once the pending edits are saved".to_string());
+        } else {
+            self.load_image_data();
+        }
+    }
+
+    fn refresh_confirm_window(&mut self, ctx: &egui::Context) {
+        if !self.refresh_confirm {
+            return;
+        }
+        let unsaved = self.manifest_saver.unsaved();
+        let mut decided = None;
+        egui::Window::new("Save changes before reloading?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} library edit(s) couldn't be written to the manifest. Reloading without saving discards them.",
+                    unsaved
+                ));
+                if let Some(reason) = &self.read_only {
+                    ui.label(egui::RichText::new(format!("The manifest is read-only: {}", reason)).weak());
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.read_only.is_none(), egui::Button::new("Save and reload")).clicked() {
+                        decided = Some(true);
+                    }
+                    if ui.button("Discard and reload").clicked() {
+                        decided = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.refresh_confirm = false;
+                    }
+                });
+            });
+        let Some(save) = decided else {
+            return;
+        };
+        self.refresh_confirm = false;
+        if save {
+            let dropped = self.manifest_saver.retry();
+            if self.manifest_saver.is_saving() {
+                // The reload follows once the retried edits are written
+                let note = match dropped {
+                    0 => String::new(),
+                    _ => format!("; dropped {} that no longer apply", dropped),
+                };
+                self.set_status(format!("Saving {} edit(s) before reloading{}…", unsaved - dropped, note));
+            } else {
+                self.status_after_load = (dropped > 0).then(|| {
+                    status::StatusMessage::new(format!("Dropped {} edit(s) that no longer apply to the manifest", dropped))
+                });
+                self.load_image_data();
+            }
+        } else {
+            self.manifest_saver.discard_failed();
+            self.load_image_data();
+        }
+    }
+
+    fn write_category_grouping(&mut self, category: &str, rule: Option<&grouping::GroupingRule>) -> Result<(), String> {
+        if let Some(reason) = &self.read_only {
+            return Err(format!("Library is read-only: {}", reason));
+        }
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::ManifestSave) {
+            return Err(reason);
+        }
+        let manifest_path = self.settings.active_profile().manifest_path;
+        self.manifest_saver
+            .submit(&manifest_path, manifest::set_grouping(category.to_string(), rule.cloned()));
+        self.selected_category = "All Categories".to_string();
+        self.show_all_categories = true;
+        Ok(())
+    }
+
+    /// Categories as the manifest knows them: grouped sub-categories map back to their source.
+    fn manifest_categories(&self) -> Vec<String> {
+        let Some(data) = &self.image_data else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = data
+            .categories
+            .keys()
+            .map(|name| self.manifest_category(name).to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The manifest category a (possibly grouped) category comes from.
+    fn manifest_category<'a>(&'a self, name: &'a str) -> &'a str {
+        self.grouping_rules
+            .keys()
+            .find(|source| name.starts_with(&format!("{}/", source)))
+            .map_or(name, String::as_str)
+    }
+
+    /// Removes the manifest entries for `full_path` outside `keep`.
+    fn keep_only_in(&mut self, full_path: &str, keep: &str) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::ManifestSave) {
+            self.set_status(reason);
+            return;
+        }
+        let Some(categories) = self.duplicates.get(full_path) else {
+            return;
+        };
+        let keep_source = self.manifest_category(keep).to_string();
+        let mut strip: Vec<String> = categories
+            .iter()
+            .map(|name| self.manifest_category(name).to_string())
+            .filter(|source| *source != keep_source)
+            .collect();
+        strip.sort();
+        strip.dedup();
+        if strip.is_empty() {
+            self.set_status("These entries are groups of the same manifest category".to_string());
+            return;
+        }
+        let Some((_, image)) = self.find_image(full_path) else {
+            return;
+        };
+        let removals = strip
+            .into_iter()
+            .map(|category| removal_review::Removal {
+                category,
+                image: image.clone(),
+                reason: format!("also listed in {}, which keeps it", keep),
+            })
+            .collect();
+        self.open_removal_review("Keep in one category", removals);
+    }
+
+    /// One removal per manifest category that lists `full_path`.
+    fn removals_for(&self, full_path: &str, reason: &str) -> Vec<removal_review::Removal> {
+        let Some(data) = &self.image_data else {
+            return Vec::new();
+        };
+        let mut removals: Vec<removal_review::Removal> = Vec::new();
+        for (name, category) in &data.categories {
+            let Some(image) = category.images.iter().find(|image| image.full_path == full_path) else {
+                continue;
+            };
+            let source = self.manifest_category(name);
+            if !removals.iter().any(|removal| removal.category == source) {
+                removals.push(removal_review::Removal {
+                    category: source.to_string(),
+                    image: image.clone(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        removals.sort_by(|a, b| a.category.cmp(&b.category));
+        removals
+    }
+
+    /// Stages removals for review; the manifest is only rewritten once they're confirmed.
+    fn open_removal_review(&mut self, title: &str, removals: Vec<removal_review::Removal>) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        if removals.is_empty() {
+            self.set_status("Nothing to remove".to_string());
+            return;
+        }
+        self.removal_review = Some(removal_review::RemovalReview::new(title, removals));
+    }
+
+    fn removal_review_window(&mut self, ctx: &egui::Context) {
+        let Some(mut review) = self.removal_review.take() else {
+            return;
+        };
+        let blocker = self.operations.blocker(operations::OperationKind::ManifestSave);
+        let outcome = review.show(ctx, blocker.as_deref(), |ui, image, size| self.thumbnail_ui(ctx, ui, image, size));
+        match outcome {
+            removal_review::Outcome::Pending => self.removal_review = Some(review),
+            removal_review::Outcome::Cancelled => {}
+            removal_review::Outcome::Approved(removals) => self.apply_removals(removals),
+        }
+    }
+
+    /// Removes approved entries in one manifest edit; the library reloads once it's saved.
+    fn apply_removals(&mut self, removals: Vec<removal_review::Removal>) {
+        let mut by_path: Vec<(String, Vec<String>)> = Vec::new();
+        for removal in &removals {
+            match by_path.iter_mut().find(|(path, _)| *path == removal.image.full_path) {
+                Some((_, categories)) => categories.push(removal.category.clone()),
+                None => by_path.push((removal.image.full_path.clone(), vec![removal.category.clone()])),
+            }
+        }
+        let mutations = by_path
+            .into_iter()
+            .map(|(path, categories)| manifest::remove_image(path, categories))
+            .collect();
+        let manifest_path = self.settings.active_profile().manifest_path;
+        self.manifest_saver.submit(&manifest_path, manifest::batch(mutations));
+        self.set_status(format!("Removing {} entries from the manifest", removals.len()));
+    }
+
+    fn duplicate_prompt_window(&mut self, ctx: &egui::Context) {
+        if !self.show_duplicate_prompt {
+            return;
+        }
+        let mut open = true;
+        let mut keep_first = false;
+        let mut review = false;
+        egui::Window::new("Images in several categories")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} image(s) are listed under more than one category.",
+                    self.duplicates.len()
+                ));
+                ui.label(
+                    egui::RichText::new("Rows show a ×N badge; click it to keep an image in just one category.")
+                        .small()
+                        .weak(),
+                );
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Keep all").clicked() {
+                        self.show_duplicate_prompt = false;
+                    }
+                    if ui
+                        .button("Keep first")
+                        .on_hover_text("Show each image only in its alphabetically first category; the manifest is unchanged")
+                        .clicked()
+                    {
+                        keep_first = true;
+                    }
+                    if self
+                        .read_only_action(ui, egui::Button::new("🗑 Review removals…"))
+                        .on_hover_text("Remove each image from every category but its first, after reviewing the list")
+                        .clicked()
+                    {
+                        review = true;
+                    }
+                });
+            });
+        if review {
+            let mut removals = Vec::new();
+            let mut paths: Vec<&String> = self.duplicates.keys().collect();
+            paths.sort();
+            for path in paths {
+                let mut entries = self.removals_for(path, "");
+                if entries.len() < 2 {
+                    continue;
+                }
+                let first = entries.remove(0).category;
+                for mut removal in entries {
+                    removal.reason = format!("duplicate; kept in {}", first);
+                    removals.push(removal);
+                }
+            }
+            self.show_duplicate_prompt = false;
+            self.open_removal_review("Remove duplicate entries", removals);
+        }
+        if keep_first {
+            if let Some(data) = &mut self.image_data {
+                let removed = duplicates::keep_first(data, &self.duplicates);
+                self.search_index = search::SearchIndex::build(data, &self.source_classifier);
+                self.set_status(format!("Hid {} duplicate entries", removed));
+            }
+            self.duplicates.clear();
+            self.update_filtered_images();
+            self.show_duplicate_prompt = false;
+        }
+        if !open {
+            self.show_duplicate_prompt = false;
+        }
+    }
+
+    fn open_import_dialog(&mut self) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        let category = if self.show_all_categories || self.selected_category == new_images::CATEGORY {
+            self.manifest_categories().into_iter().next().unwrap_or_default()
+        } else {
+            self.grouping_rules
+                .keys()
+                .find(|source| self.selected_category.starts_with(&format!("{}/", source)))
+                .cloned()
+                .unwrap_or_else(|| self.selected_category.clone())
+        };
+        self.import_dialog = Some(ImportDialog::new(category));
+    }
+
+    /// Starts the import on a background thread, resolving the category's folder from the loaded library.
+    fn start_import(&mut self) {
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::Import) {
+            self.set_status(reason);
+            return;
+        }
+        let Some(dialog) = &mut self.import_dialog else {
+            return;
+        };
+        let Some(source) = dialog.source.clone() else {
+            return;
+        };
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        // Grouped sub-categories keep their source's folder, so any of them will do
+        let in_category = |name: &String| name == &dialog.category || name.starts_with(&format!("{}/", dialog.category));
+        let directory = data
+            .categories
+            .iter()
+            .find(|(name, _)| in_category(name))
+            .map(|(_, category)| category.directory.clone());
+        let root = data
+            .categories
+            .values()
+            .flat_map(|category| category.images.iter())
+            .find_map(import::library_root);
+        let (Some(directory), Some(root)) = (directory, root) else {
+            self.set_status("Can't tell where this library's files live; import needs at least one existing image".to_string());
+            return;
+        };
+
+        let plan = import::ImportPlan {
+            source,
+            category: dialog.category.clone(),
+            root,
+            directory,
+            mode: dialog.mode,
+        };
+        let progress = std::sync::Arc::new(import::ImportProgress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("import_folder", move || import::run(plan, worker_progress));
+        dialog.running = Some((promise, progress));
+    }
+
+    fn import_dialog_window(&mut self, ctx: &egui::Context) {
+        let finished = self
+            .import_dialog
+            .as_ref()
+            .and_then(|dialog| dialog.running.as_ref())
+            .is_some_and(|(promise, _)| promise.ready().is_some());
+        if finished {
+            let Some(dialog) = self.import_dialog.take() else {
+                return;
+            };
+            let cancelled = dialog
+                .running
+                .as_ref()
+                .is_some_and(|(_, progress)| progress.cancelled.load(std::sync::atomic::Ordering::Relaxed));
+            let result = dialog
+                .running
+                .and_then(|(promise, _)| promise.try_take().ok())
+                .unwrap_or_else(|| Err("the import thread stopped unexpectedly".to_string()));
+            self.set_status(match result {
+                Ok(summary) => {
+                    let mut message = format!("Imported {} image(s)", summary.imported.len());
+                    if cancelled {
+                        message.push_str(" before the import was cancelled");
+                    }
+                    if !summary.imported.is_empty() {
+                        // The library reloads once the manifest is saved
+                        let manifest_path = self.settings.active_profile().manifest_path;
+                        self.manifest_saver
+                            .submit(&manifest_path, manifest::add_images(dialog.category, summary.imported));
+                    }
+                    if summary.skipped > 0 {
+                        message.push_str(&format!(", skipped {} non-image file(s)", summary.skipped));
+                    }
+                    if let Some(first) = summary.failed.first() {
+                        message.push_str(&format!(", {} failed (e.g. {})", summary.failed.len(), first));
+                    }
+                    message
+                }
+                Err(e) => format!("Import failed: {}", e),
+            });
+            return;
+        }
+
+        let categories = self.manifest_categories();
+        let blocker = self.operations.blocker(operations::OperationKind::Import);
+        let Some(dialog) = &mut self.import_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut start = false;
+        egui::Window::new("📥 Import folder into category")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some((_, progress)) = &dialog.running {
+                    let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+                    let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+                    let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                    ui.label(format!("Importing into {}…", dialog.category));
+                    ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {}", done, total)));
+                    let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+                    if ui.add_enabled(!cancelled, egui::Button::new("Cancel")).clicked() {
+                        progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Folder:");
+                    match &dialog.source {
+                        Some(source) => ui.label(source.display().to_string()),
+                        None => ui.label(egui::RichText::new("none chosen").weak()),
+                    };
+                    if ui.button("Choose…").clicked() {
+                        if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                            dialog.source = Some(folder);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Category:");
+                    egui::ComboBox::from_id_source("import_category")
+                        .selected_text(&dialog.category)
+                        .show_ui(ui, |ui| {
+                            for category in &categories {
+                                ui.selectable_value(&mut dialog.category, category.clone(), category);
+                            }
+                        });
+                });
+                for mode in import::ImportMode::ALL {
+                    ui.radio_value(&mut dialog.mode, mode, mode.label());
+                }
+                ui.label(
+                    egui::RichText::new("Only image files are imported; name clashes get a \" (1)\" suffix.")
+                        .small()
+                        .weak(),
+                );
+                ui.add_space(5.0);
+                let ready = dialog.source.is_some() && !dialog.category.is_empty() && blocker.is_none();
+                if ui
+                    .add_enabled(ready, egui::Button::new("📥 Import"))
+                    .on_disabled_hover_text(blocker.as_deref().unwrap_or("Choose a folder and a category"))
+                    .clicked()
+                {
+                    start = true;
+                }
+            });
+
+        if start {
+            self.start_import();
+        } else if !open && dialog.running.is_none() {
+            self.import_dialog = None;
+        }
+    }
+
+    /// Picks an archive and opens the ZIP import dialog with its folders mapped to categories.
+    fn open_zip_import(&mut self) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        let Some(archive) = rfd::FileDialog::new().add_filter("ZIP archive", &["zip"]).pick_file() else {
+            return;
+        };
+        let preview = match zip_import::preview(&archive) {
+            Ok(preview) => preview,
+            Err(e) => {
+                self.set_status(e);
+                return;
+            }
+        };
+        if preview.folders.is_empty() {
+            self.set_status(format!(
+                "{} has no images ({} other file(s))",
+                archive.display(),
+                preview.skipped
+            ));
+            return;
+        }
+        let mut taken: Vec<String> = Vec::new();
+        let mut rows = Vec::new();
+        for folder in preview.folders {
+            // Clashing names get the usual " (2)" suffix; the dialog offers merging instead
+            let name = (1..)
+                .map(|n| if n == 1 { folder.folder.clone() } else { format!("{} ({})", folder.folder, n) })
+                .find(|name| self.category_name_problem(name).is_none() && !taken.contains(name))
+                .unwrap_or_default();
+            taken.push(name.clone());
+            rows.push((folder, name, false));
+        }
+        self.zip_import = Some(ZipImportDialog {
+            archive,
+            rows,
+            skipped: preview.skipped,
+            mode: zip_import::ZipMode::Extract,
+            destination: None,
+            running: None,
+        });
+    }
+
+    /// Why each row of the ZIP import can't go ahead as it is, if it can't.
+    fn zip_row_problems(&self, dialog: &ZipImportDialog) -> Vec<Option<String>> {
+        let existing = self.manifest_categories();
+        dialog
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, (_, name, merge))| {
+                let name = name.trim();
+                if *merge {
+                    return (!existing.iter().any(|category| category == name))
+                        .then(|| format!("There's no category \"{}\" to add to", name));
+                }
+                let repeated = dialog.rows[..index]
+                    .iter()
+                    .any(|(_, other, merge)| !merge && other.trim() == name);
+                self.category_name_problem(name)
+                    .or_else(|| repeated.then(|| format!("\"{}\" is used twice", name)))
+            })
+            .collect()
+    }
+
+    /// Starts extracting (or looking up) the archive's images on a background thread.
+    fn start_zip_import(&mut self) {
+        if let Some(reason) = self.operations.blocker(operations::OperationKind::Import) {
+            self.set_status(reason);
+            return;
+        }
+        let Some(dialog) = &self.zip_import else {
+            return;
+        };
+        if let Some(problem) = self.zip_row_problems(dialog).into_iter().flatten().next() {
+            self.set_status(problem);
+            return;
+        }
+        let Some(destination) = dialog.destination.clone() else {
+            return;
+        };
+        // Inside the library root the entries are written like the others; elsewhere by full path
+        let root = self
+            .image_data
+            .as_ref()
+            .and_then(|data| data.categories.values().flat_map(|category| &category.images).find_map(import::library_root));
+        let relative = root.as_ref().and_then(|root| {
+            let root_dir = std::fs::canonicalize(root).ok()?;
+            let destination = std::fs::canonicalize(&destination).ok()?;
+            let relative = destination.strip_prefix(root_dir).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        });
+        let (root, directory) = match (root, relative) {
+            (Some(root), Some(relative)) => (root, relative),
+            _ => (String::new(), portable::stored_path(&destination).replace('\\', "/")),
+        };
+        let plan = zip_import::ZipPlan {
+            archive: dialog.archive.clone(),
+            mode: dialog.mode,
+            destination,
+            root,
+            directory,
+            targets: dialog
+                .rows
+                .iter()
+                .map(|(folder, name, _)| zip_import::Target {
+                    folder: folder.folder.clone(),
+                    category: name.trim().to_string(),
+                })
+                .collect(),
+        };
+        let progress = std::sync::Arc::new(import::ImportProgress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("import_zip", move || zip_import::run(plan, worker_progress));
+        if let Some(dialog) = &mut self.zip_import {
+            dialog.running = Some((promise, progress));
+        }
+    }
+
+    fn zip_import_window(&mut self, ctx: &egui::Context) {
+        let finished = self
+            .zip_import
+            .as_ref()
+            .and_then(|dialog| dialog.running.as_ref())
+            .is_some_and(|(promise, _)| promise.ready().is_some());
+        if finished {
+            let Some(dialog) = self.zip_import.take() else {
+                return;
+            };
+            let result = dialog
+                .running
+                .and_then(|(promise, _)| promise.try_take().ok())
+                .unwrap_or_else(|| Err("the extraction thread stopped unexpectedly".to_string()));
+            let summary = match result {
+                Ok(summary) => summary,
+                Err(e) => {
+                    self.set_status(format!("ZIP import failed: {}", e));
+                    return;
+                }
+            };
+            let merged: Vec<&String> = dialog.rows.iter().filter(|(_, _, merge)| *merge).map(|(_, name, _)| name).collect();
+            let mut count = 0;
+            let mutations: Vec<manifest::Mutation> = summary
+                .categories
+                .into_iter()
+                .map(|(category, directory, images)| {
+                    count += images.len();
+                    if merged.iter().any(|name| name.trim() == category) {
+                        manifest::add_images(category, images)
+                    } else {
+                        manifest::create_category(category, directory, images)
+                    }
+                })
+                .collect();
+            let categories = mutations.len();
+            if !mutations.is_empty() {
+                // The library reloads once the manifest is saved
+                let manifest_path = self.settings.active_profile().manifest_path;
+                self.manifest_saver.submit(&manifest_path, manifest::batch(mutations));
+            }
+            let mut message = format!("Imported {} image(s) into {} categor(ies)", count, categories);
+            if summary.skipped > 0 {
+                message.push_str(&format!(", skipped {} non-image file(s)", summary.skipped));
+            }
+            if let Some(first) = summary.failed.first() {
+                message.push_str(&format!(", {} failed (e.g. {})", summary.failed.len(), first));
+            }
+            self.set_status(message);
+            return;
+        }
+
+        let problems = self
+            .zip_import
+            .as_ref()
+            .map(|dialog| self.zip_row_problems(dialog))
+            .unwrap_or_default();
+        let existing = self.manifest_categories();
+        let blocker = self.operations.blocker(operations::OperationKind::Import);
+        let Some(dialog) = &mut self.zip_import else {
+            return;
+        };
+        let mut open = true;
+        let mut start = false;
+        egui::Window::new("🗜 Import ZIP")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some((_, progress)) = &dialog.running {
+                    let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+                    let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+                    let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                    ui.label(match dialog.mode {
+                        zip_import::ZipMode::Extract => "Extracting…",
+                        zip_import::ZipMode::Reference => "Looking up the extracted files…",
+                    });
+                    ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {}", done, total)));
+                    let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+                    if ui.add_enabled(!cancelled, egui::Button::new("Cancel")).clicked() {
+                        progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+                    return;
+                }
+
+                ui.label(dialog.archive.display().to_string());
+                egui::Grid::new("zip_import_rows").num_columns(4).striped(true).show(ui, |ui| {
+                    ui.strong("Folder");
+                    ui.strong("Images");
+                    ui.strong("Category");
+                    ui.label("");
+                    ui.end_row();
+                    for ((folder, name, merge), problem) in dialog.rows.iter_mut().zip(&problems) {
+                        ui.label(&folder.folder);
+                        ui.label(format!("{} · {}", folder.images, format_bytes(folder.bytes)));
+                        let edit = ui.text_edit_singleline(name);
+                        if let Some(problem) = problem {
+                            edit.on_hover_text(problem.as_str());
+                        }
+                        let exists = existing.iter().any(|category| category == name.trim());
+                        ui.add_enabled(exists || *merge, egui::Checkbox::new(merge, "Add to existing"))
+                            .on_hover_text("Add the images to the category of this name instead of creating one");
+                        ui.end_row();
+                        if let Some(problem) = problem {
+                            ui.label("");
+                            ui.label("");
+                            ui.label(egui::RichText::new(problem).small().color(ui.visuals().error_fg_color));
+                            ui.end_row();
+                        }
+                    }
+                });
+                if dialog.skipped > 0 {
+                    ui.label(
+                        egui::RichText::new(format!("{} file(s) that aren't images will be skipped", dialog.skipped))
+                            .small()
+                            .weak(),
+                    );
+                }
+                ui.add_space(5.0);
+                for mode in zip_import::ZipMode::ALL {
+                    ui.radio_value(&mut dialog.mode, mode, mode.label());
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Folder:");
+                    match &dialog.destination {
+                        Some(destination) => ui.label(destination.display().to_string()),
+                        None => ui.label(egui::RichText::new("none chosen").weak()),
+                    };
+                    if ui.button("Choose…").clicked() {
+                        if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                            dialog.destination = Some(folder);
+                        }
+                    }
+                });
+                ui.label(
+                    egui::RichText::new(match dialog.mode {
+                        zip_import::ZipMode::Extract => {
+                            "Each archive folder becomes a subfolder of it; name clashes get a \" (1)\" suffix."
+                        }
+                        zip_import::ZipMode::Reference => "Files missing from the folder are reported and left out.",
+                    })
+                    .small()
+                    .weak(),
+                );
+                ui.add_space(5.0);
+                let reason = if dialog.destination.is_none() {
+                    Some("Choose a folder")
+                } else if problems.iter().any(Option::is_some) {
+                    Some("Fix the category names first")
+                } else {
+                    blocker.as_deref()
+                };
+                if ui
+                    .add_enabled(reason.is_none(), egui::Button::new("🗜 Import"))
+                    .on_disabled_hover_text(reason.unwrap_or_default())
+                    .clicked()
+                {
+                    start = true;
+                }
+            });
+
+        if start {
+            self.start_zip_import();
+        } else if !open && dialog.running.is_none() {
+            self.zip_import = None;
+        }
+    }
+
+    fn open_results_category_dialog(&mut self) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        let images: Vec<(String, ImageInfo)> = (0..self.filtered_images.len())
+            .filter_map(|row| self.filtered_entry(row))
+            .map(|(category, image)| (self.manifest_category(&category).to_string(), image))
+            .collect();
+        if images.is_empty() {
+            self.set_status("No results to make a category from".to_string());
+            return;
+        }
+        self.results_category = Some(ResultsCategoryDialog {
+            name: self.search_query.trim().replace('/', "-"),
+            mode: import::ImportMode::Reference,
+            remove_from_original: false,
+            images,
+            running: None,
+        });
+    }
+
+    /// Why `name` can't be used for a new category, if it can't.
+    fn category_name_problem(&self, name: &str) -> Option<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Some("Enter a name".to_string());
+        }
+        if name.contains('/') || name.contains('\\') {
+            return Some("Names can't contain slashes".to_string());
+        }
+        if name == "All Categories" || name == new_images::CATEGORY {
+            return Some(format!("\"{}\" is reserved", name));
+        }
+        let taken = self
+            .image_data
+            .as_ref()
+            .is_some_and(|data| data.categories.contains_key(name))
+            || self.manifest_categories().iter().any(|existing| existing == name);
+        taken.then(|| format!("\"{}\" already exists", name))
+    }
+
+    /// References the results in place, or starts copying/moving them into the new category's folder.
+    fn start_results_category(&mut self) {
+        let kind = match self.results_category.as_ref().map(|dialog| dialog.mode) {
+            Some(import::ImportMode::Reference) => operations::OperationKind::ManifestSave,
+            Some(_) => operations::OperationKind::Import,
+            None => return,
+        };
+        if let Some(reason) = self.operations.blocker(kind) {
+            self.set_status(reason);
+            return;
+        }
+        let Some(dialog) = &self.results_category else {
+            return;
+        };
+        if let Some(problem) = self.category_name_problem(&dialog.name) {
+            self.set_status(problem);
+            return;
+        }
+        let name = dialog.name.trim().to_string();
+        let mut seen = std::collections::HashSet::new();
+        let unique: Vec<ImageInfo> = dialog
+            .images
+            .iter()
+            .filter(|(_, image)| seen.insert(image.full_path.clone()))
+            .map(|(_, image)| image.clone())
+            .collect();
+
+        if dialog.mode == import::ImportMode::Reference {
+            let removals = if dialog.remove_from_original {
+                dialog.images.clone()
+            } else {
+                Vec::new()
+            };
+            self.results_category = None;
+            // In-place entries keep their paths; the folder is only used by later imports
+            self.create_results_category(name.clone(), name, unique, removals);
+            return;
+        }
+
+        let Some(root) = unique.iter().find_map(import::library_root) else {
+            self.set_status("Can't tell where this library's files live".to_string());
+            return;
+        };
+        let files: Vec<std::path::PathBuf> = unique.iter().map(|image| image.full_path.clone().into()).collect();
+        let mode = dialog.mode;
+        let progress = std::sync::Arc::new(import::ImportProgress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("category_from_results", move || {
+            import::run_files(files, root, name, mode, worker_progress)
+        });
+        if let Some(dialog) = &mut self.results_category {
+            dialog.running = Some((promise, progress));
+        }
+    }
+
+    /// Queues the new category (and any removals) as one manifest edit, shows it
+    /// right away and switches the filter to it.
+    fn create_results_category(
+        &mut self,
+        name: String,
+        directory: String,
+        images: Vec<ImageInfo>,
+        removals: Vec<(String, ImageInfo)>,
+    ) {
+        let mut mutations = vec![manifest::create_category(name.clone(), directory.clone(), images.clone())];
+        mutations.extend(
+            removals
+                .iter()
+                .map(|(category, image)| manifest::remove_image(image.full_path.clone(), vec![category.clone()])),
+        );
+        let manifest_path = self.settings.active_profile().manifest_path;
+        self.manifest_saver.submit(&manifest_path, manifest::batch(mutations));
+
+        // The reload after the save replaces this; until then the category is already usable
+        let count = images.len();
+        if let Some(data) = &mut self.image_data {
+            for (category, image) in &removals {
+                if let Some(category) = data.categories.get_mut(category) {
+                    category.images.retain(|entry| entry.full_path != image.full_path);
+                    category.count = category.images.len() as u32;
+                }
+            }
+            data.categories.insert(
+                name.clone(),
+                Category {
+                    directory,
+                    images,
+                    count: count as u32,
+                },
+            );
+            self.search_index = search::SearchIndex::build(data, &self.source_classifier);
+        }
+        self.selected_category = name.clone();
+        self.show_all_categories = false;
+        self.search_query.clear();
+        self.update_filtered_images();
+        self.set_status(format!("Created {} with {} image(s)", name, count));
+    }
+
+    fn results_category_window(&mut self, ctx: &egui::Context) {
+        let finished = self
+            .results_category
+            .as_ref()
+            .and_then(|dialog| dialog.running.as_ref())
+            .is_some_and(|(promise, _)| promise.ready().is_some());
+        if finished {
+            let Some(dialog) = self.results_category.take() else {
+                return;
+            };
+            let result = dialog
+                .running
+                .and_then(|(promise, _)| promise.try_take().ok())
+                .unwrap_or_else(|| Err("the copy thread stopped unexpectedly".to_string()));
+            match result {
+                Ok(summary) if !summary.imported.is_empty() => {
+                    // Moved files no longer exist where their old entries point
+                    let removals = if dialog.mode == import::ImportMode::Move {
+                        let moved: std::collections::HashSet<&std::path::Path> =
+                            summary.sources.iter().map(|source| source.as_path()).collect();
+                        dialog
+                            .images
+                            .iter()
+                            .filter(|(_, image)| moved.contains(std::path::Path::new(&image.full_path)))
+                            .cloned()
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    let name = dialog.name.trim().to_string();
+                    self.create_results_category(name.clone(), name, summary.imported, removals);
+                    if let Some(first) = summary.failed.first() {
+                        self.set_status(format!(
+                            "Created {}; {} file(s) failed (e.g. {})",
+                            dialog.name.trim(),
+                            summary.failed.len(),
+                            first
+                        ));
+                    }
+                }
+                Ok(summary) => match summary.failed.first() {
+                    Some(first) => self.set_status(format!("No files were copied (e.g. {})", first)),
+                    None => self.set_status("Cancelled; no category was created".to_string()),
+                },
+                Err(e) => self.set_status(format!("Couldn't create the category: {}", e)),
+            }
+            return;
+        }
+
+        let problem = self
+            .results_category
+            .as_ref()
+            .and_then(|dialog| self.category_name_problem(&dialog.name));
+        let blocker = self.results_category.as_ref().and_then(|dialog| {
+            self.operations.blocker(match dialog.mode {
+                import::ImportMode::Reference => operations::OperationKind::ManifestSave,
+                _ => operations::OperationKind::Import,
+            })
+        });
+        let Some(dialog) = &mut self.results_category else {
+            return;
+        };
+        let mut open = true;
+        let mut create = false;
+        egui::Window::new("➕ Create category from results")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some((_, progress)) = &dialog.running {
+                    let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+                    let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+                    let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                    ui.label(format!("Filling {}…", dialog.name.trim()));
+                    ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {}", done, total)));
+                    let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+                    if ui.add_enabled(!cancelled, egui::Button::new("Cancel")).clicked() {
+                        progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+                    return;
+                }
+
+                ui.label(format!("{} result(s)", dialog.images.len()));
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                });
+                for mode in import::ImportMode::ALL {
+                    ui.radio_value(&mut dialog.mode, mode, mode.label());
+                }
+                ui.add_enabled(
+                    dialog.mode == import::ImportMode::Reference,
+                    egui::Checkbox::new(&mut dialog.remove_from_original, "Remove them from their current categories"),
+                )
+                .on_hover_text("Otherwise the images are listed in both");
+                if dialog.mode == import::ImportMode::Move {
+                    ui.label(
+                        egui::RichText::new("Moved files leave their current categories.")
+                            .small()
+                            .weak(),
+                    );
+                }
+                ui.add_space(5.0);
+                let reason = problem.as_ref().or(blocker.as_ref());
+                if ui
+                    .add_enabled(reason.is_none(), egui::Button::new("➕ Create"))
+                    .on_disabled_hover_text(reason.map(String::as_str).unwrap_or_default())
+                    .clicked()
+                {
+                    create = true;
+                }
+            });
+
+        if create {
+            self.start_results_category();
+        } else if !open && dialog.running.is_none() {
+            self.results_category = None;
+        }
+    }
+
+    /// Optimizes the selection, or else every image in the current category.
+    fn open_optimize_dialog(&mut self) {
+        if let Some(reason) = &self.read_only {
+            self.set_status(format!("Library is read-only: {}", reason));
+            return;
+        }
+        let (images, scope) = if !self.selection.is_empty() {
+            let images: Vec<ImageInfo> = self
+                .selection
+                .iter()
+                .filter_map(|path| self.find_image(path))
+                .map(|(_, image)| image)
+                .collect();
+            let scope = format!("{} selected image(s)", images.len());
+            (images, scope)
+        } else if self.show_all_categories {
+            self.set_status("Select images or pick a category to optimize".to_string());
+            return;
+        } else {
+            let images = self
+                .image_data
+                .as_ref()
+                .and_then(|data| data.categories.get(&self.selected_category))
+                .map(|category| category.images.clone())
+                .unwrap_or_default();
+            (images, self.selected_category.clone())
+        };
+        if images.is_empty() {
+            self.set_status("Nothing to optimize".to_string());
+            return;
+        }
+        self.optimize_dialog = Some(OptimizeDialog {
+            images,
+            scope,
+            conversion: optimize::Conversion::None,
+            jpeg_quality: 85,
+            reports: None,
+            running: None,
+        });
+    }
+
+    fn start_optimize(&mut self, apply: bool) {
+        if apply {
+            if let Some(reason) = self.operations.blocker(operations::OperationKind::Optimize) {
+                self.set_status(reason);
+                return;
+            }
+        }
+        let originals = self.settings.optimize_originals;
+        let Some(dialog) = &mut self.optimize_dialog else {
+            return;
+        };
+        let options = optimize::OptimizeOptions {
+            conversion: dialog.conversion,
+            jpeg_quality: dialog.jpeg_quality,
+            originals,
+        };
+        // Applying only touches what the dry run found would shrink
+        let images = match (&dialog.reports, apply) {
+            (Some(reports), true) => reports
+                .iter()
+                .filter(|report| matches!(report.outcome, optimize::Outcome::Smaller(_)))
+                .map(|report| report.image.clone())
+                .collect(),
+            _ => dialog.images.clone(),
+        };
+        let progress = std::sync::Arc::new(optimize::Progress::default());
+        let worker_progress = progress.clone();
+        let promise = Promise::spawn_thread("optimize", move || optimize::run(images, options, apply, worker_progress));
+        dialog.running = Some((promise, progress, apply));
+    }
+
+    /// Records the new names and sizes in the manifest and in the loaded library.
+    fn finish_optimize(&mut self, reports: &[optimize::FileReport], cancelled: bool) {
+        let replaced: Vec<(&ImageInfo, &ImageInfo)> = reports
+            .iter()
+            .filter_map(|report| match &report.outcome {
+                optimize::Outcome::Smaller(replacement) => Some((&report.image, replacement)),
+                _ => None,
+            })
+            .collect();
+        let saved: u64 = reports.iter().map(optimize::FileReport::saved).sum();
+        let grew = reports
+            .iter()
+            .filter(|report| matches!(report.outcome, optimize::Outcome::WouldGrow(_)))
+            .count();
+        let failed: Vec<&str> = reports
+            .iter()
+            .filter_map(|report| match &report.outcome {
+                optimize::Outcome::Failed(e) => Some(e.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if !replaced.is_empty() {
+            let mutations = replaced
+                .iter()
+                .map(|(image, replacement)| manifest::replace_image(image.full_path.clone(), (*replacement).clone()))
+                .collect();
+            let manifest_path = self.settings.active_profile().manifest_path;
+            self.manifest_saver.submit(&manifest_path, manifest::batch(mutations));
+            if let Some(data) = &mut self.image_data {
+                for category in data.categories.values_mut() {
+                    for entry in &mut category.images {
+                        if let Some((_, replacement)) = replaced.iter().find(|(image, _)| image.full_path == entry.full_path) {
+                            *entry = (*replacement).clone();
+                        }
+                    }
+                }
+                self.search_index = search::SearchIndex::build(data, &self.source_classifier);
+            }
+            self.update_filtered_images();
+        }
+
+        let mut status = format!(
+            "{}Optimized {} file(s), saved {}",
+            if cancelled { "Cancelled. " } else { "" },
+            replaced.len(),
+            format_bytes(saved)
+        );
+        if grew > 0 || !failed.is_empty() {
+            status.push_str(&format!(" ({} would grow, {} failed)", grew, failed.len()));
+        }
+        if let Some(first) = failed.first() {
+            status.push_str(&format!(": {}", first));
+        }
+        self.set_status(status);
+    }
+
+    fn optimize_dialog_window(&mut self, ctx: &egui::Context) {
+        let finished = self
+            .optimize_dialog
+            .as_ref()
+            .and_then(|dialog| dialog.running.as_ref())
+            .is_some_and(|(promise, _, _)| promise.ready().is_some());
+        if finished {
+            let Some(dialog) = &mut self.optimize_dialog else {
+                return;
+            };
+            let Some((promise, progress, apply)) = dialog.running.take() else {
+                return;
+            };
+            let reports = promise.try_take().unwrap_or_default();
+            let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+            if apply {
+                self.optimize_dialog = None;
+                self.finish_optimize(&reports, cancelled);
+            } else if cancelled {
+                self.set_status("Dry run cancelled".to_string());
+            } else {
+                dialog.reports = Some(reports);
+            }
+            return;
+        }
+
+        let blocker = self.operations.blocker(operations::OperationKind::Optimize);
+        let mut originals = self.settings.optimize_originals;
+        let Some(dialog) = &mut self.optimize_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut start = None;
+        egui::Window::new("🗜 Optimize images")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if let Some((_, progress, apply)) = &dialog.running {
+                    let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+                    let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+                    let fraction = if total == 0 { 0.0 } else { done as f32 / total as f32 };
+                    ui.label(if *apply { "Writing optimized files…" } else { "Measuring…" });
+                    ui.add(egui::ProgressBar::new(fraction).text(format!("{} / {}", done, total)));
+                    let cancelled = progress.cancelled.load(std::sync::atomic::Ordering::Relaxed);
+                    if ui.add_enabled(!cancelled, egui::Button::new("Cancel")).clicked() {
+                        progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    self.frame_guard.repaint_after(ctx, Duration::from_millis(100));
+                    return;
+                }
+
+                ui.label(format!("{} — {} image(s)", dialog.scope, dialog.images.len()));
+                let before = (dialog.conversion, dialog.jpeg_quality);
+                for conversion in optimize::Conversion::ALL {
+                    ui.radio_value(&mut dialog.conversion, conversion, conversion.label());
+                }
+                ui.add_enabled(
+                    dialog.conversion == optimize::Conversion::Jpeg,
+                    egui::Slider::new(&mut dialog.jpeg_quality, 50..=100).text("JPEG quality"),
+                );
+                // A different encoding invalidates the measurements
+                if before != (dialog.conversion, dialog.jpeg_quality) {
+                    dialog.reports = None;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Originals:");
+                    egui::ComboBox::from_id_source("optimize_originals")
+                        .selected_text(originals.label())
+                        .show_ui(ui, |ui| {
+                            for option in settings::Originals::ALL {
+                                ui.selectable_value(&mut originals, option, option.label());
+                            }
+                        });
+                });
+
+                if let Some(reports) = &dialog.reports {
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for report in reports {
+                            let outcome = match &report.outcome {
+                                optimize::Outcome::Smaller(replacement) => format!(
+                                    "{} → {} ({})",
+                                    format_bytes(report.image.size),
+                                    format_bytes(replacement.size),
+                                    replacement.filename
+                                ),
+                                optimize::Outcome::WouldGrow(size) => {
+                                    format!("{} → {}, skipped", format_bytes(report.image.size), format_bytes(*size))
+                                }
+                                optimize::Outcome::Skipped(reason) => format!("skipped: {}", reason),
+                                optimize::Outcome::Failed(e) => format!("failed: {}", e),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.label(&report.image.filename);
+                                ui.label(egui::RichText::new(outcome).weak());
+                            });
+                        }
+                    });
+                    let shrinking = reports
+                        .iter()
+                        .filter(|report| matches!(report.outcome, optimize::Outcome::Smaller(_)))
+                        .count();
+                    let saved: u64 = reports.iter().map(optimize::FileReport::saved).sum();
+                    ui.strong(format!("{} file(s) would shrink, saving {}", shrinking, format_bytes(saved)));
+                }
+
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("🔍 Dry run").clicked() {
+                        start = Some(false);
+                    }
+                    let shrinking = dialog.reports.as_ref().is_some_and(|reports| {
+                        reports
+                            .iter()
+                            .any(|report| matches!(report.outcome, optimize::Outcome::Smaller(_)))
+                    });
+                    let reason = if dialog.reports.is_none() {
+                        Some("Do a dry run first".to_string())
+                    } else if !shrinking {
+                        Some("No file would get smaller".to_string())
+                    } else {
+                        blocker.clone()
+                    };
+                    if ui
+                        .add_enabled(reason.is_none(), egui::Button::new("🗜 Apply"))
+                        .on_disabled_hover_text(reason.unwrap_or_default())
+                        .clicked()
+                    {
+                        start = Some(true);
+                    }
+                });
+            });
+
+        let running = dialog.running.is_some();
+        if originals != self.settings.optimize_originals {
+            self.settings.optimize_originals = originals;
+            self.persist_settings();
+        }
+        if let Some(apply) = start {
+            self.start_optimize(apply);
+        } else if !open && !running {
+            self.optimize_dialog = None;
+        }
+    }
+
+    fn grouping_dialog_window(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.grouping_dialog else {
+            return;
+        };
+        let images = self
+            .image_data
+            .as_ref()
+            .and_then(|d| d.categories.get(&dialog.category))
+            .map(|c| c.images.as_slice())
+            .unwrap_or_default();
+        let mut open = true;
+        let mut apply = None;
+
+        egui::Window::new(format!("🗂 Group \"{}\"", dialog.category))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let mut changed = false;
+                let prefix = grouping::GroupingRule::Prefix { delimiters: dialog.delimiters.clone() };
+                let pattern = grouping::GroupingRule::Pattern { regex: dialog.regex.clone() };
+                for choice in [prefix, pattern, grouping::GroupingRule::YearMonth] {
+                    let selected = std::mem::discriminant(&choice) == std::mem::discriminant(&dialog.rule);
+                    if ui.radio(selected, choice.label()).clicked() && !selected {
+                        dialog.rule = choice;
+                        changed = true;
+                    }
+                }
+
+                ui.add_space(5.0);
+                match &mut dialog.rule {
+                    grouping::GroupingRule::Prefix { delimiters } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Split at the first of:");
+                            if ui.add(egui::TextEdit::singleline(delimiters).desired_width(60.0)).changed() {
+                                dialog.delimiters = delimiters.clone();
+                                changed = true;
+                            }
+                        });
+                    }
+                    grouping::GroupingRule::Pattern { regex } => {
+                        ui.horizontal(|ui| {
+                            ui.label("Regex:");
+                            if ui.add(egui::TextEdit::singleline(regex).code_editor()).changed() {
+                                dialog.regex = regex.clone();
+                                changed = true;
+                            }
+                        });
+                        ui.label(egui::RichText::new("The (?P<category>...) capture becomes the group name.").small().weak());
+                    }
+                    grouping::GroupingRule::YearMonth => {
+                        ui.label(egui::RichText::new("Uses a date in the filename, or the file's modification time.").small().weak());
+                    }
+                }
+                if changed {
+                    dialog.refresh_preview(images);
+                }
+
+                ui.separator();
+                match &dialog.preview {
+                    Ok(groups) => {
+                        ui.label(format!("{} images → {} groups", images.len(), groups.len()));
+                        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                            egui::Grid::new("grouping_preview").num_columns(2).striped(true).show(ui, |ui| {
+                                for (name, count) in groups {
+                                    ui.label(format!("{}/{}", dialog.category, name));
+                                    ui.label(count.to_string());
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                        ui.add_space(5.0);
+                        if ui.add_enabled(!groups.is_empty(), egui::Button::new("✓ Apply grouping")).clicked() {
+                            apply = Some((dialog.category.clone(), dialog.rule.clone()));
+                        }
+                        ui.label(egui::RichText::new("The rule is saved in the manifest and reapplied on every load.").small().weak());
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                }
+            });
+
+        if let Some((category, rule)) = apply {
+            self.grouping_dialog = None;
+            self.set_category_grouping(&category, Some(&rule));
+        } else if !open {
+            self.grouping_dialog = None;
+        }
+    }
+
+    fn toggle_selection(&mut self, path: &str) {
+        if let Some(index) = self.selection.iter().position(|p| p == path) {
+            self.selection.remove(index);
+        } else {
+            self.selection.push(path.to_string());
+        }
+    }
+
+    /// Composites the selected images into one sheet and copies its pixels.
+    fn copy_selection_combined(&mut self) {
+        if self.selection.len() > encode::MAX_SHEET_IMAGES {
+            self.set_status(format!(
+                "Select at most {} images to combine ({} selected)",
+                encode::MAX_SHEET_IMAGES,
+                self.selection.len()
+            ));
+            return;
+        }
+        let mut images = Vec::with_capacity(self.selection.len());
+        for path in &self.selection {
+            match formats::open(path) {
+                Ok(img) => images.push(img),
+                Err(e) => {
+                    self.set_status(format!("Failed to combine: {}: {}", path, e));
+                    return;
+                }
+            }
+        }
+
+        let combine = &self.settings.combine;
+        let sheet = match encode::compose_sheet(images, combine.padding, combine.background, Some(combine.max_dimension)) {
+            Ok(sheet) => sheet,
+            Err(e) => {
+                self.set_status(e);
+                return;
+            }
+        };
+        let result = self.clipboard.set_image(arboard::ImageData {
+            width: sheet.width() as usize,
+            height: sheet.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(sheet.as_raw()),
+        });
+        if result.is_ok() {
+            self.on_clipboard = None;
+        }
+        self.set_status(match result {
+            Ok(()) => format!(
+                "Copied {} images as one {}×{} image",
+                self.selection.len(),
+                sheet.width(),
+                sheet.height()
+            ),
+            Err(e) => format!("Failed to copy to clipboard: {}", e),
+        });
+    }
+
+    /// Recent queries under the search box: opens on focus or Down while the box is empty.
+    fn search_history_popup(&mut self, ui: &mut egui::Ui, search_box: &egui::Response) {
+        let popup_id = search_history_popup_id();
+        let empty = self.search_query.is_empty();
+        if search_box.has_focus()
+            && empty
+            && !self.search_history.is_empty()
+            && (search_box.gained_focus() || ui.input(|i| i.key_pressed(egui::Key::ArrowDown)))
+        {
+            ui.memory_mut(|m| m.open_popup(popup_id));
+        }
+        if !empty && ui.memory(|m| m.is_popup_open(popup_id)) {
+            ui.memory_mut(|m| m.close_popup());
+        }
+
+        let mut chosen = None;
+        egui::popup_below_widget(ui, popup_id, search_box, |ui| {
+            ui.set_min_width(search_box.rect.width());
+            ui.label(egui::RichText::new("Recent searches").small().weak());
+            for query in self.search_history.queries() {
+                if ui.selectable_label(false, format!("🕘 {}", query)).clicked() {
+                    chosen = Some(query.clone());
+                }
+            }
+        });
+        if let Some(query) = chosen {
+            self.search_query = query;
+            self.update_filtered_images();
+            ui.memory_mut(|m| m.close_popup());
+        }
+    }
+
+    /// A Copy button's label: feedback right after a copy, the fallback while
+    /// the clipboard is unavailable.
+    fn copy_label(&self, path: &str, label: &'static str) -> &'static str {
+        if self.recently_copied(path) {
+            "✓ Copied"
+        } else if self.clipboard.unavailable() {
+            self.settings.clipboard_fallback.button_label()
+        } else {
+            label
+        }
+    }
+
+    fn is_on_clipboard(&self, path: &str) -> bool {
+        self.on_clipboard.as_ref().is_some_and(|marker| marker.path == path)
+    }
+
+    /// Drops the "on clipboard" marker if another app replaced the clipboard while we were unfocused.
+    fn check_clipboard_on_focus(&mut self, ctx: &egui::Context) {
+        let focused = ctx.input(|i| i.focused);
+        if focused && !self.window_focused {
+            if self.on_clipboard.as_ref().is_some_and(|marker| !marker.still_on_clipboard()) {
+                self.on_clipboard = None;
+            }
+            if self.clipboard.probe() {
+                self.set_status("The clipboard is available again");
+            }
+        }
+        self.window_focused = focused;
+    }
+
+    /// Starts the copy feedback and commits the active query to the search history.
+    fn mark_copied(&mut self, path: &str) {
+        self.last_copied = Some((path.to_string(), Instant::now()));
+        self.on_clipboard = Some(clipboard_marker::ClipboardMarker::capture(path));
+        let query = self.search_query.clone();
+        self.record_search(&query);
+        self.record_usage(path);
+    }
+
+    /// Logs a copy from `path`'s category, unless usage collection is off.
+    fn record_usage(&mut self, path: &str) {
+        if !self.settings.record_usage {
+            return;
+        }
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        let category = self.search_index.entries.iter().find_map(|entry| {
+            let image = data.categories.get(&entry.category)?.images.get(entry.image_index)?;
+            (image.full_path == path).then(|| entry.category.clone())
+        });
+        if let Some(category) = category {
+            self.usage.record(&category);
+            if let Err(e) = self.usage.save(&mut self.user_store) {
+                self.set_status(format!("Failed to save usage data: {}", e));
+            }
+        }
+    }
+
+    /// Categories ranked by copies over the last 30 days, and bars per weekday and hour.
+    fn usage_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_usage;
+        let mut clear = false;
+        egui::Window::new("📊 Usage insights")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Copies over the last {} days. Stored only on this computer.",
+                        usage::WINDOW_DAYS
+                    ))
+                    .weak(),
+                );
+                if !self.settings.record_usage {
+                    ui.label(
+                        egui::RichText::new("Collection is off (Settings → Privacy).")
+                            .small()
+                            .color(ui.visuals().warn_fg_color),
+                    );
+                }
+                ui.add_space(5.0);
+                if self.usage.is_empty() {
+                    ui.label("Nothing recorded yet.");
+                    return;
+                }
+
+                let bar = |ui: &mut egui::Ui, label: &str, count: usize, max: usize| {
+                    ui.horizontal(|ui| {
+                        ui.add_sized([90.0, 16.0], egui::Label::new(label).truncate(true));
+                        let width = 220.0 * count as f32 / max.max(1) as f32;
+                        let (rect, _) = ui.allocate_exact_size(egui::vec2(width.max(1.0), 12.0), egui::Sense::hover());
+                        ui.painter().rect_filled(rect, 2.0, ui.visuals().selection.bg_fill);
+                        ui.label(count.to_string());
+                    });
+                };
+
+                ui.heading("Categories");
+                let ranked = self.usage.by_category();
+                let max = ranked.first().map_or(0, |(_, count)| *count);
+                egui::ScrollArea::vertical().id_source("usage_categories").max_height(200.0).show(ui, |ui| {
+                    for (category, count) in &ranked {
+                        bar(ui, category, *count, max);
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.heading("Weekdays");
+                let weekdays = self.usage.by_weekday();
+                let max = weekdays.iter().copied().max().unwrap_or(0);
+                for (day, count) in usage::WEEKDAYS.iter().zip(weekdays) {
+                    bar(ui, day, count, max);
+                }
+
+                ui.add_space(10.0);
+                ui.heading("Hours");
+                let hours = self.usage.by_hour();
+                let max = hours.iter().copied().max().unwrap_or(0);
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(24.0 * 14.0, 60.0), egui::Sense::hover());
+                for (hour, count) in hours.into_iter().enumerate() {
+                    let height = 60.0 * count as f32 / max.max(1) as f32;
+                    let left = rect.left() + hour as f32 * 14.0;
+                    let column = egui::Rect::from_min_max(
+                        egui::pos2(left + 1.0, rect.bottom() - height),
+                        egui::pos2(left + 13.0, rect.bottom()),
+                    );
+                    ui.painter().rect_filled(column, 1.0, ui.visuals().selection.bg_fill);
+                }
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("0:00").small().weak());
+                    ui.add_space(24.0 * 14.0 - 80.0);
+                    ui.label(egui::RichText::new("23:00").small().weak());
+                });
+
+                ui.add_space(10.0);
+                if ui.button("🗑 Clear usage data").clicked() {
+                    clear = true;
+                }
+            });
+        self.show_usage = open;
+        if clear {
+            self.clear_usage();
+        }
+    }
+
+    fn clear_usage(&mut self) {
+        self.usage.clear();
+        match self.usage.save(&mut self.user_store) {
+            Ok(()) => self.set_status("Usage data cleared"),
+            Err(e) => self.set_status(format!("Failed to save usage data: {}", e)),
+        }
+    }
+
+    fn set_status(&mut self, text: impl Into<String>) {
+        self.status_message = status::StatusMessage::new(text);
+    }
+
+    fn set_status_with(&mut self, text: impl Into<String>, action: status::StatusAction) {
+        self.status_message = status::StatusMessage::new(text).with_action(action);
+    }
+
+    /// The status message, clickable when it carries an action.
+    fn status_label(&mut self, ui: &mut egui::Ui) {
+        let Some(action) = self.status_message.action.clone() else {
+            ui.label(&self.status_message.text);
+            return;
+        };
+        let label = ui
+            .add(egui::Label::new(egui::RichText::new(&self.status_message.text).underline()).sense(egui::Sense::click()))
+            .on_hover_text(action.hint());
+        if label.clicked() {
+            self.run_status_action(action);
+        }
+    }
+
+    fn run_status_action(&mut self, action: status::StatusAction) {
+        match action {
+            status::StatusAction::RevealImage(path) => {
+                let found = (0..self.filtered_images.len())
+                    .find(|&row| self.filtered_entry(row).is_some_and(|(_, image)| image.full_path == path));
+                match found.and_then(|row| Some((row, self.filtered_entry(row)?))) {
+                    Some((row, entry)) => {
+                        self.highlighted = Some(entry);
+                        self.results_scroll.reveal(row, self.animations_enabled());
+                    }
+                    None => self.set_status("That image isn't in the current results"),
+                }
+            }
+            status::StatusAction::ShowManifestError { path, line, column, message } => {
+                let excerpt = status::file_context(&path, line, column, MANIFEST_ERROR_CONTEXT);
+                self.manifest_error = Some(ManifestErrorView {
+                    path,
+                    line,
+                    column,
+                    message,
+                    excerpt,
+                });
+            }
+            status::StatusAction::ShowDuplicates => self.show_duplicate_prompt = !self.duplicates.is_empty(),
+            status::StatusAction::RetryUserDataSave => self.user_store.flush(),
+            status::StatusAction::ChooseThumbnailCacheDir => self.choose_thumbnail_cache_dir(),
+            status::StatusAction::OpenReleasePage(url) => {
+                if let Err(e) = launch::open_url(&url) {
+                    self.set_status(e);
+                }
+            }
+        }
+    }
+
+    fn manifest_error_window(&mut self, ctx: &egui::Context) {
+        let Some(view) = &self.manifest_error else {
+            return;
+        };
+        let mut open = true;
+        let mut open_editor = false;
+        let mut copy_report = false;
+        egui::Window::new("Manifest error")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label(format!("{}, line {}, column {}", view.path, view.line, view.column));
+                ui.add(egui::Label::new(egui::RichText::new(&view.message).color(ui.visuals().error_fg_color)).wrap(true));
+                ui.add_space(5.0);
+                match &view.excerpt {
+                    Ok(excerpt) => {
+                        egui::ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                            for (number, text) in &excerpt.lines {
+                                let row = egui::RichText::new(format!("{:>6}  {}", number, text)).monospace();
+                                if *number == view.line {
+                                    ui.label(row.color(ui.visuals().error_fg_color).strong());
+                                    let caret = format!("{:>6}  {}^", "", " ".repeat(excerpt.caret));
+                                    ui.label(egui::RichText::new(caret).monospace().color(ui.visuals().error_fg_color));
+                                } else {
+                                    ui.label(row);
+                                }
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    open_editor = ui
+                        .button(format!("📝 Open at line {}", view.line))
+                        .on_hover_text("Opens $VISUAL or $EDITOR at the line; without either, the default app at the top")
+                        .clicked();
+                    copy_report = ui.button("📋 Copy error report").clicked();
+                });
+            });
+        let (path, line) = (view.path.clone(), view.line);
+        let report = copy_report.then(|| view.report());
+        if open_editor {
+            match launch::open_at_line(Path::new(&path), line) {
+                Ok(true) => self.set_status(format!("Opened {} at line {}", path, line)),
+                Ok(false) => self.set_status(format!("Opened {}; no $EDITOR is set, so go to line {} yourself", path, line)),
+                Err(e) => self.set_status(e),
+            }
+        }
+        if let Some(report) = report {
+            self.copy_text_to_clipboard(report, "error report");
+        }
+        if !open {
+            self.manifest_error = None;
+        }
+    }
+
+    fn record_search(&mut self, query: &str) {
+        if self.search_history.record(query) {
+            if let Err(e) = self.search_history.save() {
+                self.set_status(format!("Failed to save search history: {}", e));
+            }
+        }
+    }
+
+    /// Returns true when the text made it onto the clipboard.
+    fn copy_text_to_clipboard(&mut self, text: String, what: &str) -> bool {
+        if self.clipboard.unavailable() {
+            return match clipboard_access::copy_text_with_tool(&text) {
+                Ok(tool) => {
+                    self.set_status(format!("Copied {} with {}", what, tool));
+                    self.on_clipboard = None;
+                    true
+                }
+                Err(e) => {
+                    self.set_status(format!("Failed to copy {}: clipboard unavailable and {}", what, e));
+                    false
+                }
+            };
+        }
+        match self.clipboard.set_text(text) {
+            Ok(()) => {
+                self.set_status(format!("Copied {} to clipboard", what));
+                self.on_clipboard = None;
+                true
+            }
+            Err(e) => {
+                self.set_status(format!("Failed to copy {}: {}", what, e));
+                false
+            }
+        }
+    }
+
+    /// The main Copy action: uses the category's copy behavior, or the global default.
+    fn copy_image(&mut self, category: &str, image_info: &ImageInfo) {
+        if self.clipboard.unavailable() {
+            self.copy_without_clipboard(image_info);
+            return;
+        }
+        let behavior = self.settings.copy_behavior(category);
+        match behavior.mode {
+            settings::CopyMode::Pixels if self.is_text_entry(image_info) => self.copy_snippet_text(image_info),
+            settings::CopyMode::Pixels => self.copy_image_to_clipboard(image_info, behavior.max_dimension),
+            settings::CopyMode::File => self.copy_file_to_clipboard(image_info),
+            settings::CopyMode::Path => {
+                if self.copy_text_to_clipboard(image_info.full_path.clone(), "path") {
+                    self.mark_copied(&image_info.full_path);
+                }
+            }
+        }
+    }
+
+    /// Copies pixels, asking first when the copy would be very large.
+    fn copy_image_to_clipboard(&mut self, image_info: &ImageInfo, max_dimension: Option<u32>) {
+        if let Some(size) = self.oversized_copy(image_info, max_dimension) {
+            self.large_copy_confirm = Some(LargeCopyConfirm {
+                image: image_info.clone(),
+                size,
+                always_resize: false,
+            });
+            return;
+        }
+        self.copy_pixels(image_info, max_dimension, orientation::Orientation::default());
+    }
+
+    /// The image's size if copying it at `max_dimension` would exceed the
+    /// warning threshold. Reads the cache or the file header, never the pixels,
+    /// so the warning shows instantly.
+    fn oversized_copy(&mut self, image_info: &ImageInfo, max_dimension: Option<u32>) -> Option<(u32, u32)> {
+        let limit = self.settings.large_copy_warning_mp;
+        if limit <= 0.0 {
+            return None;
+        }
+        let (width, height) = match self.dimensions.get(&image_info.full_path) {
+            Some(size) if size != (0, 0) => size,
+            _ => {
+                let size = image::image_dimensions(&image_info.full_path).ok()?;
+                self.dimensions.insert(&image_info.full_path, size);
+                size
+            }
+        };
+        let longest = width.max(height);
+        let scale = match max_dimension {
+            Some(max) if longest > max => max as f32 / longest as f32,
+            _ => 1.0,
+        };
+        let copied = ((width as f32 * scale) as u32, (height as f32 * scale) as u32);
+        (dimensions::megapixels(copied.0, copied.1) > limit).then_some((width, height))
+    }
+
+    /// Rotate and flip buttons under a detail image, plus saving the result once it differs.
+    /// What to draw behind an image: a hand-picked color, else what the setting and its luminance say.
+    fn backdrop_for(&self, path: &str) -> Option<backdrop::Backdrop> {
+        if let Some(color) = self.backdrop_overrides.get(path) {
+            return Some(backdrop::Backdrop::Solid(*color));
+        }
+        backdrop::Backdrop::choose(
+            self.settings.preview_backdrop,
+            self.settings.backdrop_color,
+            self.luminance.get(path),
+            self.settings.dark_mode,
+        )
+    }
+
+    /// Color picker overriding the automatic backdrop for one image.
+    fn backdrop_controls(&mut self, ui: &mut egui::Ui, path: &str) {
+        ui.horizontal(|ui| {
+            ui.label("Backdrop:");
+            let automatic = match self.backdrop_for(path) {
+                Some(backdrop::Backdrop::Solid(color)) => color,
+                Some(backdrop::Backdrop::Checkerboard { light: true }) => egui::Color32::from_gray(230),
+                Some(backdrop::Backdrop::Checkerboard { light: false }) => egui::Color32::from_gray(50),
+                None => ui.visuals().window_fill,
+            };
+            let mut color = self.backdrop_overrides.get(path).copied().unwrap_or(automatic);
+            if ui
+                .color_edit_button_srgba(&mut color)
+                .on_hover_text("Pick a color to show behind this image")
+                .changed()
+            {
+                self.backdrop_overrides.insert(path.to_string(), color);
+            }
+            if self.backdrop_overrides.contains_key(path) && ui.small_button("↺ Auto").clicked() {
+                self.backdrop_overrides.remove(path);
+            }
+        });
+    }
+
+    fn orientation_controls(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: u64,
+        image_info: &ImageInfo,
+        orientation: orientation::Orientation,
+    ) {
+        let mut changed = None;
+        ui.horizontal(|ui| {
+            if ui.button("⟲").on_hover_text("Rotate left").clicked() {
+                changed = Some(orientation.rotate_left());
+            }
+            if ui.button("⟳").on_hover_text("Rotate right").clicked() {
+                changed = Some(orientation.rotate_right());
+            }
+            if ui.button("⇔").on_hover_text("Flip horizontally").clicked() {
+                changed = Some(orientation.flip_horizontal());
+            }
+            if orientation.is_identity() {
+                return;
+            }
+            if ui.button("↺ Reset").clicked() {
+                changed = Some(orientation::Orientation::default());
+            }
+            if ui.button("💾 Save rotated copy as…").clicked() {
+                self.save_oriented_copy(image_info, orientation);
+            }
+            let overwritable = matches!(
+                image::ImageFormat::from_path(&image_info.full_path),
+                Ok(image::ImageFormat::Png | image::ImageFormat::Jpeg)
+            );
+            let button = egui::Button::new("Overwrite original…");
+            let response = if overwritable {
+                self.read_only_action(ui, button)
+            } else {
+                ui.add_enabled(false, button)
+                    .on_disabled_hover_text("Only PNG and JPEG files can be overwritten")
+            };
+            if response.clicked() {
+                self.overwrite_confirm = Some((id, image_info.clone(), orientation));
+            }
+        });
+        if let Some(orientation) = changed {
+            if let Some(view) = self.detail_windows.iter_mut().find(|view| view.id == id) {
+                view.orientation = orientation;
+            }
+        }
+    }
+
+    /// Writes the rotated image to a new file the user picks, in the background.
+    fn save_oriented_copy(&mut self, image_info: &ImageInfo, orientation: orientation::Orientation) {
+        let format = match image::ImageFormat::from_path(&image_info.full_path) {
+            Ok(image::ImageFormat::Jpeg) => image::ImageFormat::Jpeg,
+            _ => image::ImageFormat::Png,
+        };
+        let extension = if format == image::ImageFormat::Jpeg { "jpg" } else { "png" };
+        let stem = Path::new(&image_info.filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "image".to_string());
+        let Some(dest) = rfd::FileDialog::new()
+            .add_filter(extension.to_uppercase(), &[extension])
+            .set_file_name(format!("{}-rotated.{}", stem, extension))
+            .save_file()
+        else {
+            return;
+        };
+        let source = image_info.full_path.clone();
+        self.set_status(format!("Saving {}…", dest.display()));
+        self.export_promise = Some(Promise::spawn_thread("save_rotated", move || {
+            let img = decode_oriented(&source, orientation).map_err(|e| e.to_string())?;
+            encode::write_clean(&img, &dest, format).map(|()| dest)
+        }));
+    }
+
+    fn overwrite_confirm_window(&mut self, ctx: &egui::Context) {
+        let Some((_, image_info, _)) = &self.overwrite_confirm else {
+            return;
+        };
+        let mut decided = None;
+        egui::Window::new("Overwrite the original?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} will be replaced by the rotated image. This can't be undone, and metadata such as EXIF is not kept.",
+                    image_info.full_path
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Overwrite").clicked() {
+                        decided = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        decided = Some(false);
+                    }
+                });
+            });
+        let Some(overwrite) = decided else {
+            return;
+        };
+        let Some((id, image_info, orientation)) = self.overwrite_confirm.take() else {
+            return;
+        };
+        if !overwrite {
+            return;
+        }
+        let path = image_info.full_path.as_str();
+        let result = decode_oriented(path, orientation).map_err(|e| e.to_string()).and_then(|img| {
+            let format = image::ImageFormat::from_path(path).map_err(|e| e.to_string())?;
+            encode::write_clean(&img, Path::new(path), format).map(|()| (img.width(), img.height()))
+        });
+        match result {
+            Ok(size) => {
+                // A quarter turn swaps the stored width and height
+                self.dimensions.insert(path, size);
+                if image_info.width.is_some() && self.read_only.is_none() {
+                    let sizes = HashMap::from([(path.to_string(), size)]);
+                    self.manifest_saver
+                        .submit(&self.settings.active_profile().manifest_path, manifest::set_dimensions(sizes));
+                }
+                // Everything decoded from the old pixels is stale now
+                self.loaded_textures.remove(path);
+                self.texture_order.retain(|p| p != path);
+                self.hot_textures.remove(path);
+                self.previews.reload(path);
+                if let Some(view) = self.detail_windows.iter_mut().find(|view| view.id == id) {
+                    view.orientation = orientation::Orientation::default();
+                }
+                self.set_status(format!("Overwrote {}", image_info.filename));
+            }
+            Err(e) => self.set_status(format!("Failed to overwrite {}: {}", image_info.filename, e)),
+        }
+    }
+
+    fn large_copy_window(&mut self, ctx: &egui::Context) {
+        let Some(confirm) = &mut self.large_copy_confirm else {
+            return;
+        };
+        let (width, height) = confirm.size;
+        let mut decided = None;
+        egui::Window::new("Copy a very large image?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} is {} × {} ({:.0} MP). Pasting images this large can freeze the receiving app.",
+                    confirm.image.filename,
+                    width,
+                    height,
+                    dimensions::megapixels(width, height)
+                ));
+                ui.checkbox(&mut confirm.always_resize, "Don't ask again, always resize")
+                    .on_hover_text(format!("Sets the default copy size to max {} px", LARGE_COPY_RESIZE));
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!confirm.always_resize, egui::Button::new("Copy full size")).clicked() {
+                        decided = Some(Some(None));
+                    }
+                    if ui.button(format!("Copy resized to {}px", LARGE_COPY_RESIZE)).clicked() {
+                        decided = Some(Some(Some(LARGE_COPY_RESIZE)));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        decided = Some(None);
+                    }
+                });
+            });
+        let Some(choice) = decided else {
+            return;
+        };
+        let Some(confirm) = self.large_copy_confirm.take() else {
+            return;
+        };
+        if let Some(max_dimension) = choice {
+            if confirm.always_resize {
+                self.settings.default_copy.max_dimension = Some(LARGE_COPY_RESIZE);
+                self.persist_settings();
+            }
+            self.copy_pixels(&confirm.image, max_dimension, orientation::Orientation::default());
+        }
+    }
+
+    /// Copy while the clipboard can't be opened, per the fallback setting.
+    fn copy_without_clipboard(&mut self, image_info: &ImageInfo) {
+        match self.settings.clipboard_fallback {
+            settings::ClipboardFallback::PathWithTool => {
+                match clipboard_access::copy_text_with_tool(&image_info.full_path) {
+                    Ok(tool) => {
+                        self.set_status(format!("Copied the path of {} with {}", image_info.filename, tool));
+                        self.mark_copied(&image_info.full_path);
+                    }
+                    Err(e) => {
+                        self.set_status(format!("Clipboard unavailable, and copying the path failed: {}", e));
+                    }
+                }
+            }
+            settings::ClipboardFallback::SaveToPictures => {
+                match clipboard_access::save_to_pictures(Path::new(&image_info.full_path)) {
+                    Ok(destination) => {
+                        self.set_status(format!("Saved {}", destination.display()));
+                        let folder = clipboard_access::pictures_dir();
+                        if let Err(e) = open_in_file_manager(&folder) {
+                            self.set_status(format!("Saved {} but could not open the folder: {}", destination.display(), e));
+                        }
+                        let query = self.search_query.clone();
+                        self.record_search(&query);
+                    }
+                    Err(e) => {
+                        self.set_status(format!("Clipboard unavailable, and saving a copy failed: {}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Explains once per session why Copy stopped using the clipboard.
+    fn clipboard_banner(&mut self, ctx: &egui::Context) {
+        if !self.clipboard.unavailable() || self.clipboard_banner_dismissed {
+            return;
+        }
+        egui::TopBottomPanel::top("clipboard_banner").show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(egui::RichText::new("📋 Clipboard unavailable").strong().color(ui.visuals().warn_fg_color));
+                ui.label(format!(
+                    "The system clipboard could not be opened {} times in a row, which usually means a missing \
+                     clipboard portal or display connection. Copy now does this instead: {}.",
+                    clipboard_access::FAILURES_BEFORE_FALLBACK,
+                    self.settings.clipboard_fallback.label().to_lowercase()
+                ));
+                if ui.link("Change in settings").clicked() {
+                    self.show_settings = true;
+                    self.focus_copy_settings = true;
+                }
+                if ui.small_button("Dismiss").clicked() {
+                    self.clipboard_banner_dismissed = true;
+                }
+            });
+            ui.add_space(5.0);
+        });
+    }
+
+    /// "Saving…" while user data is waiting to be written, then "All changes saved".
+    fn user_data_state_ui(&mut self, ui: &mut egui::Ui) {
+        match self.user_store.state() {
+            store::SaveState::Saving => {
+                ui.label(egui::RichText::new("Saving…").small().weak())
+                    .on_hover_text("Favorites, pins and other changes are written a moment after the last edit");
+            }
+            store::SaveState::Saved => {
+                ui.label(egui::RichText::new("All changes saved").small().weak());
+            }
+            store::SaveState::Failed(e) => {
+                let retry = ui
+                    .add(
+                        egui::Label::new(
+                            egui::RichText::new("⚠ Changes not saved").small().color(ui.visuals().warn_fg_color),
+                        )
+                        .sense(egui::Sense::click()),
+                    )
+                    .on_hover_text(format!("{}\nClick to try again.", e));
+                if retry.clicked() {
+                    self.user_store.flush();
+                }
+            }
+        }
+        ui.separator();
+    }
+
+    /// Rebuilds the operation registry from the work actually in flight.
+    fn track_operations(&mut self) {
+        use operations::{Operation, OperationKind};
+        let mut active = Vec::new();
+        if self.library_load.is_some() {
+            active.push(Operation::new(OperationKind::LibraryLoad));
+        }
+        if self.manifest_saver.is_saving() {
+            active.push(Operation::new(OperationKind::ManifestSave));
+        }
+        let imports = [
+            self.import_dialog.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+            self.results_category.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+            self.zip_import.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+        ];
+        for progress in imports.into_iter().flatten() {
+            let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+            let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+            active.push(Operation {
+                kind: OperationKind::Import,
+                progress: (total > 0).then(|| done as f32 / total as f32),
+                cancellable: !progress.cancelled.load(std::sync::atomic::Ordering::Relaxed),
+            });
+        }
+        if let Some((_, progress, _)) = self.optimize_dialog.as_ref().and_then(|dialog| dialog.running.as_ref()) {
+            let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+            let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+            active.push(Operation {
+                kind: OperationKind::Optimize,
+                progress: (total > 0).then(|| done as f32 / total as f32),
+                cancellable: !progress.cancelled.load(std::sync::atomic::Ordering::Relaxed),
+            });
+        }
+        if self.health_promise.is_some() {
+            active.push(Operation::new(OperationKind::HealthCheck));
+        }
+        if let Some((_, progress)) = &self.checksum_run {
+            let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+            let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+            active.push(Operation {
+                kind: OperationKind::Verify,
+                progress: (total > 0).then(|| done as f32 / total as f32),
+                cancellable: !progress.cancelled.load(std::sync::atomic::Ordering::Relaxed),
+            });
+        }
+        if let Some((_, progress)) = &self.dimension_run {
+            let done = progress.done.load(std::sync::atomic::Ordering::Relaxed);
+            let total = progress.total.load(std::sync::atomic::Ordering::Relaxed);
+            active.push(Operation {
+                kind: OperationKind::IndexDimensions,
+                progress: (total > 0).then(|| done as f32 / total as f32),
+                cancellable: !progress.cancelled.load(std::sync::atomic::Ordering::Relaxed),
+            });
+        }
+        if self.export_promise.is_some() {
+            active.push(Operation::new(OperationKind::Export));
+        }
+        self.operations.replace(active);
+    }
+
+    /// Spinner in the top bar while anything runs; its menu lists the work and cancels what can be.
+    fn operations_indicator(&mut self, ui: &mut egui::Ui) {
+        if self.operations.is_empty() {
+            return;
+        }
+        let mut cancel = None;
+        self.loading_indicator(ui);
+        let count = self.operations.active().len();
+        ui.menu_button(format!("{} running", count), |ui| {
+            for operation in self.operations.active() {
+                ui.horizontal(|ui| {
+                    ui.label(operation.describe());
+                    if operation.cancellable && ui.small_button("Cancel").clicked() {
+                        cancel = Some(operation.kind);
+                    }
+                });
+            }
+        });
+        if cancel == Some(operations::OperationKind::Optimize) {
+            if let Some((_, progress, _)) = self.optimize_dialog.as_ref().and_then(|dialog| dialog.running.as_ref()) {
+                progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        if cancel == Some(operations::OperationKind::Verify) {
+            if let Some((_, progress)) = &self.checksum_run {
+                progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        if cancel == Some(operations::OperationKind::IndexDimensions) {
+            if let Some((_, progress)) = &self.dimension_run {
+                progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        if cancel == Some(operations::OperationKind::Import) {
+            let imports = [
+                self.import_dialog.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+                self.results_category.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+                self.zip_import.as_ref().and_then(|dialog| dialog.running.as_ref()).map(|(_, progress)| progress),
+            ];
+            for progress in imports.into_iter().flatten() {
+                progress.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// One line per missing drive or mount, with a button to probe them again.
+    fn offline_banner(&mut self, ctx: &egui::Context) {
+        if self.offline.is_empty() {
+            return;
+        }
+        let mut recheck = false;
+        egui::TopBottomPanel::top("offline_banner").show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                let roots: Vec<String> = self
+                    .offline
+                    .roots()
+                    .map(|(root, count)| format!("{} not mounted — {} images offline", root, count))
+                    .collect();
+                ui.label(egui::RichText::new("💽").color(ui.visuals().warn_fg_color));
+                ui.label(roots.join(" · "));
+                if ui.button("🔄 Re-check").clicked() {
+                    recheck = true;
+                }
+                egui::ComboBox::from_id_source("offline_images")
+                    .selected_text(self.settings.offline_images.label())
+                    .show_ui(ui, |ui| {
+                        for option in settings::OfflineImages::ALL {
+                            ui.selectable_value(&mut self.settings.offline_images, option, option.label());
+                        }
+                    });
+            });
+            ui.add_space(5.0);
+        });
+        if recheck {
+            self.recheck_offline();
+        }
+    }
+
+    /// Runs the folder watcher while the setting is on and a library is loaded.
+    fn sync_watcher(&mut self, ctx: &egui::Context) {
+        if !self.settings.watch_folders || self.safe_mode || self.image_data.is_none() {
+            self.watcher = None;
+            self.watch_pending.clear();
+            return;
+        }
+        if self.watcher.is_none() {
+            self.watcher = Some(watcher::Watcher::start(self.watched_dirs(), ctx.clone()));
+        }
+    }
+
+    /// One folder per manifest category, found from its entries' paths.
+    fn watched_dirs(&self) -> Vec<watcher::WatchedDir> {
+        let Some(data) = &self.image_data else {
+            return Vec::new();
+        };
+        let mut names: Vec<&String> = data.categories.keys().collect();
+        names.sort();
+        let mut dirs: Vec<watcher::WatchedDir> = Vec::new();
+        for name in names {
+            let source = self.manifest_category(name);
+            if dirs.iter().any(|dir| dir.category == source) {
+                continue;
+            }
+            let category = &data.categories[name];
+            let Some(root) = category.images.iter().find_map(import::library_root) else {
+                continue;
+            };
+            dirs.push(watcher::WatchedDir {
+                category: source.to_string(),
+                root,
+                directory: category.directory.clone(),
+            });
+        }
+        dirs
+    }
+
+    /// The library's own spelling of a path the watcher reported.
+    fn library_path(&self, path: &str) -> Option<String> {
+        let data = self.image_data.as_ref()?;
+        data.categories
+            .values()
+            .flat_map(|category| &category.images)
+            .find(|image| image.full_path == path || image.full_path.replace('\\', "/") == path)
+            .map(|image| image.full_path.clone())
+    }
+
+    /// Drops everything decoded from a file, so it's read again when next shown.
+    fn invalidate_file(&mut self, path: &str) {
+        self.loaded_textures.remove(path);
+        self.texture_order.retain(|cached| cached != path);
+        self.failed_images.remove(path);
+        self.retries.remove(path);
+        self.metadata_cache.remove(path);
+        self.text_cache.remove(path);
+    }
+
+    /// Applies what the watcher found: new files are added to their category,
+    /// deleted ones are marked missing, and changed ones are decoded again.
+    fn poll_watcher(&mut self) {
+        if let Some(watcher) = &self.watcher {
+            self.watch_pending.extend(watcher.drain());
+        }
+        if self.watch_pending.is_empty() {
+            return;
+        }
+        // Files an import or optimize writes arrive with the reload that follows it
+        if self.operations.blocker(operations::OperationKind::Import).is_some() || self.manifest_saver.is_saving() {
+            return;
+        }
+        let mut added: Vec<(String, ImageInfo)> = Vec::new();
+        let mut missing = 0;
+        let mut changed = 0;
+        for change in std::mem::take(&mut self.watch_pending) {
+            match change {
+                watcher::Change::Created { category, image } => match self.library_path(&image.full_path) {
+                    Some(path) => {
+                        self.offline.mark_present(&path);
+                        self.invalidate_file(&path);
+                        changed += 1;
+                    }
+                    None => {
+                        if self.watch_added.insert(image.full_path.clone()) {
+                            added.push((category, image));
+                        }
+                    }
+                },
+                watcher::Change::Deleted(path) => {
+                    if let Some(path) = self.library_path(&path) {
+                        self.offline.mark_missing(&path);
+                        missing += 1;
+                    }
+                }
+                watcher::Change::Modified(path) => {
+                    if let Some(path) = self.library_path(&path) {
+                        self.invalidate_file(&path);
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        if missing > 0 || changed > 0 {
+            self.update_filtered_images();
+        }
+        let mut parts = Vec::new();
+        if !added.is_empty() {
+            parts.push(format!("{} new", added.len()));
+        }
+        if missing > 0 {
+            parts.push(format!("{} deleted", missing));
+        }
+        if changed > 0 {
+            parts.push(format!("{} changed", changed));
+        }
+        if !parts.is_empty() {
+            self.set_status(format!("Library folders: {} file(s)", parts.join(", ")));
+        }
+        if !added.is_empty() {
+            self.add_watched_files(added);
+        }
+    }
+
+    /// Adds new files to the manifest, or only to this session when it's read-only.
+    fn add_watched_files(&mut self, added: Vec<(String, ImageInfo)>) {
+        let mut by_category: Vec<(String, Vec<ImageInfo>)> = Vec::new();
+        for (category, image) in added {
+            match by_category.iter_mut().find(|(name, _)| *name == category) {
+                Some((_, images)) => images.push(image),
+                None => by_category.push((category, vec![image])),
+            }
+        }
+        if self.read_only.is_none() {
+            let mutations = by_category
+                .into_iter()
+                .map(|(category, images)| manifest::add_images(category, images))
+                .collect();
+            let manifest_path = self.settings.active_profile().manifest_path;
+            self.manifest_saver.submit(&manifest_path, manifest::batch(mutations));
+            return;
+        }
+        let Some(data) = &mut self.image_data else {
+            return;
+        };
+        for (category, images) in by_category {
+            // A grouped category's files go to its first sub-category until the next reload
+            let target = if data.categories.contains_key(&category) {
+                Some(category)
+            } else {
+                let prefix = format!("{}/", category);
+                let mut names: Vec<&String> = data.categories.keys().filter(|name| name.starts_with(&prefix)).collect();
+                names.sort();
+                names.first().map(|name| name.to_string())
+            };
+            if let Some(entry) = target.and_then(|name| data.categories.get_mut(&name)) {
+                entry.images.extend(images);
+                entry.count = entry.images.len() as u32;
+            }
+        }
+        self.search_index = search::SearchIndex::build(data, &self.source_classifier);
+        self.update_filtered_images();
+    }
+
+    /// Brings back the images of roots that are present again, without reloading the library.
+    fn recheck_offline(&mut self) {
+        let back = self.offline.recheck();
+        if back.is_empty() {
+            self.set_status("Still not mounted");
+            return;
+        }
+        let under_back = |path: &str| offline::root_of(path).is_some_and(|root| back.contains(&root));
+        self.failed_images.retain(|path, _| !under_back(path));
+        self.retries.retain(|path, _| !under_back(path));
+        self.update_filtered_images();
+        self.set_status(format!("{} is back", back.join(", ")));
+    }
+
+    /// Queues a pixel copy; they're decoded one at a time off the UI thread.
+    fn copy_pixels(
+        &mut self,
+        image_info: &ImageInfo,
+        max_dimension: Option<u32>,
+        orientation: orientation::Orientation,
+    ) {
+        let ahead = self.copy_queue.push(
+            image_info.full_path.clone(),
+            image_info.filename.clone(),
+            max_dimension,
+            orientation,
+        );
+        if ahead > 0 {
+            self.set_status(format!("Queued {} ({} copy(s) ahead)", image_info.filename, ahead));
+        }
+    }
+
+    /// Puts finished copies on the clipboard and starts the next one.
+    /// Only decoded pixels reach the clipboard, so EXIF/GPS never travels with a paste.
+    fn poll_copy_queue(&mut self, ctx: &egui::Context) {
+        if let Some((item, pixels)) = self.copy_queue.take_finished() {
+            let result = pixels.and_then(|rgba| {
+                self.clipboard
+                    .set_image(arboard::ImageData {
+                        width: rgba.width() as usize,
+                        height: rgba.height() as usize,
+                        bytes: std::borrow::Cow::Borrowed(rgba.as_raw()),
+                    })
+                    .map_err(|e| e.to_string())
+            });
+            match &result {
+                Ok(()) => {
+                    self.set_status_with(
+                        format!("Copied {} to clipboard", item.filename),
+                        status::StatusAction::RevealImage(item.path.clone()),
+                    );
+                    self.mark_copied(&item.path);
+                    if self.settings.integrity_hashing {
+                        self.integrity.queue(&item.path);
+                    }
+                }
+                Err(e) => self.set_status(format!("Failed to copy {}: {}", item.filename, e)),
+            }
+            self.copy_queue.finish(&item, result);
+        }
+        self.copy_queue.start_next();
+        if self.copy_queue.is_busy() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(50));
+        }
+    }
+
+    /// The status bar's copy queue button and its popover.
+    fn copy_queue_ui(&mut self, ui: &mut egui::Ui) {
+        if self.copy_queue.items().next().is_none() {
+            return;
+        }
+        let pending = self.copy_queue.pending();
+        let label = if pending > 0 { format!("📋 {}", pending) } else { "📋".to_string() };
+        let response = ui
+            .add(egui::Button::new(egui::RichText::new(label).small()).frame(false))
+            .on_hover_text("Copy queue");
+        let popup_id = ui.make_persistent_id("copy_queue_popup");
+        if response.clicked() {
+            ui.memory_mut(|m| m.toggle_popup(popup_id));
+        }
+        egui::popup::popup_above_or_below_widget(ui, popup_id, &response, egui::AboveOrBelow::Above, |ui| {
+            ui.set_min_width(260.0);
+            for item in self.copy_queue.items() {
+                ui.horizontal(|ui| {
+                    ui.label(&item.filename);
+                    let state = egui::RichText::new(item.state.label()).small();
+                    let state = match item.state {
+                        copy_queue::State::Failed(_) => state.color(ui.visuals().error_fg_color),
+                        copy_queue::State::Done => state,
+                        _ => state.weak(),
+                    };
+                    ui.label(state);
+                });
+            }
+            ui.separator();
+            match self.copy_queue.last_done() {
+                Some(item) => ui.label(format!("On the clipboard: {}", item.filename)),
+                None => ui.label(egui::RichText::new("Nothing from this queue is on the clipboard yet").weak()),
+            };
+            ui.horizontal(|ui| {
+                let queued = self
+                    .copy_queue
+                    .items()
+                    .filter(|item| item.state == copy_queue::State::Queued)
+                    .count();
+                if ui
+                    .add_enabled(queued > 0, egui::Button::new("Cancel queued"))
+                    .on_hover_text("The copy being encoded still finishes")
+                    .clicked()
+                {
+                    self.copy_queue.cancel_queued();
+                }
+                if ui.button("Clear finished").clicked() {
+                    self.copy_queue.clear_finished();
+                }
+            });
+        });
+        ui.separator();
+    }
+}
+
+impl eframe::App for ImageSearchApp {
+    /// Panels paint the background themselves, so a translucent panel fill
+    /// shows what is behind the window.
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        [0.0; 4]
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_hot_cache();
+        self.new_images.save(&mut self.user_store);
+        if let Err(e) = self.user_store.save() {
+            eprintln!("Failed to save user data: {}", e);
+        }
+        self.luminance.save();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.frame_guard.begin(ctx);
+        // Apply theme
+        let mut visuals = if self.settings.dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        let opacity = self.settings.window_opacity;
+        if opacity < 1.0 && settings::translucency_unsupported().is_none() {
+            // Only the backgrounds get the alpha; images are painted over them and stay opaque
+            visuals.panel_fill = visuals.panel_fill.gamma_multiply(opacity);
+            visuals.window_fill = visuals.window_fill.gamma_multiply(opacity);
+        }
+        ctx.set_visuals(visuals);
+        // Reduced motion: no fades or slides anywhere, tooltips included
+        let animation_time = if self.animations_enabled() { egui::Style::default().animation_time } else { 0.0 };
+        ctx.style_mut(|style| style.animation_time = animation_time);
+
+        if self.settings != self.applied_settings {
+            let before = std::mem::replace(&mut self.applied_settings, self.settings.clone());
+            self.apply_settings(&before);
+        }
+        self.sync_hotkey(ctx);
+
+        self.handle_keyboard(ctx);
+        self.schedule_copy_feedback_repaint(ctx);
+        self.poll_user_store(ctx);
+        self.check_clipboard_on_focus(ctx);
+
+        let newly_changed = self.integrity.poll();
+        if newly_changed > 0 {
+            self.set_status(format!("⚠ {} file(s) changed since last seen", newly_changed));
+        }
+        if self.integrity.is_busy() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(500));
+        }
+
+        // Backfilled color signatures can reveal more matches for an active color filter
+        if self.colors.poll() > 0 && self.filter_stats.color_pending > 0 {
+            self.update_filtered_images();
+        }
+        if self.colors.is_busy() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(500));
+        }
+        if self.dimensions.poll() > 0 && self.filter_stats.dimension_pending > 0 {
+            self.update_filtered_images();
+        }
+        if self.dimensions.is_busy() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(250));
+        }
+
+        // Thumbnails decode for the largest size drawn last frame, in physical pixels
+        let drawn = std::mem::take(&mut self.frame_thumbnail_points);
+        if drawn > 0.0 {
+            self.thumbnail_pixels = drawn * ctx.pixels_per_point();
+        }
+        self.upload_thumbnails(ctx);
+        self.poll_library_load(ctx);
+        self.poll_thumbnail_cache_prune(ctx);
+        self.check_thumbnail_cache_health();
+        self.poll_copy_queue(ctx);
+        self.sync_watcher(ctx);
+        self.poll_watcher();
+        self.follow_pending_link(ctx);
+        self.poll_manifest_saver(ctx);
+        self.poll_export(ctx);
+        self.sync_remote_server(ctx);
+        self.poll_remote(ctx);
+        self.poll_health_check();
+        self.poll_update_check(ctx);
+        if self.health_promise.is_some() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(200));
+        }
+        self.poll_checksums();
+        self.poll_dimension_index();
+        if self.checksum_run.is_some() || self.dimension_run.is_some() {
+            self.frame_guard.repaint_after(ctx, Duration::from_millis(200));
+        }
+        self.track_operations();
+        
+        self.safe_mode_banner(ctx);
+        self.clipboard_banner(ctx);
+        self.offline_banner(ctx);
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            ui.add_space(10.0);
+            
+            ui.horizontal(|ui| {
+                ui.heading("Chlorine");
+
+                ui.add_space(20.0);
+                let active = self.settings.active_profile();
+                let mut switch_to = None;
+                egui::ComboBox::from_id_source("profile_switcher")
+                    .selected_text(format!("📚 {}", active.name))
+                    .show_ui(ui, |ui| {
+                        for profile in &self.settings.profiles {
+                            if ui.selectable_label(profile.id == active.id, &profile.name).clicked() {
+                                switch_to = Some(profile.id.clone());
+                            }
+                        }
+                    });
+                if let Some(id) = switch_to {
+                    self.switch_profile(&id);
+                }
+
+                ui.add_space(10.0);
+                ui.label("📌").on_hover_text("Pinned slots");
+                self.pinned_slots_ui(ctx, ui);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.menu_button("⋮", |ui| self.overflow_menu(ui))
+                        .response
+                        .on_hover_text("Frequently used settings, refresh and library checks");
+                    if ui.button("⚙️ Settings").clicked() {
+                        self.show_settings = !self.show_settings;
+                    }
+                    if ui.button("⌨ Shortcuts").clicked() {
+                        self.show_shortcuts = !self.show_shortcuts;
+                    }
+                    ui.add_space(10.0);
+                    if let Some(folder) = self.export_folder.clone() {
+                        if ui.small_button("📂 Open folder").on_hover_text(folder.display().to_string()).clicked() {
+                            if let Err(e) = open_in_file_manager(&folder) {
+                                self.set_status(format!("Failed to open {}: {}", folder.display(), e));
+                            }
+                            self.export_folder = None;
+                        }
+                    }
+                    self.status_label(ui);
+                    self.copy_queue_ui(ui);
+
+                    if self.manifest_saver.is_saving() {
+                        ui.label(egui::RichText::new("Saving manifest…").small());
+                        self.loading_indicator(ui);
+                        ui.separator();
+                    }
+                    let unsaved = self.manifest_saver.unsaved();
+                    if unsaved > 0 {
+                        let retry = ui
+                            .add(
+                                egui::Label::new(
+                                    egui::RichText::new(format!("⚠ {} unsaved edit(s)", unsaved))
+                                        .small()
+                                        .color(ui.visuals().warn_fg_color),
+                                )
+                                .sense(egui::Sense::click()),
+                            )
+                            .on_hover_text("These edits failed to save. Click to try again.");
+                        if retry.clicked() {
+                            let dropped = self.manifest_saver.retry();
+                            if dropped > 0 {
+                                self.set_status(format!("Dropped {} edit(s) that no longer apply to the manifest", dropped));
+                            }
+                        }
+                        ui.separator();
+                    }
+                    self.user_data_state_ui(ui);
+
+                    if self.settings.privacy_mode {
+                        let privacy = ui
+                            .add(egui::Label::new(egui::RichText::new("🕶 Privacy mode").small()).sense(egui::Sense::click()))
+                            .on_hover_text("Sensitive images stay concealed. Click or press F9 to turn off.");
+                        if privacy.clicked() {
+                            self.toggle_privacy_mode();
+                        }
+                        ui.separator();
+                    }
+
+                    if let Some(reason) = &self.read_only {
+                        ui.label(egui::RichText::new("🔒 Read-only").small().color(ui.visuals().warn_fg_color))
+                            .on_hover_text(format!(
+                                "{}\n\nBrowsing and copying work as usual. Actions that rewrite the manifest are \
+                                 disabled; favorites, history and the hidden list are kept in your profile folder.",
+                                reason
+                            ));
+                        ui.separator();
+                    }
+
+                    if let Some(report) = &self.health_report {
+                        let summary = report.summary();
+                        let has_issues = !report.issues.is_empty();
+                        let mut text = egui::RichText::new(summary).small();
+                        if has_issues {
+                            text = text.color(ui.visuals().warn_fg_color);
+                        }
+                        if ui.add(egui::Label::new(text).sense(egui::Sense::click())).clicked() {
+                            self.show_health = true;
+                        }
+                        ui.separator();
+                    }
+                });
             });
             
             ui.add_space(5.0);
@@ -261,203 +8559,1508 @@ impl eframe::App for ImageSearchApp {
                 let response = ui.add_sized(
                     [300.0, 24.0],
                     egui::TextEdit::singleline(&mut self.search_query)
-                        .hint_text("Search by filename or category..."),
+                        .id(search_box_id())
+                        .hint_text("Search by filename, path or category... (name:, path:, source:, ar:16:9, mp:>12, Ctrl+F)"),
                 );
                 
                 if response.changed() {
                     self.update_filtered_images();
+                    self.refresh_scope_suggestions();
+                }
+                self.search_history_popup(ui, &response);
+                self.scope_suggestions_popup(ui, &response);
+                if !self.settings.search_tips_seen && self.image_data.is_some() {
+                    self.search_tips(ctx, &response);
                 }
                 
                 if let Some(data) = &self.image_data {
                     let mut categories: Vec<String> = data.categories.keys().cloned().collect();
                     categories.sort();
+                    let large: HashMap<String, usize> = data
+                        .categories
+                        .iter()
+                        .filter(|(_, category)| category.images.len() >= LARGE_CATEGORY)
+                        .map(|(name, category)| (name.clone(), category.images.len()))
+                        .collect();
                     categories.insert(0, "All Categories".to_string());
+                    if self.new_images.total() > 0 || self.selected_category == new_images::CATEGORY {
+                        categories.insert(1, new_images::CATEGORY.to_string());
+                    }
+                    let new_total = self.new_images.total();
+                    let viewing_real_category =
+                        !self.show_all_categories && self.selected_category != new_images::CATEGORY;
                     
                     ui.label("Category:");
                     let prev_category = self.selected_category.clone();
-                    egui::ComboBox::from_label("")
-                        .selected_text(&self.selected_category)
+                    if viewing_real_category {
+                        self.cover_ui(ctx, ui, &prev_category);
+                    }
+                    let selected_text = if new_total > 0 && prev_category != new_images::CATEGORY {
+                        format!("{}  (+{} new)", self.selected_category, new_total)
+                    } else {
+                        self.selected_category.clone()
+                    };
+                    let combo = egui::ComboBox::from_label("")
+                        .selected_text(selected_text)
                         .show_ui(ui, |ui| {
                             for category in &categories {
-                                ui.selectable_value(&mut self.selected_category, category.clone(), category);
+                                ui.horizontal(|ui| {
+                                    if category == "All Categories" || category == new_images::CATEGORY {
+                                        ui.add_space(COVER_SIZE + ui.spacing().item_spacing.x);
+                                    } else {
+                                        self.cover_ui(ctx, ui, category);
+                                    }
+                                    if !self.category_colors.is_empty() {
+                                        self.category_color_dot(ui, category);
+                                    }
+                                    let response =
+                                        ui.selectable_value(&mut self.selected_category, category.clone(), category);
+                                    if let Some(count) = large.get(category) {
+                                        ui.label(egui::RichText::new("(large)").small().weak())
+                                            .on_hover_text(format!("{} images", count));
+                                    }
+                                    let real = category != "All Categories" && category != new_images::CATEGORY;
+                                    if real && self.settings.category_peek {
+                                        // A tooltip: shown after the hover delay, never takes input
+                                        response.on_hover_ui(|ui| self.category_peek_ui(ctx, ui, category));
+                                    }
+                                    let new_count = match category.as_str() {
+                                        "All Categories" => 0,
+                                        new_images::CATEGORY => new_total,
+                                        _ => self.new_images.count(category),
+                                    };
+                                    if new_count > 0
+                                        && ui
+                                            .small_button(format!("+{} new", new_count))
+                                            .on_hover_text("Show these images and mark them as seen")
+                                            .clicked()
+                                    {
+                                        self.selected_category = category.clone();
+                                        ui.close_menu();
+                                    }
+                                });
+                            }
+                        });
+                    if viewing_real_category {
+                        combo.response.context_menu(|ui| {
+                            ui.label("Color label");
+                            self.category_color_menu(ui, &prev_category);
+                        });
+                    }
+                    
+                    if viewing_real_category {
+                        let category = self.selected_category.clone();
+                        ui.menu_button("⚙", |ui| {
+                            self.category_copy_menu(ui, &category);
+                            ui.separator();
+                            ui.menu_button("🎨 Color label", |ui| self.category_color_menu(ui, &category));
+                            self.category_grouping_menu(ui, &category);
+                            if self.read_only_action(ui, egui::Button::new("📥 Import folder into category…")).clicked() {
+                                self.open_import_dialog();
+                                ui.close_menu();
+                            }
+                        })
+                        .response
+                        .on_hover_text("Copy behavior, color label and grouping for this category");
+                    }
+
+                    // Update filter when category changes
+                    if prev_category != self.selected_category {
+                        // Picking a category by hand replaces a "category/" prefix
+                        if self.scoped_by_prefix {
+                            self.search_query = self.effective_query().to_string();
+                            self.scoped_by_prefix = false;
+                        }
+                        self.show_all_categories = self.selected_category == "All Categories";
+                        self.update_filtered_images();
+                    }
+                    
+                    if ui.toggle_value(&mut self.favorites_only, "★ Favorites only").changed() {
+                        self.update_filtered_images();
+                    }
+
+                    let color_label = match self.color_filter {
+                        Some(color) => egui::RichText::new("🎨 Color").color(color),
+                        None => egui::RichText::new("🎨 Color"),
+                    };
+                    ui.menu_button(color_label, |ui| self.color_filter_menu(ui))
+                        .response
+                        .on_hover_text("Find images by dominant color (or type color:#rrggbb)");
+
+                    for orientation in dimensions::Orientation::ALL {
+                        let mut on = self.orientations.contains(&orientation);
+                        if ui.toggle_value(&mut on, orientation.label()).changed() {
+                            if on {
+                                self.orientations.insert(orientation);
+                            } else {
+                                self.orientations.remove(&orientation);
+                            }
+                            self.update_filtered_images();
+                        }
+                    }
+
+                    if !self.hidden.is_empty()
+                        && ui
+                            .toggle_value(&mut self.show_hidden, format!("👁 Show hidden ({})", self.hidden.len()))
+                            .changed()
+                    {
+                        self.update_filtered_images();
+                    }
+
+                    self.operations_indicator(ui);
+
+                    if ui
+                        .toggle_value(&mut self.settings.show_preview_pane, "◨ Preview")
+                        .on_hover_text("Show the highlighted result in a pane on the right")
+                        .changed()
+                    {
+                        self.persist_settings();
+                    }
+                }
+            });
+
+            self.filter_chips_ui(ui);
+            
+            ui.add_space(10.0);
+        });
+
+        if self.settings.show_favorites_bar && !self.favorite_entries.is_empty() {
+            egui::TopBottomPanel::top("favorites_bar").show(ctx, |ui| {
+                ui.add_space(4.0);
+                self.favorites_bar_ui(ctx, ui);
+                ui.add_space(4.0);
+            });
+        }
+
+        self.preview_pane(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.image_data.is_none() && self.library_load.is_some() {
+                self.hot_cache_ui(ctx, ui);
+                return;
+            }
+            if self.image_data.is_none() && self.load_failure.is_some() {
+                self.load_failure_ui(ui);
+                return;
+            }
+            if !self.selection.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("☑ {} selected", self.selection.len()));
+                    let too_many = self.selection.len() > encode::MAX_SHEET_IMAGES;
+                    let combine = ui
+                        .add_enabled(!too_many, egui::Button::new("🧩 Copy as combined image"))
+                        .on_disabled_hover_text(format!("Select at most {} images", encode::MAX_SHEET_IMAGES));
+                    if combine.clicked() {
+                        self.copy_selection_combined();
+                    }
+                    let all_favorites = self.selection.iter().all(|path| self.favorites.contains(path));
+                    let (star, hint) = if all_favorites {
+                        ("☆ Unfavorite all", "Remove every selected image from favorites")
+                    } else {
+                        ("★ Favorite all", "Add every selected image to favorites")
+                    };
+                    if ui.button(star).on_hover_text(hint).clicked() {
+                        self.toggle_selection_favorite();
+                    }
+                    let all_sensitive = self.selection.iter().all(|path| self.sensitive.contains(path));
+                    let sensitive_label = if all_sensitive { "🔓 Unmark sensitive" } else { "🔒 Mark sensitive" };
+                    if ui.button(sensitive_label).clicked() {
+                        let selection = self.selection.clone();
+                        self.set_sensitive(&selection, !all_sensitive);
+                    }
+                    if self
+                        .read_only_action(ui, egui::Button::new("🗜 Optimize…"))
+                        .on_hover_text("Re-encode the selected files to make them smaller")
+                        .clicked()
+                    {
+                        self.open_optimize_dialog();
+                    }
+                    if self
+                        .read_only_action(ui, egui::Button::new("🗑 Remove…"))
+                        .on_hover_text("Remove the selected images from the manifest, after reviewing the list")
+                        .clicked()
+                    {
+                        let removals = self
+                            .selection
+                            .iter()
+                            .flat_map(|path| self.removals_for(path, "selected for removal"))
+                            .collect();
+                        self.open_removal_review("Remove selected images", removals);
+                    }
+                    if ui.button("✖ Clear selection").clicked() {
+                        self.selection.clear();
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                let mut persist = false;
+                for view in settings::ResultView::ALL {
+                    persist |= ui.selectable_value(&mut self.settings.result_view, view, view.label()).changed();
+                }
+                if self.settings.result_view == settings::ResultView::Grid {
+                    let slider = ui.add(
+                        egui::Slider::new(&mut self.settings.grid_cell_size, 64.0..=256.0)
+                            .show_value(false)
+                            .text("Size"),
+                    );
+                    // Saved once the drag ends rather than on every step
+                    persist |= slider.drag_released() || (slider.changed() && !slider.dragged());
+                }
+                if persist {
+                    self.persist_settings();
+                }
+                ui.separator();
+                let found = self.filtered_images.len() + self.filter_stats.stacked;
+                let summary = self.filter_stats.summary(found);
+                ui.heading(format!("Found {} images", found))
+                    .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, &summary));
+                if self.settings.text_only {
+                    ui.label(egui::RichText::new(format!("· {}", summary)).weak());
+                }
+                if self.filter_stats.stacked > 0 {
+                    ui.label(
+                        egui::RichText::new(format!("· {} frames stacked", self.filter_stats.stacked))
+                            .weak(),
+                    )
+                    .on_hover_text("Numbered frames are shown as one row; turn this off under Settings → Behavior");
+                }
+                if !self.filtered_images.is_empty() {
+                    let stats = &self.filter_stats;
+                    let breakdown = stats.extension_breakdown();
+                    ui.add(egui::Label::new(
+                        egui::RichText::new(format!("· {}", format_bytes(stats.total_bytes))).weak(),
+                    ))
+                    .on_hover_text(breakdown);
+                }
+                if self.filter_stats.dimension_pending > 0 {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "· still measuring {} files",
+                            self.filter_stats.dimension_pending
+                        ))
+                        .weak()
+                        .italics(),
+                    );
+                }
+                if self.filter_stats.color_pending > 0 {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "· color signatures still computing: {} remaining",
+                            self.filter_stats.color_pending
+                        ))
+                        .weak()
+                        .italics(),
+                    );
+                }
+                if !self.search_query.trim().is_empty() && !self.filtered_images.is_empty() {
+                    let button = egui::Button::new("➕ Category from results…").small();
+                    if self
+                        .read_only_action(ui, button)
+                        .on_hover_text("Collect these results into a new category")
+                        .clicked()
+                    {
+                        self.open_results_category_dialog();
+                    }
+                }
+            });
+            
+            if self.settings.result_view == settings::ResultView::Grid {
+                self.results_grid(ctx, ui);
+                return;
+            }
+            self.results_scroll.relayout(1, scroll::RESULT_ROW_HEIGHT + ui.spacing().item_spacing.y);
+            let mut results = egui::ScrollArea::vertical().auto_shrink([false; 2]);
+            if let Some(offset) = self.results_scroll.frame_offset() {
+                results = results.vertical_scroll_offset(offset);
+            }
+            let output = results
+                .show_rows(ui, scroll::RESULT_ROW_HEIGHT, self.filtered_images.len(), |ui, row_range| {
+                    for i in row_range {
+                        if let Some((category, image_info)) = self.filtered_entry(i) {
+                            let flash = self.copy_flash_strength(&image_info.full_path);
+                            let mut frame = egui::Frame::group(ui.style());
+                            if flash > 0.0 {
+                                let accent = ui.visuals().selection.bg_fill;
+                                frame = frame.fill(accent.gamma_multiply(flash * 0.6));
+                            }
+                            let is_highlighted =
+                                self.highlighted.as_ref().is_some_and(|(_, image)| image.full_path == image_info.full_path);
+                            if is_highlighted && flash == 0.0 {
+                                frame = frame.fill(ui.visuals().selection.bg_fill.gamma_multiply(0.25));
+                            }
+                            if self.selection.contains(&image_info.full_path) {
+                                frame = frame.stroke(ui.visuals().selection.stroke);
+                            }
+                            let is_hidden = self.show_hidden && self.hidden.contains(&image_info.full_path);
+                            let is_offline = self.offline.contains(&image_info.full_path);
+                        let row = frame.show(ui, |ui| {
+                            if is_hidden || is_offline {
+                                ui.set_opacity(0.4);
+                            }
+                            ui.horizontal(|ui| {
+                                self.thumbnail_ui(ctx, ui, &image_info, 64.0);
+
+                                // Long names and paths are cut in the middle so the buttons always fit
+                                let body_font = egui::TextStyle::Body.resolve(ui.style());
+                                let text_width = (ui.available_width() - self.row_actions_width(ui)).max(80.0);
+                                
+                                ui.vertical(|ui| {
+                                    ui.set_max_width(text_width);
+                                    let stack = self
+                                        .filtered_images
+                                        .get(i)
+                                        .and_then(|entry_index| self.stacks.get(entry_index))
+                                        .map(|stack| (stack.key.clone(), stack.pattern.clone(), stack.frames.len()));
+                                    ui.horizontal(|ui| {
+                                        let checksum_badge = self.checksum_badge(ui, &image_info.full_path);
+                                        let has_badges = self.integrity.is_changed(&image_info.full_path)
+                                            || checksum_badge.is_some()
+                                            || self.is_on_clipboard(&image_info.full_path)
+                                            || self.duplicates.contains_key(&image_info.full_path)
+                                            || stack.is_some();
+                                        let name_width = if has_badges { (text_width - ROW_BADGE_WIDTH).max(40.0) } else { text_width };
+                                        let shown = elide::middle(ctx, &image_info.filename, &body_font, name_width);
+                                        let name = ui.add(
+                                            egui::Label::new(egui::RichText::new(&shown).strong())
+                                                .wrap(false)
+                                                .sense(egui::Sense::click()),
+                                        );
+                                        let mut hover = Vec::new();
+                                        if shown != image_info.filename {
+                                            hover.push(image_info.filename.clone());
+                                        }
+                                        if let Some((width, height)) = self.dimensions.get(&image_info.full_path) {
+                                            if width > 0 && height > 0 {
+                                                hover.push(format!(
+                                                    "{} × {} · {} · {:.1} MP",
+                                                    width,
+                                                    height,
+                                                    dimensions::aspect_label(width, height),
+                                                    dimensions::megapixels(width, height)
+                                                ));
+                                            }
+                                        }
+                                        hover.push(format!(
+                                            "Click to highlight, then {} for details · {} to copy",
+                                            shortcuts::keys(shortcuts::Binding::OpenHighlighted),
+                                            shortcuts::keys(shortcuts::Binding::CopyRow)
+                                        ));
+                                        let name = name.on_hover_text(hover.join("\n"));
+                                        if name.double_clicked() {
+                                            self.copy_image(&category, &image_info);
+                                        } else if name.clicked() && ui.input(|i| i.modifiers.command) {
+                                            self.toggle_selection(&image_info.full_path);
+                                        } else if name.clicked() {
+                                            self.highlighted = Some((category.clone(), image_info.clone()));
+                                        }
+                                        name.context_menu(|ui| self.image_context_menu(ui, &category, &image_info));
+                                        if self.integrity.is_changed(&image_info.full_path) {
+                                            let badge = ui
+                                                .add(
+                                                    egui::Label::new(
+                                                        egui::RichText::new("⚠ changed")
+                                                            .small()
+                                                            .color(ui.visuals().warn_fg_color),
+                                                    )
+                                                    .sense(egui::Sense::click()),
+                                                )
+                                                .on_hover_text("File content changed since it was last seen. Click to acknowledge.");
+                                            if badge.clicked() {
+                                                self.integrity.acknowledge(&image_info.full_path);
+                                            }
+                                        }
+                                        if let Some((text, hover, color)) = checksum_badge {
+                                            ui.label(egui::RichText::new(text).small().color(color)).on_hover_text(hover);
+                                        }
+                                        if self.is_on_clipboard(&image_info.full_path) {
+                                            ui.label(egui::RichText::new("📋 on clipboard").small().weak())
+                                                .on_hover_text("This is what Chlorine last copied, and the clipboard hasn't changed since");
+                                        }
+                                        if let Some((key, pattern, frames)) = &stack {
+                                            let expanded = self.expanded_stacks.contains(key);
+                                            let label = format!("{} 🎞 ×{}", if expanded { "⏷" } else { "⏵" }, frames);
+                                            let hint = if expanded {
+                                                format!("{}\nClick to collapse the sequence", pattern)
+                                            } else {
+                                                format!("{}\nClick to show every frame; copying this row copies the first", pattern)
+                                            };
+                                            if ui.small_button(label).on_hover_text(hint).clicked() {
+                                                self.toggle_stack(key);
+                                            }
+                                        }
+                                        if let Some(categories) = self.duplicates.get(&image_info.full_path).cloned() {
+                                            let badge = egui::RichText::new(format!("×{}", categories.len()))
+                                                .small()
+                                                .color(ui.visuals().warn_fg_color);
+                                            ui.menu_button(badge, |ui| {
+                                                ui.label(egui::RichText::new("Listed in:").small().weak());
+                                                for name in &categories {
+                                                    ui.label(format!("📁 {}", name));
+                                                }
+                                                ui.separator();
+                                                for name in &categories {
+                                                    if ui.button(format!("Keep only in {}", name)).clicked() {
+                                                        self.keep_only_in(&image_info.full_path, name);
+                                                        ui.close_menu();
+                                                    }
+                                                }
+                                            });
+                                        }
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("📁 {}", category));
+                                        let matched = self.filtered_images.get(i).and_then(|entry| self.matched_fields.get(entry));
+                                        if let Some(matched) = matched {
+                                            ui.label(egui::RichText::new(matched).small().weak());
+                                        }
+                                    });
+                                    ui.label(format!("📊 {}", format_bytes(image_info.size)));
+                                    let path_line = format!("📍 {}", image_info.relative_path);
+                                    let shown = elide::middle(ctx, &path_line, &body_font, text_width);
+                                    let path = ui.add(egui::Label::new(&shown).wrap(false));
+                                    if shown != path_line {
+                                        path.on_hover_text(&image_info.relative_path);
+                                    }
+                                });
+                                
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    for action in self.settings.row_actions.clone() {
+                                        let label = self.row_action_label(action, &image_info.full_path);
+                                        let hint = self.row_action_hint(action, &category, &image_info);
+                                        if ui.button(label).on_hover_text(hint).clicked() {
+                                            self.run_row_action(action, &category, &image_info);
+                                        }
+                                    }
+                                });
+                            });
+                        });
+                        self.category_stripe(ui, row.response.rect, &category);
+                        
+                        ui.add_space(5.0);
+                    }
+                }
+                });
+            let pitch = scroll::RESULT_ROW_HEIGHT + ui.spacing().item_spacing.y;
+            self.results_scroll.record(output.state.offset.y, output.inner_rect.height(), pitch);
+            if self.results_scroll.is_animating() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.update_previews(ctx);
+        let screen = ctx.screen_rect();
+        for (index, view) in self.detail_windows.clone().into_iter().enumerate() {
+            let DetailView {
+                id,
+                category,
+                image: image_info,
+                orientation,
+            } = view;
+            
+            // The title is cut to last frame's window width; the full name is shown inside
+            let window_id = egui::Id::new(("detail_window", id));
+            if self.raise_detail == Some(id) {
+                ctx.move_to_top(egui::LayerId::new(egui::Order::Middle, window_id));
+                self.raise_detail = None;
+            }
+            let title_width = ctx.memory(|m| m.area_rect(window_id)).map_or(460.0, |rect| rect.width() - 20.0);
+            let title_font = egui::TextStyle::Heading.resolve(&ctx.style());
+            let title = elide::middle(ctx, &image_info.filename, &title_font, title_width);
+            // Each new window is offset a little so none hides another completely
+            let cascade = egui::vec2(30.0, 30.0) * (index % 6) as f32;
+            let window = egui::Window::new(title)
+                .id(window_id)
+                .collapsible(false)
+                .resizable(true)
+                .default_size([500.0, 500.0])
+                .default_pos(screen.center() - egui::vec2(250.0, 300.0) + cascade)
+                .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        let is_text = self.is_text_entry(&image_info);
+                        if is_text {
+                            ui.add_space(10.0);
+                            match self.text_content(&image_info.full_path) {
+                                Ok(text) => {
+                                    egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                                        ui.add(
+                                            egui::TextEdit::multiline(&mut text.as_str())
+                                                .font(egui::TextStyle::Monospace)
+                                                .desired_width(f32::INFINITY),
+                                        );
+                                    });
+                                }
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e);
+                                }
+                            }
+                            ui.add_space(10.0);
+                        } else if self.is_concealed(&image_info.full_path) {
+                            ui.add_space(10.0);
+                            self.concealed_ui(ctx, ui, &image_info.full_path);
+                            ui.add_space(10.0);
+                        } else if !decoder_supports(&image_info.extension) {
+                            // No pixels to show or rotate; the info tile and metadata are all there is
+                            ui.add_space(10.0);
+                            self.thumbnail_ui(ctx, ui, &image_info, 128.0);
+                            ui.label(egui::RichText::new(unsupported_hint(&image_info.extension)).small().weak());
+                            ui.add_space(10.0);
+                        } else if let Some(texture) = self
+                            .previews
+                            .get(&image_info.full_path)
+                            .cloned()
+                            .or_else(|| match self.thumbnail_state(ctx, &image_info) {
+                                ThumbnailState::Ready(texture) => Some(texture),
+                                _ => None,
+                            })
+                        {
+                            // Display image in a square area
+                            let available_width = ui.available_width();
+                            let max_size = available_width.min(450.0);
+                            
+                            // Make it square by using the same dimension for both width and height
+                            let size = orientation.apply_size(texture.size_vec2());
+                            let scale = (max_size / size.x).min(max_size / size.y).min(1.0);
+                            let display_size = size * scale;
+                            
+                            ui.add_space(10.0);
+                            let (rect, _) = ui.allocate_exact_size(display_size, egui::Sense::hover());
+                            if let Some(backdrop) = self.backdrop_for(&image_info.full_path) {
+                                ui.painter().add(backdrop.shape(rect));
+                            }
+                            orientation.paint(ui, &texture, rect);
+                            ui.add_space(10.0);
+                            self.orientation_controls(ui, id, &image_info, orientation);
+                            self.backdrop_controls(ui, &image_info.full_path);
+                            ui.add_space(5.0);
+                        } else {
+                            ui.add_space(10.0);
+                            self.thumbnail_ui(ctx, ui, &image_info, 128.0);
+                            ui.add_space(10.0);
+                        }
+                        
+                        // Show filename and category
+                        ui.separator();
+                        ui.add_space(5.0);
+                        ui.add(egui::Label::new(egui::RichText::new(&image_info.filename).strong().size(14.0)).wrap(true));
+                        if self.is_on_clipboard(&image_info.full_path) {
+                            ui.label(egui::RichText::new("📋 Currently on clipboard").small().weak());
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(format!("📁 {}", category));
+                            if ui
+                                .small_button("🗂 Show files from this folder")
+                                .on_hover_text("Filter the results to this image's folder, in any category")
+                                .clicked()
+                            {
+                                self.show_folder(&image_info);
+                            }
+                        });
+                        let source = self.source_classifier.classify(&image_info.filename);
+                        ui.label(egui::RichText::new(format!("🏷 Looks like: {}", source)).small().weak())
+                            .on_hover_text(format!(
+                                "Guessed from the filename. Search source:{} to find others like it; the rules are in Settings",
+                                source_hints::key(source)
+                            ));
+                        if image_info.checksum.is_some() {
+                            let line = match self.checksum_verification.status(&image_info.full_path) {
+                                Some(checksums::Status::Verified) => "🔐 Checksum verified".to_string(),
+                                Some(checksums::Status::Mismatch(actual)) => {
+                                    format!("🔐 Checksum mismatch: the file's SHA-256 is {}", actual)
+                                }
+                                Some(checksums::Status::Missing) => "🔐 Checksum not checked: file missing".to_string(),
+                                Some(checksums::Status::Unreadable(e)) => format!("🔐 Checksum not checked: {}", e),
+                                None => "🔐 Checksum not verified yet".to_string(),
+                            };
+                            ui.add(egui::Label::new(egui::RichText::new(line).small()).wrap(true));
+                        }
+                        ui.add_space(5.0);
+                        if !is_text {
+                            self.metadata_section(ctx, ui, &image_info);
+                        }
+                        ui.add_space(10.0);
+                        
+                        // Buttons in a horizontal layout
+                        ui.horizontal(|ui| {
+                            ui.add_space(20.0);
+                            
+                            let behavior = self.settings.copy_behavior(&category);
+                            let decodable = is_text || decoder_supports(&image_info.extension);
+                            // A pixel copy can't work without a decoder; Copy as file still does
+                            let can_copy = decodable || behavior.mode != settings::CopyMode::Pixels;
+                            // The row's quick actions, minus Details; the others wait in ⋯
+                            let (mut shown, others): (Vec<_>, Vec<_>) = settings::RowAction::ALL
+                                .into_iter()
+                                .filter(|action| *action != settings::RowAction::Details)
+                                .filter(|action| *action != settings::RowAction::CopyImage || can_copy)
+                                .partition(|action| self.settings.row_actions.contains(action));
+                            shown.sort_by_key(|action| self.settings.row_actions.iter().position(|a| a == action));
+                            let mut run = None;
+                            for action in shown {
+                                let label = match action {
+                                    settings::RowAction::CopyImage => self.copy_label(&image_info.full_path, "📋 Copy"),
+                                    _ => self.row_action_label(action, &image_info.full_path),
+                                };
+                                let hint = if action == settings::RowAction::CopyImage && !orientation.is_identity() {
+                                    "Copies the rotated pixels".to_string()
+                                } else {
+                                    self.row_action_hint(action, &category, &image_info)
+                                };
+                                if ui.button(egui::RichText::new(label).size(16.0)).on_hover_text(hint).clicked() {
+                                    run = Some(action);
+                                }
+                            }
+                            if !others.is_empty() {
+                                ui.menu_button(egui::RichText::new("⋯").size(16.0), |ui| {
+                                    for action in others {
+                                        if ui.button(action.button_label()).clicked() {
+                                            run = Some(action);
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            }
+                            match run {
+                                Some(settings::RowAction::CopyImage) if !orientation.is_identity() && !is_text => {
+                                    self.copy_pixels(&image_info, None, orientation);
+                                }
+                                Some(action) => self.run_row_action(action, &category, &image_info),
+                                None => {}
+                            }
+
+                            if !is_text && decodable && ui.button(egui::RichText::new("💾 Save as…").size(16.0)).clicked() {
+                                self.export_dialog = Some(ExportDialog::new(image_info.clone()));
+                            }
+                            
+                            ui.add_space(10.0);
+                            
+                            if ui.button(egui::RichText::new("❌ Close").size(16.0)).clicked() {
+                                self.detail_windows.retain(|view| view.id != id);
+                            }
+
+                            ui.add_space(10.0);
+                            if ui.button("◀").on_hover_text("Previous result (←)").clicked() {
+                                self.step_detail(id, -1);
+                            }
+                            if ui.button("▶").on_hover_text("Next result (→)").clicked() {
+                                self.step_detail(id, 1);
+                            }
+                        });
+                        
+                        ui.add_space(10.0);
+                    });
+                });
+            // Clicking into a window makes it the one the arrow keys move
+            let clicked_inside = window.is_some_and(|window| {
+                window.response.contains_pointer() && ctx.input(|i| i.pointer.any_pressed())
+            });
+            if clicked_inside {
+                self.activate_detail(id);
+            }
+        }
+
+        // Settings window
+        if self.show_settings {
+            let settings_before = self.settings.clone();
+            egui::Window::new("⚙️ Settings")
+                .collapsible(false)
+                .resizable(false)
+                .default_size([400.0, 300.0])
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().max_height(600.0).show(ui, |ui| {
+                        ui.add_space(10.0);
+                    
+                        ui.heading("Appearance");
+                        ui.add_space(5.0);
+                    
+                        ui.horizontal(|ui| {
+                            ui.label("Theme:");
+                            if ui.selectable_label(self.settings.dark_mode, "🌙 Dark").clicked() {
+                                self.settings.dark_mode = true;
+                            }
+                            if ui.selectable_label(!self.settings.dark_mode, "☀️ Light").clicked() {
+                                self.settings.dark_mode = false;
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Animations:");
+                            egui::ComboBox::from_id_source("animations_setting")
+                                .selected_text(self.settings.animations.label())
+                                .show_ui(ui, |ui| {
+                                    for option in Animations::ALL {
+                                        ui.selectable_value(&mut self.settings.animations, option, option.label());
+                                    }
+                                });
+                        });
+                        if self.settings.animations == Animations::Auto && self.os_reduced_motion {
+                            ui.label(
+                                egui::RichText::new("Your system asks for reduced motion, so animations are off.")
+                                    .small()
+                                    .weak(),
+                            );
+                        }
+
+                        let unsupported = settings::translucency_unsupported();
+                        ui.add_enabled_ui(unsupported.is_none(), |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Window opacity:");
+                                ui.add(
+                                    egui::Slider::new(&mut self.settings.window_opacity, 0.7..=1.0)
+                                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                                );
+                            });
+                        });
+                        let note = match unsupported {
+                            Some(reason) => Some(reason),
+                            None if cfg!(target_os = "linux") && std::env::var_os("WAYLAND_DISPLAY").is_none() => {
+                                Some("On X11 this needs a compositing window manager.")
+                            }
+                            None => None,
+                        };
+                        if let Some(note) = note {
+                            ui.label(egui::RichText::new(note).small().weak());
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label("Behind transparent images:");
+                            egui::ComboBox::from_id_source("preview_backdrop_setting")
+                                .selected_text(self.settings.preview_backdrop.label())
+                                .show_ui(ui, |ui| {
+                                    for option in settings::PreviewBackdrop::ALL {
+                                        ui.selectable_value(&mut self.settings.preview_backdrop, option, option.label());
+                                    }
+                                });
+                            if self.settings.preview_backdrop == settings::PreviewBackdrop::Fixed {
+                                ui.color_edit_button_srgb(&mut self.settings.backdrop_color);
+                            }
+                        })
+                        .response
+                        .on_hover_text("Auto puts a dark checkerboard behind light images and a light one behind dark images");
+                    
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+                    
+                        ui.heading("Hotkey");
+                        ui.add_space(5.0);
+                    
+                        ui.horizontal(|ui| {
+                            ui.label("Show/Hide Window:");
+                            ui.text_edit_singleline(&mut self.settings.hotkey);
+                        });
+                    
+                        match &self.hotkey.error {
+                            Some(error) => {
+                                ui.label(egui::RichText::new(error).small().color(ui.visuals().warn_fg_color));
+                            }
+                            None if self.hotkey.is_registered() => {
+                                ui.label(egui::RichText::new("Active; changes apply as you type").small().weak());
+                            }
+                            None => {}
+                        }
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Profiles");
+                        ui.add_space(5.0);
+                        self.profiles_ui(ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Favorites");
+                        ui.add_space(5.0);
+                        ui.checkbox(&mut self.settings.show_favorites_bar, "Show favorites quick bar");
+                        ui.horizontal(|ui| {
+                            ui.label("Pinned slots:");
+                            ui.add(egui::DragValue::new(&mut self.settings.pinned_slots).clamp_range(1..=pins::MAX_SLOTS));
+                        });
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Hidden images");
+                        ui.add_space(5.0);
+                        self.hidden_settings_ui(ctx, ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Category colors");
+                        ui.add_space(5.0);
+                        self.category_colors_settings_ui(ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Library integrity");
+                        ui.add_space(5.0);
+
+                        ui.horizontal(|ui| {
+                            ui.label("Images in several categories:");
+                            for policy in DuplicatePolicy::ALL {
+                                ui.selectable_value(&mut self.settings.duplicate_policy, policy, policy.label());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Images on a missing drive:");
+                            for option in settings::OfflineImages::ALL {
+                                ui.selectable_value(&mut self.settings.offline_images, option, option.label());
+                            }
+                        });
+
+                        ui.checkbox(&mut self.settings.integrity_hashing, "Hash files in the background to detect changes");
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{} files hashed, {} changed since last seen",
+                                self.integrity.hashed_count(),
+                                self.integrity.changed_count()
+                            ))
+                            .small()
+                            .weak(),
+                        );
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Privacy");
+                        ui.add_space(5.0);
+                        ui.checkbox(&mut self.settings.privacy_mode, "Privacy mode (F9)")
+                            .on_hover_text("Images marked sensitive stay concealed in every view, even ones revealed earlier. Useful while screen-sharing.");
+                        ui.checkbox(&mut self.settings.strip_metadata, "Strip metadata (EXIF, GPS) when copying")
+                            .on_hover_text("Image copies only ever contain pixels. With this on, \"Copy as file\" hands over a sanitized temporary copy when the original carries EXIF data.");
+                        ui.checkbox(&mut self.settings.record_usage, "Record which categories I copy from")
+                            .on_hover_text("Feeds the usage insights window. Kept on this computer only.");
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Usage data: {} copies", self.usage.len()));
+                            if ui.button("📊 Show").clicked() {
+                                self.show_usage = true;
+                            }
+                            if ui
+                                .add_enabled(!self.usage.is_empty(), egui::Button::new("🗑 Clear usage data"))
+                                .clicked()
+                            {
+                                self.clear_usage();
                             }
                         });
-                    
-                    // Update filter when category changes
-                    if prev_category != self.selected_category {
-                        self.show_all_categories = self.selected_category == "All Categories";
-                        self.update_filtered_images();
-                    }
-                    
-                    if ui.button("🔄 Refresh").clicked() {
-                        self.load_image_data();
-                    }
-                }
-            });
-            
-            ui.add_space(10.0);
-        });
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading(format!("Found {} images", self.filtered_images.len()));
-            
-            egui::ScrollArea::vertical()
-                .auto_shrink([false; 2])
-                .show_rows(ui, 80.0, self.filtered_images.len(), |ui, row_range| {
-                    for i in row_range {
-                        if let Some((category, image_info)) = self.filtered_images.get(i) {
-                            let category = category.clone();
-                            let image_info = image_info.clone();
-                        ui.group(|ui| {
-                            ui.horizontal(|ui| {
-                                if let Some(texture) = self.load_image_texture(ctx, &image_info) {
-                                    ui.image((texture.id(), egui::Vec2::new(64.0, 64.0)));
-                                } else {
-                                    // Show spinner while loading
-                                    ui.allocate_ui(egui::Vec2::new(64.0, 64.0), |ui| {
-                                        ui.centered_and_justified(|ui| {
-                                            ui.spinner();
-                                        });
-                                    });
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Search history: {} entries", self.search_history.len()));
+                            if ui
+                                .add_enabled(!self.search_history.is_empty(), egui::Button::new("🗑 Clear history"))
+                                .clicked()
+                            {
+                                self.search_history.clear();
+                                if let Err(e) = self.search_history.save() {
+                                    self.set_status(format!("Failed to save search history: {}", e));
                                 }
-                                
-                                ui.vertical(|ui| {
-                                    ui.strong(&image_info.filename);
-                                    ui.label(format!("📁 {}", category));
-                                    ui.label(format!("📊 {} KB", image_info.size / 1024));
-                                    ui.label(format!("📍 {}", image_info.relative_path));
+                            }
+                        });
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Startup checks");
+                        ui.add_space(5.0);
+                        let checks = &mut self.settings.health_checks;
+                        ui.checkbox(&mut checks.manifest, "Manifest is readable");
+                        ui.checkbox(&mut checks.sample_files, "Sample of image files exists");
+                        ui.checkbox(&mut checks.cache_dir, "Cache directory is writable");
+                        ui.checkbox(&mut checks.clipboard, "Clipboard is accessible");
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        let heading = ui.heading("Copying");
+                        if std::mem::take(&mut self.focus_copy_settings) {
+                            heading.scroll_to_me(Some(egui::Align::TOP));
+                        }
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Default copy:");
+                            egui::ComboBox::from_id_source("default_copy_mode")
+                                .selected_text(self.settings.default_copy.mode.label())
+                                .show_ui(ui, |ui| {
+                                    for mode in settings::CopyMode::ALL {
+                                        ui.selectable_value(&mut self.settings.default_copy.mode, mode, mode.label());
+                                    }
                                 });
-                                
-                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                    if ui.button("📋 Copy Image").clicked() {
-                                        self.copy_image_to_clipboard(&image_info);
+                            if self.settings.default_copy.mode == settings::CopyMode::Pixels {
+                                egui::ComboBox::from_id_source("default_copy_resize")
+                                    .selected_text(settings::resize_label(self.settings.default_copy.max_dimension))
+                                    .show_ui(ui, |ui| {
+                                        for preset in settings::COPY_RESIZE_PRESETS {
+                                            ui.selectable_value(
+                                                &mut self.settings.default_copy.max_dimension,
+                                                preset,
+                                                settings::resize_label(preset),
+                                            );
+                                        }
+                                    });
+                            }
+                        });
+                        if !self.settings.category_copy.is_empty() {
+                            ui.label("Category overrides:");
+                            let mut remove = None;
+                            for (category, behavior) in &self.settings.category_copy {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{} → {}", category, behavior.describe()));
+                                    if ui.small_button("✖").on_hover_text("Remove override").clicked() {
+                                        remove = Some(category.clone());
                                     }
-                                    
-                                    if ui.button("👁️ View Details").clicked() {
-                                        self.selected_image = Some((category.clone(), image_info.clone()));
+                                });
+                            }
+                            if let Some(category) = remove {
+                                self.settings.category_copy.remove(&category);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Ask before copying images over");
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.large_copy_warning_mp)
+                                    .clamp_range(0.0..=1000.0)
+                                    .suffix(" MP"),
+                            );
+                        })
+                        .response
+                        .on_hover_text("0 turns the question off");
+                        ui.horizontal(|ui| {
+                            ui.label("If the clipboard can't be opened:");
+                            egui::ComboBox::from_id_source("clipboard_fallback")
+                                .selected_text(self.settings.clipboard_fallback.label())
+                                .show_ui(ui, |ui| {
+                                    for fallback in settings::ClipboardFallback::ALL {
+                                        ui.selectable_value(&mut self.settings.clipboard_fallback, fallback, fallback.label());
                                     }
                                 });
+                        });
+                        ui.label(
+                            egui::RichText::new("Set per-category overrides with the ⚙ button next to the category picker.")
+                                .small()
+                                .weak(),
+                        );
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Combined copies");
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Padding:");
+                            ui.add(egui::DragValue::new(&mut self.settings.combine.padding).clamp_range(0..=64).suffix(" px"));
+                            ui.label("Max image size:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.combine.max_dimension)
+                                    .clamp_range(64..=2048)
+                                    .suffix(" px"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Background:");
+                            let [r, g, b, a] = self.settings.combine.background;
+                            let mut color = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+                            if ui.color_edit_button_srgba(&mut color).changed() {
+                                self.settings.combine.background = color.to_srgba_unmultiplied();
+                            }
+                        });
+                        ui.label(
+                            egui::RichText::new("Ctrl+click filenames to select 2–12 images, then use \"Copy as combined image\".")
+                                .small()
+                                .weak(),
+                        );
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Open with");
+                        ui.add_space(5.0);
+                        self.open_with_settings_ui(ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Text entries");
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Text extensions:");
+                            ui.text_edit_singleline(&mut self.settings.text_extensions);
+                        });
+                        ui.label(
+                            egui::RichText::new("Comma-separated. These files get a text preview and Copy puts their contents on the clipboard.")
+                                .small()
+                                .weak(),
+                        );
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Source hints");
+                        ui.add_space(5.0);
+                        self.source_rules_settings_ui(ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Performance");
+                        ui.add_space(5.0);
+                        ui.checkbox(
+                            &mut self.settings.auto_download_cloud_files,
+                            "Download cloud-only files automatically",
+                        )
+                        .on_hover_text(
+                            "OneDrive and iCloud files that aren't on this device are downloaded as soon as \
+                             their row scrolls into view. When off, they show a ☁ button instead.",
+                        );
+                        ui.checkbox(&mut self.settings.low_memory, "Low memory mode")
+                            .on_hover_text(
+                                "Keeps at most 100 thumbnails in memory instead of 2000. \
+                                 Scrolling back re-decodes thumbnails, so it uses more CPU and disk. \
+                                 Press F12 to see the memory estimate.",
+                            );
+                        ui.checkbox(&mut self.settings.text_only, "Text-only mode")
+                            .on_hover_text(
+                                "Shows extension tiles instead of thumbnails and never reads images to draw them. \
+                                 For remote desktop sessions and screen readers; search and copying work as usual.",
+                            );
+                        ui.checkbox(&mut self.settings.category_peek, "Preview categories on hover")
+                            .on_hover_text(
+                                "Hovering a category in the dropdown shows its first few thumbnails. \
+                                 Turn off on slow disks, since it decodes images that aren't in the results.",
+                            );
+                        ui.add_space(5.0);
+                        self.dimension_index_settings(ui);
+                        ui.add_space(5.0);
+                        self.thumbnail_cache_settings(ui);
+
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Remote control");
+                        ui.add_space(5.0);
+                        ui.checkbox(&mut self.settings.remote_control.enabled, "Enable local HTTP endpoint");
+                        ui.add_enabled_ui(self.settings.remote_control.enabled, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Port:");
+                                ui.add(egui::DragValue::new(&mut self.settings.remote_control.port).clamp_range(1024..=65535));
+                                ui.label(egui::RichText::new("(127.0.0.1 only)").small().weak());
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Token:");
+                                ui.monospace(&self.settings.remote_control.token);
+                                if ui.small_button("📋").on_hover_text("Copy token").clicked() {
+                                    let token = self.settings.remote_control.token.clone();
+                                    self.copy_text_to_clipboard(token, "token");
+                                }
+                                if ui.small_button("🔄").on_hover_text("Generate a new token").clicked() {
+                                    self.settings.remote_control.token = remote::generate_token();
+                                }
                             });
+                            ui.label(egui::RichText::new(remote::API_HELP).monospace().small());
+                            ui.label(
+                                egui::RichText::new("Links opened while Chlorine is running are passed to it through this endpoint.")
+                                    .small()
+                                    .weak(),
+                            );
                         });
-                        
                         ui.add_space(5.0);
-                    }
-                }
-                });
-        });
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(format!("🔗 Open {}:// links with Chlorine", deep_link::SCHEME))
+                                .on_hover_text("Registers this executable as the link handler for your user account")
+                                .clicked()
+                            {
+                                self.set_status(match deep_link::register() {
+                                    Ok(message) => message,
+                                    Err(e) => format!("Couldn't register links: {}", e),
+                                });
+                            }
+                        });
 
-        if let Some((category, image_info)) = &self.selected_image {
-            let category = category.clone();
-            let image_info = image_info.clone();
-            
-            egui::Window::new(&image_info.filename)
-                .collapsible(false)
-                .resizable(true)
-                .default_size([500.0, 500.0])
-                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
-                .show(ctx, |ui| {
-                    ui.vertical_centered(|ui| {
-                        // Display image in a square area
-                        if let Some(texture) = self.load_image_texture(ctx, &image_info) {
-                            let available_width = ui.available_width();
-                            let max_size = available_width.min(450.0);
-                            
-                            // Make it square by using the same dimension for both width and height
-                            let scale = (max_size / texture.size_vec2().x).min(max_size / texture.size_vec2().y).min(1.0);
-                            let display_size = texture.size_vec2() * scale;
-                            
-                            ui.add_space(10.0);
-                            ui.image((texture.id(), display_size));
-                            ui.add_space(10.0);
-                        } else {
-                            // Show spinner while loading
-                            ui.add_space(200.0);
-                            ui.spinner();
-                            ui.add_space(200.0);
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Behavior");
+                        ui.add_space(5.0);
+
+                        ui.horizontal(|ui| {
+                            ui.label("Enter on top result:");
+                            for action in [PrimaryAction::Copy, PrimaryAction::OpenDetails] {
+                                ui.selectable_value(&mut self.settings.primary_action, action, action.label());
+                            }
+                        });
+                        self.row_action_settings(ui);
+                        ui.checkbox(&mut self.settings.watch_folders, "Watch category folders for changes")
+                            .on_hover_text(
+                                "New image files are added to their category, deleted ones show as missing, \
+                                 and edited ones are redrawn. Large libraries are checked every 30 seconds.",
+                            );
+                        ui.checkbox(&mut self.settings.stack_sequences, "Stack numbered frames into one row")
+                            .on_hover_text("frame_0001.png … frame_0240.png in one category show as a single expandable row");
+                        ui.horizontal(|ui| {
+                            ui.label("Detail windows open at once:");
+                            ui.add(egui::DragValue::new(&mut self.settings.max_detail_windows).clamp_range(1..=12));
+                        })
+                        .response
+                        .on_hover_text("Opening one more closes the oldest. Each open window keeps its full-size image in memory.");
+                    
+                        ui.add_space(15.0);
+                        ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("Backup");
+                        ui.add_space(5.0);
+
+                        ui.horizontal(|ui| {
+                            if ui.button("📤 Export settings…").clicked() {
+                                self.export_settings();
+                            }
+                            if ui.button("📥 Import settings…").clicked() {
+                                self.import_settings();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("📤 Export user data…")
+                                .on_hover_text("Favorites, hidden images and pinned slots, by path inside the library")
+                                .clicked()
+                            {
+                                self.export_user_data();
+                            }
+                            if ui.button("📥 Import user data…").clicked() {
+                                self.import_user_data();
+                            }
+                        });
+
+                        ui.add_space(5.0);
+                        if self.confirm_reset_settings {
+                            ui.horizontal(|ui| {
+                                ui.label("Reset every setting to its default?");
+                                if ui.button("Reset").clicked() {
+                                    self.reset_settings();
+                                    self.confirm_reset_settings = false;
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    self.confirm_reset_settings = false;
+                                }
+                            });
+                        } else if ui.button("↺ Reset all settings to defaults").clicked() {
+                            self.confirm_reset_settings = true;
                         }
-                        
-                        // Show filename and category
+
+                        ui.add_space(15.0);
                         ui.separator();
+                        ui.add_space(15.0);
+
+                        ui.heading("About");
                         ui.add_space(5.0);
-                        ui.label(egui::RichText::new(&image_info.filename).strong().size(14.0));
-                        ui.label(format!("📁 {}", category));
-                        ui.add_space(10.0);
-                        
-                        // Buttons in a horizontal layout
+
+                        ui.label(format!("Chlorine {}", env!("CARGO_PKG_VERSION")));
+                        self.update_check_ui(ui);
+                        if portable::is_enabled() {
+                            ui.label("💾 Portable mode: everything is stored beside the executable.");
+                        }
+                        let data_dir = settings::config_dir();
                         ui.horizontal(|ui| {
-                            ui.add_space(20.0);
-                            
-                            if ui.button(egui::RichText::new("📋 Copy").size(16.0)).clicked() {
-                                self.copy_image_to_clipboard(&image_info);
+                            ui.label("Data folder:");
+                            ui.monospace(data_dir.display().to_string());
+                            if ui.button("📂 Open").clicked() {
+                                let opened = std::fs::create_dir_all(&data_dir).and_then(|()| open_in_file_manager(&data_dir));
+                                if let Err(e) = opened {
+                                    self.set_status(format!("Failed to open {}: {}", data_dir.display(), e));
+                                }
                             }
-                            
-                            ui.add_space(10.0);
-                            
-                            if ui.button(egui::RichText::new("❌ Close").size(16.0)).clicked() {
-                                self.selected_image = None;
+                        });
+
+                        ui.add_space(20.0);
+                        ui.separator();
+                        ui.add_space(10.0);
+                    
+                        ui.horizontal(|ui| {
+                            ui.add_space(80.0);
+                            if ui.button(egui::RichText::new("✓ Close").size(16.0)).clicked() {
+                                self.show_settings = false;
                             }
                         });
-                        
+                    
                         ui.add_space(10.0);
                     });
                 });
+
+            if self.settings != settings_before {
+                self.persist_settings();
+            }
         }
 
-        // Settings window
-        if self.show_settings {
-            egui::Window::new("⚙️ Settings")
+        if self.show_health {
+            self.health_window(ctx);
+        }
+        if self.show_usage {
+            self.usage_window(ctx);
+        }
+
+        self.export_dialog_window(ctx);
+        self.grouping_dialog_window(ctx);
+        self.import_dialog_window(ctx);
+        self.zip_import_window(ctx);
+        self.results_category_window(ctx);
+        self.optimize_dialog_window(ctx);
+        self.removal_review_window(ctx);
+        self.user_data_import_window(ctx);
+        self.duplicate_prompt_window(ctx);
+        self.pin_confirm_window(ctx);
+        self.large_copy_window(ctx);
+        self.overwrite_confirm_window(ctx);
+        self.refresh_confirm_window(ctx);
+        self.manifest_error_window(ctx);
+        self.palette_window(ctx);
+        if self.show_debug_overlay {
+            self.debug_overlay(ctx);
+        }
+
+        // Shortcut cheat sheet
+        if self.show_shortcuts {
+            let enter_action = match self.settings.primary_action {
+                PrimaryAction::Copy => "Copy the top result",
+                PrimaryAction::OpenDetails => "Open the top result",
+            };
+            let shortcuts = shortcuts::ALL.iter().map(|shortcut| match shortcut.binding {
+                shortcuts::Binding::ActivateTop => ("Enter (in search)", enter_action),
+                _ => (shortcut.keys, shortcut.description),
+            });
+
+            egui::Window::new("⌨ Keyboard Shortcuts")
                 .collapsible(false)
                 .resizable(false)
-                .default_size([400.0, 300.0])
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
+                    egui::Grid::new("shortcuts_grid")
+                        .num_columns(2)
+                        .spacing([20.0, 6.0])
+                        .show(ui, |ui| {
+                            for (keys, description) in shortcuts {
+                                ui.strong(keys);
+                                ui.label(description);
+                                ui.end_row();
+                            }
+                        });
+
                     ui.add_space(10.0);
-                    
-                    ui.heading("Appearance");
-                    ui.add_space(5.0);
-                    
-                    ui.horizontal(|ui| {
-                        ui.label("Theme:");
-                        if ui.selectable_label(self.settings.dark_mode, "🌙 Dark").clicked() {
-                            self.settings.dark_mode = true;
-                        }
-                        if ui.selectable_label(!self.settings.dark_mode, "☀️ Light").clicked() {
-                            self.settings.dark_mode = false;
-                        }
-                    });
-                    
-                    ui.add_space(15.0);
-                    ui.separator();
-                    ui.add_space(15.0);
-                    
-                    ui.heading("Hotkey");
-                    ui.add_space(5.0);
-                    
-                    ui.horizontal(|ui| {
-                        ui.label("Show/Hide Window:");
-                        ui.text_edit_singleline(&mut self.settings.hotkey);
-                    });
-                    
-                    ui.label(egui::RichText::new("Note: Hotkey requires app restart").small().weak());
-                    
-                    ui.add_space(20.0);
-                    ui.separator();
-                    ui.add_space(10.0);
-                    
-                    ui.horizontal(|ui| {
-                        ui.add_space(80.0);
-                        if ui.button(egui::RichText::new("✓ Close").size(16.0)).clicked() {
-                            self.show_settings = false;
-                        }
-                    });
-                    
-                    ui.add_space(10.0);
+                    if ui.button("✓ Close").clicked() {
+                        self.show_shortcuts = false;
+                    }
                 });
         }
+        self.frame_guard.end();
+    }
+}
+
+/// Reads and downscales one file for a result row. Also used by `--bench`.
+struct ManifestErrorView {
+    path: String,
+    line: usize,
+    column: usize,
+    message: String,
+    excerpt: Result<status::Excerpt, String>,
+}
+
+impl ManifestErrorView {
+    /// The error and the lines around it as plain text, for a bug report or a chat.
+    fn report(&self) -> String {
+        let mut report = format!("{}\n{}, line {}, column {}\n", self.message, self.path, self.line, self.column);
+        if let Ok(excerpt) = &self.excerpt {
+            report.push('\n');
+            for (number, text) in &excerpt.lines {
+                report.push_str(&format!("{:>6}  {}\n", number, text));
+                if *number == self.line {
+                    report.push_str(&format!("{:>6}  {}^\n", "", " ".repeat(excerpt.caret)));
+                }
+            }
+        }
+        report
+    }
+}
+
+/// One open detail window.
+#[derive(Clone)]
+struct DetailView {
+    id: u64,
+    category: String,
+    image: ImageInfo,
+    /// Rotation and flip applied from the window; reset on navigation.
+    orientation: orientation::Orientation,
+}
+
+/// A manifest parsed, grouped and indexed off the UI thread.
+struct LoadedLibrary {
+    data: ImageData,
+    grouping: std::collections::BTreeMap<String, grouping::GroupingRule>,
+    /// Empty when the duplicate policy already removed them.
+    duplicates: HashMap<String, Vec<String>>,
+    index: search::SearchIndex,
+    read_only: Option<String>,
+    offline: offline::OfflineRoots,
+}
+
+fn load_library(
+    manifest_path: &str,
+    policy: DuplicatePolicy,
+    sources: &source_hints::Classifier,
+) -> Result<LoadedLibrary, ChlorineError> {
+    let manifest = manifest::load(manifest_path)?;
+    let mut data = manifest.data;
+    grouping::apply(&mut data, &manifest.grouping);
+    let mut duplicates = duplicates::find(&data);
+    if policy == DuplicatePolicy::KeepFirst && !duplicates.is_empty() {
+        duplicates::keep_first(&mut data, &duplicates);
+        duplicates.clear();
+    }
+    Ok(LoadedLibrary {
+        index: search::SearchIndex::build(&data, sources),
+        offline: offline::OfflineRoots::probe(&data),
+        read_only: manifest::read_only_reason(manifest_path),
+        grouping: manifest.grouping,
+        duplicates,
+        data,
+    })
+}
+
+/// The smallest tier that covers `pixels` within the upscale tolerance.
+fn thumbnail_tier(pixels: f32) -> u32 {
+    THUMBNAIL_TIERS
+        .into_iter()
+        .find(|&tier| tier as f32 * THUMBNAIL_UPSCALE_TOLERANCE >= pixels)
+        .unwrap_or(THUMBNAIL_TIERS[THUMBNAIL_TIERS.len() - 1])
+}
+
+/// Reads and decodes a full image, then applies a detail window's rotation.
+fn decode_oriented(path: &str, orientation: orientation::Orientation) -> Result<image::DynamicImage, LoaderError> {
+    let bytes = std::fs::read(path).map_err(|e| LoaderError::io(path, e))?;
+    let img = formats::decode(path, &bytes).map_err(|message| LoaderError::Decode {
+        path: path.to_string(),
+        message,
+    })?;
+    Ok(orientation.apply(img))
+}
+
+/// Decodes a thumbnail at `tier`, going through the disk cache when there is one.
+fn decode_thumbnail(
+    path: &str,
+    is_text: bool,
+    tier: u32,
+    cache: Option<&thumbnail_cache::ThumbnailCache>,
+) -> Result<Thumbnail, LoaderError> {
+    if is_text {
+        return snippet::preview(path)
+            .map(|image| Thumbnail {
+                image,
+                colors: None,
+                dimensions: None,
+                luminance: None,
+                tier: FIXED_TIER,
+            })
+            .ok_or_else(|| LoaderError::Decode {
+                path: path.to_string(),
+                message: "not a readable text file".to_string(),
+            });
+    }
+    
+    if let Some(rgba) = cache.and_then(|cache| cache.get(path, tier)) {
+        // Full-size dimensions come from the dimension index instead
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        return Ok(Thumbnail {
+            colors: Some(colors::signature(&rgba)),
+            luminance: backdrop::luminance(&rgba),
+            image: egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()),
+            dimensions: None,
+            tier,
+        });
     }
+
+    let image_data = std::fs::read(path).map_err(|e| LoaderError::io(path, e))?;
+    if image_data.is_empty() {
+        return Err(LoaderError::Unreadable {
+            path: path.to_string(),
+            message: "file is empty".to_string(),
+        });
+    }
+    let img = formats::decode(path, &image_data).map_err(|message| LoaderError::Decode {
+        path: path.to_string(),
+        message,
+    })?;
+    
+    let dimensions = (img.width(), img.height());
+
+    // Resize to the tier for better performance
+    let thumbnail = img.thumbnail(tier, tier);
+    let rgba = thumbnail.to_rgba8();
+    let signature = colors::signature(&rgba);
+    let luminance = backdrop::luminance(&rgba);
+    if let Some(cache) = cache {
+        cache.put(path, tier, &rgba);
+    }
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    let pixels = rgba.into_raw();
+    
+    Ok(Thumbnail {
+        image: egui::ColorImage::from_rgba_unmultiplied(size, &pixels),
+        colors: Some(signature),
+        dimensions: Some(dimensions),
+        luminance,
+        tier,
+    })
 }
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(bench_args) = bench::parse_args(&args) {
+        match bench_args.and_then(bench::run) {
+            Ok(report) => {
+                println!("{}", report);
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("chlorine --bench: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if portable::requested(&args) {
+        if let Err(e) = portable::enable() {
+            eprintln!("chlorine {}: {}", portable::FLAG, e);
+        }
+    }
+
+    let safe_mode = safe_mode::requested(&args);
+    if safe_mode {
+        safe_mode::prepare_scratch_dir();
+    }
+
+    // A link for a running instance is handed over instead of opening a second window
+    let link = deep_link::from_args(&args);
+    if let (Some(link), false) = (&link, safe_mode) {
+        let remote = settings::load().remote_control;
+        if remote.enabled && remote::forward_link(remote.port, &remote.token, link).is_ok() {
+            return Ok(());
+        }
+    }
+
     // Load icon
     let icon_data = load_icon();
     
@@ -466,15 +10069,18 @@ fn main() -> Result<(), eframe::Error> {
             .with_inner_size([1200.0, 800.0])
             .with_min_inner_size([800.0, 600.0])
             .with_title("Chlorine")
-            .with_icon(icon_data),
+            .with_icon(icon_data)
+            .with_transparent(settings::translucency_unsupported().is_none()),
         ..Default::default()
     };
 
     eframe::run_native(
         "Chlorine",
         options,
-        Box::new(|_cc| {
-            Box::new(ImageSearchApp::default())
+        Box::new(move |_cc| {
+            let mut app = ImageSearchApp::new(safe_mode);
+            app.pending_link = link;
+            Box::new(app)
         }),
     )
 }
@@ -503,3 +10109,20 @@ fn load_icon() -> egui::IconData {
         height: 1,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_bytes_boundaries() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        // Rounds up into the next unit rather than showing "1024.0 KB"
+        assert_eq!(format_bytes(1_048_575), "1.0 MB");
+        assert_eq!(format_bytes(1_048_576), "1.0 MB");
+        // TB is the largest unit, so it keeps counting past 1024
+        assert_eq!(format_bytes(u64::MAX), "16777216.0 TB");
+    }
+}
@@ -0,0 +1,606 @@
+//! Reading the image manifest and the small block of Chlorine metadata stored in it.
+//!
+//! The metadata lives under a top-level `"chlorine"` key that manifest
+//! generators ignore; everything else in the file is preserved on write.
+
+use crate::error::ManifestError;
+use crate::grouping::GroupingRule;
+use crate::{ImageData, ImageInfo};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+const METADATA_KEY: &str = "chlorine";
+const GROUPING_KEY: &str = "grouping";
+
+pub struct Manifest {
+    pub data: ImageData,
+    /// Category grouping rules, keyed by the category they split.
+    pub grouping: BTreeMap<String, GroupingRule>,
+}
+
+pub fn load(path: &str) -> Result<Manifest, ManifestError> {
+    let content = std::fs::read_to_string(path).map_err(|e| ManifestError::io(path, e))?;
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| ManifestError::json(path, e))?;
+    let grouping = value
+        .get(METADATA_KEY)
+        .and_then(|metadata| metadata.get(GROUPING_KEY))
+        .and_then(|rules| serde_json::from_value(rules.clone()).ok())
+        .unwrap_or_default();
+    let data = serde_json::from_value(value).map_err(|e| ManifestError::json(path, e))?;
+    Ok(Manifest { data, grouping })
+}
+
+/// Explains why the manifest can't be rewritten, or None if it can.
+///
+/// The file's permissions are checked directly; the directory is probed by
+/// creating and removing a scratch file, since that's the only reliable test
+/// on network shares.
+pub fn read_only_reason(path: &str) -> Option<String> {
+    let path = std::path::Path::new(path);
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().readonly() => {
+            return Some(format!("{} is write-protected", path.display()));
+        }
+        Ok(_) => {}
+        Err(e) => return Some(format!("{}: {}", path.display(), e)),
+    }
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    let probe = directory.join(format!(".chlorine-write-test-{}", std::process::id()));
+    match std::fs::OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            None
+        }
+        Err(e) => Some(format!("Can't write to {}: {}", directory.display(), e)),
+    }
+}
+
+/// Atomically replaces the manifest: the JSON is serialized into memory,
+/// written to a temp file next to it, then renamed over the original.
+///
+/// If the rename fails the temp file is left in place and the error names it,
+/// so the edit can be recovered by hand.
+pub fn save(path: &str, value: &serde_json::Value) -> Result<(), ManifestError> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| ManifestError::json(path, e))?;
+    let temp = format!("{}.saving", path);
+    if let Err(e) = std::fs::write(&temp, &json) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(ManifestError::io(&temp, e));
+    }
+    std::fs::rename(&temp, path).map_err(|e| ManifestError::Validation {
+        path: path.to_string(),
+        message: format!("could not replace it: {} (the new manifest is saved as {})", e, temp),
+    })
+}
+
+/// A change to the manifest's JSON, applied on the save thread. Mutations can
+/// run more than once, so a failed save can be retried.
+pub type Mutation = Box<dyn Fn(&mut serde_json::Value) -> Result<(), String> + Send>;
+
+/// Records (or with None, removes) the grouping rule for a category, leaving the rest of the file untouched.
+pub fn set_grouping(category: String, rule: Option<GroupingRule>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let root = value.as_object_mut().ok_or("expected a JSON object")?;
+        let metadata = root
+            .entry(METADATA_KEY)
+            .or_insert_with(|| serde_json::json!({}));
+        if !metadata.is_object() {
+            *metadata = serde_json::json!({});
+        }
+        let rules = metadata
+            .as_object_mut()
+            .map(|m| m.entry(GROUPING_KEY).or_insert_with(|| serde_json::json!({})))
+            .ok_or("invalid metadata block")?;
+        if !rules.is_object() {
+            *rules = serde_json::json!({});
+        }
+        let rules = rules.as_object_mut().ok_or("invalid grouping block")?;
+        match &rule {
+            Some(rule) => {
+                rules.insert(category.clone(), serde_json::to_value(rule).map_err(|e| e.to_string())?);
+            }
+            None => {
+                rules.remove(&category);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Appends entries to an existing category and updates its count and the file's total.
+pub fn add_images(category: String, images: Vec<ImageInfo>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let entries = value
+            .get_mut("categories")
+            .and_then(|categories| categories.get_mut(&category))
+            .and_then(|category| category.as_object_mut())
+            .ok_or_else(|| format!("no category \"{}\"", category))?;
+        let list = entries
+            .get_mut("images")
+            .and_then(|images| images.as_array_mut())
+            .ok_or_else(|| format!("category \"{}\" has no image list", category))?;
+        for image in &images {
+            list.push(serde_json::to_value(image).map_err(|e| e.to_string())?);
+        }
+        let count = list.len();
+        entries.insert("count".to_string(), serde_json::json!(count));
+
+        if let Some(total) = value.get("total_images").and_then(|total| total.as_u64()) {
+            value["total_images"] = serde_json::json!(total + images.len() as u64);
+        }
+        Ok(())
+    })
+}
+
+/// Adds a new category holding `images`. Fails if the name is already taken.
+pub fn create_category(category: String, directory: String, images: Vec<ImageInfo>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let categories = value
+            .as_object_mut()
+            .ok_or("expected a JSON object")?
+            .entry("categories")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or("invalid categories block")?;
+        if categories.contains_key(&category) {
+            return Err(format!("category \"{}\" already exists", category));
+        }
+        let list = images
+            .iter()
+            .map(|image| serde_json::to_value(image).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        categories.insert(
+            category.clone(),
+            serde_json::json!({ "directory": directory, "images": list, "count": images.len() }),
+        );
+
+        if let Some(total) = value.get("total_images").and_then(|total| total.as_u64()) {
+            value["total_images"] = serde_json::json!(total + images.len() as u64);
+        }
+        Ok(())
+    })
+}
+
+/// Points every entry for `full_path` at `replacement` (new name, extension or size), in all categories.
+pub fn replace_image(full_path: String, replacement: ImageInfo) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let replacement = serde_json::to_value(&replacement).map_err(|e| e.to_string())?;
+        let categories = value
+            .get_mut("categories")
+            .and_then(|categories| categories.as_object_mut())
+            .ok_or("no categories")?;
+        for category in categories.values_mut() {
+            let Some(list) = category.get_mut("images").and_then(|images| images.as_array_mut()) else {
+                continue;
+            };
+            for image in list.iter_mut() {
+                if image.get("full_path").and_then(|path| path.as_str()) == Some(full_path.as_str()) {
+                    *image = replacement.clone();
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Writes `width` and `height` onto every entry for each path, in all categories.
+pub fn set_dimensions(sizes: HashMap<String, (u32, u32)>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let categories = value
+            .get_mut("categories")
+            .and_then(|categories| categories.as_object_mut())
+            .ok_or("no categories")?;
+        for category in categories.values_mut() {
+            let Some(list) = category.get_mut("images").and_then(|images| images.as_array_mut()) else {
+                continue;
+            };
+            for image in list.iter_mut() {
+                let size = image
+                    .get("full_path")
+                    .and_then(|path| path.as_str())
+                    .and_then(|path| sizes.get(path));
+                if let (Some(&(width, height)), Some(entry)) = (size, image.as_object_mut()) {
+                    entry.insert("width".to_string(), serde_json::json!(width));
+                    entry.insert("height".to_string(), serde_json::json!(height));
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Applies several edits as one; stops at the first that fails, and the save writes nothing.
+pub fn batch(mutations: Vec<Mutation>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| mutations.iter().try_for_each(|mutation| mutation(value)))
+}
+
+/// Removes every entry for `full_path` from the given categories, updating counts and the file's total.
+pub fn remove_image(full_path: String, categories: Vec<String>) -> Mutation {
+    Box::new(move |value: &mut serde_json::Value| {
+        let mut removed = 0;
+        for category in &categories {
+            let Some(entries) = value
+                .get_mut("categories")
+                .and_then(|all| all.get_mut(category))
+                .and_then(|category| category.as_object_mut())
+            else {
+                continue;
+            };
+            let Some(list) = entries.get_mut("images").and_then(|images| images.as_array_mut()) else {
+                continue;
+            };
+            let before = list.len();
+            list.retain(|image| image.get("full_path").and_then(|path| path.as_str()) != Some(full_path.as_str()));
+            removed += before - list.len();
+            let count = list.len();
+            entries.insert("count".to_string(), serde_json::json!(count));
+        }
+
+        if let Some(total) = value.get("total_images").and_then(|total| total.as_u64()) {
+            value["total_images"] = serde_json::json!(total.saturating_sub(removed as u64));
+        }
+        Ok(())
+    })
+}
+
+struct SaveJob {
+    path: String,
+    mutation: Mutation,
+}
+
+struct SaveOutcome {
+    /// How many submitted mutations this save covered.
+    applied: usize,
+    path: String,
+    /// Whether the file was written, even if some edits were left out.
+    wrote: bool,
+    /// The mutations that didn't reach the file come back, so they can be retried.
+    result: Result<(), (ManifestError, Vec<Mutation>)>,
+}
+
+/// Applies manifest edits and writes the file on a background thread, so a
+/// large manifest doesn't freeze the UI.
+///
+/// Edits submitted while a save is running wait in the queue and go into the
+/// next pass, which re-reads the file first; nothing is dropped and two
+/// writes never overlap. An edit that doesn't apply is set aside on its own,
+/// without holding back the rest of its pass; failed edits are kept until
+/// they are retried or discarded.
+pub struct ManifestSaver {
+    sender: Sender<SaveJob>,
+    receiver: Receiver<SaveOutcome>,
+    pending: usize,
+    /// Path, edits, and whether they were rejected as not applying (rather than
+    /// the file being unreadable or unwritable), which retrying can't fix.
+    failed: Vec<(String, Vec<Mutation>, bool)>,
+}
+
+impl ManifestSaver {
+    pub fn new() -> Self {
+        let (sender, jobs) = mpsc::channel::<SaveJob>();
+        let (outcomes, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Ok(first) = jobs.recv() {
+                let mut batch: Vec<(String, Vec<Mutation>)> = Vec::new();
+                for job in std::iter::once(first).chain(jobs.try_iter()) {
+                    match batch.iter_mut().find(|(path, _)| *path == job.path) {
+                        Some((_, mutations)) => mutations.push(job.mutation),
+                        None => batch.push((job.path, vec![job.mutation])),
+                    }
+                }
+                for (path, mutations) in batch {
+                    let applied = mutations.len();
+                    let (wrote, result) = apply_and_save(&path, mutations);
+                    if outcomes.send(SaveOutcome { applied, path, wrote, result }).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Self {
+            sender,
+            receiver,
+            pending: 0,
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn submit(&mut self, path: &str, mutation: Mutation) {
+        let job = SaveJob {
+            path: path.to_string(),
+            mutation,
+        };
+        if self.sender.send(job).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// True while submitted edits haven't been written yet.
+    pub fn is_saving(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Results of the saves finished since the last call, oldest first. A
+    /// save that wrote some edits but left others out gives an `Ok` and an `Err`.
+    pub fn poll(&mut self) -> Vec<Result<(), ManifestError>> {
+        let mut results = Vec::new();
+        while let Ok(outcome) = self.receiver.try_recv() {
+            self.pending = self.pending.saturating_sub(outcome.applied);
+            if outcome.wrote && outcome.result.is_err() {
+                results.push(Ok(()));
+            }
+            results.push(outcome.result.map_err(|(error, mutations)| {
+                let invalid = matches!(error, ManifestError::Validation { .. });
+                self.failed.push((outcome.path, mutations, invalid));
+                error
+            }));
+        }
+        results
+    }
+
+    /// Edits whose save failed and that haven't been retried or discarded.
+    pub fn unsaved(&self) -> usize {
+        self.failed.iter().map(|(_, mutations, _)| mutations.len()).sum()
+    }
+
+    /// True while any edit is not on disk yet, in flight or failed.
+    pub fn is_dirty(&self) -> bool {
+        self.is_saving() || !self.failed.is_empty()
+    }
+
+    /// Queues the failed edits again, except the ones that didn't apply to the
+    /// manifest, which are dropped. Returns how many were dropped.
+    pub fn retry(&mut self) -> usize {
+        let mut dropped = 0;
+        for (path, mutations, invalid) in std::mem::take(&mut self.failed) {
+            if invalid {
+                dropped += mutations.len();
+                continue;
+            }
+            for mutation in mutations {
+                self.submit(&path, mutation);
+            }
+        }
+        dropped
+    }
+
+    pub fn discard_failed(&mut self) {
+        self.failed.clear();
+    }
+}
+
+impl Default for ManifestSaver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Re-reads the file so edits made by earlier passes (or other tools) are kept.
+///
+/// An edit that doesn't apply is left out and the rest are still written; it
+/// comes back with its error, as do all of them when the file can't be read
+/// or written. The flag says whether anything was written.
+fn apply_and_save(path: &str, mutations: Vec<Mutation>) -> (bool, Result<(), (ManifestError, Vec<Mutation>)>) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return (false, Err((ManifestError::io(path, e), mutations))),
+    };
+    let parse = || serde_json::from_str::<serde_json::Value>(&content).map_err(|e| ManifestError::json(path, e));
+    let mut value = match parse() {
+        Ok(value) => value,
+        Err(e) => return (false, Err((e, mutations))),
+    };
+    let mut rejected = Vec::new();
+    if mutations.iter().try_for_each(|mutation| mutation(&mut value)).is_err() {
+        // Start over from the file and go edit by edit, leaving out the ones that fail
+        value = match parse() {
+            Ok(value) => value,
+            Err(e) => return (false, Err((e, mutations))),
+        };
+        for (index, mutation) in mutations.iter().enumerate() {
+            let mut attempt = value.clone();
+            match mutation(&mut attempt) {
+                Ok(()) => value = attempt,
+                Err(message) => rejected.push((index, message)),
+            }
+        }
+    }
+
+    let wrote = rejected.len() < mutations.len();
+    if wrote {
+        if let Err(e) = save(path, &value) {
+            return (false, Err((e, mutations)));
+        }
+    }
+    let Some((_, first)) = rejected.first() else {
+        return (wrote, Ok(()));
+    };
+    let message = match rejected.len() {
+        1 => first.clone(),
+        count => format!("{} (and {} more edits that don't apply)", first, count - 1),
+    };
+    let kept = mutations
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| rejected.iter().any(|(rejected, _)| rejected == index))
+        .map(|(_, mutation)| mutation)
+        .collect();
+    let error = ManifestError::Validation {
+        path: path.to_string(),
+        message,
+    };
+    (wrote, Err((error, kept)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use std::time::{Duration, Instant};
+
+    /// A one-category manifest in a fresh folder, which goes away with the `TestDir`.
+    fn fixture(name: &str) -> (TestDir, String) {
+        let dir = TestDir::new(&format!("manifest-{}", name));
+        let path = dir.join("manifest.json");
+        let manifest = serde_json::json!({
+            "categories": { "icons": { "directory": "icons", "images": [], "count": 0 } },
+            "total_images": 0
+        });
+        std::fs::write(&path, manifest.to_string()).unwrap();
+        let path = path.to_string_lossy().into_owned();
+        (dir, path)
+    }
+
+    fn read(path: &str) -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// Sets `key` to `value` at the top level.
+    fn set(key: &'static str, value: serde_json::Value) -> Mutation {
+        Box::new(move |json: &mut serde_json::Value| {
+            json[key] = value.clone();
+            Ok(())
+        })
+    }
+
+    /// Polls until nothing is in flight, returning every result seen.
+    fn settle(saver: &mut ManifestSaver) -> Vec<Result<(), ManifestError>> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut results = Vec::new();
+        while saver.is_saving() {
+            assert!(Instant::now() < deadline, "save didn't finish");
+            results.extend(saver.poll());
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        results
+    }
+
+    #[test]
+    fn edit_submitted_during_a_pass_is_saved_too() {
+        let (_dir, path) = fixture("during");
+        let (started_tx, started) = mpsc::channel();
+        let (resume, resume_rx) = mpsc::channel::<()>();
+        // Holds the first pass open until the second edit has been submitted
+        let first: Mutation = Box::new(move |json: &mut serde_json::Value| {
+            let _ = started_tx.send(());
+            let _ = resume_rx.recv_timeout(Duration::from_secs(5));
+            json["first"] = serde_json::json!(1);
+            Ok(())
+        });
+
+        let mut saver = ManifestSaver::new();
+        saver.submit(&path, first);
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+        saver.submit(&path, set("second", serde_json::json!(2)));
+        assert!(saver.is_saving());
+        resume.send(()).unwrap();
+
+        let results = settle(&mut saver);
+        assert!(results.iter().all(Result::is_ok));
+        assert!(!saver.is_dirty());
+        let saved = read(&path);
+        assert_eq!(saved["first"], 1);
+        assert_eq!(saved["second"], 2);
+        // Untouched parts of the file survive both passes
+        assert_eq!(saved["categories"]["icons"]["directory"], "icons");
+    }
+
+    #[test]
+    fn apply_and_save_keeps_earlier_edits() {
+        let (_dir, path) = fixture("apply");
+        assert!(matches!(apply_and_save(&path, vec![set("first", serde_json::json!(1))]), (true, Ok(()))));
+        let (wrote, result) =
+            apply_and_save(&path, vec![set("second", serde_json::json!(2)), add_images("icons".to_string(), Vec::new())]);
+        assert!(wrote && result.is_ok());
+        let saved = read(&path);
+        assert_eq!(saved["first"], 1);
+        assert_eq!(saved["second"], 2);
+        assert!(!std::path::Path::new(&format!("{}.saving", path)).exists());
+    }
+
+    /// Appends `entry` to a top-level "log" array, to see the order edits ran in.
+    fn log(entry: &'static str) -> Mutation {
+        Box::new(move |json: &mut serde_json::Value| {
+            let root = json.as_object_mut().ok_or("expected a JSON object")?;
+            let log = root.entry("log").or_insert_with(|| serde_json::json!([]));
+            log.as_array_mut().ok_or("log isn't a list")?.push(serde_json::json!(entry));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn failed_pass_keeps_its_edits_and_retry_applies_them_in_order() {
+        let (_dir, path) = fixture("retry");
+        let content = std::fs::read_to_string(&path).unwrap();
+        // Not valid JSON, so the pass fails before anything is applied
+        std::fs::write(&path, "{").unwrap();
+
+        let mut saver = ManifestSaver::new();
+        for entry in ["first", "second", "third"] {
+            saver.submit(&path, log(entry));
+        }
+        let results = settle(&mut saver);
+        assert!(!results.is_empty() && results.iter().all(Result::is_err));
+        assert_eq!(saver.unsaved(), 3);
+        assert!(saver.is_dirty());
+
+        std::fs::write(&path, content).unwrap();
+        assert_eq!(saver.retry(), 0);
+        assert_eq!(saver.unsaved(), 0);
+        assert!(settle(&mut saver).iter().all(Result::is_ok));
+        assert!(!saver.is_dirty());
+        assert_eq!(read(&path)["log"], serde_json::json!(["first", "second", "third"]));
+    }
+
+    #[test]
+    fn failing_edit_is_set_aside_and_the_rest_are_saved() {
+        let (_dir, path) = fixture("invalid");
+        let mut saver = ManifestSaver::new();
+        // A pass on another file holds the worker, so all three edits queue up for one pass
+        let (started_tx, started) = mpsc::channel();
+        let (resume, resume_rx) = mpsc::channel::<()>();
+        let hold: Mutation = Box::new(move |_: &mut serde_json::Value| {
+            let _ = started_tx.send(());
+            let _ = resume_rx.recv_timeout(Duration::from_secs(5));
+            Ok(())
+        });
+        let (_hold_dir, hold_path) = fixture("hold");
+        saver.submit(&hold_path, hold);
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+        saver.submit(&path, log("before"));
+        saver.submit(&path, add_images("missing".to_string(), Vec::new()));
+        saver.submit(&path, log("after"));
+        resume.send(()).unwrap();
+
+        let results = settle(&mut saver);
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        assert!(matches!(results.last(), Some(Err(ManifestError::Validation { .. }))));
+        assert_eq!(saver.unsaved(), 1, "only the edit that doesn't apply is kept");
+        assert_eq!(read(&path)["log"], serde_json::json!(["before", "after"]));
+
+        // Retrying can't make it apply, so it's dropped instead of failing again
+        assert_eq!(saver.retry(), 1);
+        assert!(!saver.is_dirty());
+        assert_eq!(read(&path)["log"], serde_json::json!(["before", "after"]));
+    }
+
+    #[test]
+    fn discard_failed_drops_the_edits() {
+        let (_dir, path) = fixture("discard");
+        std::fs::write(&path, "{").unwrap();
+        let mut saver = ManifestSaver::new();
+        saver.submit(&path, log("dropped"));
+        settle(&mut saver);
+        assert_eq!(saver.unsaved(), 1);
+
+        saver.discard_failed();
+        assert_eq!(saver.unsaved(), 0);
+        assert!(!saver.is_dirty());
+        assert_eq!(saver.retry(), 0);
+        assert!(!saver.is_saving(), "nothing left to retry");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{");
+    }
+}
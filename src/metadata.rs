@@ -0,0 +1,123 @@
+//! Image metadata (dimensions, color type, EXIF) for the detail window.
+
+use std::io::BufReader;
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageMetadata {
+    /// Label/value pairs in display order.
+    pub fields: Vec<(String, String)>,
+    pub has_gps: bool,
+    pub has_exif: bool,
+    /// Number of EXIF fields, i.e. what a metadata-stripping copy would drop.
+    pub exif_field_count: usize,
+}
+
+impl ImageMetadata {
+    /// Plain-text table, one "Label: value" line per field.
+    pub fn to_text(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+const EXIF_FIELDS: &[(exif::Tag, &str)] = &[
+    (exif::Tag::DateTimeOriginal, "Date taken"),
+    (exif::Tag::Make, "Camera make"),
+    (exif::Tag::Model, "Camera model"),
+    (exif::Tag::ExposureTime, "Exposure"),
+    (exif::Tag::FNumber, "Aperture"),
+    (exif::Tag::PhotographicSensitivity, "ISO"),
+    (exif::Tag::FocalLength, "Focal length"),
+];
+
+fn read_exif(path: &str) -> Option<exif::Exif> {
+    let file = std::fs::File::open(path).ok()?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+}
+
+/// EXIF presence only, without decoding pixels: (has any EXIF, has GPS tags).
+pub fn exif_flags(path: &str) -> (bool, bool) {
+    match read_exif(path) {
+        Some(exif) => (true, exif.fields().any(|f| f.tag.context() == exif::Context::Gps)),
+        None => (false, false),
+    }
+}
+
+/// Color type from the file header, for the formats whose decoder can report
+/// it without reading pixels.
+fn header_color(path: &str) -> Option<image::ColorType> {
+    use image::codecs::{bmp, gif, jpeg, png, tiff};
+    use image::{ImageDecoder, ImageFormat};
+    let reader = image::io::Reader::open(path).ok()?.with_guessed_format().ok()?;
+    let format = reader.format()?;
+    let file = BufReader::new(std::fs::File::open(path).ok()?);
+    let color = match format {
+        ImageFormat::Png => png::PngDecoder::new(file).ok()?.color_type(),
+        ImageFormat::Jpeg => jpeg::JpegDecoder::new(file).ok()?.color_type(),
+        ImageFormat::Gif => gif::GifDecoder::new(file).ok()?.color_type(),
+        ImageFormat::Bmp => bmp::BmpDecoder::new(file).ok()?.color_type(),
+        ImageFormat::Tiff => tiff::TiffDecoder::new(file).ok()?.color_type(),
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// Reads everything cheaply available for a file, from headers only. Missing
+/// pieces are simply left out.
+pub fn read(path: &str) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+
+    if let Some((width, height)) = crate::dimensions::read_header(path) {
+        metadata
+            .fields
+            .push(("Dimensions".to_string(), format!("{} × {}", width, height)));
+        metadata
+            .fields
+            .push(("Aspect ratio".to_string(), crate::dimensions::aspect_label(width, height)));
+        metadata.fields.push((
+            "Megapixels".to_string(),
+            format!("{:.1} MP", crate::dimensions::megapixels(width, height)),
+        ));
+    }
+    if let Some(color) = header_color(path) {
+        metadata
+            .fields
+            .push(("Color type".to_string(), format!("{:?}", color)));
+    }
+
+    let Some(exif) = read_exif(path) else {
+        return metadata;
+    };
+    metadata.has_exif = true;
+    metadata.exif_field_count = exif.fields().len();
+
+    let resolution = |tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .map(|f| f.display_value().to_string())
+    };
+    if let (Some(x), Some(y)) = (resolution(exif::Tag::XResolution), resolution(exif::Tag::YResolution)) {
+        metadata.fields.push(("DPI".to_string(), format!("{} × {}", x, y)));
+    }
+
+    for (tag, label) in EXIF_FIELDS {
+        if let Some(field) = exif.get_field(*tag, exif::In::PRIMARY) {
+            let value = field.display_value().with_unit(&exif).to_string();
+            metadata
+                .fields
+                .push((label.to_string(), value.trim_matches('"').to_string()));
+        }
+    }
+
+    metadata.has_gps = exif.fields().any(|f| f.tag.context() == exif::Context::Gps);
+    metadata.fields.push((
+        "GPS location".to_string(),
+        if metadata.has_gps { "Present" } else { "None" }.to_string(),
+    ));
+
+    metadata
+}
@@ -0,0 +1,118 @@
+//! "+N new" badges: which images arrived in the library since the last visit.
+//!
+//! Each profile keeps a snapshot of the image paths it has seen, per category.
+//! When the library loads, anything not in the snapshot counts as new until
+//! its category is viewed. The snapshot is written at exit and when switching
+//! profiles, and only ever lists categories and images that still exist.
+//!
+//! The first load of a profile has no snapshot to compare with, so it records
+//! the library as it is and shows nothing as new.
+
+use crate::search::normalize_path;
+use crate::store::UserData;
+use crate::ImageData;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::SystemTime;
+
+/// Name of the virtual category listing every new image.
+pub const CATEGORY: &str = "✨ New since last visit";
+
+const SECTION: &str = "seen";
+
+pub struct NewImages {
+    /// Seen paths (normalized) by category. None until a snapshot exists.
+    seen: Option<BTreeMap<String, BTreeSet<String>>>,
+    /// New full paths by category.
+    new: BTreeMap<String, Vec<String>>,
+    /// Modification times of the new images, for ordering the virtual category.
+    modified: HashMap<String, SystemTime>,
+    dirty: bool,
+}
+
+impl NewImages {
+    pub fn load(store: &mut UserData) -> Self {
+        Self {
+            seen: store.get(SECTION),
+            new: BTreeMap::new(),
+            modified: HashMap::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn save(&mut self, store: &mut UserData) {
+        if !self.dirty {
+            return;
+        }
+        let Some(seen) = &self.seen else {
+            return;
+        };
+        if store.set(SECTION, seen).is_ok() {
+            self.dirty = false;
+        }
+    }
+
+    /// Compares a freshly loaded library with the snapshot. Categories and
+    /// images that are gone are dropped from the snapshot.
+    pub fn compare(&mut self, data: &ImageData) {
+        self.new.clear();
+        self.modified.clear();
+        let previous = self.seen.take();
+        let mut seen = BTreeMap::new();
+        for (name, category) in &data.categories {
+            let known = previous.as_ref().map(|previous| previous.get(name));
+            let mut paths = BTreeSet::new();
+            for image in &category.images {
+                let path = normalize_path(&image.full_path);
+                // No snapshot at all: everything counts as seen. A category the
+                // snapshot doesn't have is new as a whole.
+                let is_new = match known {
+                    None => false,
+                    Some(known) => !known.is_some_and(|known| known.contains(&path)),
+                };
+                if is_new {
+                    let modified = std::fs::metadata(&image.full_path)
+                        .and_then(|metadata| metadata.modified())
+                        .unwrap_or(SystemTime::UNIX_EPOCH);
+                    self.modified.insert(image.full_path.clone(), modified);
+                    self.new.entry(name.clone()).or_default().push(image.full_path.clone());
+                } else {
+                    paths.insert(path);
+                }
+            }
+            seen.insert(name.clone(), paths);
+        }
+        self.dirty = previous.as_ref() != Some(&seen) || !self.new.is_empty();
+        self.seen = Some(seen);
+    }
+
+    /// New images in a category.
+    pub fn count(&self, category: &str) -> usize {
+        self.new.get(category).map_or(0, Vec::len)
+    }
+
+    pub fn total(&self) -> usize {
+        self.new.values().map(Vec::len).sum()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.modified.contains_key(path)
+    }
+
+    /// When a new image was last modified; None for images that aren't new.
+    pub fn modified(&self, path: &str) -> Option<SystemTime> {
+        self.modified.get(path).copied()
+    }
+
+    /// Clears a category's badge by adding its new images to the snapshot.
+    pub fn mark_seen(&mut self, category: &str) {
+        let Some(paths) = self.new.remove(category) else {
+            return;
+        };
+        let seen = self.seen.get_or_insert_with(BTreeMap::new).entry(category.to_string()).or_default();
+        for path in paths {
+            self.modified.remove(&path);
+            seen.insert(normalize_path(&path));
+        }
+        self.dirty = true;
+    }
+}
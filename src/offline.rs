@@ -0,0 +1,109 @@
+//! Drives and mounts the manifest points at that aren't there.
+//!
+//! Images are grouped by path root: the drive letter or UNC share on Windows,
+//! the volume directory under `/Volumes`, `/media`, `/run/media` or `/mnt`
+//! elsewhere. Each root is probed once per load; images under a missing root
+//! count as offline in bulk instead of each failing on its own as it scrolls
+//! into view. Paths on the system root are never probed.
+
+use crate::ImageData;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path};
+
+/// Unix directories whose children (or grandchildren, per user) are mount points.
+const MOUNT_PARENTS: &[(&str, usize)] = &[("/Volumes", 1), ("/mnt", 1), ("/media", 2), ("/run/media", 2)];
+
+/// Root a path depends on, or None when it lives on the system root.
+pub fn root_of(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    if let Some(Component::Prefix(prefix)) = path.components().next() {
+        let prefix = prefix.as_os_str().to_string_lossy();
+        // "C:" is where Chlorine itself runs from, practically always
+        if prefix.eq_ignore_ascii_case("C:") {
+            return None;
+        }
+        return Some(format!("{}{}", prefix, std::path::MAIN_SEPARATOR));
+    }
+    for (parent, depth) in MOUNT_PARENTS {
+        let Ok(rest) = path.strip_prefix(parent) else {
+            continue;
+        };
+        let names: Vec<_> = rest.components().take(*depth).collect();
+        // Only paths inside the volume, not the mount point itself
+        if names.len() == *depth && rest.components().count() > *depth {
+            let mut root = Path::new(parent).to_path_buf();
+            root.extend(names);
+            return Some(root.display().to_string());
+        }
+    }
+    None
+}
+
+/// Missing roots and how many images each one holds, plus single files
+/// seen disappearing while the library folders are watched.
+#[derive(Debug, Clone, Default)]
+pub struct OfflineRoots {
+    roots: BTreeMap<String, usize>,
+    files: HashSet<String>,
+}
+
+impl OfflineRoots {
+    /// Probes every root the library uses once.
+    pub fn probe(data: &ImageData) -> Self {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for category in data.categories.values() {
+            for image in &category.images {
+                if let Some(root) = root_of(&image.full_path) {
+                    *counts.entry(root).or_default() += 1;
+                }
+            }
+        }
+        counts.retain(|root, _| !Path::new(root).exists());
+        Self {
+            roots: counts,
+            files: HashSet::new(),
+        }
+    }
+
+    /// Probes the missing roots again. Returns the ones that came back.
+    pub fn recheck(&mut self) -> Vec<String> {
+        let back: Vec<String> = self.roots.keys().filter(|root| Path::new(root).exists()).cloned().collect();
+        for root in &back {
+            self.roots.remove(root);
+        }
+        back
+    }
+
+    /// No missing roots; single missing files don't count.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains(path)
+            || (!self.roots.is_empty() && root_of(path).is_some_and(|root| self.roots.contains_key(&root)))
+    }
+
+    pub fn mark_missing(&mut self, path: &str) {
+        self.files.insert(path.to_string());
+    }
+
+    /// Files marked missing, to carry over a reload if they're still gone.
+    pub fn missing_files(&self) -> impl Iterator<Item = &String> {
+        self.files.iter()
+    }
+
+    /// Returns whether the file had been marked missing.
+    pub fn mark_present(&mut self, path: &str) -> bool {
+        self.files.remove(path)
+    }
+
+    /// (root, image count), alphabetically.
+    pub fn roots(&self) -> impl Iterator<Item = (&String, usize)> {
+        self.roots.iter().map(|(root, count)| (root, *count))
+    }
+
+    pub fn image_count(&self) -> usize {
+        self.roots.values().sum()
+    }
+}
@@ -0,0 +1,169 @@
+//! Long-running background work, and which of it must not overlap.
+//!
+//! The registry is rebuilt every frame from the promises and workers the app
+//! already owns, so it can't drift from what is really running. Entry points
+//! ask [`Operations::blocker`] before starting something and show the answer
+//! as a disabled-button tooltip.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    /// Reading and indexing the manifest (startup, Refresh, profile switch).
+    LibraryLoad,
+    /// Writing queued manifest edits.
+    ManifestSave,
+    Import,
+    HealthCheck,
+    Export,
+    /// Re-encoding files in place.
+    Optimize,
+    /// Hashing files against the manifest's checksums.
+    Verify,
+    /// Reading every file's pixel size from its header.
+    IndexDimensions,
+}
+
+impl OperationKind {
+    pub const ALL: [OperationKind; 8] = [
+        OperationKind::LibraryLoad,
+        OperationKind::ManifestSave,
+        OperationKind::Import,
+        OperationKind::HealthCheck,
+        OperationKind::Export,
+        OperationKind::Optimize,
+        OperationKind::Verify,
+        OperationKind::IndexDimensions,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OperationKind::LibraryLoad => "library load",
+            OperationKind::ManifestSave => "manifest save",
+            OperationKind::Import => "folder import",
+            OperationKind::HealthCheck => "health check",
+            OperationKind::Export => "export",
+            OperationKind::Optimize => "optimize",
+            OperationKind::Verify => "checksum verification",
+            OperationKind::IndexDimensions => "dimension indexing",
+        }
+    }
+
+    /// Whether `self` must not start while `running` is active. Saves and
+    /// reloads don't conflict: a reload asked for during a save waits for it,
+    /// and saves serialize among themselves.
+    pub fn conflicts_with(self, running: OperationKind) -> bool {
+        use OperationKind::*;
+        matches!(
+            (self, running),
+            (LibraryLoad, LibraryLoad | Import | Optimize)
+                | (Import, LibraryLoad | Import | Optimize)
+                | (Optimize, LibraryLoad | Import | Optimize)
+                // Edits are made against the library that is being replaced
+                | (ManifestSave, LibraryLoad)
+                | (HealthCheck, LibraryLoad | HealthCheck)
+                | (Verify, LibraryLoad | Verify)
+                | (IndexDimensions, LibraryLoad | IndexDimensions)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub kind: OperationKind,
+    /// 0.0 to 1.0 when the work reports it.
+    pub progress: Option<f32>,
+    pub cancellable: bool,
+}
+
+impl Operation {
+    pub fn new(kind: OperationKind) -> Self {
+        Self {
+            kind,
+            progress: None,
+            cancellable: false,
+        }
+    }
+
+    /// "folder import — 42%", or just the label without progress.
+    pub fn describe(&self) -> String {
+        match self.progress {
+            Some(fraction) => format!("{} — {:.0}%", self.kind.label(), fraction * 100.0),
+            None => self.kind.label().to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Operations {
+    active: Vec<Operation>,
+}
+
+impl Operations {
+    pub fn replace(&mut self, active: Vec<Operation>) {
+        self.active = active;
+    }
+
+    pub fn active(&self) -> &[Operation] {
+        &self.active
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Why `kind` can't start right now, e.g. "Disabled while folder import — 42% is running".
+    pub fn blocker(&self, kind: OperationKind) -> Option<String> {
+        self.active
+            .iter()
+            .find(|running| kind.conflicts_with(running.kind))
+            .map(|running| format!("Disabled while {} is running", running.describe()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One row per kind asking to start, one column per kind running, both in
+    /// `ALL` order; X marks a conflict.
+    const CONFLICTS: [&str; 8] = [
+        // Load Save Import Health Export Optimize Verify Dimensions
+        "X.X..X..", // LibraryLoad
+        "X.......", // ManifestSave
+        "X.X..X..", // Import
+        "X..X....", // HealthCheck
+        "........", // Export
+        "X.X..X..", // Optimize
+        "X.....X.", // Verify
+        "X......X", // IndexDimensions
+    ];
+
+    #[test]
+    fn conflict_table_covers_every_pair() {
+        for (starting, row) in OperationKind::ALL.into_iter().zip(CONFLICTS) {
+            for (running, mark) in OperationKind::ALL.into_iter().zip(row.chars()) {
+                assert_eq!(
+                    starting.conflicts_with(running),
+                    mark == 'X',
+                    "{:?} starting while {:?} runs",
+                    starting,
+                    running
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blocker_names_the_running_operation() {
+        let mut operations = Operations::default();
+        assert!(operations.blocker(OperationKind::Import).is_none());
+
+        let mut import = Operation::new(OperationKind::Import);
+        import.progress = Some(0.42);
+        operations.replace(vec![Operation::new(OperationKind::Export), import]);
+        assert_eq!(
+            operations.blocker(OperationKind::LibraryLoad).as_deref(),
+            Some("Disabled while folder import — 42% is running")
+        );
+        assert!(operations.blocker(OperationKind::Export).is_none());
+    }
+}
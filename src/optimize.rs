@@ -0,0 +1,428 @@
+//! "Optimize…": re-encodes library images in place to make them smaller.
+//!
+//! PNGs are recompressed at the best setting; optionally, images without
+//! transparency become JPEGs at a chosen quality, or anything becomes
+//! lossless WebP. A dry run encodes in memory and reports what each file
+//! would shrink to; applying encodes again and writes. A file that wouldn't
+//! get smaller is left alone. The original is renamed to `<name>.orig` or
+//! moved to the trash, per [`Originals`].
+//!
+//! Files are processed by a small pool of worker threads that stop taking
+//! new files once the run is cancelled.
+
+use crate::settings::Originals;
+use crate::ImageInfo;
+use image::{DynamicImage, ImageEncoder, ImageFormat};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const MAX_WORKERS: usize = 4;
+/// Inputs that can be re-encoded without losing anything but pixels' encoding.
+const INPUTS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "webp"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    /// Recompress PNGs only; other formats are skipped.
+    None,
+    /// Images without transparency become JPEG; transparent ones stay PNG.
+    Jpeg,
+    /// Everything becomes lossless WebP.
+    WebP,
+}
+
+impl Conversion {
+    pub const ALL: [Conversion; 3] = [Conversion::None, Conversion::Jpeg, Conversion::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Conversion::None => "Recompress PNGs, keep formats",
+            Conversion::Jpeg => "Convert photos to JPEG",
+            Conversion::WebP => "Convert to WebP (lossless)",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OptimizeOptions {
+    pub conversion: Conversion,
+    pub jpeg_quality: u8,
+    pub originals: Originals,
+}
+
+/// Shared with the UI while a run is going.
+#[derive(Default)]
+pub struct Progress {
+    pub done: AtomicUsize,
+    pub total: AtomicUsize,
+    /// Set by the UI; workers finish their current file and stop.
+    pub cancelled: AtomicBool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The new entry: projected on a dry run, on disk once applied.
+    Smaller(ImageInfo),
+    /// Encoded size; the file is left as it is.
+    WouldGrow(u64),
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub image: ImageInfo,
+    pub outcome: Outcome,
+}
+
+impl FileReport {
+    /// Bytes saved (or that would be), 0 unless the file shrinks.
+    pub fn saved(&self) -> u64 {
+        match &self.outcome {
+            Outcome::Smaller(replacement) => self.image.size.saturating_sub(replacement.size),
+            _ => 0,
+        }
+    }
+}
+
+fn extension_of(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn has_transparency(img: &DynamicImage) -> bool {
+    img.color().has_alpha() && img.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX)
+}
+
+/// Output format and extension for a file, or why it's skipped.
+fn target_format(extension: &str, img: &DynamicImage, conversion: Conversion) -> Result<(ImageFormat, &'static str), String> {
+    match conversion {
+        Conversion::None if extension == "png" => Ok((ImageFormat::Png, "png")),
+        Conversion::None => Err("only PNGs are recompressed without converting".to_string()),
+        Conversion::Jpeg if has_transparency(img) => Ok((ImageFormat::Png, "png")),
+        Conversion::Jpeg => Ok((ImageFormat::Jpeg, if extension == "jpeg" { "jpeg" } else { "jpg" })),
+        Conversion::WebP => Ok((ImageFormat::WebP, "webp")),
+    }
+}
+
+fn encode(img: &DynamicImage, format: ImageFormat, jpeg_quality: u8) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    match format {
+        ImageFormat::Png => {
+            let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                &mut bytes,
+                image::codecs::png::CompressionType::Best,
+                image::codecs::png::FilterType::Adaptive,
+            );
+            encoder
+                .write_image(img.as_bytes(), img.width(), img.height(), img.color())
+                .map_err(|e| e.to_string())?;
+        }
+        ImageFormat::Jpeg => {
+            let rgb = img.to_rgb8();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, jpeg_quality)
+                .encode(rgb.as_raw(), rgb.width(), rgb.height(), image::ColorType::Rgb8)
+                .map_err(|e| e.to_string())?;
+        }
+        _ => {
+            let rgba = img.to_rgba8();
+            image::codecs::webp::WebPEncoder::new_lossless(&mut bytes)
+                .encode(rgba.as_raw(), rgba.width(), rgba.height(), image::ColorType::Rgba8)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// `path` with its last component, `old`, replaced by `filename`; None when
+/// `path` doesn't end with `old`.
+fn with_filename(path: &str, old: &str, filename: &str) -> Option<String> {
+    path.strip_suffix(old).map(|parent| format!("{}{}", parent, filename))
+}
+
+/// Moves the original out of the way: `<name>.orig` next to it, or the trash.
+/// Returns where the backup went, if it went somewhere it can be put back from.
+fn retire_original(path: &Path, originals: Originals) -> Result<Option<PathBuf>, String> {
+    match originals {
+        Originals::Backup => {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let backup = dir.join(crate::import::free_name(dir, &format!("{}.orig", name)));
+            std::fs::rename(path, &backup).map_err(|e| format!("backing up: {}", e))?;
+            Ok(Some(backup))
+        }
+        Originals::Trash => trash::delete(path).map(|()| None).map_err(|e| format!("moving to trash: {}", e)),
+    }
+}
+
+fn optimize_file(image: &ImageInfo, options: &OptimizeOptions, apply: bool) -> Outcome {
+    let extension = extension_of(&image.full_path);
+    if !INPUTS.contains(&extension.as_str()) {
+        return Outcome::Skipped(format!("{} files are left alone", extension.to_ascii_uppercase()));
+    }
+    let img = match crate::formats::open(&image.full_path) {
+        Ok(img) => img,
+        Err(e) => return Outcome::Failed(e),
+    };
+    // 16-bit and float pixels would lose depth in an 8-bit encoder
+    if !matches!(
+        img,
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)
+    ) {
+        return Outcome::Skipped("high bit depth images are left alone".to_string());
+    }
+    let (format, new_extension) = match target_format(&extension, &img, options.conversion) {
+        Ok(target) => target,
+        Err(reason) => return Outcome::Skipped(reason),
+    };
+    let bytes = match encode(&img, format, options.jpeg_quality) {
+        Ok(bytes) => bytes,
+        Err(e) => return Outcome::Failed(e),
+    };
+    let size = bytes.len() as u64;
+    // The manifest's size may be stale; only what's on disk says whether this is smaller
+    let on_disk = match std::fs::metadata(&image.full_path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    if size >= on_disk {
+        return Outcome::WouldGrow(size);
+    }
+
+    let original = PathBuf::from(&image.full_path);
+    let mut target = original.with_extension(new_extension);
+    if target != original && target.exists() {
+        let dir = original.parent().unwrap_or(Path::new("."));
+        let name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        target = dir.join(crate::import::free_name(dir, &name));
+    }
+    let filename = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| image.filename.clone());
+    let (Some(full_path), Some(relative_path)) = (
+        with_filename(&image.full_path, &image.filename, &filename),
+        with_filename(&image.relative_path, &image.filename, &filename),
+    ) else {
+        return Outcome::Skipped("its paths don't end with its filename in the manifest".to_string());
+    };
+    let replacement = ImageInfo {
+        full_path,
+        relative_path,
+        extension: format!(".{}", new_extension),
+        filename,
+        size,
+        // The manifest's checksum was for the old bytes
+        checksum: None,
+        width: image.width,
+        height: image.height,
+    };
+    if !apply {
+        return Outcome::Smaller(replacement);
+    }
+
+    // Nothing touches the original until the new bytes are safely on disk
+    let temp = target.with_extension(format!("{}.optimizing", new_extension));
+    if let Err(e) = std::fs::write(&temp, &bytes) {
+        let _ = std::fs::remove_file(&temp);
+        return Outcome::Failed(e.to_string());
+    }
+    if target != original {
+        // A new name: put it in place first, so a failure leaves the original where it was
+        if let Err(e) = std::fs::rename(&temp, &target) {
+            let _ = std::fs::remove_file(&temp);
+            return Outcome::Failed(e.to_string());
+        }
+        if let Err(e) = retire_original(&original, options.originals) {
+            let _ = std::fs::remove_file(&target);
+            return Outcome::Failed(e);
+        }
+        return Outcome::Smaller(replacement);
+    }
+    let backup = match retire_original(&original, options.originals) {
+        Ok(backup) => backup,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            return Outcome::Failed(e);
+        }
+    };
+    match std::fs::rename(&temp, &target) {
+        Ok(()) => Outcome::Smaller(replacement),
+        Err(e) => match backup {
+            Some(backup) if std::fs::rename(&backup, &original).is_ok() => {
+                let _ = std::fs::remove_file(&temp);
+                Outcome::Failed(e.to_string())
+            }
+            _ => Outcome::Failed(format!("{} (the new file is {})", e, temp.display())),
+        },
+    }
+}
+
+/// Dry run (`apply` false) or the real thing, in input order. Meant for a background thread.
+pub fn run(images: Vec<ImageInfo>, options: OptimizeOptions, apply: bool, progress: Arc<Progress>) -> Vec<FileReport> {
+    progress.total.store(images.len(), Ordering::Relaxed);
+    let next = AtomicUsize::new(0);
+    let reports = Mutex::new(Vec::with_capacity(images.len()));
+    let workers = std::thread::available_parallelism()
+        .map_or(2, |count| count.get())
+        .clamp(1, MAX_WORKERS);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if progress.cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(image) = images.get(index) else {
+                    break;
+                };
+                let outcome = optimize_file(image, &options, apply);
+                if let Ok(mut reports) = reports.lock() {
+                    reports.push((
+                        index,
+                        FileReport {
+                            image: image.clone(),
+                            outcome,
+                        },
+                    ));
+                }
+                progress.done.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    });
+    let mut reports = reports.into_inner().unwrap_or_default();
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// An opaque PNG carrying `padding` bytes of text, which re-encoding drops.
+    fn png(dir: &Path, filename: &str, padding: usize) -> ImageInfo {
+        let path = dir.join(filename);
+        image::RgbImage::from_pixel(16, 16, image::Rgb([40, 90, 160])).save(&path).unwrap();
+        // A tEXt chunk right after the signature and IHDR; the CRC isn't checked
+        let mut bytes = std::fs::read(&path).unwrap();
+        let mut chunk = (padding as u32 + 8).to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"tEXtComment\0");
+        chunk.extend(std::iter::repeat_n(b'x', padding));
+        chunk.extend_from_slice(&[0; 4]);
+        bytes.splice(33..33, chunk);
+        std::fs::write(&path, &bytes).unwrap();
+        ImageInfo {
+            filename: filename.to_string(),
+            relative_path: format!("art/{}", filename),
+            full_path: path.to_string_lossy().into_owned(),
+            extension: ".png".to_string(),
+            size: bytes.len() as u64,
+            checksum: None,
+            width: Some(16),
+            height: Some(16),
+        }
+    }
+
+    fn options(conversion: Conversion) -> OptimizeOptions {
+        OptimizeOptions {
+            conversion,
+            jpeg_quality: 80,
+            originals: Originals::Backup,
+        }
+    }
+
+    #[test]
+    fn dry_run_touches_nothing() {
+        let dir = TestDir::new("optimize-dry");
+        let image = png(&dir, "sky.png", 64 * 1024);
+        let before = std::fs::read(&image.full_path).unwrap();
+
+        let Outcome::Smaller(replacement) = optimize_file(&image, &options(Conversion::Jpeg), false) else {
+            panic!("expected a smaller file");
+        };
+        assert_eq!(replacement.filename, "sky.jpg");
+        assert_eq!(replacement.relative_path, "art/sky.jpg");
+        assert_eq!(std::fs::read(&image.full_path).unwrap(), before);
+        assert!(!dir.join("sky.jpg").exists());
+    }
+
+    #[test]
+    fn converting_backs_up_the_original_next_to_the_new_file() {
+        let dir = TestDir::new("optimize-convert");
+        let image = png(&dir, "sky.png", 64 * 1024);
+        let before = std::fs::read(&image.full_path).unwrap();
+
+        let Outcome::Smaller(replacement) = optimize_file(&image, &options(Conversion::Jpeg), true) else {
+            panic!("expected a smaller file");
+        };
+        assert_eq!(ImageFormat::from_path(&replacement.full_path).unwrap(), ImageFormat::Jpeg);
+        assert_eq!(std::fs::metadata(&replacement.full_path).unwrap().len(), replacement.size);
+        assert!(!Path::new(&image.full_path).exists());
+        assert_eq!(std::fs::read(dir.join("sky.png.orig")).unwrap(), before);
+        assert!(!dir.join("sky.jpg.optimizing").exists());
+    }
+
+    #[test]
+    fn converting_never_overwrites_an_existing_file() {
+        let dir = TestDir::new("optimize-taken");
+        let image = png(&dir, "sky.png", 64 * 1024);
+        std::fs::write(dir.join("sky.jpg"), b"someone else's").unwrap();
+
+        let Outcome::Smaller(replacement) = optimize_file(&image, &options(Conversion::Jpeg), true) else {
+            panic!("expected a smaller file");
+        };
+        assert_ne!(replacement.filename, "sky.jpg");
+        assert_eq!(std::fs::read(dir.join("sky.jpg")).unwrap(), b"someone else's");
+        assert!(Path::new(&replacement.full_path).exists());
+    }
+
+    #[test]
+    fn recompressing_in_place_keeps_a_backup() {
+        let dir = TestDir::new("optimize-recompress");
+        let image = png(&dir, "sky.png", 64 * 1024);
+        let before = std::fs::read(&image.full_path).unwrap();
+
+        let Outcome::Smaller(replacement) = optimize_file(&image, &options(Conversion::None), true) else {
+            panic!("expected a smaller file");
+        };
+        assert_eq!(replacement.full_path, image.full_path);
+        assert_eq!(std::fs::metadata(&image.full_path).unwrap().len(), replacement.size);
+        assert_eq!(std::fs::read(dir.join("sky.png.orig")).unwrap(), before);
+        assert!(!dir.join("sky.png.optimizing").exists());
+    }
+
+    #[test]
+    fn skips_what_it_cannot_improve() {
+        let dir = TestDir::new("optimize-skip");
+        let image = png(&dir, "sky.png", 0);
+        assert!(matches!(optimize_file(&image, &options(Conversion::Jpeg), true), Outcome::WouldGrow(_)));
+        let mut text = image.clone();
+        text.full_path = dir.join("notes.txt").to_string_lossy().into_owned();
+        assert!(matches!(optimize_file(&text, &options(Conversion::None), true), Outcome::Skipped(_)));
+    }
+
+    #[test]
+    fn stale_manifest_size_does_not_count() {
+        let dir = TestDir::new("optimize-stale");
+        let mut image = png(&dir, "sky.png", 0);
+        let before = std::fs::read(&image.full_path).unwrap();
+        // The manifest remembers a far bigger file than the one on disk
+        image.size = 10_000_000;
+        assert!(matches!(optimize_file(&image, &options(Conversion::Jpeg), true), Outcome::WouldGrow(_)));
+        assert_eq!(std::fs::read(&image.full_path).unwrap(), before);
+        assert!(!dir.join("sky.jpg").exists());
+    }
+
+    #[test]
+    fn entry_whose_path_does_not_end_with_its_filename_is_skipped() {
+        let dir = TestDir::new("optimize-mismatch");
+        let mut image = png(&dir, "sky.png", 64 * 1024);
+        image.filename = "other.png".to_string();
+        assert!(matches!(optimize_file(&image, &options(Conversion::Jpeg), true), Outcome::Skipped(_)));
+        assert!(!dir.join("sky.jpg").exists());
+        assert!(dir.join("sky.png").exists());
+    }
+}
@@ -0,0 +1,84 @@
+//! Quarter turns and mirroring applied by hand in the detail window.
+//!
+//! The texture is never re-uploaded: the window draws it as a quad whose
+//! corners are reassigned, and the pixels are only transformed when the
+//! result is copied or saved.
+
+use eframe::egui;
+use image::DynamicImage;
+
+/// A horizontal flip followed by `quarter_turns` clockwise rotations. Every
+/// combination of rotate and flip buttons reduces to one of these eight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    quarter_turns: u8,
+    flipped: bool,
+}
+
+impl Orientation {
+    pub fn is_identity(self) -> bool {
+        self == Self::default()
+    }
+
+    pub fn rotate_right(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 1) % 4,
+            ..self
+        }
+    }
+
+    pub fn rotate_left(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 3) % 4,
+            ..self
+        }
+    }
+
+    /// Mirrors what is currently shown. Flipping after a rotation equals
+    /// flipping first and rotating the other way.
+    pub fn flip_horizontal(self) -> Self {
+        Self {
+            quarter_turns: (4 - self.quarter_turns) % 4,
+            flipped: !self.flipped,
+        }
+    }
+
+    /// Size of the image once oriented.
+    pub fn apply_size(self, size: egui::Vec2) -> egui::Vec2 {
+        if self.quarter_turns % 2 == 1 {
+            egui::vec2(size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    pub fn apply(self, image: DynamicImage) -> DynamicImage {
+        let image = if self.flipped { image.fliph() } else { image };
+        match self.quarter_turns {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        }
+    }
+
+    /// Paints `texture` into `rect` (already sized with `apply_size`) in this orientation.
+    pub fn paint(self, ui: &egui::Ui, texture: &egui::TextureHandle, rect: egui::Rect) {
+        // Corners clockwise from the top left, as texture coordinates and as places in `rect`
+        let uvs = [egui::pos2(0.0, 0.0), egui::pos2(1.0, 0.0), egui::pos2(1.0, 1.0), egui::pos2(0.0, 1.0)];
+        let corners = [rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()];
+        let mut mesh = egui::Mesh::with_texture(texture.id());
+        for (corner, uv) in uvs.into_iter().enumerate() {
+            let mirrored = if self.flipped { [1, 0, 3, 2][corner] } else { corner };
+            let placed = (mirrored + self.quarter_turns as usize) % 4;
+            mesh.vertices.push(egui::epaint::Vertex {
+                pos: corners[placed],
+                uv,
+                color: egui::Color32::WHITE,
+            });
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 2, 3);
+        ui.painter().add(egui::Shape::mesh(mesh));
+    }
+}
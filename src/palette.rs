@@ -0,0 +1,132 @@
+//! Ctrl+P command palette: a flat registry of named actions with fuzzy filtering.
+
+/// Something the palette can run. The app maps each variant to its own method,
+/// so registering a feature here is one variant plus one match arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    RefreshLibrary,
+    OpenSettings,
+    ShowShortcuts,
+    ShowHealth,
+    VerifyChecksums,
+    IndexDimensions,
+    ShowUsage,
+    ToggleFavoritesOnly,
+    ToggleFavoritesBar,
+    TogglePreviewPane,
+    TogglePrivacyMode,
+    ToggleTextOnly,
+    ToggleDarkMode,
+    ImportFolder,
+    ImportZip,
+    CategoryFromResults,
+    Optimize,
+    ShowCategory(String),
+    SwitchProfile(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    pub shortcut: Option<&'static str>,
+    pub action: Action,
+}
+
+impl Command {
+    pub fn new(name: impl Into<String>, action: Action) -> Self {
+        Self {
+            name: name.into(),
+            shortcut: None,
+            action,
+        }
+    }
+
+    pub fn with_shortcut(mut self, shortcut: &'static str) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+}
+
+/// Open palette state; the command list is built once when it opens.
+pub struct CommandPalette {
+    pub query: String,
+    pub selected: usize,
+    pub commands: Vec<Command>,
+    /// Indices into `commands` matching `query`, best first.
+    pub matches: Vec<usize>,
+}
+
+impl CommandPalette {
+    pub fn new(commands: Vec<Command>) -> Self {
+        let mut palette = Self {
+            query: String::new(),
+            selected: 0,
+            commands,
+            matches: Vec::new(),
+        };
+        palette.refilter();
+        palette
+    }
+
+    /// Recomputes `matches` after the query changes and resets the selection.
+    pub fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut scored: Vec<(i32, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| fuzzy_score(&query, &command.name.to_lowercase()).map(|s| (s, index)))
+            .collect();
+        // Stable sort keeps registration order among equal scores
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches
+            .get(self.selected)
+            .map(|&index| self.commands[index].action.clone())
+    }
+}
+
+/// Subsequence match: every query character must appear in order. Consecutive
+/// runs and matches at word starts score higher; None means no match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let mut score = 0;
+    let mut previous_end: Option<usize> = None;
+    let mut chars = candidate.char_indices();
+    let mut previous_char = ' ';
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (position, c) = chars.next()?;
+            let at_word_start = !previous_char.is_alphanumeric();
+            previous_char = c;
+            if c != wanted {
+                continue;
+            }
+            score += 1;
+            if at_word_start {
+                score += 3;
+            }
+            if previous_end == Some(position) {
+                score += 2;
+            }
+            previous_end = Some(position + c.len_utf8());
+            break;
+        }
+    }
+    Some(score)
+}
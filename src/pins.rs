@@ -0,0 +1,45 @@
+//! Pinned clipboard slots, stored per profile as a list of full paths (null for an empty slot).
+
+use crate::store::UserData;
+
+const SECTION: &str = "pins";
+
+/// Upper bound for the slot count setting; slots map to Ctrl+Shift+1..9.
+pub const MAX_SLOTS: usize = 9;
+
+pub struct PinnedSlots {
+    slots: Vec<Option<String>>,
+}
+
+impl PinnedSlots {
+    pub fn load(store: &mut UserData, count: usize) -> Self {
+        let mut pins = Self {
+            slots: store.get(SECTION),
+        };
+        pins.resize(count);
+        pins
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.slots)
+    }
+
+    /// Grows or shrinks to `count` slots. Shrinking drops the pins in the removed slots.
+    pub fn resize(&mut self, count: usize) {
+        self.slots.resize(count.clamp(1, MAX_SLOTS), None);
+    }
+
+    pub fn slots(&self) -> &[Option<String>] {
+        &self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&str> {
+        self.slots.get(slot)?.as_deref()
+    }
+
+    pub fn set(&mut self, slot: usize, path: Option<String>) {
+        if let Some(entry) = self.slots.get_mut(slot) {
+            *entry = path;
+        }
+    }
+}
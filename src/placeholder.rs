@@ -0,0 +1,218 @@
+//! Placeholder tiles for entries without a thumbnail.
+//!
+//! Tiles are rasterized once per extension with a tiny built-in pixel font, so
+//! the list can draw thousands of them as plain textures without text layout.
+
+use eframe::egui;
+
+const TILE_SIZE: usize = 64;
+const CORNER_RADIUS: f32 = 8.0;
+const MAX_LABEL_CHARS: usize = 4;
+
+/// 3x5 pixel glyphs, one row per byte (low three bits used).
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Uppercase label shown on the tile, e.g. ".psd" -> "PSD".
+pub fn extension_label(extension: &str) -> String {
+    let label: String = extension
+        .trim_start_matches('.')
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(MAX_LABEL_CHARS)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if label.is_empty() {
+        "?".to_string()
+    } else {
+        label
+    }
+}
+
+/// Stable tile color derived from the label (FNV-1a hash mapped to a hue).
+fn label_color(label: &str) -> egui::Color32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in label.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::Color32::from(egui::ecolor::Hsva::new(hue, 0.55, 0.65, 1.0))
+}
+
+/// Renders a rounded, colored tile with the label drawn in white.
+pub fn render_tile(label: &str) -> egui::ColorImage {
+    render_tile_colored(label, label_color(label))
+}
+
+/// Tile for files whose decode failed: a red tile with "!".
+pub fn render_error_tile() -> egui::ColorImage {
+    render_tile_colored("!", egui::Color32::from_rgb(190, 60, 60))
+}
+
+/// Tile for a file no decoder can read: the extension, plus the file size and
+/// (when the header gave them) the pixel dimensions in small print below.
+pub fn render_info_tile(label: &str, bytes: u64, dimensions: Option<(u32, u32)>) -> egui::ColorImage {
+    const LINE_HEIGHT: usize = 7;
+    let mut image = render_rounded(label_color(label));
+    let chars: Vec<char> = label.chars().collect();
+    let scale = 2;
+    let text_width = (chars.len() * 4 - 1) * scale;
+    for (index, c) in chars.iter().enumerate() {
+        draw_glyph(&mut image, glyph(*c), (TILE_SIZE - text_width) / 2 + index * 4 * scale, 12, scale, egui::Color32::WHITE);
+    }
+
+    let mut lines = vec![size_label(bytes)];
+    if let Some((width, height)) = dimensions {
+        lines.push(format!("{}X{}", width, height));
+    }
+    for (line_index, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().take((TILE_SIZE - 4) / 4).collect();
+        let text_width = chars.len() * 4 - 1;
+        let y = 12 + 5 * scale + 8 + line_index * LINE_HEIGHT;
+        for (index, c) in chars.iter().enumerate() {
+            draw_glyph(&mut image, glyph(*c), (TILE_SIZE - text_width) / 2 + index * 4, y, 1, egui::Color32::WHITE);
+        }
+    }
+    image
+}
+
+/// "850KB", "12.4MB": short enough for the tile's small print.
+fn size_label(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KB * KB * KB {
+        format!("{:.1}GB", bytes / (KB * KB * KB))
+    } else if bytes >= KB * KB {
+        format!("{:.1}MB", bytes / (KB * KB))
+    } else {
+        format!("{:.0}KB", (bytes / KB).max(1.0))
+    }
+}
+
+fn render_rounded(background: egui::Color32) -> egui::ColorImage {
+    let mut image = egui::ColorImage::new([TILE_SIZE, TILE_SIZE], egui::Color32::TRANSPARENT);
+
+    // Rounded rect background
+    let max = (TILE_SIZE - 1) as f32;
+    for y in 0..TILE_SIZE {
+        for x in 0..TILE_SIZE {
+            let (fx, fy) = (x as f32, y as f32);
+            let cx = fx.clamp(CORNER_RADIUS, max - CORNER_RADIUS);
+            let cy = fy.clamp(CORNER_RADIUS, max - CORNER_RADIUS);
+            if (fx - cx).powi(2) + (fy - cy).powi(2) <= CORNER_RADIUS * CORNER_RADIUS {
+                image.pixels[y * TILE_SIZE + x] = background;
+            }
+        }
+    }
+    image
+}
+
+fn render_tile_colored(label: &str, background: egui::Color32) -> egui::ColorImage {
+    let mut image = render_rounded(background);
+
+    // Label, scaled to fit with one column of spacing between glyphs
+    let chars: Vec<char> = label.chars().collect();
+    let units_wide = chars.len() * 4 - 1;
+    let scale = ((TILE_SIZE - 12) / units_wide).clamp(1, 6);
+    let text_width = units_wide * scale;
+    let text_height = 5 * scale;
+    let origin_x = (TILE_SIZE - text_width) / 2;
+    let origin_y = (TILE_SIZE - text_height) / 2;
+
+    for (index, c) in chars.iter().enumerate() {
+        draw_glyph(&mut image, glyph(*c), origin_x + index * 4 * scale, origin_y, scale, egui::Color32::WHITE);
+    }
+
+    image
+}
+
+fn draw_glyph(image: &mut egui::ColorImage, rows: [u8; 5], x: usize, y: usize, scale: usize, color: egui::Color32) {
+    for (row, bits) in rows.iter().enumerate() {
+        for col in 0..3 {
+            if bits & (0b100 >> col) == 0 {
+                continue;
+            }
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let px = x + col * scale + dx;
+                    let py = y + row * scale + dy;
+                    if px < TILE_SIZE && py < TILE_SIZE {
+                        image.pixels[py * TILE_SIZE + px] = color;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renders the first lines of a text file onto a paper-colored tile.
+/// Letters and digits use the pixel font; other visible characters become a dot.
+pub fn render_text_tile(text: &str) -> egui::ColorImage {
+    const MARGIN: usize = 4;
+    const LINE_HEIGHT: usize = 7;
+    const CHARS_PER_LINE: usize = (TILE_SIZE - 2 * MARGIN) / 4;
+    const MAX_LINES: usize = (TILE_SIZE - 2 * MARGIN) / LINE_HEIGHT;
+    const DOT: [u8; 5] = [0b000, 0b000, 0b000, 0b000, 0b010];
+
+    let paper = egui::Color32::from_rgb(245, 243, 235);
+    let ink = egui::Color32::from_rgb(70, 70, 80);
+    let mut image = egui::ColorImage::new([TILE_SIZE, TILE_SIZE], paper);
+
+    for (line_index, line) in text.lines().take(MAX_LINES).enumerate() {
+        let y = MARGIN + line_index * LINE_HEIGHT;
+        for (column, c) in line.chars().take(CHARS_PER_LINE).enumerate() {
+            let rows = if c.is_ascii_alphanumeric() {
+                glyph(c.to_ascii_uppercase())
+            } else if c.is_whitespace() {
+                continue;
+            } else {
+                DOT
+            };
+            draw_glyph(&mut image, rows, MARGIN + column * 4, y, 1, ink);
+        }
+    }
+
+    image
+}
@@ -0,0 +1,55 @@
+//! Portable mode, for running from a USB stick: with `--portable` or a
+//! `portable.flag` file beside the executable, settings, profile sidecars and
+//! caches live in a `data/` folder next to the binary instead of the per-user
+//! directories.
+//!
+//! The working directory is switched to the executable's folder, so relative
+//! paths in the settings and the manifest resolve there on every machine, and
+//! paths the app writes are stored relative to it when they point inside it.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const FLAG: &str = "--portable";
+pub const MARKER: &str = "portable.flag";
+
+/// The executable's folder once portable mode is on.
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == FLAG) || exe_dir().is_some_and(|dir| dir.join(MARKER).is_file())
+}
+
+/// Turns portable mode on for the rest of the process. Call before anything
+/// reads settings.
+pub fn enable() -> Result<(), String> {
+    let root = exe_dir().ok_or("Could not find the executable's folder")?;
+    std::env::set_current_dir(&root).map_err(|e| format!("{}: {}", root.display(), e))?;
+    let _ = ROOT.set(root);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ROOT.get().is_some()
+}
+
+/// `data/` beside the executable, or None outside portable mode.
+pub fn data_dir() -> Option<PathBuf> {
+    ROOT.get().map(|root| root.join("data"))
+}
+
+/// How a path should be written to settings or the manifest: relative to the
+/// executable's folder when portable mode is on and the path is inside it,
+/// so the stick still works under another drive letter or mount point.
+pub fn stored_path(path: &Path) -> String {
+    ROOT.get()
+        .and_then(|root| path.strip_prefix(root).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,159 @@
+//! Full-resolution textures for the detail windows.
+//!
+//! The shown images, plus the active window's previous and next neighbors
+//! (in filter order), are decoded on a background thread, so arrow-key
+//! browsing rarely waits. Only that set stays resident, so memory is bounded
+//! by the number of open windows. When the set changes before a decode
+//! starts, the decode is dropped, so holding an arrow key settles on the last
+//! image without decoding every one in between.
+
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Longest side of a preview texture; larger images are scaled down to stay
+/// within common GPU texture limits.
+const MAX_PREVIEW_SIDE: u32 = 4096;
+
+/// Paths the worker should decode next, in priority order.
+type Wanted = Arc<(Mutex<Vec<String>>, Condvar)>;
+
+pub struct PreviewCache {
+    window: Vec<String>,
+    textures: HashMap<String, egui::TextureHandle>,
+    /// Paths handed to the worker whose result hasn't come back yet.
+    pending: HashSet<String>,
+    wanted: Wanted,
+    results: Receiver<(String, Result<egui::ColorImage, String>)>,
+}
+
+fn decode(path: &str) -> Result<egui::ColorImage, String> {
+    let image = crate::formats::open(path)?;
+    let image = if image.width().max(image.height()) > MAX_PREVIEW_SIDE {
+        image.thumbnail(MAX_PREVIEW_SIDE, MAX_PREVIEW_SIDE)
+    } else {
+        image
+    };
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, &rgba.into_raw()))
+}
+
+impl PreviewCache {
+    pub fn new() -> Self {
+        let wanted: Wanted = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+        let (sender, results) = mpsc::channel();
+        let worker_wanted = wanted.clone();
+        std::thread::spawn(move || loop {
+            let path = {
+                let (queue, ready) = &*worker_wanted;
+                let Ok(mut queue) = queue.lock() else {
+                    return;
+                };
+                while queue.is_empty() {
+                    queue = match ready.wait(queue) {
+                        Ok(queue) => queue,
+                        Err(_) => return,
+                    };
+                }
+                queue.remove(0)
+            };
+            let result = decode(&path);
+            if sender.send((path, result)).is_err() {
+                return;
+            }
+        });
+        Self {
+            window: Vec::new(),
+            textures: HashMap::new(),
+            pending: HashSet::new(),
+            wanted,
+            results,
+        }
+    }
+
+    /// Keeps textures for `wanted` (highest priority first), evicting the
+    /// rest and replacing any decodes that haven't started yet.
+    pub fn show(&mut self, wanted: &[&str]) {
+        let mut window: Vec<String> = Vec::with_capacity(wanted.len());
+        for path in wanted {
+            if !window.iter().any(|kept| kept == path) {
+                window.push(path.to_string());
+            }
+        }
+        if window == self.window {
+            return;
+        }
+        self.textures.retain(|path, _| window.contains(path));
+
+        let (queue, ready) = &*self.wanted;
+        if let Ok(mut queue) = queue.lock() {
+            // Whatever the worker hasn't picked up yet is superseded
+            for path in queue.drain(..) {
+                self.pending.remove(&path);
+            }
+            for path in &window {
+                if !self.textures.contains_key(path) && self.pending.insert(path.clone()) {
+                    queue.push(path.clone());
+                }
+            }
+            ready.notify_one();
+        }
+        self.window = window;
+    }
+
+    /// Uploads finished decodes that are still in the window. Returns true if
+    /// a new texture arrived.
+    pub fn poll(&mut self, ctx: &egui::Context) -> bool {
+        let mut arrived = false;
+        while let Ok((path, result)) = self.results.try_recv() {
+            self.pending.remove(&path);
+            if !self.window.contains(&path) {
+                continue;
+            }
+            // Failures fall back to the thumbnail, which reports its own errors
+            if let Ok(image) = result {
+                let texture = ctx.load_texture(format!("preview:{}", path), image, egui::TextureOptions::LINEAR);
+                self.textures.insert(path, texture);
+                arrived = true;
+            }
+        }
+        arrived
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&egui::TextureHandle> {
+        self.textures.get(path)
+    }
+
+    /// Decodes `path` again on the next `show`, e.g. after the file was rewritten.
+    pub fn reload(&mut self, path: &str) {
+        self.textures.remove(path);
+        self.window.retain(|kept| kept != path);
+    }
+
+    /// Frees everything, e.g. when the last detail window closes.
+    pub fn clear(&mut self) {
+        if self.window.is_empty() {
+            return;
+        }
+        self.textures.clear();
+        let (queue, _) = &*self.wanted;
+        if let Ok(mut queue) = queue.lock() {
+            for path in queue.drain(..) {
+                self.pending.remove(&path);
+            }
+        }
+        self.window.clear();
+    }
+}
+
+impl Default for PreviewCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
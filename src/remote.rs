@@ -0,0 +1,407 @@
+//! Optional local HTTP endpoint for scripts and stream-deck buttons.
+//!
+//! The server thread only parses and validates requests; anything that needs
+//! app state is sent over a channel and answered from `update`, so malformed
+//! requests never reach the app.
+
+use crate::deep_link::{self, DeepLink};
+use eframe::egui;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+const MAX_BODY_BYTES: usize = 16 * 1024;
+/// Request line plus headers; no client of this API comes close.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+/// Connections handled at once; more are closed right away.
+const MAX_CONNECTIONS: usize = 8;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Shown next to the setting so the API documents itself.
+pub const API_HELP: &str = "\
+GET  /search?q=<query>         → JSON list of matches
+POST /copy  {\"path\": \"…\"}      → copy that image
+POST /copy  {\"query\": \"…\"}     → copy the top match
+POST /show  {\"query\": \"…\"}     → raise the window with the query filled in
+POST /open  {\"link\": \"chlorine://…\"} → follow a deep link
+Send the token as \"Authorization: Bearer <token>\".";
+
+#[derive(Debug, Clone)]
+pub enum RemoteCommand {
+    Search { query: String },
+    CopyPath { path: String },
+    CopyQuery { query: String },
+    Show { query: String },
+    Open { link: DeepLink },
+}
+
+/// Answer from the app: HTTP status plus a JSON body.
+pub struct RemoteReply {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl RemoteReply {
+    pub fn ok(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message.into() }),
+        }
+    }
+}
+
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    pub reply: mpsc::Sender<RemoteReply>,
+}
+
+/// A running listener; dropping it stops the thread.
+pub struct RemoteServer {
+    pub port: u16,
+    pub token: String,
+    pub requests: mpsc::Receiver<RemoteRequest>,
+    stop: Arc<AtomicBool>,
+}
+
+impl RemoteServer {
+    /// Binds to 127.0.0.1 only; the port must be free.
+    pub fn start(port: u16, token: String, ctx: egui::Context) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("127.0.0.1:{}: {}", port, e))?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let (sender, requests) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread_token = token.clone();
+        std::thread::Builder::new()
+            .name("remote_control".to_string())
+            .spawn(move || {
+                let open = Arc::new(AtomicUsize::new(0));
+                while !thread_stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        // Each connection gets its own thread, so a slow client can't hold up the others
+                        Ok((stream, _)) => {
+                            if open.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                                open.fetch_sub(1, Ordering::Relaxed);
+                                continue;
+                            }
+                            let (done, token, sender, ctx) = (open.clone(), thread_token.clone(), sender.clone(), ctx.clone());
+                            let spawned = std::thread::Builder::new().name("remote_connection".to_string()).spawn(move || {
+                                let _ = handle_connection(stream, &token, &sender, &ctx);
+                                done.fetch_sub(1, Ordering::Relaxed);
+                            });
+                            if spawned.is_err() {
+                                open.fetch_sub(1, Ordering::Relaxed);
+                            }
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(Duration::from_millis(50));
+                        }
+                        Err(_) => std::thread::sleep(Duration::from_millis(200)),
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            port,
+            token,
+            requests,
+            stop,
+        })
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 128 random bits from the std hasher's per-process random keys, as hex.
+pub fn generate_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    let part = || {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default(),
+        );
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", part(), part())
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    token: &str,
+    sender: &mpsc::Sender<RemoteRequest>,
+    ctx: &egui::Context,
+) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let reply = match read_request(&mut reader) {
+        Ok(request) if !authorized(&request, token) => RemoteReply::error(401, "missing or wrong token"),
+        Ok(request) => match parse_command(&request) {
+            Ok(command) => dispatch(command, sender, ctx),
+            Err(reply) => reply,
+        },
+        Err(message) => RemoteReply::error(400, message),
+    };
+    write_reply(stream, reply)
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Reads one line, counting it against what's left of `budget`.
+fn read_line(reader: &mut impl BufRead, budget: &mut usize) -> Result<String, String> {
+    let mut line = String::new();
+    let read = reader
+        .take(*budget as u64)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    if !line.ends_with('\n') {
+        return Err(if read == *budget { "request headers too large" } else { "request ended early" }.to_string());
+    }
+    *budget -= read;
+    Ok(line)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, String> {
+    let mut budget = MAX_HEAD_BYTES;
+    let request_line = read_line(reader, &mut budget)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".to_string());
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    let mut authorization = None;
+    loop {
+        let line = read_line(reader, &mut budget)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err("malformed header".to_string());
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| "bad Content-Length".to_string())?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = value.strip_prefix("Bearer ").map(str::to_string);
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err("request body too large".to_string());
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        authorization,
+        body,
+    })
+}
+
+fn authorized(request: &HttpRequest, token: &str) -> bool {
+    !token.is_empty() && request.authorization.as_deref().is_some_and(|given| same_secret(given, token))
+}
+
+/// Compares without stopping at the first difference, so the time taken
+/// doesn't tell a caller how much of a guess was right.
+fn same_secret(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len() && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn parse_command(request: &HttpRequest) -> Result<RemoteCommand, RemoteReply> {
+    let json: serde_json::Value = if request.body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&request.body).map_err(|_| RemoteReply::error(400, "body is not valid JSON"))?
+    };
+    let body_field = |field: &str| json.get(field).and_then(|v| v.as_str()).map(str::to_string);
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/search") => query_param(&request.query, "q")
+            .map(|query| RemoteCommand::Search { query })
+            .ok_or_else(|| RemoteReply::error(400, "missing q parameter")),
+        ("POST", "/copy") => {
+            if let Some(path) = body_field("path") {
+                Ok(RemoteCommand::CopyPath { path })
+            } else if let Some(query) = body_field("query") {
+                Ok(RemoteCommand::CopyQuery { query })
+            } else {
+                Err(RemoteReply::error(400, "expected a JSON body with \"path\" or \"query\""))
+            }
+        }
+        ("POST", "/show") => Ok(RemoteCommand::Show {
+            query: body_field("query").unwrap_or_default(),
+        }),
+        ("POST", "/open") => {
+            let link = body_field("link").ok_or_else(|| RemoteReply::error(400, "expected a JSON body with \"link\""))?;
+            deep_link::parse(&link)
+                .map(|link| RemoteCommand::Open { link })
+                .map_err(|e| RemoteReply::error(400, e))
+        }
+        (_, "/search" | "/copy" | "/show" | "/open") => Err(RemoteReply::error(405, "method not allowed")),
+        _ => Err(RemoteReply::error(404, "unknown endpoint")),
+    }
+}
+
+/// Passes a deep link to an instance already listening on `port`, so a link
+/// opened while the app is running doesn't start a second window.
+pub fn forward_link(port: u16, token: &str, link: &str) -> Result<(), String> {
+    let address = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = TcpStream::connect_timeout(&address, Duration::from_millis(300)).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT)).map_err(|e| e.to_string())?;
+    let body = serde_json::json!({ "link": link }).to_string();
+    write!(
+        stream,
+        "POST /open HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        token,
+        body.len(),
+        body
+    )
+    .map_err(|e| e.to_string())?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line).map_err(|e| e.to_string())?;
+    match status_line.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        Some(status) => Err(format!("the running instance answered {}", status)),
+        None => Err("no answer from the running instance".to_string()),
+    }
+}
+
+/// Hands a valid command to the app and waits briefly for its answer.
+fn dispatch(command: RemoteCommand, sender: &mpsc::Sender<RemoteRequest>, ctx: &egui::Context) -> RemoteReply {
+    let (reply, answer) = mpsc::channel();
+    if sender.send(RemoteRequest { command, reply }).is_err() {
+        return RemoteReply::error(503, "app is shutting down");
+    }
+    ctx.request_repaint();
+    answer
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| RemoteReply::error(503, "app did not respond"))
+}
+
+fn write_reply(mut stream: TcpStream, reply: RemoteReply) -> std::io::Result<()> {
+    let body = reply.body.to_string();
+    let reason = match reply.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        reply.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Value of `name` in an `a=1&b=2` query string, percent-decoded.
+pub fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| percent_decode(value))
+}
+
+pub fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next()?, input.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &[u8]) -> Result<HttpRequest, String> {
+        read_request(&mut BufReader::new(raw))
+    }
+
+    #[test]
+    fn token_must_match_exactly() {
+        assert!(same_secret("0123abcd", "0123abcd"));
+        assert!(!same_secret("0123abce", "0123abcd"));
+        assert!(!same_secret("0123abc", "0123abcd"));
+        assert!(!same_secret("", "0123abcd"));
+
+        let request = request(b"GET /search?q=cat HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n").unwrap();
+        assert!(authorized(&request, "secret"));
+        assert!(!authorized(&request, "secreT"));
+        assert!(!authorized(&request, ""), "an empty token never authorizes");
+    }
+
+    #[test]
+    fn oversized_or_unfinished_heads_are_rejected() {
+        let mut long = b"GET /search HTTP/1.1\r\nX-Padding: ".to_vec();
+        long.extend(std::iter::repeat_n(b'a', MAX_HEAD_BYTES));
+        long.extend_from_slice(b"\r\n\r\n");
+        assert_eq!(request(&long).err().as_deref(), Some("request headers too large"));
+
+        let many: Vec<u8> = b"GET /search HTTP/1.1\r\n"
+            .iter()
+            .chain(b"X-Padding: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n".repeat(MAX_HEAD_BYTES / 32).iter())
+            .copied()
+            .collect();
+        assert_eq!(request(&many).err().as_deref(), Some("request headers too large"));
+
+        assert_eq!(request(b"GET /search HTTP/1.1\r\nHost: x").err().as_deref(), Some("request ended early"));
+        let body_too_large = format!("POST /copy HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert_eq!(request(body_too_large.as_bytes()).err().as_deref(), Some("request body too large"));
+    }
+
+    #[test]
+    fn reads_a_well_formed_request() {
+        let request = request(b"POST /copy HTTP/1.1\r\nContent-Length: 13\r\n\r\n{\"query\":\"a\"}").unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/copy"));
+        assert_eq!(request.body, b"{\"query\":\"a\"}");
+    }
+}
@@ -0,0 +1,123 @@
+//! The review step in front of every bulk removal from the manifest.
+//!
+//! Callers collect what they would remove, each entry with the reason, and
+//! open a [`RemovalReview`]. Nothing is written until the user confirms;
+//! unticked entries are left alone, and cancelling changes nothing. The
+//! caller applies the approved entries.
+
+use crate::ImageInfo;
+
+const ROW_HEIGHT: f32 = 52.0;
+const THUMBNAIL_SIZE: f32 = 48.0;
+
+#[derive(Debug, Clone)]
+pub struct Removal {
+    /// The manifest category the entry is removed from.
+    pub category: String,
+    pub image: ImageInfo,
+    pub reason: String,
+}
+
+pub enum Outcome {
+    Pending,
+    Cancelled,
+    Approved(Vec<Removal>),
+}
+
+pub struct RemovalReview {
+    title: String,
+    /// Each entry and whether it's still ticked.
+    items: Vec<(Removal, bool)>,
+}
+
+impl RemovalReview {
+    pub fn new(title: impl Into<String>, removals: Vec<Removal>) -> Self {
+        Self {
+            title: title.into(),
+            items: removals.into_iter().map(|removal| (removal, true)).collect(),
+        }
+    }
+
+    /// Draws the window. `thumbnail` draws a square thumbnail of the given size.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        blocker: Option<&str>,
+        mut thumbnail: impl FnMut(&mut egui::Ui, &ImageInfo, f32),
+    ) -> Outcome {
+        let mut open = true;
+        let mut outcome = Outcome::Pending;
+        egui::Window::new(format!("🗑 {}", self.title))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let ticked = self.items.iter().filter(|(_, ticked)| *ticked).count();
+                ui.label(format!(
+                    "{} of {} entries will be removed from the manifest. Files on disk are not touched.",
+                    ticked,
+                    self.items.len()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.small_button("Tick all").clicked() {
+                        self.items.iter_mut().for_each(|(_, ticked)| *ticked = true);
+                    }
+                    if ui.small_button("Untick all").clicked() {
+                        self.items.iter_mut().for_each(|(_, ticked)| *ticked = false);
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .auto_shrink([false, true])
+                    .show_rows(ui, ROW_HEIGHT, self.items.len(), |ui, rows| {
+                        for (removal, ticked) in &mut self.items[rows] {
+                            ui.push_id((&removal.category, &removal.image.full_path), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.set_height(ROW_HEIGHT);
+                                    ui.checkbox(ticked, "");
+                                    thumbnail(ui, &removal.image, THUMBNAIL_SIZE);
+                                    ui.vertical(|ui| {
+                                        ui.label(&removal.image.relative_path);
+                                        ui.label(
+                                            egui::RichText::new(format!("{} · {}", removal.category, removal.reason))
+                                                .small()
+                                                .weak(),
+                                        );
+                                    });
+                                });
+                            });
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let reason = match blocker {
+                        Some(blocker) => Some(blocker.to_string()),
+                        None if ticked == 0 => Some("Nothing is ticked".to_string()),
+                        None => None,
+                    };
+                    if ui
+                        .add_enabled(reason.is_none(), egui::Button::new(format!("🗑 Remove {}", ticked)))
+                        .on_disabled_hover_text(reason.unwrap_or_default())
+                        .clicked()
+                    {
+                        let approved = self
+                            .items
+                            .iter()
+                            .filter(|(_, ticked)| *ticked)
+                            .map(|(removal, _)| removal.clone())
+                            .collect();
+                        outcome = Outcome::Approved(approved);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        outcome = Outcome::Cancelled;
+                    }
+                });
+            });
+        if !open {
+            Outcome::Cancelled
+        } else {
+            outcome
+        }
+    }
+}
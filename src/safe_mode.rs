@@ -0,0 +1,73 @@
+//! `--safe-mode`: start with default settings and a throwaway profile folder,
+//! so a corrupted settings file or sidecar can't keep the app from starting.
+//!
+//! Nothing in safe mode writes to the real settings or profile files. The
+//! banner's reset actions are the exception; they act on the real files after
+//! a confirmation.
+
+use std::path::PathBuf;
+
+pub const FLAG: &str = "--safe-mode";
+
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == FLAG)
+}
+
+/// Where sidecar files go during a safe-mode session. Emptied at startup, so
+/// every safe-mode session starts clean.
+pub fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join("chlorine-safe-mode")
+}
+
+pub fn prepare_scratch_dir() {
+    let _ = std::fs::remove_dir_all(scratch_dir());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reset {
+    SettingsFile,
+    ThumbnailCache,
+}
+
+impl Reset {
+    pub fn label(self) -> &'static str {
+        match self {
+            Reset::SettingsFile => "Reset settings file",
+            Reset::ThumbnailCache => "Clear thumbnail cache",
+        }
+    }
+
+    pub fn path(self) -> PathBuf {
+        match self {
+            Reset::SettingsFile => crate::settings::settings_path(),
+            Reset::ThumbnailCache => crate::settings::cache_dir(),
+        }
+    }
+
+    /// Deletes the file or directory; a missing one counts as success.
+    pub fn run(self) -> Result<String, String> {
+        let path = self.path();
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => Ok(format!("Removed {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(format!("{} was already gone", path.display())),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Starts a normal instance with the same arguments minus `--safe-mode`.
+/// The caller closes this one.
+pub fn restart_normally() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let args: Vec<String> = std::env::args().skip(1).filter(|arg| arg != FLAG).collect();
+    std::process::Command::new(&exe)
+        .args(args)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not start {}: {}", exe.display(), e))
+}
@@ -0,0 +1,139 @@
+//! Scrolling the virtualized result list to the keyboard highlight.
+//!
+//! `show_rows` only lays out the rows in view, so a highlighted row outside
+//! them has no widget to scroll to. Instead the row's offset is computed from
+//! the fixed row height and the scroll area is moved there directly.
+//!
+//! The grid view is the same list with several results per row. When the
+//! number of columns or the row height changes (a resize, the cell-size
+//! slider, switching views), the scroll offset is recomputed so the first
+//! visible result stays first.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Height of one result row as passed to `show_rows`, without item spacing.
+/// Every offset computation in the list goes through this.
+pub const RESULT_ROW_HEIGHT: f32 = 80.0;
+/// Height of a grid cell's name line, below the thumbnail.
+pub const GRID_LABEL_HEIGHT: f32 = 22.0;
+/// Space around the thumbnail inside a grid cell.
+pub const GRID_CELL_PADDING: f32 = 6.0;
+
+/// Grid columns that fit `width` for square cells of `cell` points.
+pub fn grid_columns(width: f32, cell: f32, spacing: f32) -> usize {
+    let cell_width = cell + 2.0 * GRID_CELL_PADDING;
+    (((width + spacing) / (cell_width + spacing)).floor() as usize).max(1)
+}
+
+/// Row height passed to `show_rows` for the grid.
+pub fn grid_row_height(cell: f32) -> f32 {
+    cell + GRID_LABEL_HEIGHT + 2.0 * GRID_CELL_PADDING
+}
+
+const SCROLL_DURATION: Duration = Duration::from_millis(180);
+
+struct Animation {
+    from: f32,
+    to: f32,
+    started: Instant,
+    duration: Duration,
+}
+
+/// What the results list looked like last frame, plus any scroll in progress.
+#[derive(Default)]
+pub struct ResultsScroll {
+    offset: f32,
+    viewport: f32,
+    /// Row height plus item spacing.
+    pitch: f32,
+    /// Results per row: 1 in the list, more in the grid.
+    columns: usize,
+    animation: Option<Animation>,
+}
+
+impl ResultsScroll {
+    /// Remembers the list's scroll offset and size after it was drawn.
+    pub fn record(&mut self, offset: f32, viewport: f32, pitch: f32) {
+        self.offset = offset;
+        self.viewport = viewport;
+        self.pitch = pitch;
+    }
+
+    /// Call before drawing with this frame's layout. If it differs from the
+    /// last one, the scroll jumps so the result that was first in view still is.
+    pub fn relayout(&mut self, columns: usize, pitch: f32) {
+        let columns = columns.max(1);
+        if self.columns == columns && self.pitch == pitch {
+            return;
+        }
+        if self.pitch > 0.0 && self.columns > 0 && self.animation.is_none() {
+            let anchor = (self.offset / self.pitch).floor() as usize * self.columns;
+            let target = (anchor / columns) as f32 * pitch;
+            self.animation = Some(Animation {
+                from: target,
+                to: target,
+                started: Instant::now(),
+                duration: Duration::ZERO,
+            });
+        }
+        self.columns = columns;
+        self.pitch = pitch;
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns.max(1)
+    }
+
+    /// Results that fit in the view, the distance Page Up / Page Down move.
+    pub fn page_items(&self) -> usize {
+        if self.pitch <= 0.0 {
+            return 1;
+        }
+        ((self.viewport / self.pitch).floor() as usize).max(1) * self.columns.max(1)
+    }
+
+    /// Rows entirely inside the view last frame.
+    fn visible_rows(&self) -> Range<usize> {
+        if self.pitch <= 0.0 {
+            return 0..0;
+        }
+        let first = (self.offset / self.pitch).ceil() as usize;
+        let end = ((self.offset + self.viewport) / self.pitch).floor() as usize;
+        first..end.max(first)
+    }
+
+    /// Centers the row holding result `index` unless it is already fully
+    /// visible; animated or a jump.
+    pub fn reveal(&mut self, index: usize, animate: bool) {
+        let row = index / self.columns.max(1);
+        if self.pitch <= 0.0 || self.visible_rows().contains(&row) {
+            return;
+        }
+        let target = (row as f32 * self.pitch + self.pitch / 2.0 - self.viewport / 2.0).max(0.0);
+        self.animation = Some(Animation {
+            from: self.offset,
+            to: target,
+            started: Instant::now(),
+            duration: if animate { SCROLL_DURATION } else { Duration::ZERO },
+        });
+    }
+
+    /// The offset to force on the scroll area this frame, while a scroll is in progress.
+    pub fn frame_offset(&mut self) -> Option<f32> {
+        let animation = self.animation.as_ref()?;
+        let elapsed = animation.started.elapsed();
+        if elapsed >= animation.duration {
+            let to = animation.to;
+            self.animation = None;
+            return Some(to);
+        }
+        let t = elapsed.as_secs_f32() / animation.duration.as_secs_f32();
+        let eased = 1.0 - (1.0 - t).powi(3);
+        Some(animation.from + (animation.to - animation.from) * eased)
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+}
@@ -0,0 +1,412 @@
+//! Prebuilt search index and query matching.
+//!
+//! Every searchable field is lowercased once when the library loads, so
+//! filtering on each keystroke is plain substring checks.
+
+use crate::source_hints::{self, Classifier};
+use crate::ImageData;
+
+/// One image in the index, pointing back into `ImageData` by category and position.
+pub struct IndexEntry {
+    pub category: String,
+    pub image_index: usize,
+    pub filename: String,
+    /// Relative path, lowercased with `/` separators regardless of the manifest's OS.
+    pub path: String,
+    pub category_lower: String,
+    /// Best-guess source from the filename, as a `source:` key ("phone-photo").
+    pub source: String,
+}
+
+/// Entries are ordered by category, then filename, which is also the order
+/// results are shown in. Filtering therefore only sorts by match rank, and
+/// a single category is a contiguous range that can be scanned on its own.
+#[derive(Default)]
+pub struct SearchIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+/// Lowercases a path and unifies Windows and Unix separators.
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+/// Everything before the last `/`: "memes/cats" for "memes/cats/a.png",
+/// and "" for a file at the top of the library. Expects `/` separators.
+pub fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map_or("", |slash| &path[..slash])
+}
+
+/// Splits a leading "category/" scope off a query: "reac/thumbs big" gives
+/// ("reac", "thumbs big"). The scope is everything up to the last `/` of the
+/// first word, so grouped names like "screens/slack/" work; words holding an
+/// operator (`path:a/b`) are never scopes.
+pub fn split_scope(query: &str) -> Option<(&str, &str)> {
+    let query = query.trim_start();
+    let word_end = query.find(char::is_whitespace).unwrap_or(query.len());
+    let word = &query[..word_end];
+    let slash = word.rfind('/')?;
+    let scope = &word[..slash];
+    if scope.is_empty() || word.contains(':') {
+        return None;
+    }
+    Some((scope, query[slash + 1..].trim_start()))
+}
+
+/// Category names starting with `prefix`, ignoring case, sorted.
+pub fn categories_starting_with<'a>(names: impl Iterator<Item = &'a String>, prefix: &str) -> Vec<&'a String> {
+    let prefix = prefix.to_lowercase();
+    let mut matches: Vec<&String> = names.filter(|name| name.to_lowercase().starts_with(&prefix)).collect();
+    matches.sort();
+    matches
+}
+
+/// The category a scope names: an exact match (ignoring case), else the only
+/// category starting with it.
+pub fn resolve_scope<'a>(names: impl Iterator<Item = &'a String> + Clone, scope: &str) -> Option<String> {
+    if let Some(exact) = names.clone().find(|name| name.eq_ignore_ascii_case(scope)) {
+        return Some(exact.clone());
+    }
+    match categories_starting_with(names, scope).as_slice() {
+        [only] => Some((*only).clone()),
+        _ => None,
+    }
+}
+
+impl SearchIndex {
+    pub fn build(data: &ImageData, sources: &Classifier) -> Self {
+        let mut entries = Vec::new();
+        let mut categories: Vec<_> = data.categories.iter().collect();
+        categories.sort_by(|a, b| a.0.cmp(b.0));
+        for (category_name, category) in categories {
+            let category_lower = category_name.to_lowercase();
+            let mut order: Vec<usize> = (0..category.images.len()).collect();
+            order.sort_by(|&a, &b| category.images[a].filename.cmp(&category.images[b].filename));
+            for image_index in order {
+                let image = &category.images[image_index];
+                entries.push(IndexEntry {
+                    category: category_name.clone(),
+                    image_index,
+                    filename: image.filename.to_lowercase(),
+                    path: normalize_path(&image.relative_path),
+                    category_lower: category_lower.clone(),
+                    source: source_hints::key(sources.classify(&image.filename)),
+                });
+            }
+        }
+        Self { entries }
+    }
+
+    /// Where `category`'s entries are; empty when it has none.
+    pub fn category_range(&self, category: &str) -> std::ops::Range<usize> {
+        let start = self.entries.partition_point(|entry| entry.category.as_str() < category);
+        let end = start + self.entries[start..].partition_point(|entry| entry.category == category);
+        start..end
+    }
+
+    /// Labels every entry again, after the source rules changed.
+    pub fn classify(&mut self, sources: &Classifier) {
+        for entry in &mut self.entries {
+            entry.source = source_hints::key(sources.classify(&entry.filename));
+        }
+    }
+}
+
+/// Match quality, best first; results are sorted by this before name.
+/// A filename beats the category name, which beats the rest of the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchRank {
+    FilenamePrefix,
+    FilenameContains,
+    Category,
+    Path,
+}
+
+impl MatchRank {
+    /// The field that matched, when it isn't the filename the row already shows.
+    pub fn field(self) -> Option<&'static str> {
+        match self {
+            MatchRank::FilenamePrefix | MatchRank::FilenameContains => None,
+            MatchRank::Category => Some("category"),
+            MatchRank::Path => Some("path"),
+        }
+    }
+}
+
+/// Orders filtered results best match first. The sort is stable, so ties
+/// keep the index order: category, then filename.
+pub fn sort_matches(matches: &mut [(MatchRank, usize)]) {
+    matches.sort_by_key(|&(rank, _)| rank);
+}
+
+/// A search box query split into free text and operators.
+#[derive(Debug, Default)]
+pub struct ParsedQuery {
+    /// Free text matched against filename, path and category.
+    pub text: String,
+    /// `path:` terms, each of which must appear in the relative path.
+    pub path_terms: Vec<String>,
+    /// `name:` terms, each of which must appear in the filename.
+    pub name_terms: Vec<String>,
+    /// `color:#rrggbb` target; checked against color signatures by the caller.
+    pub color: Option<crate::colors::Rgb>,
+    /// `ar:16:9` target width/height ratio; checked against the dimension cache by the caller.
+    pub aspect: Option<f32>,
+    /// `mp:>12` megapixel bound; checked against the dimension cache by the caller.
+    pub megapixels: Option<NumberFilter>,
+    /// `source:screenshot`; matches sources starting with it, so `source:phone` works too.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    AtMost,
+    /// Within 5%, since megapixel counts are rarely round.
+    About,
+    AtLeast,
+    Greater,
+}
+
+/// A numeric operator value such as ">12", "<=2" or "8".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFilter {
+    pub comparison: Comparison,
+    pub value: f32,
+}
+
+impl NumberFilter {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (comparison, number) = if let Some(rest) = text.strip_prefix(">=") {
+            (Comparison::AtLeast, rest)
+        } else if let Some(rest) = text.strip_prefix("<=") {
+            (Comparison::AtMost, rest)
+        } else if let Some(rest) = text.strip_prefix('>') {
+            (Comparison::Greater, rest)
+        } else if let Some(rest) = text.strip_prefix('<') {
+            (Comparison::Less, rest)
+        } else {
+            (Comparison::About, text.strip_prefix('=').unwrap_or(text))
+        };
+        let value: f32 = number.parse().ok()?;
+        (value.is_finite() && value >= 0.0).then_some(Self { comparison, value })
+    }
+
+    pub fn matches(&self, value: f32) -> bool {
+        match self.comparison {
+            Comparison::Less => value < self.value,
+            Comparison::AtMost => value <= self.value,
+            Comparison::About => (value - self.value).abs() <= self.value * 0.05,
+            Comparison::AtLeast => value >= self.value,
+            Comparison::Greater => value > self.value,
+        }
+    }
+}
+
+/// "16:9", "16/9" or "1.78" as a width/height ratio.
+fn parse_aspect(text: &str) -> Option<f32> {
+    let ratio = match text.split_once([':', '/']) {
+        Some((w, h)) => w.parse::<f32>().ok()? / h.parse::<f32>().ok()?,
+        None => text.parse().ok()?,
+    };
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
+/// True for a search word that is a working operator (`mp:>12`), false for
+/// free text, including operators whose value doesn't parse.
+pub fn is_operator(word: &str) -> bool {
+    ParsedQuery::parse(word).text.is_empty()
+}
+
+impl ParsedQuery {
+    /// True if the query needs image sizes to decide matches.
+    pub fn needs_dimensions(&self) -> bool {
+        self.aspect.is_some() || self.megapixels.is_some()
+    }
+
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = ParsedQuery::default();
+        let mut text_words = Vec::new();
+        for word in query.split_whitespace() {
+            match word.split_once(':') {
+                Some((operator, value)) if operator.eq_ignore_ascii_case("path") => {
+                    if !value.is_empty() {
+                        parsed.path_terms.push(normalize_path(value));
+                    }
+                }
+                Some((operator, value)) if operator.eq_ignore_ascii_case("name") => {
+                    if !value.is_empty() {
+                        parsed.name_terms.push(value.to_lowercase());
+                    }
+                }
+                Some((operator, value)) if operator.eq_ignore_ascii_case("ar") => match parse_aspect(value) {
+                    Some(aspect) => parsed.aspect = Some(aspect),
+                    None => text_words.push(word.to_lowercase()),
+                },
+                Some((operator, value)) if operator.eq_ignore_ascii_case("mp") => match NumberFilter::parse(value) {
+                    Some(filter) => parsed.megapixels = Some(filter),
+                    None => text_words.push(word.to_lowercase()),
+                },
+                Some((operator, value)) if operator.eq_ignore_ascii_case("source") => {
+                    if !value.is_empty() {
+                        parsed.source = Some(source_hints::key(value));
+                    }
+                }
+                Some((operator, value)) if operator.eq_ignore_ascii_case("color") => {
+                    match crate::colors::parse_hex(value) {
+                        Some(color) => parsed.color = Some(color),
+                        None => text_words.push(word.to_lowercase()),
+                    }
+                }
+                _ => text_words.push(word.to_lowercase()),
+            }
+        }
+        parsed.text = text_words.join(" ");
+        parsed
+    }
+
+    /// Returns how well an entry matches, or None if it doesn't.
+    pub fn rank(&self, entry: &IndexEntry) -> Option<MatchRank> {
+        if !self.path_terms.iter().all(|term| entry.path.contains(term.as_str())) {
+            return None;
+        }
+        if !self.name_terms.iter().all(|term| entry.filename.contains(term.as_str())) {
+            return None;
+        }
+        if self.source.as_ref().is_some_and(|source| !entry.source.starts_with(source.as_str())) {
+            return None;
+        }
+        if self.text.is_empty() {
+            return Some(MatchRank::FilenamePrefix);
+        }
+        if entry.filename.starts_with(&self.text) {
+            Some(MatchRank::FilenamePrefix)
+        } else if entry.filename.contains(&self.text) {
+            Some(MatchRank::FilenameContains)
+        } else if entry.category_lower.contains(&self.text) {
+            Some(MatchRank::Category)
+        } else if entry.path.contains(&self.text) {
+            Some(MatchRank::Path)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(category: &str, relative_path: &str) -> IndexEntry {
+        IndexEntry {
+            category: category.to_string(),
+            image_index: 0,
+            filename: relative_path.rsplit('/').next().unwrap_or_default().to_lowercase(),
+            path: normalize_path(relative_path),
+            category_lower: category.to_lowercase(),
+            source: String::new(),
+        }
+    }
+
+    fn rank(query: &str, entry: &IndexEntry) -> Option<MatchRank> {
+        ParsedQuery::parse(query).rank(entry)
+    }
+
+    fn category(name: &str, filenames: Vec<String>) -> (String, crate::Category) {
+        let images: Vec<crate::ImageInfo> = filenames
+            .into_iter()
+            .map(|filename| crate::ImageInfo {
+                relative_path: format!("{}/{}", name, filename),
+                full_path: format!("/library/{}/{}", name, filename),
+                extension: ".png".to_string(),
+                filename,
+                size: 1,
+                checksum: None,
+                width: None,
+                height: None,
+            })
+            .collect();
+        let category = crate::Category {
+            directory: name.to_string(),
+            count: images.len() as u32,
+            images,
+        };
+        (name.to_string(), category)
+    }
+
+    #[test]
+    fn filename_match_ranks_above_category_match() {
+        let by_name = entry("Misc", "misc/Cat.png");
+        let by_category = entry("Cats", "cats/dog.png");
+        assert_eq!(rank("cat", &by_name), Some(MatchRank::FilenamePrefix));
+        assert_eq!(rank("cat", &entry("Misc", "misc/wildcat.png")), Some(MatchRank::FilenameContains));
+        assert_eq!(rank("cat", &by_category), Some(MatchRank::Category));
+        assert!(MatchRank::FilenameContains < MatchRank::Category);
+    }
+
+    #[test]
+    fn category_match_ranks_above_path_match() {
+        let by_category = entry("Cats", "cats/dog.png");
+        let by_path = entry("Pets", "pets/cats/dog.png");
+        assert_eq!(rank("cats", &by_category), Some(MatchRank::Category));
+        assert_eq!(rank("cats", &by_path), Some(MatchRank::Path));
+        assert!(MatchRank::Category < MatchRank::Path);
+        assert_eq!(rank("cats", &entry("Pets", "pets/dog.png")), None);
+    }
+
+    #[test]
+    fn name_operator_only_looks_at_the_filename() {
+        assert_eq!(rank("name:cat", &entry("Cats", "cats/dog.png")), None);
+        assert_eq!(rank("name:cat", &entry("Pets", "cats/dog.png")), None);
+        assert!(rank("name:cat", &entry("Pets", "pets/wildcat.png")).is_some());
+        // Combined with free text, both have to match
+        assert_eq!(rank("name:cat dog", &entry("Pets", "pets/wildcat.png")), None);
+        assert_eq!(rank("name:CAT pets", &entry("Pets", "pets/wildcat.png")), Some(MatchRank::Category));
+    }
+
+    #[test]
+    fn large_category_is_one_range_in_filename_order() {
+        const COUNT: usize = 100_000;
+        // Stored out of order; 7919 is coprime to COUNT, so every number appears once
+        let middle = (0..COUNT)
+            .map(|n| {
+                let number = n * 7919 % COUNT;
+                match n % 3 {
+                    0 => format!("img_{:06}.png", number),
+                    1 => format!("x_img_{:06}.png", number),
+                    _ => format!("other_{:06}.png", number),
+                }
+            })
+            .collect();
+        let data = crate::ImageData {
+            categories: std::collections::HashMap::from([
+                category("Alpha", vec!["img_a.png".to_string(), "b.png".to_string()]),
+                category("Middle", middle),
+                category("Zulu", vec!["img_z.png".to_string()]),
+            ]),
+        };
+        let index = SearchIndex::build(&data, &Classifier::new(&source_hints::default_rules()));
+
+        let range = index.category_range("Middle");
+        assert_eq!(range, 2..2 + COUNT);
+        assert!(index.entries[range.clone()].iter().all(|entry| entry.category == "Middle"));
+        assert!(index.entries[range.clone()].windows(2).all(|pair| pair[0].filename < pair[1].filename));
+        assert_eq!(index.category_range("Zulu"), 2 + COUNT..3 + COUNT);
+        assert!(index.category_range("Nope").is_empty());
+
+        let query = ParsedQuery::parse("img");
+        let mut matches: Vec<(MatchRank, usize)> = range
+            .filter_map(|entry_index| Some((query.rank(&index.entries[entry_index])?, entry_index)))
+            .collect();
+        sort_matches(&mut matches);
+
+        assert_eq!(matches.len(), COUNT / 3 * 2 + 1);
+        assert_eq!(matches.first().map(|m| m.0), Some(MatchRank::FilenamePrefix));
+        assert_eq!(matches.last().map(|m| m.0), Some(MatchRank::FilenameContains));
+        for pair in matches.windows(2) {
+            let (rank, a) = pair[0];
+            let (next_rank, b) = pair[1];
+            assert!(rank < next_rank || (rank == next_rank && index.entries[a].filename < index.entries[b].filename));
+        }
+    }
+}
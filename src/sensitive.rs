@@ -0,0 +1,41 @@
+//! Per-profile list of images marked sensitive. Their thumbnails are never
+//! drawn; larger views show them only after an explicit reveal, and privacy
+//! mode hides them even then.
+//!
+//! Paths are stored normalized, like the hidden list.
+
+use crate::search::normalize_path;
+use crate::store::UserData;
+use std::collections::BTreeSet;
+
+const SECTION: &str = "sensitive";
+
+pub struct SensitiveList {
+    paths: BTreeSet<String>,
+}
+
+impl SensitiveList {
+    pub fn load(store: &mut UserData) -> Self {
+        Self {
+            paths: store.get(SECTION),
+        }
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.paths)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(&normalize_path(path))
+    }
+
+    /// Marks or unmarks a path. Returns true if anything changed.
+    pub fn set(&mut self, path: &str, sensitive: bool) -> bool {
+        let path = normalize_path(path);
+        if sensitive {
+            self.paths.insert(path)
+        } else {
+            self.paths.remove(&path)
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Runs of numbered frames ("frame_0001.png" … "frame_0240.png") shown as
+//! one stacked row.
+//!
+//! Detection only compares filenames within a category: the last group of
+//! digits in the name is the frame number and everything around it must
+//! match. Digits that read as a date, a time or a timestamp are left alone,
+//! so a folder of daily screenshots doesn't collapse. Nothing is written to
+//! the manifest; stacks are rebuilt whenever the results change.
+
+use std::collections::HashMap;
+
+/// Fewer numbered files than this are just files.
+pub const MIN_FRAMES: usize = 3;
+
+/// A filename split around its frame number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameName<'a> {
+    pub prefix: &'a str,
+    pub digits: &'a str,
+    /// Text after the number, extension included.
+    pub suffix: &'a str,
+}
+
+fn is_date(digits: &str) -> bool {
+    let part = |range: std::ops::Range<usize>| digits.get(range).and_then(|part| part.parse::<u32>().ok());
+    match (part(0..4), part(4..6), part(6..8)) {
+        (Some(year), Some(month), Some(day)) => {
+            (1900..=2099).contains(&year) && (1..=12).contains(&month) && (1..=31).contains(&day)
+        }
+        _ => false,
+    }
+}
+
+/// Splits `filename` at the last group of digits before the extension, or
+/// returns None when there is none or it doesn't look like a frame number.
+pub fn frame_name(filename: &str) -> Option<FrameName<'_>> {
+    let stem_end = filename.rfind('.').filter(|&dot| dot > 0).unwrap_or(filename.len());
+    let stem = &filename[..stem_end];
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |before| before + 1);
+    let digits = &stem[start..end];
+
+    // Unix timestamps and other long IDs
+    if digits.len() >= 10 {
+        return None;
+    }
+    // 20240512, 20240512-143012 and the like
+    if digits.len() == 8 && is_date(digits) {
+        return None;
+    }
+    // The last part of 2024-05-12, 14.30.12 or 1.2.3
+    let mut before = stem[..start].chars().rev();
+    if matches!(before.next(), Some('-' | '.' | ':')) && before.next().is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // The time after a compact date: IMG_20240512_143012
+    if stem[..start]
+        .strip_suffix(['_', '-', ' ', 'T'])
+        .and_then(|head| head.get(head.len().checked_sub(8)?..))
+        .is_some_and(is_date)
+    {
+        return None;
+    }
+
+    Some(FrameName {
+        prefix: &stem[..start],
+        digits,
+        suffix: &filename[end..],
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct Stack {
+    /// Identifies the stack across refilters, e.g. to remember it's expanded.
+    pub key: String,
+    /// "frame_####.png"
+    pub pattern: String,
+    /// Entry indices in frame order; the first is the row that stands for the stack.
+    pub frames: Vec<usize>,
+}
+
+/// A frame of a run: its number, how many digits it was written with, and its entry index.
+type Frame = (u64, usize, usize);
+
+/// Finds the sequences among `files`, given as (entry index, category, filename).
+pub fn detect<'a>(files: impl IntoIterator<Item = (usize, &'a str, &'a str)>) -> Vec<Stack> {
+    let mut runs: HashMap<(&str, &str, &str), Vec<Frame>> = HashMap::new();
+    for (entry, category, filename) in files {
+        let Some(frame) = frame_name(filename) else {
+            continue;
+        };
+        let Ok(number) = frame.digits.parse::<u64>() else {
+            continue;
+        };
+        runs.entry((category, frame.prefix, frame.suffix))
+            .or_default()
+            .push((number, frame.digits.len(), entry));
+    }
+
+    let mut stacks: Vec<Stack> = runs
+        .into_iter()
+        .filter(|(_, frames)| frames.len() >= MIN_FRAMES)
+        .map(|((category, prefix, suffix), mut frames)| {
+            frames.sort();
+            let width = frames.iter().map(|&(_, width, _)| width).min().unwrap_or(1);
+            Stack {
+                key: format!("{}/{}#{}", category, prefix, suffix),
+                pattern: format!("{}{}{}", prefix, "#".repeat(width), suffix),
+                frames: frames.into_iter().map(|(_, _, entry)| entry).collect(),
+            }
+        })
+        .collect();
+    stacks.sort_by(|a, b| a.key.cmp(&b.key));
+    stacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stacks found among `names`, all in one category, as their patterns.
+    fn patterns(names: &[&str]) -> Vec<String> {
+        detect(names.iter().enumerate().map(|(index, name)| (index, "shots", *name)))
+            .into_iter()
+            .map(|stack| stack.pattern)
+            .collect()
+    }
+
+    #[test]
+    fn numbered_frames_stack_in_frame_order() {
+        let names = ["frame_0010.png", "frame_0002.png", "frame_0001.png", "other.png"];
+        let stacks = detect(names.iter().enumerate().map(|(index, name)| (index, "anim", *name)));
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].pattern, "frame_####.png");
+        assert_eq!(stacks[0].key, "anim/frame_#.png");
+        assert_eq!(stacks[0].frames, vec![2, 1, 0]);
+        assert_eq!(
+            frame_name("frame_0010.png"),
+            Some(FrameName {
+                prefix: "frame_",
+                digits: "0010",
+                suffix: ".png"
+            })
+        );
+    }
+
+    #[test]
+    fn dates_are_not_frame_numbers() {
+        for name in ["screenshot 20240512.png", "2024-05-12.png", "notes_2024.05.13.png"] {
+            assert_eq!(frame_name(name), None, "{}", name);
+        }
+        assert!(patterns(&["daily_20240511.png", "daily_20240512.png", "daily_20240513.png"]).is_empty());
+        // Eight digits that aren't a valid date are still a frame number
+        assert!(frame_name("frame_12345678.png").is_some());
+    }
+
+    #[test]
+    fn date_times_are_not_frame_numbers() {
+        for name in ["IMG_20240512_143012.jpg", "IMG_20240512-143013.jpg", "shot 2024-05-12 14.30.12.png"] {
+            assert_eq!(frame_name(name), None, "{}", name);
+        }
+        assert!(patterns(&["IMG_20240512_143012.jpg", "IMG_20240512_143013.jpg", "IMG_20240512_143014.jpg"]).is_empty());
+    }
+
+    #[test]
+    fn dotted_versions_are_not_frame_numbers() {
+        for name in ["setup-1.2.3.png", "icon v2.0.1.png", "build 10.4.png"] {
+            assert_eq!(frame_name(name), None, "{}", name);
+        }
+        assert!(patterns(&["logo-1.0.1.png", "logo-1.0.2.png", "logo-1.0.3.png"]).is_empty());
+    }
+
+    #[test]
+    fn long_timestamps_are_not_frame_numbers() {
+        assert_eq!(frame_name("capture_1715520612.png"), None);
+        assert_eq!(frame_name("capture_1715520612345.png"), None);
+        assert!(patterns(&["tmp_1715520612.png", "tmp_1715520613.png", "tmp_1715520614.png"]).is_empty());
+        // Nine digits still count
+        assert!(frame_name("frame_000000001.png").is_some());
+    }
+
+    #[test]
+    fn fewer_than_three_frames_dont_stack() {
+        assert!(patterns(&["walk_01.png", "walk_02.png"]).is_empty());
+        assert_eq!(patterns(&["walk_01.png", "walk_02.png", "walk_03.png"]), vec!["walk_##.png"]);
+        // Same numbers in different categories are separate runs
+        let split = detect([(0, "a", "run_1.png"), (1, "a", "run_2.png"), (2, "b", "run_3.png")]);
+        assert!(split.is_empty());
+    }
+}
@@ -0,0 +1,955 @@
+//! User settings and their on-disk persistence.
+
+use crate::error::ChlorineError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrimaryAction {
+    Copy,
+    OpenDetails,
+}
+
+impl PrimaryAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            PrimaryAction::Copy => "📋 Copy image",
+            PrimaryAction::OpenDetails => "👁️ Open details",
+        }
+    }
+}
+
+/// Whether spinners and the copy flash animate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Animations {
+    /// Follow the OS reduced-motion preference.
+    Auto,
+    On,
+    Off,
+}
+
+impl Animations {
+    pub const ALL: [Animations; 3] = [Animations::Auto, Animations::On, Animations::Off];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Animations::Auto => "Auto (follow system)",
+            Animations::On => "On",
+            Animations::Off => "Off",
+        }
+    }
+}
+
+/// Best-effort read of the OS "reduce motion" preference; false when it can't be determined.
+pub fn os_prefers_reduced_motion() -> bool {
+    let query = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    if cfg!(target_os = "macos") {
+        query("defaults", &["read", "com.apple.universalaccess", "reduceMotion"]).is_some_and(|v| v == "1")
+    } else if cfg!(target_os = "linux") {
+        query("gsettings", &["get", "org.gnome.desktop.interface", "enable-animations"]).is_some_and(|v| v == "false")
+    } else {
+        false
+    }
+}
+
+/// Why a translucent window can't work here, or None when it can. On X11 it
+/// also needs a compositing window manager, which isn't detected.
+pub fn translucency_unsupported() -> Option<&'static str> {
+    if cfg!(any(target_os = "windows", target_os = "macos", target_os = "linux")) {
+        None
+    } else {
+        Some("Translucent windows aren't supported on this platform.")
+    }
+}
+
+/// What the main Copy action puts on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CopyMode {
+    Pixels,
+    File,
+    Path,
+}
+
+impl CopyMode {
+    pub const ALL: [CopyMode; 3] = [CopyMode::Pixels, CopyMode::File, CopyMode::Path];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyMode::Pixels => "🖼 Image pixels",
+            CopyMode::File => "📄 File reference",
+            CopyMode::Path => "🔤 Path as text",
+        }
+    }
+}
+
+/// What Copy does instead once the system clipboard can't be opened at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipboardFallback {
+    /// Copy the file's path as text through wl-copy, xclip or a similar tool.
+    PathWithTool,
+    /// Save a copy to the Pictures folder and open it.
+    SaveToPictures,
+}
+
+impl ClipboardFallback {
+    pub const ALL: [ClipboardFallback; 2] = [ClipboardFallback::PathWithTool, ClipboardFallback::SaveToPictures];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClipboardFallback::PathWithTool => "Copy the path as text with wl-copy / xclip",
+            ClipboardFallback::SaveToPictures => "Save to Pictures and open the folder",
+        }
+    }
+
+    /// Replaces "📋 Copy" on buttons while the fallback is in use.
+    pub fn button_label(self) -> &'static str {
+        match self {
+            ClipboardFallback::PathWithTool => "🔤 Copy path",
+            ClipboardFallback::SaveToPictures => "💾 Save copy",
+        }
+    }
+}
+
+/// Resize choices offered for pixel copies (longest side in pixels).
+pub const COPY_RESIZE_PRESETS: [Option<u32>; 4] = [None, Some(512), Some(1024), Some(2048)];
+
+pub fn resize_label(max_dimension: Option<u32>) -> String {
+    match max_dimension {
+        Some(max) => format!("max {} px", max),
+        None => "original size".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CopyBehavior {
+    pub mode: CopyMode,
+    /// Only applies to pixel copies.
+    pub max_dimension: Option<u32>,
+}
+
+impl Default for CopyBehavior {
+    fn default() -> Self {
+        Self {
+            mode: CopyMode::Pixels,
+            max_dimension: None,
+        }
+    }
+}
+
+impl CopyBehavior {
+    pub fn describe(&self) -> String {
+        match self.mode {
+            CopyMode::Pixels => format!("image pixels, {}", resize_label(self.max_dimension)),
+            CopyMode::File => "file reference".to_string(),
+            CopyMode::Path => "path as text".to_string(),
+        }
+    }
+}
+
+/// What to do at load time with images listed under more than one category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    KeepAll,
+    /// Keep each image only in the alphabetically first category, without touching the manifest.
+    KeepFirst,
+    Ask,
+}
+
+impl DuplicatePolicy {
+    pub const ALL: [DuplicatePolicy; 3] = [DuplicatePolicy::KeepAll, DuplicatePolicy::KeepFirst, DuplicatePolicy::Ask];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DuplicatePolicy::KeepAll => "Keep all",
+            DuplicatePolicy::KeepFirst => "Keep first",
+            DuplicatePolicy::Ask => "Ask",
+        }
+    }
+}
+
+/// How images on a drive or mount that isn't present show up in results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OfflineImages {
+    Grey,
+    Hide,
+}
+
+impl OfflineImages {
+    pub const ALL: [OfflineImages; 2] = [OfflineImages::Grey, OfflineImages::Hide];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OfflineImages::Grey => "Grey out",
+            OfflineImages::Hide => "Hide",
+        }
+    }
+}
+
+/// How results are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResultView {
+    List,
+    Grid,
+}
+
+impl ResultView {
+    pub const ALL: [ResultView; 2] = [ResultView::List, ResultView::Grid];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResultView::List => "☰ List",
+            ResultView::Grid => "▦ Grid",
+        }
+    }
+}
+
+/// A button shown on each result row and in detail windows; the rest stay
+/// in the context menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RowAction {
+    CopyImage,
+    CopyPath,
+    CopyAsFile,
+    OpenExternally,
+    RevealInFolder,
+    Favorite,
+    Details,
+}
+
+impl RowAction {
+    pub const ALL: [RowAction; 7] = [
+        RowAction::CopyImage,
+        RowAction::CopyPath,
+        RowAction::CopyAsFile,
+        RowAction::OpenExternally,
+        RowAction::RevealInFolder,
+        RowAction::Favorite,
+        RowAction::Details,
+    ];
+    /// Row buttons shown at most.
+    pub const MAX: usize = 3;
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RowAction::CopyImage => "Copy image",
+            RowAction::CopyPath => "Copy path",
+            RowAction::CopyAsFile => "Copy as file",
+            RowAction::OpenExternally => "Open externally",
+            RowAction::RevealInFolder => "Reveal in folder",
+            RowAction::Favorite => "Favorite toggle",
+            RowAction::Details => "Details",
+        }
+    }
+
+    /// The button's text; Copy image and Favorite change with the image's state.
+    pub fn button_label(self) -> &'static str {
+        match self {
+            RowAction::CopyImage => "📋 Copy Image",
+            RowAction::CopyPath => "📍 Copy path",
+            RowAction::CopyAsFile => "📄 Copy as file",
+            RowAction::OpenExternally => "↗ Open",
+            RowAction::RevealInFolder => "📂 Reveal",
+            RowAction::Favorite => "☆",
+            RowAction::Details => "👁️ View Details",
+        }
+    }
+}
+
+/// How thumbnails are written to the disk cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThumbnailEncoding {
+    Png,
+    /// Lossy at `thumbnail_quality`; thumbnails with transparency stay PNG.
+    Jpeg,
+    /// Lossless, usually smaller than PNG.
+    WebP,
+}
+
+impl ThumbnailEncoding {
+    pub const ALL: [ThumbnailEncoding; 3] = [ThumbnailEncoding::Png, ThumbnailEncoding::Jpeg, ThumbnailEncoding::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThumbnailEncoding::Png => "PNG (lossless)",
+            ThumbnailEncoding::Jpeg => "JPEG (lossy)",
+            ThumbnailEncoding::WebP => "WebP (lossless)",
+        }
+    }
+}
+
+/// What transparent images are drawn over in the list and detail windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewBackdrop {
+    /// A checkerboard contrasting with the image, only behind images with transparency.
+    Auto,
+    Checkerboard,
+    Fixed,
+}
+
+impl PreviewBackdrop {
+    pub const ALL: [PreviewBackdrop; 3] = [PreviewBackdrop::Auto, PreviewBackdrop::Checkerboard, PreviewBackdrop::Fixed];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PreviewBackdrop::Auto => "Auto (contrasting)",
+            PreviewBackdrop::Checkerboard => "Always checkerboard",
+            PreviewBackdrop::Fixed => "Fixed color",
+        }
+    }
+}
+
+/// What "Optimize…" does with the file it replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Originals {
+    /// Renamed to `<name>.orig` next to the new file.
+    Backup,
+    Trash,
+}
+
+impl Originals {
+    pub const ALL: [Originals; 2] = [Originals::Backup, Originals::Trash];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Originals::Backup => "Keep as .orig backups",
+            Originals::Trash => "Move to the trash",
+        }
+    }
+}
+
+/// A named library: which manifest to load plus its own sidecar directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryProfile {
+    /// Stable identifier used for the sidecar directory, so renames don't move data.
+    pub id: String,
+    pub name: String,
+    pub manifest_path: String,
+    pub default_category: Option<String>,
+}
+
+impl Default for LibraryProfile {
+    fn default() -> Self {
+        Self {
+            id: "default".to_string(),
+            name: "Default".to_string(),
+            manifest_path: "image_list.json".to_string(),
+            default_category: None,
+        }
+    }
+}
+
+impl LibraryProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: Self::new_id(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn new_id() -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("{:x}", nanos)
+    }
+
+    /// Ids name the profile's data folder, so only `[A-Za-z0-9_-]` is allowed:
+    /// nothing that could be absolute or climb out with `..`.
+    pub fn is_valid_id(id: &str) -> bool {
+        !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }
+
+    /// Directory for this profile's own sidecar files (favorites, history, ...).
+    pub fn data_dir(&self) -> PathBuf {
+        config_dir().join("profiles").join(&self.id)
+    }
+}
+
+/// Which startup health checks run; slow network mounts may want some off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckSettings {
+    pub manifest: bool,
+    pub sample_files: bool,
+    pub cache_dir: bool,
+    pub clipboard: bool,
+}
+
+impl Default for HealthCheckSettings {
+    fn default() -> Self {
+        Self {
+            manifest: true,
+            sample_files: true,
+            cache_dir: true,
+            clipboard: true,
+        }
+    }
+}
+
+/// How "Copy as combined image" lays out its sheet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CombineSettings {
+    pub padding: u32,
+    /// RGBA; fully transparent keeps the gaps see-through where the target app supports it.
+    pub background: [u8; 4],
+    /// Longest side of each image on the sheet.
+    pub max_dimension: u32,
+}
+
+impl Default for CombineSettings {
+    fn default() -> Self {
+        Self {
+            padding: 8,
+            background: [255, 255, 255, 255],
+            max_dimension: 512,
+        }
+    }
+}
+
+/// Local HTTP control endpoint; always bound to 127.0.0.1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteControlSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Bearer token every request must carry; generated when the endpoint is first enabled.
+    pub token: String,
+}
+
+impl Default for RemoteControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47615,
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub dark_mode: bool,
+    pub animations: Animations,
+    pub hotkey: String,
+    pub primary_action: PrimaryAction,
+    pub profiles: Vec<LibraryProfile>,
+    pub active_profile: String,
+    pub integrity_hashing: bool,
+    pub show_favorites_bar: bool,
+    /// Number of pinned clipboard slots in the top bar.
+    pub pinned_slots: usize,
+    pub health_checks: HealthCheckSettings,
+    /// Copies never carry EXIF/GPS: pixel copies are re-encoded, file copies go through a sanitized temp file.
+    pub strip_metadata: bool,
+    pub default_copy: CopyBehavior,
+    /// Per-category overrides of `default_copy`, keyed by category name.
+    pub category_copy: BTreeMap<String, CopyBehavior>,
+    /// Pixel copies larger than this many megapixels ask first; 0 turns the check off.
+    pub large_copy_warning_mp: f32,
+    pub clipboard_fallback: ClipboardFallback,
+    /// Per-extension "open externally" commands, keyed by lowercase extension without the dot.
+    pub open_with: BTreeMap<String, String>,
+    /// Comma-separated extensions treated as text snippets, e.g. "txt, md".
+    pub text_extensions: String,
+    pub remote_control: RemoteControlSettings,
+    /// Smaller texture cache for very large libraries, at the cost of more reloading while scrolling.
+    pub low_memory: bool,
+    /// Never load or draw thumbnails; every slot shows its extension tile.
+    /// For remote desktop sessions and screen readers.
+    pub text_only: bool,
+    /// Probe every image's size after the library loads, see "Index dimensions".
+    pub index_dimensions: bool,
+    /// Hovering a category in the dropdown shows its first few thumbnails.
+    pub category_peek: bool,
+    /// Load cloud-sync placeholders like normal files, downloading them on demand.
+    pub auto_download_cloud_files: bool,
+    pub duplicate_policy: DuplicatePolicy,
+    pub offline_images: OfflineImages,
+    pub combine: CombineSettings,
+    /// Side pane showing the highlighted result at a larger size.
+    pub show_preview_pane: bool,
+    pub preview_pane_width: f32,
+    /// Keeps images marked sensitive concealed everywhere, even ones revealed this session.
+    pub privacy_mode: bool,
+    /// Log which categories are copied from, for the usage insights window.
+    pub record_usage: bool,
+    /// Detail windows open at once; opening another closes the oldest.
+    pub max_detail_windows: usize,
+    /// Collapse runs of numbered frames (frame_0001 … frame_0240) into one row.
+    pub stack_sequences: bool,
+    /// The first-run shortcut tips under the search box were dismissed.
+    pub search_tips_seen: bool,
+    pub optimize_originals: Originals,
+    /// Opacity of panel and window backgrounds, 0.7 to 1.0. Images stay opaque.
+    pub window_opacity: f32,
+    pub preview_backdrop: PreviewBackdrop,
+    pub result_view: ResultView,
+    /// Thumbnail size in grid view, in points.
+    pub grid_cell_size: f32,
+    /// RGB used when `preview_backdrop` is Fixed.
+    pub backdrop_color: [u8; 3],
+    pub thumbnail_encoding: ThumbnailEncoding,
+    /// JPEG quality for cached thumbnails, 40 to 95.
+    pub thumbnail_quality: u8,
+    /// The disk thumbnail cache is pruned back under this, least recently used first.
+    pub thumbnail_cache_mb: u32,
+    /// Folder of the disk thumbnail cache; empty for the default one in the cache directory.
+    pub thumbnail_cache_dir: String,
+    /// Look for a newer release on GitHub at startup, at most once a day.
+    pub check_for_updates: bool,
+    /// When the last update check ran, in seconds since the Unix epoch; 0 for never.
+    pub last_update_check: u64,
+    /// Buttons on each result row, one to [`RowAction::MAX`], rightmost first.
+    pub row_actions: Vec<RowAction>,
+    /// Picks up image files added, removed or changed in the category folders.
+    pub watch_folders: bool,
+    /// Filename patterns naming where an image came from, first match wins.
+    pub source_rules: Vec<crate::source_hints::SourceRule>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            animations: Animations::Auto,
+            hotkey: "Ctrl+Shift+C".to_string(),
+            primary_action: PrimaryAction::Copy,
+            profiles: vec![LibraryProfile::default()],
+            active_profile: "default".to_string(),
+            integrity_hashing: false,
+            show_favorites_bar: true,
+            pinned_slots: 3,
+            health_checks: HealthCheckSettings::default(),
+            strip_metadata: true,
+            default_copy: CopyBehavior::default(),
+            category_copy: BTreeMap::new(),
+            large_copy_warning_mp: 40.0,
+            clipboard_fallback: ClipboardFallback::PathWithTool,
+            open_with: BTreeMap::new(),
+            text_extensions: "txt, md".to_string(),
+            remote_control: RemoteControlSettings::default(),
+            low_memory: false,
+            text_only: false,
+            index_dimensions: false,
+            category_peek: true,
+            auto_download_cloud_files: false,
+            duplicate_policy: DuplicatePolicy::KeepAll,
+            offline_images: OfflineImages::Grey,
+            combine: CombineSettings::default(),
+            show_preview_pane: false,
+            preview_pane_width: 320.0,
+            privacy_mode: false,
+            record_usage: true,
+            max_detail_windows: 4,
+            stack_sequences: true,
+            search_tips_seen: false,
+            optimize_originals: Originals::Backup,
+            window_opacity: 1.0,
+            preview_backdrop: PreviewBackdrop::Auto,
+            result_view: ResultView::List,
+            grid_cell_size: 128.0,
+            backdrop_color: [128, 128, 128],
+            thumbnail_encoding: ThumbnailEncoding::Png,
+            thumbnail_quality: 85,
+            thumbnail_cache_mb: 512,
+            thumbnail_cache_dir: String::new(),
+            check_for_updates: false,
+            last_update_check: 0,
+            row_actions: vec![RowAction::CopyImage, RowAction::Details, RowAction::Favorite],
+            watch_folders: false,
+            source_rules: crate::source_hints::default_rules(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Guarantees at least one profile exists, every id is safe to use as a
+    /// folder name, and the active id points at a profile.
+    pub fn normalize(&mut self) {
+        if self.profiles.is_empty() {
+            self.profiles.push(LibraryProfile::default());
+        }
+        for (index, profile) in self.profiles.iter_mut().enumerate() {
+            if !LibraryProfile::is_valid_id(&profile.id) {
+                // The index keeps ids replaced within the same nanosecond apart
+                let id = format!("{}-{}", LibraryProfile::new_id(), index);
+                if self.active_profile == profile.id {
+                    self.active_profile = id.clone();
+                }
+                profile.id = id;
+            }
+        }
+        if !self.profiles.iter().any(|p| p.id == self.active_profile) {
+            self.active_profile = self.profiles[0].id.clone();
+        }
+        self.window_opacity = if self.window_opacity.is_finite() {
+            self.window_opacity.clamp(0.7, 1.0)
+        } else {
+            1.0
+        };
+        self.thumbnail_quality = self.thumbnail_quality.clamp(40, 95);
+        let mut seen = Vec::new();
+        self.row_actions.retain(|action| {
+            let first = !seen.contains(action);
+            seen.push(*action);
+            first
+        });
+        self.row_actions.truncate(RowAction::MAX);
+        if self.row_actions.is_empty() {
+            self.row_actions = AppSettings::default().row_actions;
+        }
+    }
+
+    /// Copy behavior for a category: its override, or the global default.
+    pub fn copy_behavior(&self, category: &str) -> CopyBehavior {
+        self.category_copy
+            .get(category)
+            .copied()
+            .unwrap_or(self.default_copy)
+    }
+
+    /// The configured application for an extension, or None for the OS default.
+    pub fn open_command(&self, extension: &str) -> Option<&str> {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        self.open_with.get(&extension).map(String::as_str)
+    }
+
+    pub fn is_text_extension(&self, extension: &str) -> bool {
+        crate::snippet::matches_extension(&self.text_extensions, extension)
+    }
+
+    pub fn active_profile(&self) -> LibraryProfile {
+        self.profiles
+            .iter()
+            .find(|p| p.id == self.active_profile)
+            .or_else(|| self.profiles.first())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// What it takes for a settings change to take effect, beyond the new values
+/// being read. Worked out by [`changes`]; each flag is one step in applying it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Another profile, or the active one's manifest moved. Reloading covers
+    /// everything, so no other flag is set.
+    pub reload_profile: bool,
+    pub resize_pins: bool,
+    /// Low-memory mode was turned on.
+    pub trim_textures: bool,
+    pub move_thumbnail_cache: bool,
+    pub prune_thumbnail_cache: bool,
+    pub stop_hashing: bool,
+    /// Text-only mode was turned on; nothing is drawn from textures until it's off.
+    pub drop_textures: bool,
+    pub reclassify_sources: bool,
+    /// Entries may switch between image and text previews.
+    pub reset_previews: bool,
+    pub reset_cloud_files: bool,
+    /// At most one of the last three is set, the first that applies.
+    pub reload_library: bool,
+    pub refilter: bool,
+    pub restart_health_check: bool,
+}
+
+pub fn changes(before: &AppSettings, after: &AppSettings) -> Changes {
+    let (old_profile, new_profile) = (before.active_profile(), after.active_profile());
+    if old_profile.id != new_profile.id || old_profile.manifest_path != new_profile.manifest_path {
+        return Changes {
+            reload_profile: true,
+            ..Changes::default()
+        };
+    }
+    let reload_library = after.duplicate_policy != before.duplicate_policy;
+    let refilter = !reload_library
+        && (after.offline_images != before.offline_images || after.stack_sequences != before.stack_sequences);
+    let move_thumbnail_cache = after.thumbnail_cache_dir != before.thumbnail_cache_dir;
+    Changes {
+        reload_profile: false,
+        resize_pins: after.pinned_slots != before.pinned_slots,
+        trim_textures: after.low_memory && !before.low_memory,
+        move_thumbnail_cache,
+        prune_thumbnail_cache: !move_thumbnail_cache && after.thumbnail_cache_mb < before.thumbnail_cache_mb,
+        stop_hashing: !after.integrity_hashing && before.integrity_hashing,
+        drop_textures: after.text_only && !before.text_only,
+        reclassify_sources: after.source_rules != before.source_rules,
+        reset_previews: after.text_extensions != before.text_extensions,
+        reset_cloud_files: after.auto_download_cloud_files && !before.auto_download_cloud_files,
+        reload_library,
+        refilter,
+        restart_health_check: !reload_library && !refilter && after.health_checks != before.health_checks,
+    }
+}
+
+/// Directory holding settings and other per-user files; `data/` beside the
+/// executable in portable mode.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = crate::portable::data_dir() {
+        return dir;
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("chlorine")
+}
+
+/// Directory for regenerable data such as thumbnails.
+pub fn cache_dir() -> PathBuf {
+    if let Some(dir) = crate::portable::data_dir() {
+        return dir.join("cache");
+    }
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("chlorine")
+}
+
+pub fn settings_path() -> PathBuf {
+    config_dir().join("settings.json")
+}
+
+/// Loads the settings file, falling back to defaults when it is missing or unreadable.
+pub fn load() -> AppSettings {
+    let mut settings: AppSettings = std::fs::read_to_string(settings_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    settings.normalize();
+    settings
+}
+
+pub fn save(settings: &AppSettings) -> Result<(), ChlorineError> {
+    let path = settings_path();
+    let error = |path: &Path, e: &dyn std::fmt::Display| ChlorineError::Settings {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| error(parent, &e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| error(&path, &e))?;
+    std::fs::write(&path, json).map_err(|e| error(&path, &e))
+}
+
+/// Everything a user might want to carry to another machine, one section per feature.
+///
+/// Sections are optional so partial files import cleanly, and unknown sections
+/// from newer versions are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub settings: Option<AppSettings>,
+    /// Favorites of the profile that was active when exporting.
+    pub favorites: Option<Vec<String>>,
+}
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+pub fn export_bundle(path: &Path, bundle: &SettingsBundle) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(bundle).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Reads a bundle, validating each section independently.
+///
+/// Returns the sections that parsed plus a list of "section: error" messages for
+/// the ones that did not, so one bad section doesn't block the rest.
+pub fn import_bundle(path: &Path) -> Result<(SettingsBundle, Vec<String>), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{}: expected a JSON object", path.display()))?;
+
+    let mut bundle = SettingsBundle {
+        version: object
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32,
+        ..Default::default()
+    };
+    let mut errors = Vec::new();
+
+    if let Some(section) = object.get("settings") {
+        match serde_json::from_value::<AppSettings>(section.clone()) {
+            Ok(mut settings) => {
+                settings.normalize();
+                bundle.settings = Some(settings);
+            }
+            Err(e) => errors.push(format!("settings: {}", e)),
+        }
+    }
+
+    if let Some(section) = object.get("favorites") {
+        match serde_json::from_value::<Vec<String>>(section.clone()) {
+            Ok(favorites) => bundle.favorites = Some(favorites),
+            Err(e) => errors.push(format!("favorites: {}", e)),
+        }
+    }
+
+    Ok((bundle, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// `changes` from the defaults to the defaults with `edit` applied.
+    fn changes_after(edit: impl FnOnce(&mut AppSettings)) -> Changes {
+        let before = AppSettings::default();
+        let mut after = before.clone();
+        edit(&mut after);
+        changes(&before, &after)
+    }
+
+    #[test]
+    fn unchanged_settings_need_nothing() {
+        assert_eq!(changes_after(|_| {}), Changes::default());
+        // Read every frame, so applying them needs no step
+        assert_eq!(
+            changes_after(|s| {
+                s.dark_mode = !s.dark_mode;
+                s.animations = Animations::Off;
+            }),
+            Changes::default()
+        );
+    }
+
+    #[test]
+    fn manifest_or_profile_switch_reloads_and_nothing_else() {
+        let moved = changes_after(|s| {
+            s.profiles[0].manifest_path = "other.json".to_string();
+            s.pinned_slots += 1;
+            s.low_memory = true;
+        });
+        assert_eq!(
+            moved,
+            Changes {
+                reload_profile: true,
+                ..Changes::default()
+            }
+        );
+
+        let switched = changes_after(|s| {
+            let second = LibraryProfile::new("Second");
+            s.active_profile = second.id.clone();
+            s.profiles.push(second);
+        });
+        assert!(switched.reload_profile);
+        // Renaming the active profile doesn't reload it
+        assert!(!changes_after(|s| s.profiles[0].name = "Renamed".to_string()).reload_profile);
+    }
+
+    #[test]
+    fn one_off_toggles_apply_only_when_turned_on() {
+        assert!(changes_after(|s| s.low_memory = true).trim_textures);
+        assert!(changes_after(|s| s.text_only = true).drop_textures);
+        assert!(changes_after(|s| s.auto_download_cloud_files = true).reset_cloud_files);
+
+        let mut on = AppSettings {
+            low_memory: true,
+            text_only: true,
+            auto_download_cloud_files: true,
+            integrity_hashing: true,
+            ..AppSettings::default()
+        };
+        let off = AppSettings {
+            integrity_hashing: false,
+            ..on.clone()
+        };
+        assert!(changes(&on, &off).stop_hashing);
+        on.low_memory = false;
+        on.text_only = false;
+        on.auto_download_cloud_files = false;
+        assert_eq!(
+            changes(&off, &on),
+            Changes::default(),
+            "turning these off, or hashing on, needs no step"
+        );
+    }
+
+    #[test]
+    fn thumbnail_cache_moves_or_prunes() {
+        let moved = changes_after(|s| {
+            s.thumbnail_cache_dir = "/elsewhere".to_string();
+            s.thumbnail_cache_mb /= 2;
+        });
+        assert!(moved.move_thumbnail_cache);
+        assert!(!moved.prune_thumbnail_cache, "moving already prunes");
+        assert!(changes_after(|s| s.thumbnail_cache_mb /= 2).prune_thumbnail_cache);
+        assert!(!changes_after(|s| s.thumbnail_cache_mb *= 2).prune_thumbnail_cache);
+    }
+
+    #[test]
+    fn library_steps_take_the_first_that_applies() {
+        let all = changes_after(|s| {
+            s.duplicate_policy = DuplicatePolicy::ALL
+                .into_iter()
+                .find(|policy| *policy != s.duplicate_policy)
+                .unwrap();
+            s.stack_sequences = !s.stack_sequences;
+            s.health_checks.clipboard = !s.health_checks.clipboard;
+        });
+        assert!(all.reload_library && !all.refilter && !all.restart_health_check);
+
+        let refilter = changes_after(|s| {
+            s.stack_sequences = !s.stack_sequences;
+            s.health_checks.clipboard = !s.health_checks.clipboard;
+        });
+        assert!(!refilter.reload_library && refilter.refilter && !refilter.restart_health_check);
+
+        let health = changes_after(|s| s.health_checks.clipboard = !s.health_checks.clipboard);
+        assert!(health.restart_health_check);
+    }
+
+    #[test]
+    fn content_settings_are_detected() {
+        assert!(changes_after(|s| s.pinned_slots += 1).resize_pins);
+        assert!(changes_after(|s| s.text_extensions.push_str(",log")).reset_previews);
+        assert!(changes_after(|s| s.source_rules.clear()).reclassify_sources);
+    }
+
+    #[test]
+    fn imported_profile_ids_cannot_leave_the_profiles_folder() {
+        let dir = TestDir::new("settings-import-ids");
+        let path = dir.join("bundle.json");
+        let settings = serde_json::json!({
+            "active_profile": "../..",
+            "profiles": [
+                { "id": "../..", "name": "Escape", "manifest_path": "a.json" },
+                { "id": "/home/me", "name": "Absolute", "manifest_path": "b.json" },
+                { "id": "work_2-b", "name": "Fine", "manifest_path": "c.json" }
+            ]
+        });
+        std::fs::write(&path, serde_json::json!({ "version": BUNDLE_VERSION, "settings": settings }).to_string()).unwrap();
+
+        let (bundle, errors) = import_bundle(&path).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let settings = bundle.settings.unwrap();
+        let ids: Vec<&str> = settings.profiles.iter().map(|p| p.id.as_str()).collect();
+        assert!(ids.iter().all(|id| LibraryProfile::is_valid_id(id)), "{:?}", ids);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[2], "work_2-b");
+        assert_eq!(settings.active_profile, ids[0], "the active profile follows its new id");
+        for profile in &settings.profiles {
+            assert_eq!(profile.data_dir().parent(), Some(config_dir().join("profiles").as_path()));
+        }
+    }
+}
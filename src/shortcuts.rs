@@ -0,0 +1,80 @@
+//! Every keyboard and mouse shortcut, described in one place.
+//!
+//! The cheat sheet, tooltips and the first-run hint all read their key
+//! labels from here, so a key that changes is only described once. The
+//! bindings themselves are still matched in `handle_keyboard`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Palette,
+    FocusSearch,
+    /// Enter in the search box; what it does follows the primary action setting.
+    ActivateTop,
+    CategoryScope,
+    Close,
+    StepDetail,
+    StepHighlight,
+    PageHighlight,
+    OpenHighlighted,
+    CopyRow,
+    PrivacyMode,
+    Select,
+    CopyFavorite,
+    CopyPinned,
+    Undo,
+    Redo,
+    CheatSheet,
+    DebugOverlay,
+}
+
+pub struct Shortcut {
+    pub binding: Binding,
+    pub keys: &'static str,
+    pub description: &'static str,
+}
+
+/// In cheat-sheet order.
+pub const ALL: &[Shortcut] = &[
+    Shortcut { binding: Binding::Palette, keys: "Ctrl+P  or  Ctrl+K", description: "Open the command palette" },
+    Shortcut { binding: Binding::FocusSearch, keys: "Ctrl+F  or  /", description: "Focus the search box" },
+    Shortcut { binding: Binding::ActivateTop, keys: "Enter", description: "Copy or open the top result" },
+    Shortcut {
+        binding: Binding::CategoryScope,
+        keys: "name/ (in search)",
+        description: "Search within the category starting with name",
+    },
+    Shortcut {
+        binding: Binding::Close,
+        keys: "Esc",
+        description: "Close settings, then details, then clear selection and search",
+    },
+    Shortcut { binding: Binding::StepDetail, keys: "← / →", description: "Previous / next result in the active detail window" },
+    Shortcut { binding: Binding::StepHighlight, keys: "↑ / ↓", description: "Move the highlight shown in the preview pane" },
+    Shortcut { binding: Binding::PageHighlight, keys: "Page Up / Page Down", description: "Move the highlight by a screenful" },
+    Shortcut { binding: Binding::OpenHighlighted, keys: "Space", description: "Open details for the highlighted result" },
+    Shortcut { binding: Binding::CopyRow, keys: "Double-click", description: "Copy the result whose name was double-clicked" },
+    Shortcut { binding: Binding::PrivacyMode, keys: "F9", description: "Toggle privacy mode for sensitive images" },
+    Shortcut { binding: Binding::Select, keys: "Ctrl+click name", description: "Add to or remove from the selection" },
+    Shortcut { binding: Binding::CopyFavorite, keys: "Ctrl+1 … Ctrl+9", description: "Copy favorite 1–9" },
+    Shortcut { binding: Binding::CopyPinned, keys: "Ctrl+Shift+1 … 9", description: "Copy pinned slot 1–9" },
+    Shortcut { binding: Binding::Undo, keys: "Ctrl+Z", description: "Undo the last favorite, hide or grouping change" },
+    Shortcut { binding: Binding::Redo, keys: "Ctrl+Shift+Z", description: "Redo" },
+    Shortcut { binding: Binding::CheatSheet, keys: "F1", description: "Toggle this cheat sheet" },
+    Shortcut { binding: Binding::DebugOverlay, keys: "F12", description: "Toggle the debug overlay" },
+];
+
+pub fn keys(binding: Binding) -> &'static str {
+    ALL.iter()
+        .find(|shortcut| shortcut.binding == binding)
+        .map_or("", |shortcut| shortcut.keys)
+}
+
+/// "Copy — Enter / Double-click": an action followed by every binding that does it.
+pub fn hint(action: &str, bindings: &[Binding]) -> String {
+    let keys: Vec<&str> = bindings.iter().map(|&binding| keys(binding)).filter(|keys| !keys.is_empty()).collect();
+    if keys.is_empty() {
+        action.to_string()
+    } else {
+        format!("{} — {}", action, keys.join(" / "))
+    }
+}
@@ -0,0 +1,37 @@
+//! Text and code-snippet entries: files whose content is copied as text.
+
+use std::path::Path;
+
+/// Larger files are refused rather than pasted wholesale.
+pub const MAX_TEXT_BYTES: u64 = 1024 * 1024;
+
+/// Reads a file as UTF-8 text, rejecting binaries that only look like text by extension.
+pub fn read_text(path: &str) -> Result<String, String> {
+    let size = std::fs::metadata(path).map_err(|e| format!("{}: {}", path, e))?.len();
+    if size > MAX_TEXT_BYTES {
+        return Err(format!("{} is too large to copy as text ({})", path, crate::format_bytes(size)));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    if bytes.contains(&0) {
+        return Err(format!("{} looks like a binary file, not text", path));
+    }
+    String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8 text", path))
+}
+
+/// Whether `extension` (with or without the dot) is in a comma-separated list like "txt, md".
+pub fn matches_extension(list: &str, extension: &str) -> bool {
+    let extension = extension.trim_start_matches('.');
+    !extension.is_empty()
+        && list
+            .split(',')
+            .map(|e| e.trim().trim_start_matches('.'))
+            .any(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// Tile showing the first lines of the file, or None if it isn't readable text.
+pub fn preview(path: &str) -> Option<eframe::egui::ColorImage> {
+    if !Path::new(path).exists() {
+        return None;
+    }
+    read_text(path).ok().map(|text| crate::placeholder::render_text_tile(&text))
+}
@@ -0,0 +1,84 @@
+//! Best-guess origin of an image from its filename alone: "Screenshot_2024…"
+//! is a screenshot, "IMG_1234.HEIC" a phone photo, "image (3).png" an export.
+//!
+//! Rules are regexes from the settings, tried in order against the filename,
+//! case-insensitively; the first match names the source. Nothing is read
+//! from disk, so the whole library is classified while it's indexed and
+//! again whenever the rules change.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// The source of filenames no rule matches.
+pub const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRule {
+    pub pattern: String,
+    /// What a match is labelled, e.g. "screenshot".
+    pub label: String,
+}
+
+impl SourceRule {
+    fn new(pattern: &str, label: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            label: label.to_string(),
+        }
+    }
+}
+
+pub fn default_rules() -> Vec<SourceRule> {
+    vec![
+        SourceRule::new(
+            r"^(screenshot|screen shot|scr_|cleanshot|snip|greenshot|flameshot|shottr|capture)",
+            "screenshot",
+        ),
+        SourceRule::new(r"^(img|pxl|dsc|dscn|mvimg|photo)[_-]?\d{3,}", "phone photo"),
+        SourceRule::new(r"^\d{8}_\d{6}", "phone photo"),
+        SourceRule::new(r"^(image|download|untitled|export)( ?\(\d+\))?\.\w+$", "export"),
+        SourceRule::new(r"[-_ ]export(ed)?\b", "export"),
+    ]
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Why a rule's pattern can't be used, if it can't.
+pub fn validate(pattern: &str) -> Option<String> {
+    compile(pattern).err()
+}
+
+/// How a label is written in a `source:` operator: lowercase, spaces as dashes.
+pub fn key(label: &str) -> String {
+    label.trim().to_lowercase().replace([' ', '_'], "-")
+}
+
+/// The compiled rules; invalid and unlabelled ones are left out.
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Classifier {
+    pub fn new(rules: &[SourceRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|rule| !rule.label.trim().is_empty())
+            .filter_map(|rule| Some((compile(&rule.pattern).ok()?, rule.label.trim().to_string())))
+            .collect();
+        Self { rules }
+    }
+
+    /// The label of the first rule matching `filename`, or [`UNKNOWN`].
+    pub fn classify(&self, filename: &str) -> &str {
+        self.rules
+            .iter()
+            .find(|(regex, _)| regex.is_match(filename))
+            .map_or(UNKNOWN, |(_, label)| label.as_str())
+    }
+}
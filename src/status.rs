@@ -0,0 +1,128 @@
+//! The status bar message. Messages can carry a follow-up action, so the
+//! message is clickable when there is more to see behind it.
+
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusAction {
+    /// Highlight the image's result row and scroll to it.
+    RevealImage(String),
+    /// Show the manifest around the line a parse error points at.
+    ShowManifestError { path: String, line: usize, column: usize, message: String },
+    /// Open the duplicate entries prompt.
+    ShowDuplicates,
+    /// Write the user data again after a failed save.
+    RetryUserDataSave,
+    /// Pick another folder for the thumbnail cache after writing to it failed.
+    ChooseThumbnailCacheDir,
+    /// Open a newer release's page in the browser.
+    OpenReleasePage(String),
+}
+
+impl StatusAction {
+    pub fn hint(&self) -> &'static str {
+        match self {
+            StatusAction::RevealImage(_) => "Click to show it in the results",
+            StatusAction::ShowManifestError { .. } => "Click to see the lines around the error",
+            StatusAction::ShowDuplicates => "Click to review the duplicate entries",
+            StatusAction::RetryUserDataSave => "Click to try saving again",
+            StatusAction::ChooseThumbnailCacheDir => "Click to choose another folder for the thumbnail cache",
+            StatusAction::OpenReleasePage(_) => "Click to open the release page",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusMessage {
+    pub text: String,
+    pub action: Option<StatusAction>,
+}
+
+impl StatusMessage {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            action: None,
+        }
+    }
+
+    pub fn with_action(mut self, action: StatusAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+}
+
+/// Characters of a line shown before it is cut. The error's own line is
+/// cut to a window around the error instead, so a parse error in minified
+/// JSON, all on one line, still shows its surroundings.
+const MAX_LINE_CHARS: usize = 300;
+
+pub struct Excerpt {
+    /// Numbered lines, cut to [`MAX_LINE_CHARS`] with "…" where they go on.
+    pub lines: Vec<(usize, String)>,
+    /// Where the error is in its line as shown, in characters.
+    pub caret: usize,
+}
+
+fn cut(text: &str) -> String {
+    if text.chars().count() <= MAX_LINE_CHARS {
+        return text.to_string();
+    }
+    let mut shown: String = text.chars().take(MAX_LINE_CHARS).collect();
+    shown.push('…');
+    shown
+}
+
+/// The part of `text` around byte offset `at`, and the error's position in it.
+fn window(text: &str, at: usize) -> (String, usize) {
+    let at = text.char_indices().take_while(|(index, _)| *index < at).count();
+    let length = text.chars().count();
+    if length <= MAX_LINE_CHARS {
+        return (text.to_string(), at);
+    }
+    let start = at.saturating_sub(MAX_LINE_CHARS / 2).min(length - MAX_LINE_CHARS);
+    let mut shown = String::new();
+    if start > 0 {
+        shown.push('…');
+    }
+    let caret = at - start + shown.chars().count();
+    shown.extend(text.chars().skip(start).take(MAX_LINE_CHARS));
+    if start + MAX_LINE_CHARS < length {
+        shown.push('…');
+    }
+    (shown, caret)
+}
+
+/// Up to `radius` lines either side of `line` (1-based), numbered, for
+/// showing where a parse error is. Reads only as far as the last line
+/// shown, so an error early in a huge manifest is quick.
+pub fn file_context(path: &str, line: usize, column: usize, radius: usize) -> Result<Excerpt, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut reader = std::io::BufReader::new(file);
+    let first = line.saturating_sub(radius).max(1);
+    let mut excerpt = Excerpt {
+        lines: Vec::new(),
+        caret: 0,
+    };
+    let mut buffer = Vec::new();
+    for number in 1..=line + radius {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).map_err(|e| format!("{}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        if number < first {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&buffer);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if number == line {
+            let (shown, caret) = window(text, column.saturating_sub(1));
+            excerpt.lines.push((number, shown));
+            excerpt.caret = caret;
+        } else {
+            excerpt.lines.push((number, cut(text)));
+        }
+    }
+    Ok(excerpt)
+}
@@ -0,0 +1,464 @@
+//! One file per profile, userdata.json, for everything the user curates:
+//! favorites, hidden and sensitive images, pinned slots, the snapshot behind
+//! the "+N new" badges and the usage log.
+//!
+//! Each feature owns a named section and keeps its own types; this module
+//! only stores their JSON. Changing a section marks the store dirty; the
+//! write happens on a background thread once nothing has changed for
+//! [`SAVE_DELAY`], so a burst of edits (typing, toggling favorites, a run of
+//! copies) becomes a single write. Leaving the window, switching profiles and
+//! exiting write right away. Saves are atomic like the manifest's: a temp
+//! file next to it, then a rename.
+//!
+//! The file carries a schema version, and [`MIGRATIONS`] brings older
+//! layouts up to date when a profile is opened. Version 1 is the old layout
+//! of one file per feature; those files are renamed to `*.migrated` once
+//! their content is safely in userdata.json. Anything that doesn't parse is
+//! renamed aside as `*.corrupt-<time>.json` and reported, never overwritten
+//! or silently dropped.
+
+use poll_promise::Promise;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const VERSION: u32 = 2;
+const FILE_NAME: &str = "userdata.json";
+/// Quiet time after the last change before it is written.
+pub const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Sections and the file each lived in before version 2.
+const LEGACY_FILES: &[(&str, &str)] = &[
+    ("favorites", "favorites.json"),
+    ("hidden", "hidden.json"),
+    ("pins", "pins.json"),
+    ("sensitive", "sensitive.json"),
+    ("seen", "seen.json"),
+    ("usage", "usage.json"),
+];
+
+type Sections = BTreeMap<String, serde_json::Value>;
+
+/// A migration gets the profile directory, the sections so far, warnings
+/// to show, and a list of files to retire after the next successful save.
+type Migration = fn(&Path, &mut Sections, &mut Vec<String>, &mut Vec<PathBuf>);
+
+/// Upgrades from version `index + 1` to `index + 2`.
+const MIGRATIONS: &[Migration] = &[migrate_per_file_layout];
+
+#[derive(Default, Serialize, Deserialize)]
+struct Layout {
+    version: u32,
+    #[serde(default)]
+    sections: Sections,
+}
+
+/// What the status bar says about the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveState {
+    Saved,
+    /// Changed, waiting for the quiet time or writing.
+    Saving,
+    /// The last write failed; the changes are still in memory.
+    Failed(String),
+}
+
+#[derive(Default)]
+pub struct UserData {
+    /// Empty until a profile is opened; saving then does nothing.
+    file: PathBuf,
+    sections: Sections,
+    /// Changed since the last write started.
+    dirty: bool,
+    /// When the first change not yet being written was made; the write is
+    /// due [`SAVE_DELAY`] after the latest one.
+    changed_at: Option<Instant>,
+    writing: Option<Promise<Result<(), String>>>,
+    failed: Option<String>,
+    /// Files already copied into `sections`, renamed aside after the next save.
+    retired: Vec<PathBuf>,
+    /// Problems found while loading, for the status bar.
+    warnings: Vec<String>,
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Renames an unreadable file to "name.corrupt-<time>.json" next to it. Returns the new name.
+fn quarantine(path: &Path) -> Result<PathBuf, String> {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let aside = path.with_file_name(format!("{}.corrupt-{}.json", stem, timestamp()));
+    std::fs::rename(path, &aside).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(aside)
+}
+
+fn quarantine_warning(path: &Path, reason: &str) -> String {
+    match quarantine(path) {
+        Ok(aside) => format!("{} was unreadable ({}) and was moved aside as {}", path.display(), reason, aside.display()),
+        Err(e) => format!("{} is unreadable ({}) and couldn't be moved aside: {}", path.display(), reason, e),
+    }
+}
+
+/// Version 1 → 2: one file per feature becomes one section each.
+fn migrate_per_file_layout(dir: &Path, sections: &mut Sections, warnings: &mut Vec<String>, retired: &mut Vec<PathBuf>) {
+    for (section, file_name) in LEGACY_FILES {
+        let path = dir.join(file_name);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        match serde_json::from_str(&content) {
+            Ok(value) => {
+                sections.insert(section.to_string(), value);
+                retired.push(path);
+            }
+            Err(e) => warnings.push(quarantine_warning(&path, &e.to_string())),
+        }
+    }
+}
+
+impl UserData {
+    /// Loads the profile's store, migrating and quarantining as needed.
+    pub fn open(dir: &Path) -> Self {
+        let file = dir.join(FILE_NAME);
+        let mut warnings = Vec::new();
+        let layout = match std::fs::read_to_string(&file) {
+            Ok(content) => match serde_json::from_str::<Layout>(&content) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    warnings.push(quarantine_warning(&file, &e.to_string()));
+                    // Older per-file data was retired when this file was first written
+                    Layout {
+                        version: VERSION,
+                        sections: Sections::new(),
+                    }
+                }
+            },
+            // No store yet: the per-feature files of version 1, if there are any
+            Err(_) => Layout {
+                version: 1,
+                sections: Sections::new(),
+            },
+        };
+
+        let mut sections = layout.sections;
+        let mut retired = Vec::new();
+        if layout.version > VERSION {
+            warnings.push(format!(
+                "{} was written by a newer version of Chlorine; sections it doesn't know are kept as they are",
+                file.display()
+            ));
+        }
+        let first = (layout.version.max(1) - 1) as usize;
+        for migration in MIGRATIONS.iter().skip(first) {
+            migration(dir, &mut sections, &mut warnings, &mut retired);
+        }
+        // Migrated content is written in the new layout on the first save
+        let dirty = first < MIGRATIONS.len() && !sections.is_empty();
+
+        Self {
+            file,
+            sections,
+            dirty,
+            retired,
+            warnings,
+            ..Default::default()
+        }
+    }
+
+    /// A section's content, or the default when it's missing. A section
+    /// that no longer fits its type is written aside and reported.
+    pub fn get<T: DeserializeOwned + Default>(&mut self, section: &str) -> T {
+        let Some(value) = self.sections.get(section) else {
+            return T::default();
+        };
+        match serde_json::from_value(value.clone()) {
+            Ok(content) => content,
+            Err(e) => {
+                let aside = self
+                    .file
+                    .with_file_name(format!("{}.corrupt-{}.json", section, timestamp()));
+                let kept = serde_json::to_string_pretty(value)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| std::fs::write(&aside, json).map_err(|e| e.to_string()));
+                let moved = kept.is_ok();
+                self.warnings.push(match kept {
+                    Ok(()) => format!("Saved {} couldn't be read ({}) and was moved aside as {}", section, e, aside.display()),
+                    Err(write_error) => format!("Saved {} couldn't be read ({}) and couldn't be moved aside: {}", section, e, write_error),
+                });
+                if moved {
+                    self.sections.remove(section);
+                    self.mark_dirty();
+                }
+                T::default()
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.changed_at = Some(Instant::now());
+    }
+
+    /// Replaces a section in memory; it is written once the edits settle.
+    pub fn set<T: Serialize>(&mut self, section: &str, content: &T) -> Result<(), String> {
+        let value = serde_json::to_value(content).map_err(|e| e.to_string())?;
+        if self.sections.get(section) != Some(&value) {
+            self.sections.insert(section.to_string(), value);
+            self.mark_dirty();
+        }
+        Ok(())
+    }
+
+    /// The file's content as of now. Later changes mark the store dirty again.
+    fn snapshot(&mut self) -> Result<Vec<u8>, String> {
+        let layout = Layout {
+            version: VERSION,
+            sections: self.sections.clone(),
+        };
+        let json = serde_json::to_vec_pretty(&layout).map_err(|e| e.to_string())?;
+        self.dirty = false;
+        self.changed_at = None;
+        Ok(json)
+    }
+
+    fn finished(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.failed = None;
+                for path in std::mem::take(&mut self.retired) {
+                    let _ = std::fs::rename(&path, path.with_extension("json.migrated"));
+                }
+            }
+            // Kept dirty, and written again with the next change or a retry
+            Err(e) => {
+                self.dirty = true;
+                self.failed = Some(e);
+            }
+        }
+    }
+
+    fn can_write(&self) -> bool {
+        self.dirty && !self.file.as_os_str().is_empty()
+    }
+
+    /// Writes everything changed right now, after any write already under
+    /// way. Blocks; for exit and profile switches.
+    pub fn save(&mut self) -> Result<(), String> {
+        if let Some(writing) = self.writing.take() {
+            let result = writing.block_and_take();
+            self.finished(result);
+        }
+        if !self.can_write() {
+            return Ok(());
+        }
+        let result = self.snapshot().and_then(|json| write(&self.file, &json));
+        self.finished(result.clone());
+        result
+    }
+
+    /// Starts a background write of the pending changes without waiting for
+    /// the quiet time, e.g. when the window loses focus.
+    pub fn flush(&mut self) {
+        if self.writing.is_some() || !self.can_write() {
+            return;
+        }
+        match self.snapshot() {
+            Ok(json) => {
+                let file = self.file.clone();
+                self.writing = Some(Promise::spawn_thread("save_user_data", move || write(&file, &json)));
+            }
+            Err(e) => self.finished(Err(e)),
+        }
+    }
+
+    /// Collects a finished write and starts the next one when it's due.
+    /// Call once a frame; returns the error of a write that just failed.
+    pub fn poll(&mut self) -> Option<String> {
+        let mut failed = None;
+        if let Some(writing) = self.writing.take() {
+            match writing.try_take() {
+                Ok(result) => {
+                    failed = result.clone().err();
+                    self.finished(result);
+                }
+                Err(writing) => {
+                    self.writing = Some(writing);
+                    return None;
+                }
+            }
+        }
+        if self.changed_at.is_some_and(|at| at.elapsed() >= SAVE_DELAY) {
+            self.flush();
+        }
+        failed
+    }
+
+    /// Whether a write is waiting or running, so the caller keeps polling.
+    pub fn is_busy(&self) -> bool {
+        self.changed_at.is_some() || self.writing.is_some()
+    }
+
+    pub fn state(&self) -> SaveState {
+        if self.is_busy() {
+            SaveState::Saving
+        } else if let Some(e) = &self.failed {
+            SaveState::Failed(e.clone())
+        } else {
+            SaveState::Saved
+        }
+    }
+
+    /// Load problems since the last call.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+fn write(file: &Path, json: &[u8]) -> Result<(), String> {
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    let temp = file.with_extension("json.saving");
+    if let Err(e) = std::fs::write(&temp, json) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("{}: {}", temp.display(), e));
+    }
+    std::fs::rename(&temp, file).map_err(|e| format!("{}: {}", file.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn per_file_layout_migrates_into_userdata() {
+        let dir = TestDir::new("store-migrate");
+        std::fs::write(dir.join("favorites.json"), r#"["a.png", "b.png"]"#).unwrap();
+        std::fs::write(dir.join("hidden.json"), r#"["c.png"]"#).unwrap();
+        std::fs::write(dir.join("pins.json"), r#"["a.png", null]"#).unwrap();
+
+        let mut store = UserData::open(&dir);
+        assert!(store.take_warnings().is_empty());
+        assert_eq!(store.get::<Vec<String>>("favorites"), vec!["a.png", "b.png"]);
+        // Nothing is renamed until the content is safely written
+        assert!(dir.join("favorites.json").exists());
+        store.save().unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(FILE_NAME)).unwrap()).unwrap();
+        assert_eq!(saved["version"], VERSION);
+        assert_eq!(saved["sections"]["favorites"], serde_json::json!(["a.png", "b.png"]));
+        assert_eq!(saved["sections"]["hidden"], serde_json::json!(["c.png"]));
+        assert_eq!(saved["sections"]["pins"], serde_json::json!(["a.png", null]));
+        assert_eq!(
+            files_in(&dir),
+            vec!["favorites.json.migrated", "hidden.json.migrated", "pins.json.migrated", FILE_NAME]
+        );
+
+        // Opening again reads userdata.json and migrates nothing
+        let mut reopened = UserData::open(&dir);
+        assert_eq!(reopened.get::<Vec<String>>("hidden"), vec!["c.png"]);
+        assert_eq!(reopened.state(), SaveState::Saved);
+    }
+
+    #[test]
+    fn corrupt_legacy_file_is_quarantined() {
+        let dir = TestDir::new("store-corrupt");
+        std::fs::write(dir.join("favorites.json"), r#"["a.png"]"#).unwrap();
+        std::fs::write(dir.join("hidden.json"), "[\"c.png\",").unwrap();
+
+        let mut store = UserData::open(&dir);
+        let warnings = store.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("hidden.json"), "{}", warnings[0]);
+        assert_eq!(store.get::<Vec<String>>("hidden"), Vec::<String>::new());
+        store.save().unwrap();
+
+        let files = files_in(&dir);
+        let aside: Vec<&String> = files.iter().filter(|name| name.starts_with("hidden.corrupt-")).collect();
+        assert_eq!(aside.len(), 1, "{:?}", files);
+        let time = aside[0].trim_start_matches("hidden.corrupt-").trim_end_matches(".json");
+        assert!(time.parse::<u64>().is_ok(), "{}", aside[0]);
+        // The broken content is kept as it was
+        assert_eq!(std::fs::read_to_string(dir.join(aside[0])).unwrap(), "[\"c.png\",");
+        assert!(!dir.join("hidden.json").exists());
+        assert!(dir.join("favorites.json.migrated").exists());
+    }
+
+    #[test]
+    fn corrupt_userdata_is_quarantined_and_legacy_files_left_alone() {
+        let dir = TestDir::new("store-corrupt-store");
+        std::fs::write(dir.join(FILE_NAME), "{ not json").unwrap();
+        std::fs::write(dir.join("favorites.json.migrated"), r#"["old.png"]"#).unwrap();
+
+        let mut store = UserData::open(&dir);
+        assert_eq!(store.take_warnings().len(), 1);
+        assert_eq!(store.get::<Vec<String>>("favorites"), Vec::<String>::new());
+        let files = files_in(&dir);
+        assert!(files.iter().any(|name| name.starts_with("userdata.corrupt-")), "{:?}", files);
+        assert!(!dir.join(FILE_NAME).exists());
+    }
+
+    /// Polls like the app does every frame until nothing is waiting or running.
+    fn poll_until_idle(store: &mut UserData) {
+        let deadline = Instant::now() + SAVE_DELAY * 3;
+        while store.is_busy() {
+            assert!(Instant::now() < deadline, "store never went idle");
+            assert_eq!(store.poll(), None);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn changes_within_the_delay_are_written_once() {
+        let dir = TestDir::new("store-debounce");
+        let file = dir.join(FILE_NAME);
+        let mut store = UserData::open(&dir);
+        for favorites in [vec!["a.png"], vec!["a.png", "b.png"], vec!["a.png", "b.png", "c.png"]] {
+            store.set("favorites", &favorites).unwrap();
+            assert_eq!(store.poll(), None);
+            std::thread::sleep(SAVE_DELAY / 10);
+        }
+        assert!(!file.exists(), "written before the edits settled");
+        assert_eq!(store.state(), SaveState::Saving);
+
+        poll_until_idle(&mut store);
+        let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(saved["sections"]["favorites"], serde_json::json!(["a.png", "b.png", "c.png"]));
+        assert_eq!(store.state(), SaveState::Saved);
+
+        // Nothing else is due: with the file gone, another quiet period writes nothing
+        std::fs::remove_file(&file).unwrap();
+        std::thread::sleep(SAVE_DELAY + Duration::from_millis(100));
+        assert_eq!(store.poll(), None);
+        assert!(!store.is_busy());
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn setting_the_same_content_writes_nothing() {
+        let dir = TestDir::new("store-unchanged");
+        let mut store = UserData::open(&dir);
+        store.set("pins", &vec![Some("a.png")]).unwrap();
+        store.save().unwrap();
+        store.set("pins", &vec![Some("a.png")]).unwrap();
+        assert!(!store.is_busy());
+        assert_eq!(store.state(), SaveState::Saved);
+    }
+}
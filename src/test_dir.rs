@@ -0,0 +1,31 @@
+//! Scratch folders for tests.
+
+use std::path::{Path, PathBuf};
+
+/// A fresh empty folder under the system temp dir, removed again on drop,
+/// so a failing test doesn't leave it behind either.
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// `name` only has to be unique among the tests of one run.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("chlorine-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl std::ops::Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,234 @@
+//! Decoded thumbnails kept on disk, so reopening a library doesn't decode
+//! every image again.
+//!
+//! A cache file is named after the image's path, size and modification time,
+//! the thumbnail tier and the configured encoding. Editing the image or
+//! changing the encoding therefore just misses, and the thumbnail is
+//! regenerated the next time it's shown. Hits refresh the file's
+//! modification time, which is what [`prune`] goes by when the cache grows
+//! past its cap.
+//!
+//! A damaged entry is deleted and the image decoded again. Failed writes
+//! are counted in a [`Health`] shared with the UI, which turns the cache
+//! off for the session once [`WRITE_FAILURE_LIMIT`] fail in a row, e.g.
+//! when its drive is unplugged or full.
+
+use crate::settings::ThumbnailEncoding;
+use image::{ImageEncoder, RgbaImage};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const EXTENSION: &str = "thumb";
+/// Writes failing in a row before the cache is given up on.
+pub const WRITE_FAILURE_LIMIT: usize = 5;
+
+/// The configured folder, or the default one in the cache directory when
+/// `configured` is empty.
+pub fn dir(configured: &str) -> PathBuf {
+    if configured.trim().is_empty() {
+        crate::settings::cache_dir().join("thumbnails")
+    } else {
+        PathBuf::from(configured.trim())
+    }
+}
+
+/// Whether thumbnails can be written to `dir`, by writing and removing a file there.
+pub fn probe(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let file = dir.join("probe.partial");
+    let written = std::fs::write(&file, b"chlorine");
+    let _ = std::fs::remove_file(&file);
+    written.map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+/// Write failures since the last successful write, across every loader thread.
+#[derive(Debug, Default)]
+pub struct Health {
+    failures: AtomicUsize,
+    last_error: Mutex<String>,
+}
+
+impl Health {
+    fn record(&self, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.failures.store(0, Ordering::Relaxed),
+            Err(e) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                if let Ok(mut last_error) = self.last_error.lock() {
+                    *last_error = e;
+                }
+            }
+        }
+    }
+
+    /// The last error once [`WRITE_FAILURE_LIMIT`] writes have failed in a row.
+    pub fn failing(&self) -> Option<String> {
+        (self.failures.load(Ordering::Relaxed) >= WRITE_FAILURE_LIMIT)
+            .then(|| self.last_error.lock().map(|e| e.clone()).unwrap_or_default())
+    }
+
+    pub fn reset(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    encoding: ThumbnailEncoding,
+    quality: u8,
+    health: Arc<Health>,
+}
+
+/// Size of the cache on disk.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub bytes: u64,
+    pub entries: usize,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf, encoding: ThumbnailEncoding, quality: u8, health: Arc<Health>) -> Self {
+        Self {
+            dir,
+            encoding,
+            quality,
+            health,
+        }
+    }
+
+    fn tag(&self) -> String {
+        match self.encoding {
+            ThumbnailEncoding::Png => "png".to_string(),
+            ThumbnailEncoding::Jpeg => format!("jpeg{}", self.quality),
+            ThumbnailEncoding::WebP => "webp".to_string(),
+        }
+    }
+
+    /// Where the thumbnail of `path` at `tier` lives with the current
+    /// encoding, or None if the image itself can't be read.
+    fn entry_path(&self, path: &str, tier: u32) -> Option<PathBuf> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (path, metadata.len(), modified).hash(&mut hasher);
+        let name = format!("{:016x}-{}-{}.{}", hasher.finish(), tier, self.tag(), EXTENSION);
+        Some(self.dir.join(name))
+    }
+
+    pub fn get(&self, path: &str, tier: u32) -> Option<RgbaImage> {
+        let entry = self.entry_path(path, tier)?;
+        let bytes = match std::fs::read(&entry) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                // A miss; anything else is an entry that can't be trusted
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    let _ = std::fs::remove_file(&entry);
+                }
+                return None;
+            }
+        };
+        let Ok(image) = image::load_from_memory(&bytes) else {
+            // Half-written or damaged; it's rewritten after decoding
+            let _ = std::fs::remove_file(&entry);
+            return None;
+        };
+        if let Ok(file) = std::fs::File::options().write(true).open(&entry) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(image.to_rgba8())
+    }
+
+    /// Stores a thumbnail; failures only mean it's decoded again next time,
+    /// but are counted in the cache's [`Health`].
+    pub fn put(&self, path: &str, tier: u32, rgba: &RgbaImage) {
+        let Some(entry) = self.entry_path(path, tier) else {
+            return;
+        };
+        let Ok(bytes) = self.encode(rgba) else {
+            return;
+        };
+        self.health.record(self.write(&entry, &bytes));
+    }
+
+    fn write(&self, entry: &Path, bytes: &[u8]) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("{}: {}", self.dir.display(), e))?;
+        // Written under a temporary name so a reader never sees half a file
+        let temp = entry.with_extension("partial");
+        let written = std::fs::write(&temp, bytes).and_then(|()| std::fs::rename(&temp, entry));
+        written.map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            format!("{}: {}", self.dir.display(), e)
+        })
+    }
+
+    fn encode(&self, rgba: &RgbaImage) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let (width, height) = rgba.dimensions();
+        let opaque = rgba.pixels().all(|pixel| pixel[3] == u8::MAX);
+        match self.encoding {
+            ThumbnailEncoding::Jpeg if opaque => {
+                let rgb = image::DynamicImage::ImageRgba8(rgba.clone()).to_rgb8();
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, self.quality)
+                    .encode(rgb.as_raw(), width, height, image::ColorType::Rgb8)
+                    .map_err(|e| e.to_string())?;
+            }
+            ThumbnailEncoding::WebP => {
+                image::codecs::webp::WebPEncoder::new_lossless(&mut bytes)
+                    .encode(rgba.as_raw(), width, height, image::ColorType::Rgba8)
+                    .map_err(|e| e.to_string())?;
+            }
+            _ => {
+                image::codecs::png::PngEncoder::new(&mut bytes)
+                    .write_image(rgba.as_raw(), width, height, image::ColorType::Rgba8)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Cache files with their size and when they were last used.
+fn entries(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read.flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+            Some((entry.path(), metadata.len(), used))
+        })
+        .collect()
+}
+
+/// Deletes the least recently used files until the cache fits in
+/// `limit_bytes`, and reports what's left. Meant for a background thread.
+pub fn prune(dir: &Path, limit_bytes: u64) -> Usage {
+    let mut entries = entries(dir);
+    let mut bytes: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    entries.sort_by_key(|(_, _, used)| *used);
+    let mut removed = 0;
+    for (path, size, _) in &entries {
+        if bytes <= limit_bytes {
+            break;
+        }
+        if std::fs::remove_file(path).is_ok() {
+            bytes -= size;
+            removed += 1;
+        }
+    }
+    Usage {
+        bytes,
+        entries: entries.len() - removed,
+    }
+}
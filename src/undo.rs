@@ -0,0 +1,81 @@
+//! Per-session undo/redo for library edits.
+//!
+//! Each [`Edit`] records both the old and the new state, so the same value can
+//! be applied in either direction. Nothing here is persisted.
+
+use crate::grouping::GroupingRule;
+use std::collections::VecDeque;
+
+/// Edits kept before the oldest is dropped.
+pub const UNDO_LIMIT: usize = 50;
+
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Favorite { path: String, filename: String, added: bool },
+    /// One bulk change from the selection bar, undone as a single step.
+    Favorites { paths: Vec<String>, added: bool },
+    Hidden { path: String, filename: String, hidden: bool },
+    Grouping {
+        category: String,
+        before: Option<GroupingRule>,
+        after: Option<GroupingRule>,
+    },
+}
+
+impl Edit {
+    /// Short description for "Undid: …" / "Redid: …" messages.
+    pub fn describe(&self) -> String {
+        match self {
+            Edit::Favorite { filename, added: true, .. } => format!("favorite {}", filename),
+            Edit::Favorite { filename, added: false, .. } => format!("unfavorite {}", filename),
+            Edit::Favorites { paths, added: true } => format!("favorite {} images", paths.len()),
+            Edit::Favorites { paths, added: false } => format!("unfavorite {} images", paths.len()),
+            Edit::Hidden { filename, hidden: true, .. } => format!("hide {}", filename),
+            Edit::Hidden { filename, hidden: false, .. } => format!("unhide {}", filename),
+            Edit::Grouping { category, after: Some(rule), .. } => {
+                format!("group \"{}\" by {}", category, rule.label().to_lowercase())
+            }
+            Edit::Grouping { category, after: None, .. } => format!("ungroup \"{}\"", category),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    done: VecDeque<Edit>,
+    undone: Vec<Edit>,
+}
+
+impl UndoStack {
+    /// Records a new edit; anything that was undone can no longer be redone.
+    pub fn record(&mut self, edit: Edit) {
+        self.undone.clear();
+        self.done.push_back(edit);
+        while self.done.len() > UNDO_LIMIT {
+            self.done.pop_front();
+        }
+    }
+
+    pub fn take_undo(&mut self) -> Option<Edit> {
+        self.done.pop_back()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Edit> {
+        self.undone.pop()
+    }
+
+    /// Files an edit after it was undone, or puts back one whose redo failed.
+    pub fn push_undone(&mut self, edit: Edit) {
+        self.undone.push(edit);
+    }
+
+    /// Files an edit after it was redone, or puts back one whose undo failed.
+    pub fn push_done(&mut self, edit: Edit) {
+        self.done.push_back(edit);
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
@@ -0,0 +1,76 @@
+//! Optional check for a newer release on GitHub.
+//!
+//! One request to the releases API, on a background thread with a short
+//! timeout. Only the tag of the latest published release is compared with
+//! this build's version; nothing is downloaded or installed.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const LATEST_RELEASE: &str = "https://api.github.com/repos/Coder-soft/chlorine/releases/latest";
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Automatic checks happen at most this often.
+pub const INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub struct Release {
+    /// Without a leading "v", e.g. "0.3.1".
+    pub version: String,
+    /// The release's page on GitHub.
+    pub url: String,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether an automatic check is due, given when the last one ran (0 for never).
+pub fn is_due(last_checked: u64) -> bool {
+    now().saturating_sub(last_checked) >= INTERVAL_SECS
+}
+
+/// "1.2.3" as numbers; a "v" prefix and anything after a "-" or "+" are ignored.
+fn parse(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Whether `candidate` is a later version than `current`; false when either doesn't parse.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let (Some(mut candidate), Some(mut current)) = (parse(candidate), parse(current)) else {
+        return false;
+    };
+    let length = candidate.len().max(current.len());
+    candidate.resize(length, 0);
+    current.resize(length, 0);
+    candidate > current
+}
+
+/// The latest release if it's newer than `current`. Meant for a background thread.
+pub fn check(current: &str) -> Result<Option<Release>, String> {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    let body = agent
+        .get(LATEST_RELEASE)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", concat!("chlorine/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    let release: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    let tag = release
+        .get("tag_name")
+        .and_then(|tag| tag.as_str())
+        .ok_or("the response has no tag_name")?;
+    let url = release
+        .get("html_url")
+        .and_then(|url| url.as_str())
+        .ok_or("the response has no html_url")?;
+    Ok(is_newer(tag, current).then(|| Release {
+        version: tag.trim().trim_start_matches(['v', 'V']).to_string(),
+        url: url.to_string(),
+    }))
+}
@@ -0,0 +1,136 @@
+//! Which categories were copied from, and when. Stored in the profile's
+//! user data store and never sent anywhere.
+//!
+//! Each successful copy appends one event. Events older than
+//! [`WINDOW_DAYS`] are dropped on load, so the section stays small and every
+//! view covers the same period.
+
+use crate::store::UserData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const WINDOW_DAYS: u64 = 30;
+const SECTION: &str = "usage";
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UsageEvent {
+    category: String,
+    /// Seconds since the Unix epoch.
+    at: u64,
+    /// Local weekday (0 = Monday) and hour when the copy happened.
+    weekday: u8,
+    hour: u8,
+}
+
+pub struct UsageLog {
+    events: Vec<UsageEvent>,
+    /// Local time minus UTC, in seconds; 0 when it can't be determined.
+    utc_offset: i64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Best-effort local UTC offset from `date +%z`; std has no time zone support.
+fn local_utc_offset() -> i64 {
+    if cfg!(target_os = "windows") {
+        return 0;
+    }
+    let Some(output) = std::process::Command::new("date")
+        .arg("+%z")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+    else {
+        return 0;
+    };
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let (sign, digits) = if let Some(digits) = text.strip_prefix('-') {
+        (-1, digits)
+    } else if let Some(digits) = text.strip_prefix('+') {
+        (1, digits)
+    } else {
+        return 0;
+    };
+    match (digits.get(..2).and_then(|h| h.parse::<i64>().ok()), digits.get(2..4).and_then(|m| m.parse::<i64>().ok())) {
+        (Some(hours), Some(minutes)) => sign * (hours * 3600 + minutes * 60),
+        _ => 0,
+    }
+}
+
+impl UsageLog {
+    pub fn load(store: &mut UserData) -> Self {
+        let mut events: Vec<UsageEvent> = store.get(SECTION);
+        let cutoff = now().saturating_sub(WINDOW_DAYS * DAY_SECS);
+        events.retain(|event| event.at >= cutoff);
+        Self {
+            events,
+            utc_offset: local_utc_offset(),
+        }
+    }
+
+    pub fn save(&self, store: &mut UserData) -> Result<(), String> {
+        store.set(SECTION, &self.events)
+    }
+
+    pub fn record(&mut self, category: &str) {
+        let at = now();
+        let local = (at as i64 + self.utc_offset).max(0) as u64;
+        let days = local / DAY_SECS;
+        self.events.push(UsageEvent {
+            category: category.to_string(),
+            at,
+            // 1970-01-01 was a Thursday
+            weekday: ((days + 3) % 7) as u8,
+            hour: ((local % DAY_SECS) / 3600) as u8,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Copies per category, most used first.
+    pub fn by_category(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for event in &self.events {
+            *counts.entry(&event.category).or_default() += 1;
+        }
+        let mut ranked: Vec<(String, usize)> = counts.into_iter().map(|(name, count)| (name.to_string(), count)).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    /// Copies per weekday, Monday first.
+    pub fn by_weekday(&self) -> [usize; 7] {
+        let mut counts = [0; 7];
+        for event in &self.events {
+            counts[event.weekday as usize % 7] += 1;
+        }
+        counts
+    }
+
+    pub fn by_hour(&self) -> [usize; 24] {
+        let mut counts = [0; 24];
+        for event in &self.events {
+            counts[event.hour as usize % 24] += 1;
+        }
+        counts
+    }
+}
@@ -0,0 +1,396 @@
+//! "Export user data…" and "Import user data…": favorites, hidden images,
+//! pinned slots and category color labels in one JSON file, so a curated set
+//! can be handed to a teammate using the same shared library.
+//!
+//! Entries are written by `relative_path` rather than full path, so the file
+//! still applies on a machine that mounts the library somewhere else. On
+//! import they are matched against the loaded manifest; entries the manifest
+//! doesn't have are skipped and counted. Color labels are keyed by category
+//! name and matched the same way.
+
+use crate::category_colors::LabelColor;
+use crate::search::normalize_path;
+use crate::ImageData;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub const VERSION: u32 = 1;
+
+/// Sections are optional so a file with only favorites imports cleanly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserDataBundle {
+    pub version: u32,
+    pub favorites: Option<Vec<String>>,
+    pub hidden: Option<Vec<String>>,
+    /// One entry per slot, null for an empty one.
+    pub pins: Option<Vec<Option<String>>>,
+    pub category_colors: Option<BTreeMap<String, LabelColor>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Add to what is already there.
+    Merge,
+    /// Make the section exactly what the file says.
+    Replace,
+}
+
+impl ImportMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportMode::Merge => "Merge",
+            ImportMode::Replace => "Replace",
+        }
+    }
+}
+
+/// Translates between full and relative paths for every image in a library.
+/// Both directions are keyed by normalized path, so case and separators
+/// written by another OS still match.
+pub struct PathMap {
+    relative_by_full: HashMap<String, String>,
+    full_by_relative: HashMap<String, String>,
+}
+
+impl PathMap {
+    pub fn build(data: &ImageData) -> Self {
+        let mut map = Self {
+            relative_by_full: HashMap::new(),
+            full_by_relative: HashMap::new(),
+        };
+        for category in data.categories.values() {
+            for image in &category.images {
+                map.relative_by_full
+                    .entry(normalize_path(&image.full_path))
+                    .or_insert_with(|| image.relative_path.clone());
+                map.full_by_relative
+                    .entry(normalize_path(&image.relative_path))
+                    .or_insert_with(|| image.full_path.clone());
+            }
+        }
+        map
+    }
+
+    pub fn relative(&self, full_path: &str) -> Option<&str> {
+        self.relative_by_full.get(&normalize_path(full_path)).map(String::as_str)
+    }
+
+    pub fn full(&self, relative_path: &str) -> Option<&str> {
+        self.full_by_relative.get(&normalize_path(relative_path)).map(String::as_str)
+    }
+
+    /// Relative paths for a list of full paths; ones not in the library are left out.
+    pub fn to_relative<'a>(&self, full_paths: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+        full_paths
+            .into_iter()
+            .filter_map(|path| self.relative(path))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Full paths for the entries the library has, plus how many it doesn't.
+    pub fn resolve(&self, relative_paths: &[String]) -> (Vec<String>, usize) {
+        let resolved: Vec<String> = relative_paths
+            .iter()
+            .filter_map(|path| self.full(path))
+            .map(str::to_string)
+            .collect();
+        let skipped = relative_paths.len() - resolved.len();
+        (resolved, skipped)
+    }
+}
+
+/// Builds the bundle for export, plus how many favorites and hidden entries
+/// were left out because the library doesn't have them.
+pub fn bundle<'a>(
+    data: &ImageData,
+    favorites: &[String],
+    hidden: impl IntoIterator<Item = &'a String>,
+    pins: &[Option<String>],
+    category_colors: &BTreeMap<String, LabelColor>,
+) -> (UserDataBundle, usize) {
+    let map = PathMap::build(data);
+    let hidden: Vec<&String> = hidden.into_iter().collect();
+    let relative_favorites = map.to_relative(favorites);
+    let relative_hidden = map.to_relative(hidden.iter().copied());
+    let left_out = favorites.len() - relative_favorites.len() + hidden.len() - relative_hidden.len();
+    let pins = pins
+        .iter()
+        .map(|slot| slot.as_deref().and_then(|path| map.relative(path)).map(str::to_string))
+        .collect();
+    let bundle = UserDataBundle {
+        version: VERSION,
+        favorites: Some(relative_favorites),
+        hidden: Some(relative_hidden),
+        pins: Some(pins),
+        category_colors: Some(category_colors.clone()),
+    };
+    (bundle, left_out)
+}
+
+/// A bundle matched against a library: each section present in the file,
+/// with full paths, and how many of its entries the library doesn't have.
+#[derive(Debug, Default)]
+pub struct Resolved {
+    pub favorites: Option<(Vec<String>, usize)>,
+    pub hidden: Option<(Vec<String>, usize)>,
+    pub pins: Option<(Vec<Option<String>>, usize)>,
+    pub category_colors: Option<(BTreeMap<String, LabelColor>, usize)>,
+}
+
+pub fn resolve(bundle: UserDataBundle, data: &ImageData) -> Resolved {
+    let map = PathMap::build(data);
+    let pins = bundle.pins.map(|slots| {
+        let resolved: Vec<Option<String>> = slots
+            .iter()
+            .map(|slot| slot.as_deref().and_then(|path| map.full(path)).map(str::to_string))
+            .collect();
+        let skipped = slots.iter().zip(&resolved).filter(|(slot, full)| slot.is_some() && full.is_none()).count();
+        (resolved, skipped)
+    });
+    let category_colors = bundle.category_colors.map(|labels| {
+        let total = labels.len();
+        let known: BTreeMap<_, _> = labels
+            .into_iter()
+            .filter(|(category, _)| data.categories.contains_key(category))
+            .collect();
+        let skipped = total - known.len();
+        (known, skipped)
+    });
+    Resolved {
+        favorites: bundle.favorites.map(|paths| map.resolve(&paths)),
+        hidden: bundle.hidden.map(|paths| map.resolve(&paths)),
+        pins,
+        category_colors,
+    }
+}
+
+/// Favorites or hidden images after an import: merge appends the new paths
+/// that aren't there yet, keeping the existing order.
+pub fn merge_paths(current: Vec<String>, imported: Vec<String>, mode: ImportMode) -> Vec<String> {
+    let mut merged = match mode {
+        ImportMode::Merge => current,
+        ImportMode::Replace => Vec::new(),
+    };
+    for path in imported {
+        if !merged.contains(&path) {
+            merged.push(path);
+        }
+    }
+    merged
+}
+
+/// Pinned slots after an import. Merge only fills empty slots; replace
+/// overwrites every slot the file has. Slots past the current count are dropped.
+pub fn merge_pins(current: &[Option<String>], imported: Vec<Option<String>>, mode: ImportMode) -> Vec<Option<String>> {
+    let mut slots = current.to_vec();
+    for (slot, path) in slots.iter_mut().zip(imported) {
+        let keep = mode == ImportMode::Merge && (path.is_none() || slot.is_some());
+        if !keep {
+            *slot = path;
+        }
+    }
+    slots
+}
+
+/// Category colors after an import; on merge the file wins for categories labelled in both.
+pub fn merge_labels(
+    current: &BTreeMap<String, LabelColor>,
+    imported: BTreeMap<String, LabelColor>,
+    mode: ImportMode,
+) -> BTreeMap<String, LabelColor> {
+    let mut merged = match mode {
+        ImportMode::Merge => current.clone(),
+        ImportMode::Replace => BTreeMap::new(),
+    };
+    merged.extend(imported);
+    merged
+}
+
+pub fn export(path: &Path, bundle: &UserDataBundle) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(bundle).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Reads a bundle, validating each section on its own: the sections that
+/// parsed plus "section: error" messages for the ones that did not.
+pub fn read(path: &Path) -> Result<(UserDataBundle, Vec<String>), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{}: expected a JSON object", path.display()))?;
+
+    let mut bundle = UserDataBundle {
+        version: object.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+        ..Default::default()
+    };
+    let mut errors = Vec::new();
+    if let Some(section) = object.get("favorites") {
+        match serde_json::from_value(section.clone()) {
+            Ok(favorites) => bundle.favorites = Some(favorites),
+            Err(e) => errors.push(format!("favorites: {}", e)),
+        }
+    }
+    if let Some(section) = object.get("hidden") {
+        match serde_json::from_value(section.clone()) {
+            Ok(hidden) => bundle.hidden = Some(hidden),
+            Err(e) => errors.push(format!("hidden: {}", e)),
+        }
+    }
+    if let Some(section) = object.get("pins") {
+        match serde_json::from_value(section.clone()) {
+            Ok(pins) => bundle.pins = Some(pins),
+            Err(e) => errors.push(format!("pins: {}", e)),
+        }
+    }
+    if let Some(section) = object.get("category_colors") {
+        match serde_json::from_value(section.clone()) {
+            Ok(labels) => bundle.category_colors = Some(labels),
+            Err(e) => errors.push(format!("category colors: {}", e)),
+        }
+    }
+    Ok((bundle, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A library rooted at `root` with one category per (name, relative paths).
+    fn library(root: &str, categories: &[(&str, &[&str])]) -> ImageData {
+        let categories: serde_json::Map<String, serde_json::Value> = categories
+            .iter()
+            .map(|(name, paths)| {
+                let images: Vec<serde_json::Value> = paths
+                    .iter()
+                    .map(|relative| {
+                        serde_json::json!({
+                            "filename": relative.rsplit('/').next().unwrap(),
+                            "relative_path": relative,
+                            "full_path": format!("{}/{}", root, relative),
+                            "extension": ".png",
+                            "size": 1,
+                        })
+                    })
+                    .collect();
+                let category = serde_json::json!({ "directory": name, "count": images.len(), "images": images });
+                (name.to_string(), category)
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({ "categories": categories })).unwrap()
+    }
+
+    fn paths(list: &[&str]) -> Vec<String> {
+        list.iter().map(|path| path.to_string()).collect()
+    }
+
+    /// Exports from one machine's library and reads the file back against a
+    /// teammate's copy mounted elsewhere, which lacks `icons/old.png` and the
+    /// "Old" category.
+    fn round_trip(name: &str) -> Resolved {
+        let mine = library(
+            "/home/me/lib",
+            &[("Icons", &["icons/a.png", "icons/b.png", "icons/old.png"]), ("Old", &["old/x.png"])],
+        );
+        let theirs = library("D:/Shared/lib", &[("Icons", &["icons/a.png", "icons/b.png"])]);
+
+        let favorites = paths(&["/home/me/lib/icons/a.png", "/home/me/lib/icons/old.png", "/elsewhere/c.png"]);
+        let hidden = paths(&["/home/me/lib/icons/b.png"]);
+        let pins = vec![Some("/home/me/lib/icons/old.png".to_string()), None, Some("/home/me/lib/icons/b.png".to_string())];
+        let labels = BTreeMap::from([("Icons".to_string(), LabelColor::Red), ("Old".to_string(), LabelColor::Blue)]);
+        let (bundle, left_out) = bundle(&mine, &favorites, &hidden, &pins, &labels);
+        assert_eq!(left_out, 1, "only the path outside the library is left out");
+
+        let file = std::env::temp_dir().join(format!("chlorine-user-data-{}-{}.json", name, std::process::id()));
+        export(&file, &bundle).unwrap();
+        let (read_back, errors) = read(&file).unwrap();
+        let _ = std::fs::remove_file(&file);
+        assert!(errors.is_empty());
+        assert_eq!(read_back.version, VERSION);
+        assert_eq!(read_back.favorites, Some(paths(&["icons/a.png", "icons/old.png"])));
+        resolve(read_back, &theirs)
+    }
+
+    #[test]
+    fn round_trip_counts_matched_and_skipped() {
+        let resolved = round_trip("counts");
+        assert_eq!(resolved.favorites, Some((paths(&["D:/Shared/lib/icons/a.png"]), 1)));
+        assert_eq!(resolved.hidden, Some((paths(&["D:/Shared/lib/icons/b.png"]), 0)));
+        assert_eq!(
+            resolved.pins,
+            Some((vec![None, None, Some("D:/Shared/lib/icons/b.png".to_string())], 1))
+        );
+        assert_eq!(
+            resolved.category_colors,
+            Some((BTreeMap::from([("Icons".to_string(), LabelColor::Red)]), 1))
+        );
+    }
+
+    #[test]
+    fn paths_merge_or_replace() {
+        let (favorites, _) = round_trip("favorites").favorites.unwrap();
+        let current = paths(&["D:/Shared/lib/icons/b.png", "D:/Shared/lib/icons/a.png"]);
+        // Already there, so nothing is added twice
+        assert_eq!(merge_paths(current.clone(), favorites.clone(), ImportMode::Merge), current);
+        assert_eq!(merge_paths(current, favorites.clone(), ImportMode::Replace), favorites);
+
+        let (hidden, _) = round_trip("hidden").hidden.unwrap();
+        let current = paths(&["D:/Shared/lib/icons/a.png"]);
+        assert_eq!(
+            merge_paths(current, hidden.clone(), ImportMode::Merge),
+            paths(&["D:/Shared/lib/icons/a.png", "D:/Shared/lib/icons/b.png"])
+        );
+        assert_eq!(merge_paths(Vec::new(), hidden.clone(), ImportMode::Replace), hidden);
+    }
+
+    #[test]
+    fn pins_merge_fills_empty_slots_and_replace_overwrites() {
+        let (slots, _) = round_trip("pins").pins.unwrap();
+        let current = vec![Some("mine/1.png".to_string()), Some("mine/2.png".to_string()), None, None];
+        let b = Some("D:/Shared/lib/icons/b.png".to_string());
+
+        assert_eq!(
+            merge_pins(&current, slots.clone(), ImportMode::Merge),
+            vec![current[0].clone(), current[1].clone(), b.clone(), None]
+        );
+        // Slots the file doesn't have are left alone either way
+        assert_eq!(
+            merge_pins(&current, slots.clone(), ImportMode::Replace),
+            vec![None, None, b.clone(), None]
+        );
+        assert_eq!(merge_pins(&current[..2], slots, ImportMode::Replace), vec![None, None]);
+    }
+
+    #[test]
+    fn labels_merge_or_replace() {
+        let (labels, _) = round_trip("labels").category_colors.unwrap();
+        let current = BTreeMap::from([
+            ("Icons".to_string(), LabelColor::Gray),
+            ("Photos".to_string(), LabelColor::Green),
+        ]);
+        assert_eq!(
+            merge_labels(&current, labels.clone(), ImportMode::Merge),
+            BTreeMap::from([
+                ("Icons".to_string(), LabelColor::Red),
+                ("Photos".to_string(), LabelColor::Green),
+            ])
+        );
+        assert_eq!(merge_labels(&current, labels.clone(), ImportMode::Replace), labels);
+    }
+
+    #[test]
+    fn broken_section_is_reported_and_the_rest_kept() {
+        let file = std::env::temp_dir().join(format!("chlorine-user-data-broken-{}.json", std::process::id()));
+        std::fs::write(&file, r#"{ "version": 1, "favorites": ["a.png"], "pins": "not a list" }"#).unwrap();
+        let (bundle, errors) = read(&file).unwrap();
+        let _ = std::fs::remove_file(&file);
+        assert_eq!(bundle.favorites, Some(paths(&["a.png"])));
+        assert!(bundle.pins.is_none() && bundle.hidden.is_none());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("pins: "));
+    }
+}
@@ -0,0 +1,224 @@
+//! Watching the library's category folders for image files that appear,
+//! disappear or change on disk.
+//!
+//! A single background thread lists the folders the categories live in and
+//! compares each listing with the last one by size and modification time.
+//! Small libraries are listed every few seconds; big ones, or folders that
+//! take long to list, much less often. Findings go through a channel that
+//! the app drains in `update`; the thread never touches app state.
+
+use crate::ImageInfo;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+const FAST_INTERVAL: Duration = Duration::from_secs(3);
+const SLOW_INTERVAL: Duration = Duration::from_secs(30);
+/// Above this many files, or when a listing takes longer than
+/// [`SLOW_LISTING`], folders are listed at [`SLOW_INTERVAL`].
+const FAST_FILE_LIMIT: usize = 5_000;
+const SLOW_LISTING: Duration = Duration::from_millis(250);
+/// Files tracked at most; anything past it is ignored.
+const MAX_FILES: usize = 200_000;
+/// Levels below a category folder that are looked into.
+const MAX_DEPTH: usize = 4;
+
+/// A category folder on disk and how its entries' paths are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedDir {
+    /// The manifest category new files are added to.
+    pub category: String,
+    /// Library root as it appears in `full_path`.
+    pub root: String,
+    /// The category's folder relative to `root`.
+    pub directory: String,
+}
+
+impl WatchedDir {
+    fn path(&self) -> PathBuf {
+        PathBuf::from(join(&self.root, &self.directory))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// A new image file; `image` is the entry it would get in `category`.
+    Created { category: String, image: ImageInfo },
+    Deleted(String),
+    Modified(String),
+}
+
+fn join(root: &str, relative: &str) -> String {
+    match (root.is_empty(), relative.is_empty()) {
+        (true, _) => relative.to_string(),
+        (_, true) => root.to_string(),
+        _ => format!("{}/{}", root, relative),
+    }
+}
+
+type Listing = HashMap<PathBuf, (u64, Option<SystemTime>)>;
+
+fn list(dir: &Path, depth: usize, listing: &mut Listing) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if listing.len() >= MAX_FILES {
+            return;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if depth < MAX_DEPTH {
+                list(&path, depth + 1, listing);
+            }
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(crate::decoder_supports)
+        {
+            if let Ok(metadata) = entry.metadata() {
+                listing.insert(path, (metadata.len(), metadata.modified().ok()));
+            }
+        }
+    }
+}
+
+/// Folders to list: the category folders, minus ones inside another.
+fn roots(dirs: &[WatchedDir]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = dirs.iter().map(WatchedDir::path).collect();
+    paths.sort();
+    paths.dedup();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !roots.iter().any(|root| path.starts_with(root)) {
+            roots.push(path);
+        }
+    }
+    roots
+}
+
+/// The entry a new file gets, in the category with the deepest folder holding it.
+fn entry_for(dirs: &[WatchedDir], path: &Path, size: u64) -> Option<(String, ImageInfo)> {
+    let (dir, rest) = dirs
+        .iter()
+        .filter_map(|dir| path.strip_prefix(dir.path()).ok().map(|rest| (dir, rest)))
+        .min_by_key(|(_, rest)| rest.components().count())?;
+    let filename = path.file_name()?.to_string_lossy().into_owned();
+    let rest = rest.to_string_lossy().replace('\\', "/");
+    let relative_path = join(&dir.directory, &rest);
+    let image = ImageInfo {
+        full_path: join(&dir.root, &relative_path),
+        relative_path,
+        extension: path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy().to_ascii_lowercase()))
+            .unwrap_or_default(),
+        filename,
+        size,
+        checksum: None,
+        width: None,
+        height: None,
+    };
+    Some((dir.category.clone(), image))
+}
+
+fn diff(dirs: &[WatchedDir], before: &Listing, after: &Listing, sender: &Sender<Change>) -> bool {
+    for (path, (size, modified)) in after {
+        let change = match before.get(path) {
+            None => entry_for(dirs, path, *size).map(|(category, image)| Change::Created { category, image }),
+            Some(old) if old != &(*size, *modified) => {
+                entry_for(dirs, path, *size).map(|(_, image)| Change::Modified(image.full_path))
+            }
+            Some(_) => None,
+        };
+        if let Some(change) = change {
+            if sender.send(change).is_err() {
+                return false;
+            }
+        }
+    }
+    for path in before.keys().filter(|path| !after.contains_key(*path)) {
+        if let Some((_, image)) = entry_for(dirs, path, 0) {
+            if sender.send(Change::Deleted(image.full_path)).is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn run(dirs: Vec<WatchedDir>, sender: Sender<Change>, stop: Arc<AtomicBool>, wake: impl Fn()) {
+    let roots = roots(&dirs);
+    let scan = || {
+        let started = Instant::now();
+        let mut listing = Listing::new();
+        for root in &roots {
+            list(root, 0, &mut listing);
+        }
+        let slow = listing.len() > FAST_FILE_LIMIT || started.elapsed() > SLOW_LISTING;
+        (listing, if slow { SLOW_INTERVAL } else { FAST_INTERVAL })
+    };
+    let (mut listing, mut interval) = scan();
+    loop {
+        // Sleeps in short steps so stopping doesn't wait out a slow interval
+        let until = Instant::now() + interval;
+        while Instant::now() < until {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(250));
+        }
+        let (next, next_interval) = scan();
+        let changed = next != listing;
+        if changed && !diff(&dirs, &listing, &next, &sender) {
+            return;
+        }
+        if changed {
+            wake();
+        }
+        listing = next;
+        interval = next_interval;
+    }
+}
+
+/// Stops its thread when dropped.
+pub struct Watcher {
+    dirs: Vec<WatchedDir>,
+    receiver: Receiver<Change>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    /// Starts watching; `ctx` is repainted when something changes.
+    pub fn start(dirs: Vec<WatchedDir>, ctx: egui::Context) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread_dirs = dirs.clone();
+        let _ = std::thread::Builder::new()
+            .name("watch_library".to_string())
+            .spawn(move || run(thread_dirs, sender, thread_stop, || ctx.request_repaint()));
+        Self { dirs, receiver, stop }
+    }
+
+    pub fn dirs(&self) -> &[WatchedDir] {
+        &self.dirs
+    }
+
+    pub fn drain(&self) -> Vec<Change> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,459 @@
+//! "Import ZIP…": turns an archive's top-level folders into categories.
+//!
+//! The preview only reads the archive's listing and entry headers, so it is
+//! quick even for large packs. Images at the top level, outside any folder,
+//! go into a category named after the archive. Extraction then writes the
+//! image entries below a chosen folder, or, for a pack someone already
+//! unpacked, looks the same entries up there without extracting anything.
+//! The new entries are handed back so the caller can queue the manifest update.
+//!
+//! Archives can claim far more than they hold, or hold far more than they
+//! claim, so extraction checks the declared sizes against a cap and the free
+//! space first, and never writes more of an entry than it declared.
+
+use crate::import::{free_name, ImportProgress};
+use crate::ImageInfo;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use zip::result::ZipError;
+
+/// Largest single image extracted; anything bigger is left out.
+const MAX_ENTRY_BYTES: u64 = 512 * 1024 * 1024;
+/// Largest total extracted by one import.
+const MAX_TOTAL_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+/// Left free on the destination's disk after extracting.
+const FREE_SPACE_MARGIN: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipMode {
+    /// Unpack the images into the chosen folder.
+    Extract,
+    /// The archive was unpacked into the chosen folder already; just list it.
+    Reference,
+}
+
+impl ZipMode {
+    pub const ALL: [ZipMode; 2] = [ZipMode::Extract, ZipMode::Reference];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ZipMode::Extract => "Extract into a folder",
+            ZipMode::Reference => "Use a folder it was already extracted to",
+        }
+    }
+}
+
+/// A top-level folder of the archive, which becomes one category.
+#[derive(Debug, Clone)]
+pub struct FolderPreview {
+    /// The folder's name in the archive; the archive's name for loose images.
+    pub folder: String,
+    pub images: usize,
+    /// Uncompressed size of its images.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub folders: Vec<FolderPreview>,
+    /// Entries that aren't decodable images.
+    pub skipped: usize,
+}
+
+/// Where one archive folder goes.
+pub struct Target {
+    pub folder: String,
+    pub category: String,
+}
+
+pub struct ZipPlan {
+    pub archive: PathBuf,
+    pub mode: ZipMode,
+    /// The chosen folder on disk.
+    pub destination: PathBuf,
+    /// Library root as it appears in `full_path`; empty when the folder is outside it.
+    pub root: String,
+    /// The chosen folder relative to `root`, or its stored path when `root` is empty.
+    pub directory: String,
+    pub targets: Vec<Target>,
+}
+
+pub struct ZipSummary {
+    /// Per category: its folder relative to the root, and its new entries.
+    pub categories: Vec<(String, String, Vec<ImageInfo>)>,
+    pub skipped: usize,
+    /// Entries that couldn't be extracted or found, with the reason.
+    pub failed: Vec<String>,
+}
+
+fn join(root: &str, relative: &str) -> String {
+    match (root.is_empty(), relative.is_empty()) {
+        (true, _) => relative.to_string(),
+        (_, true) => root.to_string(),
+        _ => format!("{}/{}", root, relative),
+    }
+}
+
+fn archive_name(archive: &Path) -> String {
+    archive
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported".to_string())
+}
+
+fn open(archive: &Path) -> Result<zip::ZipArchive<std::fs::File>, String> {
+    let file = std::fs::File::open(archive).map_err(|e| format!("{}: {}", archive.display(), e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("{} isn't a readable ZIP archive: {}", archive.display(), e))
+}
+
+/// An entry's path inside the archive split into its top-level folder and
+/// the rest; None for folders, and for names that would escape the
+/// destination ("../", absolute paths).
+fn split(archive_name: &str, entry: &zip::read::ZipFile) -> Option<(String, PathBuf)> {
+    if entry.is_dir() {
+        return None;
+    }
+    let path = entry.enclosed_name()?.to_path_buf();
+    let mut components = path.components();
+    let first = components.next()?.as_os_str().to_string_lossy().into_owned();
+    let rest = components.as_path().to_path_buf();
+    Some(if rest.as_os_str().is_empty() {
+        (archive_name.to_string(), PathBuf::from(first))
+    } else {
+        (first, rest)
+    })
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(crate::decoder_supports)
+}
+
+/// Reads the archive's listing. Fails on corrupt and password-protected archives.
+pub fn preview(archive: &Path) -> Result<Preview, String> {
+    let mut zip = open(archive)?;
+    let name = archive_name(archive);
+    let mut folders: BTreeMap<String, FolderPreview> = BTreeMap::new();
+    let mut skipped = 0;
+    for index in 0..zip.len() {
+        // zip 0.6 only says an entry is encrypted when asked to open it
+        if let Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)) = zip.by_index(index).map(drop) {
+            return Err(format!(
+                "{} is password-protected; extract it with an archive tool and import the folder instead",
+                archive.display()
+            ));
+        }
+        let entry = zip
+            .by_index_raw(index)
+            .map_err(|e| format!("{} is damaged: {}", archive.display(), e))?;
+        let Some((folder, rest)) = split(&name, &entry) else {
+            continue;
+        };
+        if !is_image(&rest) {
+            skipped += 1;
+            continue;
+        }
+        let preview = folders.entry(folder.clone()).or_insert_with(|| FolderPreview {
+            folder,
+            images: 0,
+            bytes: 0,
+        });
+        preview.images += 1;
+        preview.bytes += entry.size();
+    }
+    Ok(Preview {
+        folders: folders.into_values().collect(),
+        skipped,
+    })
+}
+
+/// Extracts or looks up the planned folders' images. Meant for a background thread.
+pub fn run(plan: ZipPlan, progress: Arc<ImportProgress>) -> Result<ZipSummary, String> {
+    let mut zip = open(&plan.archive)?;
+    let name = archive_name(&plan.archive);
+    let categories: BTreeMap<&str, &str> = plan
+        .targets
+        .iter()
+        .map(|target| (target.folder.as_str(), target.category.as_str()))
+        .collect();
+
+    // Listed first, so progress has a total and skipped entries are counted once
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    let mut failed = Vec::new();
+    for index in 0..zip.len() {
+        let entry = zip.by_index_raw(index).map_err(|e| format!("{} is damaged: {}", plan.archive.display(), e))?;
+        let Some((folder, rest)) = split(&name, &entry) else {
+            continue;
+        };
+        if !is_image(&rest) {
+            skipped += 1;
+        } else if !categories.contains_key(folder.as_str()) {
+            continue;
+        } else if plan.mode == ZipMode::Extract && entry.size() > MAX_ENTRY_BYTES {
+            let display = join(&folder, &rest.to_string_lossy().replace('\\', "/"));
+            failed.push(format!("{}: larger than {}, left out", display, crate::format_bytes(MAX_ENTRY_BYTES)));
+        } else {
+            entries.push((index, folder, rest, entry.size()));
+        }
+    }
+    if plan.mode == ZipMode::Extract {
+        let total = entries.iter().map(|&(_, _, _, size)| size).sum();
+        check_space(total, free_space(&plan.destination))?;
+    }
+    progress.total.store(entries.len(), Ordering::Relaxed);
+
+    let mut imported: BTreeMap<String, Vec<ImageInfo>> = BTreeMap::new();
+    for (index, folder, rest, size) in entries {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let display = join(&folder, &rest.to_string_lossy().replace('\\', "/"));
+        let result = match plan.mode {
+            ZipMode::Extract => extract(&mut zip, index, size, &plan.destination.join(&folder).join(&rest)),
+            ZipMode::Reference => {
+                let path = plan.destination.join(&folder).join(&rest);
+                std::fs::metadata(&path)
+                    .map(|metadata| (path, metadata.len()))
+                    .map_err(|_| format!("not found in {}", plan.destination.display()))
+            }
+        };
+        match result {
+            Ok((path, size)) => {
+                let relative = path
+                    .strip_prefix(&plan.destination)
+                    .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default();
+                let relative_path = join(&plan.directory, &relative);
+                let image = ImageInfo {
+                    filename: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                    full_path: join(&plan.root, &relative_path),
+                    relative_path,
+                    extension: path
+                        .extension()
+                        .map(|ext| format!(".{}", ext.to_string_lossy().to_ascii_lowercase()))
+                        .unwrap_or_default(),
+                    size,
+                    checksum: None,
+                    width: None,
+                    height: None,
+                };
+                imported.entry(folder).or_default().push(image);
+            }
+            Err(e) => failed.push(format!("{}: {}", display, e)),
+        }
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let categories = plan
+        .targets
+        .iter()
+        .filter_map(|target| {
+            let images = imported.remove(&target.folder)?;
+            Some((target.category.clone(), join(&plan.directory, &target.folder), images))
+        })
+        .collect();
+    Ok(ZipSummary {
+        categories,
+        skipped,
+        failed,
+    })
+}
+
+/// Whether `total` bytes may be extracted to a disk with `free` bytes left;
+/// the free space is only checked where the platform reports it.
+fn check_space(total: u64, free: Option<u64>) -> Result<(), String> {
+    if total > MAX_TOTAL_BYTES {
+        return Err(format!(
+            "The archive's images add up to {}, more than the {} one import may extract",
+            crate::format_bytes(total),
+            crate::format_bytes(MAX_TOTAL_BYTES)
+        ));
+    }
+    match free {
+        Some(free) if total.saturating_add(FREE_SPACE_MARGIN) > free => Err(format!(
+            "The archive's images need {}, but only {} is free there",
+            crate::format_bytes(total),
+            crate::format_bytes(free)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Free bytes on the disk holding `dir`, or its nearest existing parent.
+#[cfg(unix)]
+fn free_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|dir| dir.exists())?;
+    let output = std::process::Command::new("df").arg("-Pk").arg(existing).output().ok()?;
+    // Filesystem, 1024-blocks, Used, Available, ...
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let kilobytes: u64 = listing.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(windows)]
+fn free_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|dir| dir.exists())?;
+    let path = existing.to_string_lossy().replace('\'', "''");
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command"])
+        .arg(format!("[System.IO.DriveInfo]::new('{}').AvailableFreeSpace", path))
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Writes one entry to `target`, or next to it under a free name when taken.
+/// Stops at `declared` bytes, failing if the entry holds more. A half-written
+/// file is removed again.
+fn extract(
+    zip: &mut zip::ZipArchive<std::fs::File>,
+    index: usize,
+    declared: u64,
+    target: &Path,
+) -> Result<(PathBuf, u64), String> {
+    let mut entry = zip.by_index(index).map_err(|e| e.to_string())?;
+    let dir = target.parent().ok_or("invalid path")?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let filename = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let target = dir.join(free_name(dir, &filename));
+    let written = std::fs::File::create(&target)
+        .and_then(|mut file| std::io::copy(&mut (&mut entry).take(declared), &mut file))
+        .map_err(|e| e.to_string())
+        .and_then(|size| match entry.read(&mut [0]) {
+            Ok(0) => Ok(size),
+            Ok(_) => Err(format!("holds more than the {} it declares", crate::format_bytes(declared))),
+            Err(e) => Err(e.to_string()),
+        });
+    written.map(|size| (target.clone(), size)).inspect_err(|_| {
+        let _ = std::fs::remove_file(&target);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+    use std::io::Write;
+
+    /// A deflated archive of `entries`, each `(name, length)` filled with zeros.
+    fn archive(path: &Path, entries: &[(&str, usize)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for &(name, length) in entries {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&vec![0; length]).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Overwrites a field of every entry's local header and central directory
+    /// record, given its offset in each.
+    fn patch(path: &Path, local: usize, central: usize, value: &[u8]) {
+        let mut bytes = std::fs::read(path).unwrap();
+        for offset in 0..bytes.len().saturating_sub(4) {
+            let field = match &bytes[offset..offset + 4] {
+                [0x50, 0x4b, 0x03, 0x04] => offset + local,
+                [0x50, 0x4b, 0x01, 0x02] => offset + central,
+                _ => continue,
+            };
+            bytes[field..field + value.len()].copy_from_slice(value);
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    /// Makes every entry declare `size` uncompressed bytes, as a zip bomb would.
+    fn declare(path: &Path, size: u32) {
+        patch(path, 22, 24, &size.to_le_bytes());
+    }
+
+    fn plan(archive: &Path, destination: &Path) -> ZipPlan {
+        ZipPlan {
+            archive: archive.to_path_buf(),
+            mode: ZipMode::Extract,
+            destination: destination.to_path_buf(),
+            root: String::new(),
+            directory: "packs".to_string(),
+            targets: vec![Target {
+                folder: "icons".to_string(),
+                category: "Icons".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn extracts_the_planned_folders() {
+        let dir = TestDir::new("zip-extract");
+        let path = dir.join("pack.zip");
+        archive(&path, &[("icons/a.png", 300), ("icons/readme.txt", 10), ("other/b.png", 10)]);
+
+        let summary = run(plan(&path, &dir.join("out")), Arc::default()).unwrap();
+        assert!(summary.failed.is_empty(), "{:?}", summary.failed);
+        assert_eq!(summary.skipped, 1);
+        let (category, directory, images) = &summary.categories[0];
+        assert_eq!((category.as_str(), directory.as_str()), ("Icons", "packs/icons"));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].size, 300);
+        assert_eq!(std::fs::metadata(dir.join("out/icons/a.png")).unwrap().len(), 300);
+    }
+
+    #[test]
+    fn entry_holding_more_than_it_declares_is_not_written() {
+        let dir = TestDir::new("zip-bomb");
+        let path = dir.join("pack.zip");
+        archive(&path, &[("icons/a.png", 64 * 1024)]);
+        declare(&path, 100);
+        assert_eq!(preview(&path).unwrap().folders[0].bytes, 100);
+
+        let summary = run(plan(&path, &dir.join("out")), Arc::default()).unwrap();
+        assert!(summary.categories.is_empty());
+        assert_eq!(summary.failed.len(), 1);
+        assert!(summary.failed[0].contains("holds more than"), "{}", summary.failed[0]);
+        assert!(!dir.join("out/icons/a.png").exists());
+    }
+
+    #[test]
+    fn oversized_entries_and_totals_are_refused_up_front() {
+        let dir = TestDir::new("zip-oversized");
+        let path = dir.join("pack.zip");
+        archive(&path, &[("icons/a.png", 10), ("icons/b.png", 10)]);
+        declare(&path, MAX_ENTRY_BYTES as u32 + 1);
+
+        let summary = run(plan(&path, &dir.join("out")), Arc::default()).unwrap();
+        assert_eq!(summary.failed.len(), 2);
+        assert!(summary.failed.iter().all(|failure| failure.contains("left out")));
+        assert!(!dir.join("out").exists(), "nothing is written");
+
+        assert!(check_space(MAX_TOTAL_BYTES, None).is_ok());
+        assert!(check_space(MAX_TOTAL_BYTES + 1, None).is_err());
+        assert!(check_space(1024, Some(FREE_SPACE_MARGIN + 1024)).is_ok());
+        assert!(check_space(1024, Some(FREE_SPACE_MARGIN)).is_err());
+    }
+
+    #[test]
+    fn password_protected_archive_is_refused() {
+        let dir = TestDir::new("zip-password");
+        let path = dir.join("pack.zip");
+        archive(&path, &[("icons/a.png", 10)]);
+        // General purpose flags with only the "encrypted" bit set
+        patch(&path, 6, 8, &[0x01, 0x00]);
+        let error = preview(&path).unwrap_err();
+        assert!(error.contains("password-protected"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn free_space_is_read_for_folders_not_created_yet() {
+        let dir = TestDir::new("zip-free-space");
+        assert!(free_space(&dir.join("not/yet")).is_some_and(|free| free > 0));
+    }
+}